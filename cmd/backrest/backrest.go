@@ -5,6 +5,7 @@ import (
 	"crypto/rand"
 	"errors"
 	"flag"
+	"fmt"
 	"net/http"
 	"os"
 	"os/signal"
@@ -14,11 +15,14 @@ import (
 	"sync"
 	"sync/atomic"
 	"syscall"
+	"time"
 
+	"connectrpc.com/connect"
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
 	"github.com/garethgeorge/backrest/gen/go/v1/v1connect"
 	"github.com/garethgeorge/backrest/internal/api"
 	syncapi "github.com/garethgeorge/backrest/internal/api/syncapi"
+	"github.com/garethgeorge/backrest/internal/auditlog"
 	"github.com/garethgeorge/backrest/internal/auth"
 	"github.com/garethgeorge/backrest/internal/config"
 	"github.com/garethgeorge/backrest/internal/env"
@@ -28,7 +32,10 @@ import (
 	"github.com/garethgeorge/backrest/internal/oplog/bboltstore"
 	"github.com/garethgeorge/backrest/internal/oplog/sqlitestore"
 	"github.com/garethgeorge/backrest/internal/orchestrator"
+	"github.com/garethgeorge/backrest/internal/orchestrator/tasks"
 	"github.com/garethgeorge/backrest/internal/resticinstaller"
+	"github.com/garethgeorge/backrest/internal/tlsutil"
+	"github.com/garethgeorge/backrest/internal/tracing"
 	"github.com/garethgeorge/backrest/webui"
 	"github.com/mattn/go-colorable"
 	"go.uber.org/zap"
@@ -62,6 +69,23 @@ func main() {
 	go onterm(os.Interrupt, cancel)
 	go onterm(os.Interrupt, newForceKillHandler())
 
+	// Export trace spans if a collector or trace file is configured.
+	traceOpts := tracing.OptionsFromEnv(env.TraceFile(), version)
+	shutdownTracing, err := tracing.Init(ctx, traceOpts)
+	if err != nil {
+		zap.S().Fatalf("error initializing tracing: %v", err)
+	}
+	if traceOpts.Enabled() {
+		zap.S().Info("OpenTelemetry tracing enabled")
+	}
+	defer func() {
+		shutdownCtx, cancel := context.WithTimeout(context.Background(), 5*time.Second)
+		defer cancel()
+		if err := shutdownTracing(shutdownCtx); err != nil {
+			zap.S().Warnf("error flushing trace spans: %v", err)
+		}
+	}()
+
 	// Load the configuration
 	configStore := createConfigProvider()
 	cfg, err := configStore.Get()
@@ -120,6 +144,11 @@ func main() {
 		zap.S().Fatalf("error creating orchestrator: %v", err)
 	}
 
+	if err := metric.GetRegistry().TrackOpLog(log, cfg.Instance); err != nil {
+		zap.S().Errorf("error restoring metrics from the oplog: %v", err)
+	}
+	metric.GetRegistry().SetTaskQueueSource(orchestrator.QueuedTasks)
+
 	wg.Add(1)
 	go func() {
 		orchestrator.Run(ctx)
@@ -137,26 +166,47 @@ func main() {
 
 	syncHandler := syncapi.NewBackrestSyncHandler(syncMgr)
 
+	auditLog, err := auditlog.NewAuditLog(filepath.Join(env.DataDir(), "audit.sqlite"))
+	if err != nil {
+		zap.S().Fatalf("error creating audit log: %v", err)
+	}
+	defer auditLog.Close()
+	auditInterceptor := connect.WithInterceptors(api.NewAuditInterceptor(auditLog, configMgr))
+
+	sessionStore, err := auth.NewSessionStore(filepath.Join(env.DataDir(), "sessions.sqlite"))
+	if err != nil {
+		zap.S().Fatalf("error creating session store: %v", err)
+	}
+	defer sessionStore.Close()
+
+	authenticator := auth.NewAuthenticator(getSecret(), configMgr, sessionStore)
+	authenticator.SetLockoutHandler((&lockoutNotifier{auditLog: auditLog, orch: orchestrator}).notify)
+	tlsCertFile, tlsKeyFile := env.TLSCertFiles()
+	certs := tlsutil.NewCertManager(configMgr, filepath.Join(env.DataDir(), "tls"), tlsCertFile, tlsKeyFile)
+
 	apiBackrestHandler := api.NewBackrestHandler(
 		configMgr,
 		remoteConfigStore,
 		orchestrator,
 		log,
 		logStore,
+		authenticator,
+		auditLog,
+		certs,
 	)
-	authenticator := auth.NewAuthenticator(getSecret(), configMgr)
 	apiAuthenticationHandler := api.NewAuthenticationHandler(authenticator)
 
 	mux := http.NewServeMux()
-	mux.Handle(v1connect.NewAuthenticationHandler(apiAuthenticationHandler))
+	mux.Handle(v1connect.NewAuthenticationHandler(apiAuthenticationHandler, auditInterceptor))
 	if cfg.GetMultihost() != nil {
 		// alpha feature, only available if the user manually enables it in the config.
 		mux.Handle(v1connect.NewBackrestSyncServiceHandler(syncHandler))
 	}
-	backrestHandlerPath, backrestHandler := v1connect.NewBackrestHandler(apiBackrestHandler)
+	backrestHandlerPath, backrestHandler := v1connect.NewBackrestHandler(apiBackrestHandler, auditInterceptor)
 	mux.Handle(backrestHandlerPath, auth.RequireAuthentication(backrestHandler, authenticator))
 	mux.Handle("/", webui.Handler())
-	mux.Handle("/download/", http.StripPrefix("/download", api.NewDownloadHandler(log)))
+	mux.Handle("/download/", http.StripPrefix("/download", api.NewDownloadHandler(log, orchestrator)))
+	mux.Handle(auth.OIDCPathPrefix+"/", http.StripPrefix(auth.OIDCPathPrefix, auth.NewOIDCHandler(authenticator)))
 	mux.Handle("/metrics", auth.RequireAuthentication(metric.GetRegistry().Handler(), authenticator))
 
 	// Serve the HTTP gateway
@@ -164,13 +214,27 @@ func main() {
 		Addr:    env.BindAddress(),
 		Handler: h2c.NewHandler(mux, &http2.Server{}), // h2c is HTTP/2 without TLS for grpc-connect support.
 	}
+	useTLS := certs.Enabled()
+	if useTLS {
+		// HTTP/2 is negotiated with ALPN when serving TLS.
+		server.Handler = mux
+		server.TLSConfig = certs.TLSConfig()
+		if _, err := certs.GetCertificate(nil); err != nil {
+			zap.S().Fatalf("error loading TLS certificate: %v", err)
+		}
+	}
 
-	zap.S().Infof("starting web server %v", server.Addr)
+	zap.S().Infof("starting web server %v (tls: %v)", server.Addr, useTLS)
 	go func() {
 		<-ctx.Done()
 		server.Shutdown(context.Background())
 	}()
-	if err := server.ListenAndServe(); !errors.Is(err, http.ErrServerClosed) {
+	if useTLS {
+		err = server.ListenAndServeTLS("", "")
+	} else {
+		err = server.ListenAndServe()
+	}
+	if !errors.Is(err, http.ErrServerClosed) {
 		zap.L().Error("error starting server", zap.Error(err))
 	}
 	zap.L().Info("HTTP gateway shutdown")
@@ -179,8 +243,76 @@ func main() {
 }
 
 func createConfigProvider() config.ConfigStore {
+	store := &config.JsonFileStore{Path: env.ConfigFilePath()}
+
+	key, err := env.ConfigKey()
+	if err != nil {
+		zap.S().Fatalf("error loading config key: %v", err)
+	}
+	if key != nil {
+		store.Cipher, err = config.NewSecretCipher(key)
+		if err != nil {
+			zap.S().Fatalf("error creating config cipher: %v", err)
+		}
+		zap.S().Info("secrets in the config file are encrypted at rest")
+		if err := store.EncryptPlaintextSecrets(); err != nil {
+			zap.S().Fatalf("error encrypting config secrets: %v", err)
+		}
+	}
+
 	return &config.CachingValidatingStore{
-		ConfigStore: &config.JsonFileStore{Path: env.ConfigFilePath()},
+		ConfigStore: store,
+	}
+}
+
+// lockoutHookInterval is the minimum time between runs of the login lockout hooks, an attack from many addresses
+// locks out each of them and must not flood the hooks' destinations.
+const lockoutHookInterval = 15 * time.Minute
+
+// lockoutNotifier records every login lockout in the audit log and runs the hooks subscribed to lockouts at most once
+// per lockoutHookInterval, the next run reports how many lockouts were not notified in between.
+type lockoutNotifier struct {
+	auditLog *auditlog.AuditLog
+	orch     *orchestrator.Orchestrator
+
+	mu         sync.Mutex
+	nextHookAt time.Time
+	suppressed int
+}
+
+func (n *lockoutNotifier) notify(event auth.LoginLockoutEvent) {
+	if err := n.auditLog.Append(&v1.AuditLogEntry{
+		UnixTimeMs: time.Now().UnixMilli(),
+		User:       event.Username,
+		SourceIp:   event.ClientAddr,
+		Rpc:        v1connect.AuthenticationLoginProcedure,
+		Summary:    event.String(),
+		Result:     "locked_out",
+	}); err != nil {
+		zap.S().Errorf("failed to write login lockout to audit log: %v", err)
+	}
+
+	n.mu.Lock()
+	now := time.Now()
+	if now.Before(n.nextHookAt) {
+		n.suppressed++
+		n.mu.Unlock()
+		return
+	}
+	n.nextHookAt = now.Add(lockoutHookInterval)
+	suppressed := n.suppressed
+	n.suppressed = 0
+	n.mu.Unlock()
+
+	msg := event.String()
+	if suppressed > 0 {
+		msg = fmt.Sprintf("%s (%d more lockouts since the last notification, see the audit log)", msg, suppressed)
+	}
+	if err := n.orch.RunGlobalHooks([]v1.Hook_Condition{v1.Hook_CONDITION_LOGIN_LOCKOUT}, tasks.HookVars{
+		Task:  "login lockout",
+		Error: msg,
+	}); err != nil {
+		zap.S().Errorf("failed to run login lockout hooks: %v", err)
 	}
 }
 
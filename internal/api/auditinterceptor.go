@@ -0,0 +1,179 @@
+package api
+
+import (
+	"context"
+	"fmt"
+	"net"
+	"time"
+
+	"connectrpc.com/connect"
+	"github.com/garethgeorge/backrest/gen/go/types"
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/gen/go/v1/v1connect"
+	"github.com/garethgeorge/backrest/internal/auditlog"
+	"github.com/garethgeorge/backrest/internal/auth"
+	"github.com/garethgeorge/backrest/internal/config"
+	"go.uber.org/zap"
+)
+
+// auditedProcedures maps each mutating procedure, and each procedure that reads file contents or names out of
+// snapshots, to a summarizer of its request. Summaries must never include secrets.
+var auditedProcedures = map[string]func(msg any) string{
+	v1connect.AuthenticationLoginProcedure: func(msg any) string {
+		return fmt.Sprintf("user %q", msg.(*v1.LoginRequest).GetUsername())
+	},
+	v1connect.BackrestGetConfigProcedure: func(msg any) string { return "including secrets" },
+	v1connect.BackrestSetConfigProcedure: func(msg any) string { return "" },
+	v1connect.BackrestAddRepoProcedure: func(msg any) string {
+		return fmt.Sprintf("repo %q", msg.(*v1.Repo).GetId())
+	},
+	v1connect.BackrestRemoveRepoProcedure: func(msg any) string {
+		return fmt.Sprintf("repo %q", msg.(*types.StringValue).GetValue())
+	},
+	v1connect.BackrestBackupProcedure: func(msg any) string {
+		return fmt.Sprintf("plan %q", msg.(*types.StringValue).GetValue())
+	},
+	v1connect.BackrestDoRepoTaskProcedure: func(msg any) string {
+		req := msg.(*v1.DoRepoTaskRequest)
+		return fmt.Sprintf("repo %q task %v", req.GetRepoId(), req.GetTask())
+	},
+	v1connect.BackrestForgetProcedure: func(msg any) string {
+		req := msg.(*v1.ForgetRequest)
+		return fmt.Sprintf("repo %q plan %q snapshot %q", req.GetRepoId(), req.GetPlanId(), req.GetSnapshotId())
+	},
+	v1connect.BackrestRestoreProcedure: func(msg any) string {
+		req := msg.(*v1.RestoreSnapshotRequest)
+		if req.GetTargetDatabase() != "" {
+			return fmt.Sprintf("repo %q snapshot %q path %q to database %q", req.GetRepoId(), req.GetSnapshotId(), req.GetPath(), req.GetTargetDatabase())
+		}
+		return fmt.Sprintf("repo %q snapshot %q path %q to %q", req.GetRepoId(), req.GetSnapshotId(), req.GetPath(), req.GetTarget())
+	},
+	v1connect.BackrestCancelProcedure: func(msg any) string {
+		return fmt.Sprintf("operation %d", msg.(*types.Int64Value).GetValue())
+	},
+	v1connect.BackrestRunCommandProcedure: func(msg any) string {
+		req := msg.(*v1.RunCommandRequest)
+		return fmt.Sprintf("repo %q command %q", req.GetRepoId(), req.GetCommand())
+	},
+	v1connect.BackrestGetDownloadURLProcedure: func(msg any) string {
+		return fmt.Sprintf("operation %d", msg.(*types.Int64Value).GetValue())
+	},
+	v1connect.BackrestGetSnapshotDownloadURLProcedure: func(msg any) string {
+		req := msg.(*v1.GetSnapshotDownloadURLRequest)
+		return fmt.Sprintf("repo %q snapshot %q path %q", req.GetRepoId(), req.GetSnapshotId(), req.GetPath())
+	},
+	v1connect.BackrestDiffSnapshotsProcedure: func(msg any) string {
+		req := msg.(*v1.DiffSnapshotsRequest)
+		return fmt.Sprintf("repo %q snapshots %q to %q", req.GetRepoId(), req.GetFromSnapshotId(), req.GetToSnapshotId())
+	},
+	v1connect.BackrestFindFilesProcedure: func(msg any) string {
+		req := msg.(*v1.FindFilesRequest)
+		return fmt.Sprintf("repo %q plan %q pattern %q", req.GetRepoId(), req.GetPlanId(), req.GetPattern())
+	},
+	v1connect.BackrestGetFileHistoryProcedure: func(msg any) string {
+		req := msg.(*v1.GetFileHistoryRequest)
+		return fmt.Sprintf("repo %q plan %q path %q", req.GetRepoId(), req.GetPlanId(), req.GetPath())
+	},
+	v1connect.BackrestClearHistoryProcedure: func(msg any) string {
+		req := msg.(*v1.ClearHistoryRequest)
+		return fmt.Sprintf("selector %v only failed %v", req.GetSelector(), req.GetOnlyFailed())
+	},
+	v1connect.BackrestCreateAPIKeyProcedure: func(msg any) string {
+		req := msg.(*v1.CreateAPIKeyRequest)
+		return fmt.Sprintf("key %q for user %q", req.GetName(), req.GetUser())
+	},
+	v1connect.BackrestRevokeAPIKeyProcedure: func(msg any) string {
+		return fmt.Sprintf("key %q", msg.(*types.StringValue).GetValue())
+	},
+	v1connect.BackrestConfirmTOTPEnrollmentProcedure: func(msg any) string { return "" },
+	v1connect.BackrestDisableTOTPProcedure: func(msg any) string {
+		return fmt.Sprintf("user %q", msg.(*v1.DisableTOTPRequest).GetUsername())
+	},
+	v1connect.BackrestRevokeSessionProcedure: func(msg any) string {
+		return fmt.Sprintf("session %q", msg.(*types.StringValue).GetValue())
+	},
+}
+
+// auditFilters limit the audit of a procedure to the requests matching the filter.
+var auditFilters = map[string]func(msg any) bool{
+	v1connect.BackrestGetConfigProcedure: func(msg any) bool {
+		return msg.(*v1.GetConfigRequest).GetIncludeSecrets()
+	},
+}
+
+// configChangingProcedures are summarized with a diff of the config before and after the call.
+var configChangingProcedures = map[string]bool{
+	v1connect.BackrestSetConfigProcedure:  true,
+	v1connect.BackrestAddRepoProcedure:    true,
+	v1connect.BackrestRemoveRepoProcedure: true,
+}
+
+// NewAuditInterceptor returns an interceptor that records every call of a mutating procedure in the audit log.
+func NewAuditInterceptor(log *auditlog.AuditLog, configStore config.ConfigStore) connect.UnaryInterceptorFunc {
+	return func(next connect.UnaryFunc) connect.UnaryFunc {
+		return func(ctx context.Context, req connect.AnyRequest) (connect.AnyResponse, error) {
+			procedure := req.Spec().Procedure
+			summarize, ok := auditedProcedures[procedure]
+			if !ok {
+				return next(ctx, req)
+			}
+			if filter, ok := auditFilters[procedure]; ok && !filter(req.Any()) {
+				return next(ctx, req)
+			}
+
+			var before *v1.Config
+			if configChangingProcedures[procedure] {
+				before, _ = configStore.Get()
+			}
+
+			resp, err := next(ctx, req)
+
+			entry := &v1.AuditLogEntry{
+				UnixTimeMs: time.Now().UnixMilli(),
+				SourceIp:   sourceIP(req),
+				Rpc:        procedure,
+				Summary:    summarize(req.Any()),
+				Result:     "ok",
+			}
+			if user := auth.UserFromContext(ctx); user != nil {
+				entry.User = user.Name
+			} else if login, ok := req.Any().(*v1.LoginRequest); ok {
+				entry.User = login.GetUsername()
+			}
+			if key := auth.APIKeyFromContext(ctx); key != nil {
+				entry.ApiKey = key.Name
+			}
+			if err != nil {
+				entry.Result = connect.CodeOf(err).String()
+				entry.Error = err.Error()
+			} else if configChangingProcedures[procedure] {
+				if after, e := configStore.Get(); e == nil {
+					entry.Summary = joinSummary(entry.Summary, auditlog.DiffConfig(before, after))
+				}
+			}
+
+			if e := log.Append(entry); e != nil {
+				zap.L().Error("failed to write audit log entry", zap.String("rpc", procedure), zap.Error(e))
+			}
+			return resp, err
+		}
+	}
+}
+
+func sourceIP(req connect.AnyRequest) string {
+	addr := req.Peer().Addr
+	if host, _, err := net.SplitHostPort(addr); err == nil {
+		addr = host
+	}
+	if forwarded := req.Header().Get("X-Forwarded-For"); forwarded != "" {
+		addr += " (forwarded for " + forwarded + ")"
+	}
+	return addr
+}
+
+func joinSummary(summary, diff string) string {
+	if summary == "" {
+		return diff
+	}
+	return summary + ": " + diff
+}
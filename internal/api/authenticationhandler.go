@@ -2,6 +2,8 @@ package api
 
 import (
 	"context"
+	"errors"
+	"net"
 
 	"connectrpc.com/connect"
 	"github.com/garethgeorge/backrest/gen/go/types"
@@ -9,6 +11,7 @@ import (
 	"github.com/garethgeorge/backrest/gen/go/v1/v1connect"
 	"github.com/garethgeorge/backrest/internal/auth"
 	"go.uber.org/zap"
+	"google.golang.org/protobuf/types/known/emptypb"
 )
 
 type AuthenticationHandler struct {
@@ -26,20 +29,47 @@ func NewAuthenticationHandler(authenticator *auth.Authenticator) *Authentication
 
 func (s *AuthenticationHandler) Login(ctx context.Context, req *connect.Request[v1.LoginRequest]) (*connect.Response[v1.LoginResponse], error) {
 	zap.L().Debug("login request", zap.String("username", req.Msg.Username))
-	user, err := s.authenticator.Login(req.Msg.Username, req.Msg.Password)
-	if err != nil {
+	clientAddr := peerAddr(req)
+	user, err := s.authenticator.Login(clientAddr, req.Msg.Username, req.Msg.Password, req.Msg.TotpCode)
+	if errors.Is(err, auth.ErrLoginLockedOut) {
+		return nil, connect.NewError(connect.CodeResourceExhausted, err)
+	} else if errors.Is(err, auth.ErrTOTPRequired) {
+		return connect.NewResponse(&v1.LoginResponse{
+			TotpRequired: true,
+		}), nil
+	} else if errors.Is(err, auth.ErrInvalidTOTPCode) {
+		zap.L().Warn("failed login attempt", zap.String("username", req.Msg.Username), zap.Error(err))
+		return nil, connect.NewError(connect.CodeUnauthenticated, auth.ErrInvalidTOTPCode)
+	} else if err != nil {
 		zap.L().Warn("failed login attempt", zap.Error(err))
 		return nil, connect.NewError(connect.CodeUnauthenticated, auth.ErrInvalidPassword)
 	}
 
-	token, err := s.authenticator.CreateJWT(user)
+	tokens, err := s.authenticator.CreateSession(user, req.Header().Get("User-Agent"), clientAddr)
 	if err != nil {
 		return nil, err
 	}
+	return connect.NewResponse(loginResponse(tokens)), nil
+}
 
-	return connect.NewResponse(&v1.LoginResponse{
-		Token: token,
-	}), nil
+func (s *AuthenticationHandler) RefreshToken(ctx context.Context, req *connect.Request[v1.RefreshTokenRequest]) (*connect.Response[v1.LoginResponse], error) {
+	tokens, err := s.authenticator.RefreshSession(req.Msg.RefreshToken, peerAddr(req))
+	if errors.Is(err, auth.ErrInvalidRefreshToken) || errors.Is(err, auth.ErrSessionRevoked) || errors.Is(err, auth.ErrUserNotFound) {
+		zap.L().Debug("refresh token rejected", zap.Error(err))
+		return nil, connect.NewError(connect.CodeUnauthenticated, auth.ErrInvalidRefreshToken)
+	} else if err != nil {
+		return nil, err
+	}
+	return connect.NewResponse(loginResponse(tokens)), nil
+}
+
+func (s *AuthenticationHandler) Logout(ctx context.Context, req *connect.Request[v1.RefreshTokenRequest]) (*connect.Response[emptypb.Empty], error) {
+	err := s.authenticator.EndSession(req.Msg.RefreshToken)
+	if err != nil && !errors.Is(err, auth.ErrInvalidRefreshToken) && !errors.Is(err, auth.ErrSessionRevoked) && !errors.Is(err, auth.ErrUserNotFound) {
+		return nil, err
+	}
+	// logging out of a session that already ended succeeds.
+	return connect.NewResponse(&emptypb.Empty{}), nil
 }
 
 func (s *AuthenticationHandler) HashPassword(ctx context.Context, req *connect.Request[types.StringValue]) (*connect.Response[types.StringValue], error) {
@@ -49,3 +79,32 @@ func (s *AuthenticationHandler) HashPassword(ctx context.Context, req *connect.R
 	}
 	return connect.NewResponse(&types.StringValue{Value: hash}), nil
 }
+
+func (s *AuthenticationHandler) GetLoginOptions(ctx context.Context, req *connect.Request[emptypb.Empty]) (*connect.Response[v1.LoginOptions], error) {
+	opts := &v1.LoginOptions{}
+	provider, err := s.authenticator.OIDCProvider()
+	if err == nil {
+		opts.OidcLoginUrl = "." + auth.OIDCLoginPath // relative to the UI so that reverse proxies serving backrest under a sub path work.
+		opts.OidcDisplayName = provider.DisplayName
+	} else if !errors.Is(err, auth.ErrOIDCNotConfigured) {
+		return nil, err
+	}
+	return connect.NewResponse(opts), nil
+}
+
+func loginResponse(tokens *auth.Tokens) *v1.LoginResponse {
+	return &v1.LoginResponse{
+		Token:        tokens.AccessToken,
+		RefreshToken: tokens.RefreshToken,
+		ExpiresAtMs:  tokens.ExpiresAt.UnixMilli(),
+	}
+}
+
+// peerAddr returns the IP address of the client that sent the request.
+func peerAddr(req connect.AnyRequest) string {
+	addr := req.Peer().Addr
+	if host, _, err := net.SplitHostPort(addr); err == nil {
+		return host
+	}
+	return addr
+}
@@ -0,0 +1,131 @@
+package api
+
+import (
+	"context"
+	"fmt"
+	"slices"
+
+	"connectrpc.com/connect"
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/auth"
+	"github.com/garethgeorge/backrest/internal/config"
+	"github.com/garethgeorge/backrest/internal/orchestrator/repo"
+	"github.com/garethgeorge/backrest/internal/orchestrator/tasks"
+)
+
+// authorize checks that the user making the request holds the permission and may access the repo and plan.
+// Empty repo or plan IDs are not checked against the user's allowlists.
+func authorize(ctx context.Context, perm auth.Permission, repoID, planID string) error {
+	user := auth.UserFromContext(ctx)
+	if user == nil {
+		return nil // authentication is disabled.
+	}
+	if !auth.HasPermission(user, perm) {
+		return connect.NewError(connect.CodePermissionDenied, fmt.Errorf("user %q lacks %v permission: %w", user.Name, perm, auth.ErrPermissionDenied))
+	}
+	if repoID != "" && !auth.CanAccessRepo(user, repoID) {
+		return connect.NewError(connect.CodePermissionDenied, fmt.Errorf("user %q may not access repo %q: %w", user.Name, repoID, auth.ErrPermissionDenied))
+	}
+	if planID != "" && !isSystemPlanID(planID) && !auth.CanAccessPlan(user, planID) {
+		return connect.NewError(connect.CodePermissionDenied, fmt.Errorf("user %q may not access plan %q: %w", user.Name, planID, auth.ErrPermissionDenied))
+	}
+	return nil
+}
+
+// authorizePlanScope checks access like authorize for requests that search a repo's snapshots. Users limited to a
+// subset of plans must name one of their plans as a repo may hold the snapshots of plans they may not access, and the
+// plan must back up to the repo.
+func (s *BackrestHandler) authorizePlanScope(ctx context.Context, perm auth.Permission, repoID, planID string) error {
+	if err := authorize(ctx, perm, repoID, planID); err != nil {
+		return err
+	}
+	user := auth.UserFromContext(ctx)
+	if user == nil || len(user.GetAllowedPlans()) == 0 {
+		return nil
+	}
+	if isSystemPlanID(planID) {
+		return connect.NewError(connect.CodePermissionDenied, fmt.Errorf("user %q is restricted to a subset of plans and must select a plan: %w", user.Name, auth.ErrPermissionDenied))
+	}
+	plan, err := s.orchestrator.GetPlan(planID)
+	if err != nil || !slices.Contains(config.PlanRepos(plan), repoID) {
+		return connect.NewError(connect.CodePermissionDenied, fmt.Errorf("plan %q does not back up to repo %q: %w", planID, repoID, auth.ErrPermissionDenied))
+	}
+	return nil
+}
+
+// authorizeSnapshots checks access like authorize and, for users limited to a subset of plans, that each snapshot is
+// tagged with a plan the user may access. The snapshots' tags are read from the repo, the plan ID in a request is
+// only trusted to match them.
+func (s *BackrestHandler) authorizeSnapshots(ctx context.Context, perm auth.Permission, repoID, planID string, snapshotIDs ...string) error {
+	if err := authorize(ctx, perm, repoID, planID); err != nil {
+		return err
+	}
+	user := auth.UserFromContext(ctx)
+	if user == nil || len(user.GetAllowedPlans()) == 0 {
+		return nil
+	}
+	r, err := s.orchestrator.GetRepoOrchestrator(repoID)
+	if err != nil {
+		return fmt.Errorf("failed to get repo: %w", err)
+	}
+	snapshots, err := r.SnapshotsByID(ctx, snapshotIDs)
+	if err != nil {
+		return connect.NewError(connect.CodePermissionDenied, fmt.Errorf("resolve snapshot plans: %w", err))
+	}
+	for _, snapshot := range snapshots {
+		snapshotPlan := repo.PlanFromTags(snapshot.Tags)
+		if snapshotPlan == "" || !auth.CanAccessPlan(user, snapshotPlan) || (planID != "" && snapshotPlan != planID) {
+			return connect.NewError(connect.CodePermissionDenied, fmt.Errorf("user %q may not access snapshot %q: %w", user.Name, snapshot.Id, auth.ErrPermissionDenied))
+		}
+	}
+	return nil
+}
+
+// authorizeUnrestricted checks that the user holds the permission and is not limited to a subset of repos or plans.
+func authorizeUnrestricted(ctx context.Context, perm auth.Permission) error {
+	if err := authorize(ctx, perm, "", ""); err != nil {
+		return err
+	}
+	if user := auth.UserFromContext(ctx); user != nil && !auth.IsUnrestricted(user) {
+		return connect.NewError(connect.CodePermissionDenied, fmt.Errorf("user %q is restricted to a subset of repos or plans: %w", user.Name, auth.ErrPermissionDenied))
+	}
+	return nil
+}
+
+// canViewOperation returns true if the user making the request may see the operation.
+func canViewOperation(user *v1.User, op *v1.Operation) bool {
+	if user == nil {
+		return true
+	}
+	if !auth.CanAccessRepo(user, op.RepoId) {
+		return false
+	}
+	return isSystemPlanID(op.PlanId) || auth.CanAccessPlan(user, op.PlanId)
+}
+
+func filterOperationsForUser(user *v1.User, ops []*v1.Operation) []*v1.Operation {
+	if user == nil || auth.IsUnrestricted(user) {
+		return ops
+	}
+	filtered := make([]*v1.Operation, 0, len(ops))
+	for _, op := range ops {
+		if canViewOperation(user, op) {
+			filtered = append(filtered, op)
+		}
+	}
+	return filtered
+}
+
+// isSystemPlanID returns true for plan IDs that are not user defined e.g. repo maintenance tasks.
+func isSystemPlanID(planID string) bool {
+	return planID == "" || planID == tasks.PlanForSystemTasks || planID == tasks.PlanForUnassociatedOperations
+}
+
+// authorizeCredentialManagement rejects requests authenticated with an API key, keys may not be used to mint or revoke
+// keys or to change a user's second factor.
+func authorizeCredentialManagement(ctx context.Context) error {
+	if key := auth.APIKeyFromContext(ctx); key != nil {
+		return connect.NewError(connect.CodePermissionDenied, fmt.Errorf("api key %q may not manage credentials: %w", key.Name, auth.ErrPermissionDenied))
+	}
+	return nil
+}
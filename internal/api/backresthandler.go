@@ -2,14 +2,17 @@ package api
 
 import (
 	"bytes"
+	"cmp"
 	"context"
 	"encoding/hex"
 	"errors"
 	"fmt"
 	"io"
+	"net/url"
 	"os"
 	"path"
 	"slices"
+	"strconv"
 	"strings"
 	"sync"
 	"time"
@@ -19,8 +22,11 @@ import (
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
 	"github.com/garethgeorge/backrest/gen/go/v1/v1connect"
 	syncapi "github.com/garethgeorge/backrest/internal/api/syncapi"
+	"github.com/garethgeorge/backrest/internal/auditlog"
+	"github.com/garethgeorge/backrest/internal/auth"
 	"github.com/garethgeorge/backrest/internal/config"
 	"github.com/garethgeorge/backrest/internal/cryptoutil"
+	"github.com/garethgeorge/backrest/internal/dbdump"
 	"github.com/garethgeorge/backrest/internal/env"
 	"github.com/garethgeorge/backrest/internal/logstore"
 	"github.com/garethgeorge/backrest/internal/oplog"
@@ -29,6 +35,7 @@ import (
 	"github.com/garethgeorge/backrest/internal/orchestrator/tasks"
 	"github.com/garethgeorge/backrest/internal/protoutil"
 	"github.com/garethgeorge/backrest/internal/resticinstaller"
+	"github.com/garethgeorge/backrest/internal/tlsutil"
 	"github.com/garethgeorge/backrest/pkg/restic"
 	"go.uber.org/zap"
 	"google.golang.org/protobuf/proto"
@@ -42,33 +49,54 @@ type BackrestHandler struct {
 	oplog             *oplog.OpLog
 	logStore          *logstore.LogStore
 	remoteConfigStore syncapi.RemoteConfigStore
+	authenticator     *auth.Authenticator
+	auditLog          *auditlog.AuditLog
+	certs             *tlsutil.CertManager // nil if TLS is not managed by this instance.
 }
 
 var _ v1connect.BackrestHandler = &BackrestHandler{}
 
-func NewBackrestHandler(config config.ConfigStore, remoteConfigStore syncapi.RemoteConfigStore, orchestrator *orchestrator.Orchestrator, oplog *oplog.OpLog, logStore *logstore.LogStore) *BackrestHandler {
+func NewBackrestHandler(config config.ConfigStore, remoteConfigStore syncapi.RemoteConfigStore, orchestrator *orchestrator.Orchestrator, oplog *oplog.OpLog, logStore *logstore.LogStore, authenticator *auth.Authenticator, auditLog *auditlog.AuditLog, certs *tlsutil.CertManager) *BackrestHandler {
 	s := &BackrestHandler{
 		config:            config,
 		orchestrator:      orchestrator,
 		oplog:             oplog,
 		logStore:          logStore,
 		remoteConfigStore: remoteConfigStore,
+		authenticator:     authenticator,
+		auditLog:          auditLog,
+		certs:             certs,
 	}
 
 	return s
 }
 
 // GetConfig implements GET /v1/config
-func (s *BackrestHandler) GetConfig(ctx context.Context, req *connect.Request[emptypb.Empty]) (*connect.Response[v1.Config], error) {
-	config, err := s.config.Get()
+func (s *BackrestHandler) GetConfig(ctx context.Context, req *connect.Request[v1.GetConfigRequest]) (*connect.Response[v1.Config], error) {
+	if err := authorize(ctx, auth.PermissionRead, "", ""); err != nil {
+		return nil, err
+	}
+	if req.Msg.IncludeSecrets {
+		if err := authorizeUnrestricted(ctx, auth.PermissionAdmin); err != nil {
+			return nil, err
+		}
+	}
+	c, err := s.config.Get()
 	if err != nil {
 		return nil, fmt.Errorf("failed to get config: %w", err)
 	}
-	return connect.NewResponse(config), nil
+	c = auth.FilterConfigForUser(c, auth.UserFromContext(ctx))
+	if !req.Msg.IncludeSecrets {
+		c = config.RedactSecrets(c)
+	}
+	return connect.NewResponse(c), nil
 }
 
 // SetConfig implements POST /v1/config
 func (s *BackrestHandler) SetConfig(ctx context.Context, req *connect.Request[v1.Config]) (*connect.Response[v1.Config], error) {
+	if err := authorizeUnrestricted(ctx, auth.PermissionAdmin); err != nil {
+		return nil, err
+	}
 	existing, err := s.config.Get()
 	if err != nil {
 		return nil, fmt.Errorf("failed to check current config: %w", err)
@@ -79,6 +107,10 @@ func (s *BackrestHandler) SetConfig(ctx context.Context, req *connect.Request[v1
 		return nil, errors.New("config modno mismatch, reload and try again")
 	}
 
+	if err := config.RestoreRedactedSecrets(req.Msg, existing); err != nil {
+		return nil, fmt.Errorf("validation error: %w", err)
+	}
+
 	if err := config.ValidateConfig(req.Msg); err != nil {
 		return nil, fmt.Errorf("validation error: %w", err)
 	}
@@ -93,15 +125,22 @@ func (s *BackrestHandler) SetConfig(ctx context.Context, req *connect.Request[v1
 	if err != nil {
 		return nil, fmt.Errorf("failed to get newly set config: %w", err)
 	}
-	return connect.NewResponse(newConfig), nil
+	return connect.NewResponse(config.RedactSecrets(newConfig)), nil
 }
 
 func (s *BackrestHandler) CheckRepoExists(ctx context.Context, req *connect.Request[v1.Repo]) (*connect.Response[types.BoolValue], error) {
+	if err := authorize(ctx, auth.PermissionAdmin, req.Msg.Id, ""); err != nil {
+		return nil, err
+	}
 	c, err := s.config.Get()
 	if err != nil {
 		return nil, fmt.Errorf("failed to get config: %w", err)
 	}
 
+	if err := config.RestoreRedactedSecrets(&v1.Config{Repos: []*v1.Repo{req.Msg}}, c); err != nil {
+		return nil, fmt.Errorf("validation error: %w", err)
+	}
+
 	c = proto.Clone(c).(*v1.Config)
 	if idx := slices.IndexFunc(c.Repos, func(r *v1.Repo) bool { return r.Id == req.Msg.Id }); idx != -1 {
 		c.Repos[idx] = req.Msg
@@ -143,12 +182,18 @@ func (s *BackrestHandler) CheckRepoExists(ctx context.Context, req *connect.Requ
 
 // AddRepo implements POST /v1/config/repo, it includes validation that the repo can be initialized.
 func (s *BackrestHandler) AddRepo(ctx context.Context, req *connect.Request[v1.Repo]) (*connect.Response[v1.Config], error) {
+	if err := authorize(ctx, auth.PermissionAdmin, req.Msg.Id, ""); err != nil {
+		return nil, err
+	}
 	c, err := s.config.Get()
 	if err != nil {
 		return nil, fmt.Errorf("failed to get config: %w", err)
 	}
 
 	newRepo := req.Msg
+	if err := config.RestoreRedactedSecrets(&v1.Config{Repos: []*v1.Repo{newRepo}}, c); err != nil {
+		return nil, fmt.Errorf("validation error: %w", err)
+	}
 
 	// Deep copy the configuration
 	c = proto.Clone(c).(*v1.Config)
@@ -241,10 +286,13 @@ func (s *BackrestHandler) AddRepo(ctx context.Context, req *connect.Request[v1.R
 	s.orchestrator.ScheduleTask(tasks.NewOneoffIndexSnapshotsTask(newRepo, time.Now()), tasks.TaskPriorityInteractive+tasks.TaskPriorityIndexSnapshots)
 
 	zap.L().Debug("done add repo")
-	return connect.NewResponse(c), nil
+	return connect.NewResponse(config.RedactSecrets(auth.FilterConfigForUser(c, auth.UserFromContext(ctx)))), nil
 }
 
 func (s *BackrestHandler) RemoveRepo(ctx context.Context, req *connect.Request[types.StringValue]) (*connect.Response[v1.Config], error) {
+	if err := authorize(ctx, auth.PermissionAdmin, req.Msg.Value, ""); err != nil {
+		return nil, err
+	}
 	cfg, err := s.config.Get()
 	if err != nil {
 		return nil, fmt.Errorf("failed to get config: %w", err)
@@ -282,13 +330,16 @@ func (s *BackrestHandler) RemoveRepo(ctx context.Context, req *connect.Request[t
 		opIDs = opIDs[batchSize:]
 	}
 
-	return connect.NewResponse(cfg), nil
+	return connect.NewResponse(config.RedactSecrets(auth.FilterConfigForUser(cfg, auth.UserFromContext(ctx)))), nil
 }
 
 // ListSnapshots implements POST /v1/snapshots
 func (s *BackrestHandler) ListSnapshots(ctx context.Context, req *connect.Request[v1.ListSnapshotsRequest]) (*connect.Response[v1.ResticSnapshotList], error) {
 	query := req.Msg
-	repo, err := s.orchestrator.GetRepoOrchestrator(query.RepoId)
+	if err := authorize(ctx, auth.PermissionRead, query.RepoId, query.PlanId); err != nil {
+		return nil, err
+	}
+	repoOrch, err := s.orchestrator.GetRepoOrchestrator(query.RepoId)
 	if err != nil {
 		return nil, fmt.Errorf("failed to get repo: %w", err)
 	}
@@ -300,18 +351,25 @@ func (s *BackrestHandler) ListSnapshots(ctx context.Context, req *connect.Reques
 		if err != nil {
 			return nil, fmt.Errorf("failed to get plan %q: %w", query.PlanId, err)
 		}
-		snapshots, err = repo.SnapshotsForPlan(ctx, plan)
+		snapshots, err = repoOrch.SnapshotsForPlan(ctx, plan)
 	} else {
-		snapshots, err = repo.Snapshots(ctx)
+		snapshots, err = repoOrch.Snapshots(ctx)
 	}
 
 	if err != nil {
 		return nil, fmt.Errorf("failed to list snapshots: %w", err)
 	}
 
-	// Transform the snapshots and return them.
+	// Transform the snapshots and return them, users limited to a subset of plans only see their plans' snapshots.
+	user := auth.UserFromContext(ctx)
+	restricted := user != nil && len(user.GetAllowedPlans()) != 0
 	var rs []*v1.ResticSnapshot
 	for _, snapshot := range snapshots {
+		if restricted {
+			if planID := repo.PlanFromTags(snapshot.Tags); planID == "" || !auth.CanAccessPlan(user, planID) {
+				continue
+			}
+		}
 		rs = append(rs, protoutil.SnapshotToProto(snapshot))
 	}
 
@@ -320,8 +378,206 @@ func (s *BackrestHandler) ListSnapshots(ctx context.Context, req *connect.Reques
 	}), nil
 }
 
+// maxDiffChanges limits the paths returned by DiffSnapshots, e.g. a diff against the first snapshot lists every file.
+const maxDiffChanges = 10000
+
+func (s *BackrestHandler) DiffSnapshots(ctx context.Context, req *connect.Request[v1.DiffSnapshotsRequest]) (*connect.Response[v1.DiffSnapshotsResponse], error) {
+	for _, id := range []string{req.Msg.FromSnapshotId, req.Msg.ToSnapshotId} {
+		if err := restic.ValidateSnapshotId(id); err != nil {
+			return nil, connect.NewError(connect.CodeInvalidArgument, fmt.Errorf("snapshot %q: %w", id, err))
+		}
+	}
+	if err := s.authorizeSnapshots(ctx, auth.PermissionRead, req.Msg.RepoId, req.Msg.PlanId, req.Msg.FromSnapshotId, req.Msg.ToSnapshotId); err != nil {
+		return nil, err
+	}
+	repo, err := s.orchestrator.GetRepoOrchestrator(req.Msg.RepoId)
+	if err != nil {
+		return nil, fmt.Errorf("failed to get repo: %w", err)
+	}
+
+	resp, err := repo.DiffSnapshots(ctx, req.Msg.FromSnapshotId, req.Msg.ToSnapshotId, maxDiffChanges)
+	if err != nil {
+		return nil, err
+	}
+	return connect.NewResponse(resp), nil
+}
+
+// maxFindMatches limits the files returned by FindFiles across all snapshots.
+const maxFindMatches = 10000
+
+func (s *BackrestHandler) FindFiles(ctx context.Context, req *connect.Request[v1.FindFilesRequest]) (*connect.Response[v1.FindFilesResponse], error) {
+	query := req.Msg
+	if err := s.authorizePlanScope(ctx, auth.PermissionRead, query.RepoId, query.PlanId); err != nil {
+		return nil, err
+	}
+	if query.Pattern == "" {
+		return nil, connect.NewError(connect.CodeInvalidArgument, errors.New("pattern is required"))
+	}
+	repo, err := s.orchestrator.GetRepoOrchestrator(query.RepoId)
+	if err != nil {
+		return nil, fmt.Errorf("failed to get repo: %w", err)
+	}
+
+	var snapshots []*restic.Snapshot
+	if query.PlanId != "" {
+		var plan *v1.Plan
+		plan, err = s.orchestrator.GetPlan(query.PlanId)
+		if err != nil {
+			return nil, fmt.Errorf("failed to get plan %q: %w", query.PlanId, err)
+		}
+		snapshots, err = repo.SnapshotsForPlan(ctx, plan)
+	} else {
+		snapshots, err = repo.Snapshots(ctx)
+	}
+	if err != nil {
+		return nil, fmt.Errorf("failed to list snapshots: %w", err)
+	}
+
+	// restic find filters files by modification time, not snapshots by time, so the snapshots are selected here. They're
+	// searched newest first so that the newest matches are returned if the search stops at the limit.
+	slices.SortFunc(snapshots, func(a, b *restic.Snapshot) int {
+		return cmp.Compare(b.UnixTimeMs(), a.UnixTimeMs())
+	})
+	snapshotsByID := make(map[string]*restic.Snapshot)
+	var ids []string
+	for _, snapshot := range snapshots {
+		if query.AfterUnixTimeMs != 0 && snapshot.UnixTimeMs() < query.AfterUnixTimeMs {
+			continue
+		}
+		if query.BeforeUnixTimeMs != 0 && snapshot.UnixTimeMs() > query.BeforeUnixTimeMs {
+			continue
+		}
+		snapshotsByID[snapshot.Id] = snapshot
+		ids = append(ids, snapshot.Id)
+	}
+	if len(ids) == 0 {
+		return connect.NewResponse(&v1.FindFilesResponse{}), nil
+	}
+
+	results, truncated, err := repo.FindFiles(ctx, ids, query.Pattern, query.IgnoreCase, maxFindMatches)
+	if err != nil {
+		return nil, err
+	}
+
+	resp := &v1.FindFilesResponse{Truncated: truncated}
+	for _, result := range results {
+		snapshot, ok := snapshotsByID[result.Snapshot]
+		if !ok || len(result.Matches) == 0 {
+			continue
+		}
+		matches := &v1.FindFilesResponse_SnapshotMatches{
+			Snapshot: protoutil.SnapshotToProto(snapshot),
+		}
+		for _, entry := range result.Matches {
+			matches.Matches = append(matches.Matches, entry.ToProto())
+		}
+		resp.Snapshots = append(resp.Snapshots, matches)
+	}
+	slices.SortFunc(resp.Snapshots, func(a, b *v1.FindFilesResponse_SnapshotMatches) int {
+		return cmp.Compare(b.Snapshot.UnixTimeMs, a.Snapshot.UnixTimeMs)
+	})
+
+	return connect.NewResponse(resp), nil
+}
+
+// maxHistorySnapshots limits the snapshots looked up by a single GetFileHistory request, older snapshots are returned
+// in further pages.
+const maxHistorySnapshots = 20
+
+func (s *BackrestHandler) GetFileHistory(ctx context.Context, req *connect.Request[v1.GetFileHistoryRequest]) (*connect.Response[v1.GetFileHistoryResponse], error) {
+	query := req.Msg
+	if err := s.authorizePlanScope(ctx, auth.PermissionRead, query.RepoId, query.PlanId); err != nil {
+		return nil, err
+	}
+	if query.PlanId == "" {
+		return nil, connect.NewError(connect.CodeInvalidArgument, errors.New("plan id is required"))
+	}
+	if !path.IsAbs(query.Path) {
+		return nil, connect.NewError(connect.CodeInvalidArgument, fmt.Errorf("path %q must be absolute", query.Path))
+	}
+	repoOrch, err := s.orchestrator.GetRepoOrchestrator(query.RepoId)
+	if err != nil {
+		return nil, fmt.Errorf("failed to get repo: %w", err)
+	}
+
+	// the indexed snapshots are used rather than listing the repo's snapshots with restic.
+	q := oplog.Query{}.SetRepoGUID(repoOrch.Config().GetGuid()).SetPlanID(query.PlanId)
+	seen := make(map[string]struct{})
+	var snapshots []*v1.ResticSnapshot
+	if err := s.oplog.Query(q, func(op *v1.Operation) error {
+		indexOp, ok := op.Op.(*v1.Operation_OperationIndexSnapshot)
+		if !ok || indexOp.OperationIndexSnapshot.GetForgot() {
+			return nil
+		}
+		snapshot := indexOp.OperationIndexSnapshot.GetSnapshot()
+		if _, ok := seen[snapshot.GetId()]; ok {
+			return nil
+		}
+		seen[snapshot.GetId()] = struct{}{}
+		snapshots = append(snapshots, snapshot)
+		return nil
+	}); err != nil {
+		return nil, fmt.Errorf("failed to query indexed snapshots: %w", err)
+	}
+	slices.SortFunc(snapshots, func(a, b *v1.ResticSnapshot) int {
+		return cmp.Or(cmp.Compare(b.UnixTimeMs, a.UnixTimeMs), strings.Compare(a.Id, b.Id))
+	})
+
+	// a page starts after the snapshot that ended the previous page.
+	if query.BeforeSnapshotId != "" {
+		idx := slices.IndexFunc(snapshots, func(s *v1.ResticSnapshot) bool { return s.Id == query.BeforeSnapshotId })
+		if idx == -1 {
+			return nil, connect.NewError(connect.CodeInvalidArgument, fmt.Errorf("snapshot %q is not an indexed snapshot of plan %q", query.BeforeSnapshotId, query.PlanId))
+		}
+		snapshots = snapshots[idx+1:]
+	}
+	resp := &v1.GetFileHistoryResponse{}
+	page := snapshots
+	if len(page) > maxHistorySnapshots {
+		page = page[:maxHistorySnapshots]
+		resp.NextBeforeSnapshotId = page[len(page)-1].Id
+	}
+	if len(page) == 0 {
+		return connect.NewResponse(resp), nil
+	}
+
+	// each snapshot's entry is looked up with a non-recursive restic ls. The snapshot after the page is looked up too to
+	// tell whether the oldest version in the page changed.
+	lookups := snapshots[:min(len(snapshots), len(page)+1)]
+	entries := make([]*restic.LsEntry, len(lookups))
+	for i, snapshot := range lookups {
+		entry, err := repoOrch.StatSnapshotPath(ctx, snapshot.Id, query.Path)
+		if errors.Is(err, repo.ErrPathNotFound) {
+			continue
+		} else if err != nil {
+			return nil, err
+		}
+		entries[i] = entry
+	}
+	for i, snapshot := range page {
+		entry := entries[i]
+		if entry == nil {
+			continue
+		}
+		var prev *restic.LsEntry
+		if i+1 < len(entries) {
+			prev = entries[i+1]
+		}
+		resp.Versions = append(resp.Versions, &v1.GetFileHistoryResponse_Version{
+			Snapshot: snapshot,
+			Entry:    entry.ToProto(),
+			Changed:  prev == nil || prev.Size != entry.Size || prev.Mtime != entry.Mtime,
+		})
+	}
+
+	return connect.NewResponse(resp), nil
+}
+
 func (s *BackrestHandler) ListSnapshotFiles(ctx context.Context, req *connect.Request[v1.ListSnapshotFilesRequest]) (*connect.Response[v1.ListSnapshotFilesResponse], error) {
 	query := req.Msg
+	if err := s.authorizeSnapshots(ctx, auth.PermissionRead, query.RepoId, "", query.SnapshotId); err != nil {
+		return nil, err
+	}
 	repo, err := s.orchestrator.GetRepoOrchestrator(query.RepoId)
 	if err != nil {
 		return nil, fmt.Errorf("failed to get repo: %w", err)
@@ -340,6 +596,11 @@ func (s *BackrestHandler) ListSnapshotFiles(ctx context.Context, req *connect.Re
 
 // GetOperationEvents implements GET /v1/events/operations
 func (s *BackrestHandler) GetOperationEvents(ctx context.Context, req *connect.Request[emptypb.Empty], resp *connect.ServerStream[v1.OperationEvent]) error {
+	if err := authorize(ctx, auth.PermissionRead, "", ""); err != nil {
+		return err
+	}
+	user := auth.UserFromContext(ctx)
+
 	errChan := make(chan error, 1)
 	events := make(chan *v1.OperationEvent, 100)
 
@@ -347,6 +608,11 @@ func (s *BackrestHandler) GetOperationEvents(ctx context.Context, req *connect.R
 	defer timer.Stop()
 
 	callback := func(ops []*v1.Operation, eventType oplog.OperationEvent) {
+		ops = filterOperationsForUser(user, ops)
+		if len(ops) == 0 {
+			return
+		}
+
 		var event *v1.OperationEvent
 		switch eventType {
 		case oplog.OPERATION_ADDED:
@@ -420,18 +686,28 @@ func (s *BackrestHandler) GetOperationEvents(ctx context.Context, req *connect.R
 }
 
 func (s *BackrestHandler) GetOperations(ctx context.Context, req *connect.Request[v1.GetOperationsRequest]) (*connect.Response[v1.OperationList], error) {
+	if err := authorize(ctx, auth.PermissionRead, "", ""); err != nil {
+		return nil, err
+	}
 	q, err := protoutil.OpSelectorToQuery(req.Msg.Selector)
 	if req.Msg.LastN != 0 {
 		q.Reversed = true
-		q.Limit = int(req.Msg.LastN)
 	}
 	if err != nil {
 		return nil, err
 	}
 
+	// operations are filtered for the user before the last N are taken so that restricted users get full pages.
+	user := auth.UserFromContext(ctx)
 	var ops []*v1.Operation
 	opCollector := func(op *v1.Operation) error {
+		if !canViewOperation(user, op) {
+			return nil
+		}
 		ops = append(ops, op)
+		if req.Msg.LastN != 0 && len(ops) >= int(req.Msg.LastN) {
+			return oplog.ErrStopIteration
+		}
 		return nil
 	}
 	err = s.oplog.Query(q, opCollector)
@@ -453,6 +729,9 @@ func (s *BackrestHandler) GetOperations(ctx context.Context, req *connect.Reques
 
 func (s *BackrestHandler) IndexSnapshots(ctx context.Context, req *connect.Request[types.StringValue]) (*connect.Response[emptypb.Empty], error) {
 	// Ensure the repo is valid before scheduling the task
+	if err := authorize(ctx, auth.PermissionOperate, req.Msg.Value, ""); err != nil {
+		return nil, err
+	}
 	repo, err := s.orchestrator.GetRepo(req.Msg.Value)
 	if err != nil {
 		return nil, fmt.Errorf("failed to get repo %q: %w", req.Msg.Value, err)
@@ -467,29 +746,57 @@ func (s *BackrestHandler) IndexSnapshots(ctx context.Context, req *connect.Reque
 }
 
 func (s *BackrestHandler) Backup(ctx context.Context, req *connect.Request[types.StringValue]) (*connect.Response[emptypb.Empty], error) {
-	plan, err := s.orchestrator.GetPlan(req.Msg.Value)
-	if err != nil {
+	// the plan is authorized before it's looked up so that callers can't probe for plan IDs.
+	if err := authorize(ctx, auth.PermissionOperate, "", req.Msg.Value); err != nil {
 		return nil, err
 	}
-	repo, err := s.orchestrator.GetRepo(plan.Repo)
+	plan, err := s.orchestrator.GetPlan(req.Msg.Value)
 	if err != nil {
 		return nil, err
 	}
-	wait := make(chan struct{})
-	if err := s.orchestrator.ScheduleTask(tasks.NewOneoffBackupTask(repo, plan, time.Now()), tasks.TaskPriorityInteractive, func(e error) {
-		err = e
-		close(wait)
-	}); err != nil {
-		return nil, err
+	// back up to every repo of the plan, one after another, and report the errors of all of them.
+	var repos []*v1.Repo
+	for _, repoID := range config.PlanRepos(plan) {
+		if err := authorize(ctx, auth.PermissionOperate, repoID, plan.Id); err != nil {
+			return nil, err
+		}
+		repo, err := s.orchestrator.GetRepo(repoID)
+		if err != nil {
+			return nil, err
+		}
+		repos = append(repos, repo)
+	}
+	errs := make([]error, len(repos))
+	var wg sync.WaitGroup
+	for i, repo := range repos {
+		wg.Add(1)
+		if err := s.orchestrator.ScheduleTask(tasks.NewOneoffBackupTask(repo, plan, time.Now()), tasks.TaskPriorityInteractive, func(e error) {
+			if e != nil {
+				errs[i] = fmt.Errorf("backup to repo %q: %w", repo.Id, e)
+			}
+			wg.Done()
+		}); err != nil {
+			wg.Done()
+			errs[i] = err
+		}
 	}
-	<-wait
-	return connect.NewResponse(&emptypb.Empty{}), err
+	wg.Wait()
+	return connect.NewResponse(&emptypb.Empty{}), errors.Join(errs...)
 }
 
 func (s *BackrestHandler) Forget(ctx context.Context, req *connect.Request[v1.ForgetRequest]) (*connect.Response[emptypb.Empty], error) {
 	at := time.Now()
 	var err error
 
+	if req.Msg.SnapshotId != "" {
+		// the snapshot's own plan is authorized, not only the plan named in the request.
+		if err := s.authorizeSnapshots(ctx, auth.PermissionOperate, req.Msg.RepoId, req.Msg.PlanId, req.Msg.SnapshotId); err != nil {
+			return nil, err
+		}
+	} else if err := authorize(ctx, auth.PermissionOperate, req.Msg.RepoId, req.Msg.PlanId); err != nil {
+		return nil, err
+	}
+
 	repo, err := s.orchestrator.GetRepo(req.Msg.RepoId)
 	if err != nil {
 		return nil, err
@@ -526,9 +833,66 @@ func (s *BackrestHandler) Forget(ctx context.Context, req *connect.Request[v1.Fo
 	return connect.NewResponse(&emptypb.Empty{}), nil
 }
 
+func (s *BackrestHandler) PreviewRetention(ctx context.Context, req *connect.Request[v1.PreviewRetentionRequest]) (*connect.Response[v1.PreviewRetentionResponse], error) {
+	plan, err := s.orchestrator.GetPlan(req.Msg.PlanId)
+	if err != nil {
+		return nil, connect.NewError(connect.CodeNotFound, fmt.Errorf("plan %q not found", req.Msg.PlanId))
+	}
+	repoID := req.Msg.RepoId
+	if repoID == "" {
+		repoID = plan.Repo
+	} else if !slices.Contains(config.PlanRepos(plan), repoID) {
+		return nil, connect.NewError(connect.CodeInvalidArgument, fmt.Errorf("plan %q doesn't back up to repo %q", plan.Id, repoID))
+	}
+	if err := authorize(ctx, auth.PermissionRead, repoID, plan.Id); err != nil {
+		return nil, err
+	}
+	if req.Msg.Retention == nil {
+		return nil, connect.NewError(connect.CodeInvalidArgument, errors.New("retention is required"))
+	}
+	if err := config.ValidateRetention(req.Msg.Retention); err != nil {
+		return nil, connect.NewError(connect.CodeInvalidArgument, err)
+	}
+
+	repo, err := s.orchestrator.GetRepoOrchestrator(repoID)
+	if err != nil {
+		return nil, fmt.Errorf("failed to get repo: %w", err)
+	}
+	result, err := repo.PreviewForget(ctx, plan.Id, req.Msg.Retention)
+	if err != nil {
+		return nil, err
+	}
+
+	reasons := make(map[string][]string)
+	for _, reason := range result.Reasons {
+		reasons[reason.Snapshot.Id] = reason.Matches
+	}
+	resp := &v1.PreviewRetentionResponse{}
+	for _, snapshot := range result.Keep {
+		resp.Snapshots = append(resp.Snapshots, &v1.PreviewRetentionResponse_Snapshot{
+			Snapshot: protoutil.SnapshotToProto(&snapshot),
+			Keep:     true,
+			Reasons:  reasons[snapshot.Id],
+		})
+	}
+	for _, snapshot := range result.Remove {
+		resp.Snapshots = append(resp.Snapshots, &v1.PreviewRetentionResponse_Snapshot{
+			Snapshot: protoutil.SnapshotToProto(&snapshot),
+		})
+	}
+	slices.SortFunc(resp.Snapshots, func(a, b *v1.PreviewRetentionResponse_Snapshot) int {
+		return cmp.Compare(b.Snapshot.UnixTimeMs, a.Snapshot.UnixTimeMs)
+	})
+	return connect.NewResponse(resp), nil
+}
+
 func (s BackrestHandler) DoRepoTask(ctx context.Context, req *connect.Request[v1.DoRepoTaskRequest]) (*connect.Response[emptypb.Empty], error) {
 	var task tasks.Task
 
+	if err := authorize(ctx, auth.PermissionOperate, req.Msg.RepoId, ""); err != nil {
+		return nil, err
+	}
+
 	repo, err := s.orchestrator.GetRepo(req.Msg.RepoId)
 	if err != nil {
 		return nil, err
@@ -547,6 +911,11 @@ func (s BackrestHandler) DoRepoTask(ctx context.Context, req *connect.Request[v1
 	case v1.DoRepoTaskRequest_TASK_INDEX_SNAPSHOTS:
 		task = tasks.NewOneoffIndexSnapshotsTask(repo, time.Now())
 		priority |= tasks.TaskPriorityIndexSnapshots
+	case v1.DoRepoTaskRequest_TASK_COPY:
+		if repo.GetCopyPolicy().GetToRepo() == "" {
+			return nil, connect.NewError(connect.CodeFailedPrecondition, fmt.Errorf("repo %q has no copy policy", req.Msg.RepoId))
+		}
+		task = tasks.NewCopyTask(repo, tasks.PlanForSystemTasks, true)
 	case v1.DoRepoTaskRequest_TASK_UNLOCK:
 		repo, err := s.orchestrator.GetRepoOrchestrator(req.Msg.RepoId)
 		if err != nil {
@@ -575,9 +944,17 @@ func (s BackrestHandler) DoRepoTask(ctx context.Context, req *connect.Request[v1
 }
 
 func (s *BackrestHandler) Restore(ctx context.Context, req *connect.Request[v1.RestoreSnapshotRequest]) (*connect.Response[emptypb.Empty], error) {
+	if err := s.authorizeSnapshots(ctx, auth.PermissionRestore, req.Msg.RepoId, req.Msg.PlanId, req.Msg.SnapshotId); err != nil {
+		return nil, err
+	}
+
 	req.Msg.Target = strings.TrimSpace(req.Msg.Target)
 	req.Msg.Path = strings.TrimSpace(req.Msg.Path)
 
+	if req.Msg.TargetDatabase != "" {
+		return s.restoreDatabase(ctx, req.Msg)
+	}
+
 	if req.Msg.Target == "" {
 		req.Msg.Target = path.Join(os.Getenv("HOME"), "Downloads", fmt.Sprintf("restic-restore-%v", time.Now().Format("2006-01-02T15-04-05")))
 	}
@@ -600,7 +977,51 @@ func (s *BackrestHandler) Restore(ctx context.Context, req *connect.Request[v1.R
 	return connect.NewResponse(&emptypb.Empty{}), nil
 }
 
+func (s *BackrestHandler) restoreDatabase(ctx context.Context, req *v1.RestoreSnapshotRequest) (*connect.Response[emptypb.Empty], error) {
+	if req.Path == "" || req.Path == "/" {
+		return nil, errors.New("path to the database dump is required")
+	}
+	plan, err := s.orchestrator.GetPlan(req.PlanId)
+	if err != nil {
+		return nil, err
+	}
+	if plan.GetDatabaseSource() == nil {
+		return nil, fmt.Errorf("plan %q does not back up databases", req.PlanId)
+	}
+	if _, _, err := dbdump.RestoreCommand(plan.GetDatabaseSource(), req.Path, req.TargetDatabase); err != nil {
+		return nil, connect.NewError(connect.CodeInvalidArgument, err)
+	}
+	// the dump is piped into the database with the plan's credentials, it must be one of the plan's own dumps.
+	if !slices.Contains(config.PlanRepos(plan), req.RepoId) {
+		return nil, connect.NewError(connect.CodeInvalidArgument, fmt.Errorf("plan %q does not back up to repo %q", req.PlanId, req.RepoId))
+	}
+
+	repoOrchestrator, err := s.orchestrator.GetRepoOrchestrator(req.RepoId)
+	if err != nil {
+		return nil, err
+	}
+	snapshots, err := repoOrchestrator.SnapshotsByID(ctx, []string{req.SnapshotId})
+	if err != nil {
+		return nil, connect.NewError(connect.CodeInvalidArgument, err)
+	}
+	if repo.PlanFromTags(snapshots[0].Tags) != req.PlanId {
+		return nil, connect.NewError(connect.CodeInvalidArgument, fmt.Errorf("snapshot %q was not created by plan %q", req.SnapshotId, req.PlanId))
+	}
+
+	repo, err := s.orchestrator.GetRepo(req.RepoId)
+	if err != nil {
+		return nil, err
+	}
+
+	s.orchestrator.ScheduleTask(tasks.NewOneoffDatabaseRestoreTask(repo, req.PlanId, 0 /* flowID */, time.Now(), req.SnapshotId, req.Path, req.TargetDatabase), tasks.TaskPriorityInteractive+tasks.TaskPriorityDefault)
+
+	return connect.NewResponse(&emptypb.Empty{}), nil
+}
+
 func (s *BackrestHandler) RunCommand(ctx context.Context, req *connect.Request[v1.RunCommandRequest]) (*connect.Response[types.Int64Value], error) {
+	if err := authorize(ctx, auth.PermissionAdmin, req.Msg.RepoId, ""); err != nil {
+		return nil, err
+	}
 	cfg, err := s.config.Get()
 	if err != nil {
 		return nil, fmt.Errorf("failed to get config: %w", err)
@@ -638,6 +1059,19 @@ func (s *BackrestHandler) RunCommand(ctx context.Context, req *connect.Request[v
 }
 
 func (s *BackrestHandler) Cancel(ctx context.Context, req *connect.Request[types.Int64Value]) (*connect.Response[emptypb.Empty], error) {
+	if err := authorize(ctx, auth.PermissionOperate, "", ""); err != nil {
+		return nil, err
+	}
+	if user := auth.UserFromContext(ctx); user != nil && !auth.IsUnrestricted(user) {
+		op, err := s.oplog.Get(req.Msg.Value)
+		if err != nil {
+			return nil, fmt.Errorf("failed to get operation %v: %w", req.Msg.Value, err)
+		}
+		if err := authorize(ctx, auth.PermissionOperate, op.RepoId, op.PlanId); err != nil {
+			return nil, err
+		}
+	}
+
 	if err := s.orchestrator.CancelOperation(req.Msg.Value, v1.OperationStatus_STATUS_USER_CANCELLED); err != nil {
 		return nil, err
 	}
@@ -646,10 +1080,18 @@ func (s *BackrestHandler) Cancel(ctx context.Context, req *connect.Request[types
 }
 
 func (s *BackrestHandler) ClearHistory(ctx context.Context, req *connect.Request[v1.ClearHistoryRequest]) (*connect.Response[emptypb.Empty], error) {
+	if err := authorize(ctx, auth.PermissionOperate, "", ""); err != nil {
+		return nil, err
+	}
+	user := auth.UserFromContext(ctx)
+
 	var err error
 	var ids []int64
 
 	opCollector := func(op *v1.Operation) error {
+		if !canViewOperation(user, op) {
+			return nil
+		}
 		if !req.Msg.OnlyFailed || op.Status == v1.OperationStatus_STATUS_ERROR {
 			ids = append(ids, op.Id)
 		}
@@ -672,6 +1114,9 @@ func (s *BackrestHandler) ClearHistory(ctx context.Context, req *connect.Request
 }
 
 func (s *BackrestHandler) GetLogs(ctx context.Context, req *connect.Request[v1.LogDataRequest], resp *connect.ServerStream[types.BytesValue]) error {
+	if err := authorize(ctx, auth.PermissionRead, "", ""); err != nil {
+		return err
+	}
 	r, err := s.logStore.Open(req.Msg.Ref)
 	if err != nil {
 		if errors.Is(err, logstore.ErrLogNotFound) {
@@ -764,6 +1209,9 @@ func (s *BackrestHandler) GetDownloadURL(ctx context.Context, req *connect.Reque
 	if !ok {
 		return nil, fmt.Errorf("operation %v is not a restore operation", req.Msg.Value)
 	}
+	if err := authorize(ctx, auth.PermissionRestore, op.RepoId, op.PlanId); err != nil {
+		return nil, err
+	}
 	signature, err := signInt64(op.Id) // the signature authenticates the download URL. Note that the shared URL will be valid for any downloader.
 	if err != nil {
 		return nil, fmt.Errorf("failed to generate signature: %w", err)
@@ -773,7 +1221,58 @@ func (s *BackrestHandler) GetDownloadURL(ctx context.Context, req *connect.Reque
 	}), nil
 }
 
+// snapshotDownloadURLTTL is how long a snapshot download URL can be used to start a download.
+const snapshotDownloadURLTTL = time.Hour
+
+func (s *BackrestHandler) GetSnapshotDownloadURL(ctx context.Context, req *connect.Request[v1.GetSnapshotDownloadURLRequest]) (*connect.Response[types.StringValue], error) {
+	if err := restic.ValidateSnapshotId(req.Msg.SnapshotId); err != nil {
+		return nil, connect.NewError(connect.CodeInvalidArgument, fmt.Errorf("snapshot %q: %w", req.Msg.SnapshotId, err))
+	}
+	// the request's plan isn't used, the snapshot's plan is read from its tags.
+	if err := s.authorizeSnapshots(ctx, auth.PermissionRestore, req.Msg.RepoId, "", req.Msg.SnapshotId); err != nil {
+		return nil, err
+	}
+	if !path.IsAbs(req.Msg.Path) {
+		return nil, connect.NewError(connect.CodeInvalidArgument, fmt.Errorf("path %q must be absolute", req.Msg.Path))
+	}
+	if strings.ContainsRune(req.Msg.Path, 0) {
+		return nil, connect.NewError(connect.CodeInvalidArgument, fmt.Errorf("path %q must not contain NUL", req.Msg.Path))
+	}
+	if _, err := s.orchestrator.GetRepo(req.Msg.RepoId); err != nil {
+		return nil, fmt.Errorf("failed to get repo %q: %w", req.Msg.RepoId, err)
+	}
+	var format string
+	if req.Msg.ArchiveFormat == v1.GetSnapshotDownloadURLRequest_ARCHIVE_FORMAT_ZIP {
+		format = "zip"
+	}
+	// like restore downloads, the URL is valid for any downloader, but only until it expires.
+	expires := time.Now().Add(snapshotDownloadURLTTL).Unix()
+	signature, err := signSnapshotPath(req.Msg.RepoId, req.Msg.SnapshotId, req.Msg.Path, format, expires)
+	if err != nil {
+		return nil, fmt.Errorf("failed to generate signature: %w", err)
+	}
+
+	q := url.Values{}
+	q.Set("repo", req.Msg.RepoId)
+	q.Set("snapshot", req.Msg.SnapshotId)
+	q.Set("path", req.Msg.Path)
+	q.Set("expires", strconv.FormatInt(expires, 10))
+	if format != "" {
+		q.Set("format", format)
+	}
+	name := path.Base(req.Msg.Path)
+	if name == "/" {
+		name = "snapshot"
+	}
+	return connect.NewResponse(&types.StringValue{
+		Value: fmt.Sprintf("./download/snapshot/%s/%s?%s", hex.EncodeToString(signature), url.PathEscape(name), q.Encode()),
+	}), nil
+}
+
 func (s *BackrestHandler) PathAutocomplete(ctx context.Context, path *connect.Request[types.StringValue]) (*connect.Response[types.StringList], error) {
+	if err := authorize(ctx, auth.PermissionRestore, "", ""); err != nil {
+		return nil, err
+	}
 	ents, err := os.ReadDir(path.Msg.Value)
 	if errors.Is(err, os.ErrNotExist) {
 		return connect.NewResponse(&types.StringList{}), nil
@@ -790,12 +1289,16 @@ func (s *BackrestHandler) PathAutocomplete(ctx context.Context, path *connect.Re
 }
 
 func (s *BackrestHandler) GetSummaryDashboard(ctx context.Context, req *connect.Request[emptypb.Empty]) (*connect.Response[v1.SummaryDashboardResponse], error) {
+	if err := authorize(ctx, auth.PermissionRead, "", ""); err != nil {
+		return nil, err
+	}
 	config, err := s.config.Get()
 	if err != nil {
 		return nil, fmt.Errorf("failed to get config: %w", err)
 	}
+	config = auth.FilterConfigForUser(config, auth.UserFromContext(ctx))
 
-	generateSummaryHelper := func(id string, q oplog.Query) (*v1.SummaryDashboardResponse_Summary, error) {
+	generateSummaryHelper := func(id string, q oplog.Query) (*v1.SummaryDashboardResponse_Summary, tasks.BackupHistory, error) {
 		var backupsExamined int64
 		var bytesScanned30 int64
 		var bytesAdded30 int64
@@ -803,13 +1306,20 @@ func (s *BackrestHandler) GetSummaryDashboard(ctx context.Context, req *connect.
 		var backupsSuccess30 int64
 		var backupsWarning30 int64
 		var nextBackupTime int64
+		var history tasks.BackupHistory
+		var examined int // operations examined, bounds the search for the last successful backup.
 		backupChart := &v1.SummaryDashboardResponse_BackupChart{}
 
 		s.oplog.Query(q, func(op *v1.Operation) error {
 			t := time.UnixMilli(op.UnixTimeStartMs)
 
+			examined++
 			if backupOp := op.GetOperationBackup(); backupOp != nil {
+				history.ObserveBackup(op)
 				if time.Since(t) > 30*24*time.Hour {
+					if history.LastSuccess.IsZero() && examined < tasks.BackupHistoryLimit {
+						return nil // keep looking for the last successful backup.
+					}
 					return oplog.ErrStopIteration
 				} else if op.GetStatus() == v1.OperationStatus_STATUS_PENDING {
 					nextBackupTime = op.UnixTimeStartMs
@@ -852,6 +1362,11 @@ func (s *BackrestHandler) GetSummaryDashboard(ctx context.Context, req *connect.
 			backupsExamined = 1 // prevent division by zero for avg calculations
 		}
 
+		var lastSuccessMs int64
+		if !history.LastSuccess.IsZero() {
+			lastSuccessMs = history.LastSuccess.UnixMilli()
+		}
+
 		return &v1.SummaryDashboardResponse_Summary{
 			Id:                        id,
 			BytesScannedLast_30Days:   bytesScanned30,
@@ -862,8 +1377,9 @@ func (s *BackrestHandler) GetSummaryDashboard(ctx context.Context, req *connect.
 			BytesScannedAvg:           bytesScanned30 / backupsExamined,
 			BytesAddedAvg:             bytesAdded30 / backupsExamined,
 			NextBackupTimeMs:          nextBackupTime,
+			LastSuccessfulBackupMs:    lastSuccessMs,
 			RecentBackups:             backupChart,
-		}, nil
+		}, history, nil
 	}
 
 	response := &v1.SummaryDashboardResponse{
@@ -872,7 +1388,7 @@ func (s *BackrestHandler) GetSummaryDashboard(ctx context.Context, req *connect.
 	}
 
 	for _, repo := range config.Repos {
-		resp, err := generateSummaryHelper(repo.Id, oplog.Query{}.
+		resp, _, err := generateSummaryHelper(repo.Id, oplog.Query{}.
 			SetInstanceID(config.Instance).
 			SetRepoGUID(repo.GetGuid()).
 			SetReversed(true).
@@ -885,7 +1401,7 @@ func (s *BackrestHandler) GetSummaryDashboard(ctx context.Context, req *connect.
 	}
 
 	for _, plan := range config.Plans {
-		resp, err := generateSummaryHelper(plan.Id, oplog.Query{}.
+		resp, history, err := generateSummaryHelper(plan.Id, oplog.Query{}.
 			SetInstanceID(config.Instance).
 			SetPlanID(plan.Id).
 			SetReversed(true).
@@ -893,9 +1409,334 @@ func (s *BackrestHandler) GetSummaryDashboard(ctx context.Context, req *connect.
 		if err != nil {
 			return nil, fmt.Errorf("summary for plan %q: %w", plan.Id, err)
 		}
+		resp.Stale = tasks.IsPlanStale(plan, history, history.FirstAttempt, time.Now())
+		if len(plan.Destinations) > 0 {
+			resp.Destinations = s.destinationSummaries(config, plan)
+			for _, d := range resp.Destinations {
+				resp.Stale = resp.Stale || d.Stale // a plan is stale if any of its repos is.
+			}
+		}
 
 		response.PlanSummaries = append(response.PlanSummaries, resp)
 	}
 
 	return connect.NewResponse(response), nil
 }
+
+// destinationSummaries returns the latest backup of a plan to each repo it backs up to.
+func (s *BackrestHandler) destinationSummaries(cfg *v1.Config, plan *v1.Plan) []*v1.SummaryDashboardResponse_DestinationSummary {
+	var summaries []*v1.SummaryDashboardResponse_DestinationSummary
+	for _, repoID := range config.PlanRepos(plan) {
+		repo := config.FindRepo(cfg, repoID)
+		if repo == nil {
+			continue // filtered out for the user.
+		}
+		summary := &v1.SummaryDashboardResponse_DestinationSummary{RepoId: repoID}
+		var history tasks.BackupHistory
+		if err := s.oplog.Query(oplog.Query{}.
+			SetInstanceID(cfg.Instance).
+			SetRepoGUID(repo.Guid).
+			SetPlanID(plan.Id).
+			SetReversed(true).
+			SetLimit(tasks.BackupHistoryLimit), func(op *v1.Operation) error {
+			if op.GetOperationBackup() == nil || op.Status == v1.OperationStatus_STATUS_PENDING || op.Status == v1.OperationStatus_STATUS_INPROGRESS {
+				return nil
+			}
+			history.ObserveBackup(op)
+			if summary.LastBackupMs == 0 {
+				summary.LastBackupMs = op.UnixTimeStartMs
+				summary.LastBackupStatus = op.Status
+			}
+			if !history.LastSuccess.IsZero() {
+				return oplog.ErrStopIteration
+			}
+			return nil
+		}); err != nil {
+			zap.L().Warn("failed to summarize plan destination", zap.String("plan", plan.Id), zap.String("repo", repoID), zap.Error(err))
+			continue
+		}
+		if !history.LastSuccess.IsZero() {
+			summary.LastSuccessfulBackupMs = history.LastSuccess.UnixMilli()
+		}
+		summary.Stale = tasks.IsPlanStale(plan, history, history.FirstAttempt, time.Now())
+		summaries = append(summaries, summary)
+	}
+	return summaries
+}
+
+func (s *BackrestHandler) CreateAPIKey(ctx context.Context, req *connect.Request[v1.CreateAPIKeyRequest]) (*connect.Response[v1.CreateAPIKeyResponse], error) {
+	if err := authorizeCredentialManagement(ctx); err != nil {
+		return nil, err
+	}
+
+	caller := auth.UserFromContext(ctx)
+	username := req.Msg.User
+	if username == "" {
+		if caller == nil {
+			return nil, connect.NewError(connect.CodeInvalidArgument, errors.New("user is required when authentication is disabled"))
+		}
+		username = caller.Name
+	} else if caller != nil && username != caller.Name {
+		if err := authorizeUnrestricted(ctx, auth.PermissionAdmin); err != nil {
+			return nil, err
+		}
+	}
+
+	var expiresAt time.Time
+	if req.Msg.ExpiresAtMs != 0 {
+		expiresAt = time.UnixMilli(req.Msg.ExpiresAtMs)
+		if expiresAt.Before(time.Now()) {
+			return nil, connect.NewError(connect.CodeInvalidArgument, errors.New("expiration time is in the past"))
+		}
+	}
+
+	secret, key, err := s.authenticator.CreateAPIKey(username, req.Msg.Name, expiresAt)
+	if err != nil {
+		return nil, fmt.Errorf("create api key: %w", err)
+	}
+	zap.S().Infof("created api key %q for user %q", key.Name, key.User)
+
+	return connect.NewResponse(&v1.CreateAPIKeyResponse{
+		Key:    key,
+		Secret: secret,
+	}), nil
+}
+
+func (s *BackrestHandler) ListAPIKeys(ctx context.Context, req *connect.Request[emptypb.Empty]) (*connect.Response[v1.APIKeyList], error) {
+	if err := authorize(ctx, auth.PermissionRead, "", ""); err != nil {
+		return nil, err
+	}
+
+	keys, err := s.authenticator.ListAPIKeys()
+	if err != nil {
+		return nil, fmt.Errorf("list api keys: %w", err)
+	}
+
+	// users that are not unrestricted admins only see their own keys.
+	if authorizeUnrestricted(ctx, auth.PermissionAdmin) != nil {
+		caller := auth.UserFromContext(ctx)
+		keys = slices.DeleteFunc(keys, func(k *v1.APIKey) bool { return k.User != caller.Name })
+	}
+
+	return connect.NewResponse(&v1.APIKeyList{Keys: keys}), nil
+}
+
+func (s *BackrestHandler) RevokeAPIKey(ctx context.Context, req *connect.Request[types.StringValue]) (*connect.Response[emptypb.Empty], error) {
+	if err := authorizeCredentialManagement(ctx); err != nil {
+		return nil, err
+	}
+
+	key, err := s.authenticator.GetAPIKey(req.Msg.Value)
+	if err != nil {
+		if errors.Is(err, auth.ErrAPIKeyNotFound) {
+			return nil, connect.NewError(connect.CodeNotFound, err)
+		}
+		return nil, err
+	}
+	if caller := auth.UserFromContext(ctx); caller != nil && caller.Name != key.User {
+		if err := authorizeUnrestricted(ctx, auth.PermissionAdmin); err != nil {
+			return nil, err
+		}
+	}
+
+	if err := s.authenticator.RevokeAPIKey(key.Id); err != nil {
+		return nil, fmt.Errorf("revoke api key: %w", err)
+	}
+	zap.S().Infof("revoked api key %q of user %q", key.Name, key.User)
+
+	return connect.NewResponse(&emptypb.Empty{}), nil
+}
+
+func (s *BackrestHandler) BeginTOTPEnrollment(ctx context.Context, req *connect.Request[emptypb.Empty]) (*connect.Response[v1.TOTPEnrollment], error) {
+	user, err := s.totpUser(ctx)
+	if err != nil {
+		return nil, err
+	}
+	config, err := s.config.Get()
+	if err != nil {
+		return nil, fmt.Errorf("failed to get config: %w", err)
+	}
+
+	secret, uri, err := auth.GenerateTOTPSecret(user.Name, config.Instance)
+	if err != nil {
+		return nil, err
+	}
+	return connect.NewResponse(&v1.TOTPEnrollment{
+		Secret:          secret,
+		ProvisioningUri: uri,
+	}), nil
+}
+
+func (s *BackrestHandler) ConfirmTOTPEnrollment(ctx context.Context, req *connect.Request[v1.ConfirmTOTPEnrollmentRequest]) (*connect.Response[v1.TOTPRecoveryCodes], error) {
+	user, err := s.totpUser(ctx)
+	if err != nil {
+		return nil, err
+	}
+	// replacing an enrolled second factor requires proving possession of the current credentials.
+	if user.GetTotp() != nil {
+		if err := s.verifyCurrentCredential(req, user.Name, req.Msg.CurrentCredential); err != nil {
+			return nil, err
+		}
+	}
+
+	codes, err := s.authenticator.EnrollTOTP(user.Name, req.Msg.Secret, req.Msg.Code)
+	if err != nil {
+		if errors.Is(err, auth.ErrInvalidTOTPCode) {
+			return nil, connect.NewError(connect.CodeInvalidArgument, err)
+		}
+		return nil, fmt.Errorf("enroll totp: %w", err)
+	}
+	zap.S().Infof("enabled totp for user %q", user.Name)
+
+	return connect.NewResponse(&v1.TOTPRecoveryCodes{Codes: codes}), nil
+}
+
+func (s *BackrestHandler) DisableTOTP(ctx context.Context, req *connect.Request[v1.DisableTOTPRequest]) (*connect.Response[emptypb.Empty], error) {
+	user, err := s.totpUser(ctx)
+	if err != nil {
+		return nil, err
+	}
+	username := req.Msg.Username
+	if username == "" || username == user.Name {
+		username = user.Name
+		if err := s.verifyCurrentCredential(req, username, req.Msg.CurrentCredential); err != nil {
+			return nil, err
+		}
+	} else if err := authorizeUnrestricted(ctx, auth.PermissionAdmin); err != nil {
+		return nil, err
+	}
+
+	if err := s.authenticator.DisableTOTP(username); err != nil {
+		if errors.Is(err, auth.ErrUserNotFound) {
+			return nil, connect.NewError(connect.CodeNotFound, err)
+		}
+		return nil, fmt.Errorf("disable totp: %w", err)
+	}
+	zap.S().Infof("disabled totp for user %q", username)
+
+	return connect.NewResponse(&emptypb.Empty{}), nil
+}
+
+// verifyCurrentCredential re-authenticates the user with their password or a one time code before a change to their
+// second factor.
+func (s *BackrestHandler) verifyCurrentCredential(req connect.AnyRequest, username, credential string) error {
+	err := s.authenticator.VerifyCurrentCredential(peerAddr(req), username, credential)
+	switch {
+	case err == nil:
+		return nil
+	case errors.Is(err, auth.ErrLoginLockedOut):
+		return connect.NewError(connect.CodeResourceExhausted, err)
+	case errors.Is(err, auth.ErrCurrentCredentialRequired):
+		return connect.NewError(connect.CodePermissionDenied, err)
+	case errors.Is(err, auth.ErrUserNotFound):
+		return connect.NewError(connect.CodeNotFound, err)
+	default:
+		return fmt.Errorf("verify current credential: %w", err)
+	}
+}
+
+// totpUser returns the logged in user whose second factor is being managed.
+func (s *BackrestHandler) totpUser(ctx context.Context) (*v1.User, error) {
+	if err := authorizeCredentialManagement(ctx); err != nil {
+		return nil, err
+	}
+	user := auth.UserFromContext(ctx)
+	if user == nil {
+		return nil, connect.NewError(connect.CodeFailedPrecondition, errors.New("two factor authentication requires authentication to be enabled"))
+	}
+	return user, nil
+}
+
+func (s *BackrestHandler) ListSessions(ctx context.Context, req *connect.Request[emptypb.Empty]) (*connect.Response[v1.SessionList], error) {
+	user, err := s.sessionUser(ctx)
+	if err != nil {
+		return nil, err
+	}
+	username := user.Name
+	if authorizeUnrestricted(ctx, auth.PermissionAdmin) == nil {
+		username = "" // unrestricted admins see the sessions of all users.
+	}
+
+	sessions, err := s.authenticator.ListSessions(username)
+	if err != nil {
+		return nil, fmt.Errorf("list sessions: %w", err)
+	}
+	currentID := auth.SessionIDFromContext(ctx)
+	for i, session := range sessions {
+		if session.Id == currentID {
+			session = proto.Clone(session).(*v1.Session)
+			session.Current = true
+			sessions[i] = session
+		}
+	}
+	return connect.NewResponse(&v1.SessionList{Sessions: sessions}), nil
+}
+
+func (s *BackrestHandler) RevokeSession(ctx context.Context, req *connect.Request[types.StringValue]) (*connect.Response[emptypb.Empty], error) {
+	user, err := s.sessionUser(ctx)
+	if err != nil {
+		return nil, err
+	}
+
+	session, err := s.authenticator.GetSession(req.Msg.Value)
+	if err != nil {
+		if errors.Is(err, auth.ErrSessionNotFound) {
+			return nil, connect.NewError(connect.CodeNotFound, err)
+		}
+		return nil, fmt.Errorf("get session: %w", err)
+	}
+	if session.User != user.Name {
+		if err := authorizeUnrestricted(ctx, auth.PermissionAdmin); err != nil {
+			return nil, err
+		}
+	}
+
+	if err := s.authenticator.RevokeSession(session.Id); err != nil && !errors.Is(err, auth.ErrSessionNotFound) {
+		return nil, fmt.Errorf("revoke session: %w", err)
+	}
+	zap.S().Infof("revoked session %q of user %q", session.Id, session.User)
+
+	return connect.NewResponse(&emptypb.Empty{}), nil
+}
+
+// sessionUser returns the logged in user whose sessions are being managed.
+func (s *BackrestHandler) sessionUser(ctx context.Context) (*v1.User, error) {
+	if err := authorizeCredentialManagement(ctx); err != nil {
+		return nil, err
+	}
+	user := auth.UserFromContext(ctx)
+	if user == nil {
+		return nil, connect.NewError(connect.CodeFailedPrecondition, errors.New("sessions require authentication to be enabled"))
+	}
+	return user, nil
+}
+
+func (s *BackrestHandler) GetAuditLog(ctx context.Context, req *connect.Request[v1.GetAuditLogRequest]) (*connect.Response[v1.AuditLogEntryList], error) {
+	if err := authorizeUnrestricted(ctx, auth.PermissionAdmin); err != nil {
+		return nil, err
+	}
+	entries, err := s.auditLog.Query(auditlog.Query{
+		User:    req.Msg.User,
+		RPC:     req.Msg.Rpc,
+		SinceMs: req.Msg.SinceMs,
+		Limit:   int(req.Msg.LastN),
+	})
+	if err != nil {
+		return nil, fmt.Errorf("query audit log: %w", err)
+	}
+	return connect.NewResponse(&v1.AuditLogEntryList{Entries: entries}), nil
+}
+
+func (s *BackrestHandler) GetTLSInfo(ctx context.Context, req *connect.Request[emptypb.Empty]) (*connect.Response[v1.TLSInfo], error) {
+	if err := authorize(ctx, auth.PermissionRead, "", ""); err != nil {
+		return nil, err
+	}
+	if s.certs == nil {
+		return connect.NewResponse(&v1.TLSInfo{}), nil
+	}
+	info, err := s.certs.Info()
+	if err != nil {
+		return nil, fmt.Errorf("get tls certificate: %w", err)
+	}
+	return connect.NewResponse(info), nil
+}
@@ -19,6 +19,8 @@ import (
 	"github.com/garethgeorge/backrest/gen/go/types"
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
 	syncapi "github.com/garethgeorge/backrest/internal/api/syncapi"
+	"github.com/garethgeorge/backrest/internal/auditlog"
+	"github.com/garethgeorge/backrest/internal/auth"
 	"github.com/garethgeorge/backrest/internal/config"
 	"github.com/garethgeorge/backrest/internal/cryptoutil"
 	"github.com/garethgeorge/backrest/internal/logstore"
@@ -307,6 +309,404 @@ func TestMultipleBackup(t *testing.T) {
 	}
 }
 
+func TestPreviewRetention(t *testing.T) {
+	t.Parallel()
+
+	sut := createSystemUnderTest(t, createConfigManager(&v1.Config{
+		Modno:    1234,
+		Instance: "test",
+		Repos: []*v1.Repo{
+			{
+				Id:       "local",
+				Guid:     cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+				Uri:      t.TempDir(),
+				Password: "test",
+				Flags:    []string{"--no-cache"},
+			},
+		},
+		Plans: []*v1.Plan{
+			{
+				Id:   "test",
+				Repo: "local",
+				Paths: []string{
+					t.TempDir(),
+				},
+				Schedule: &v1.Schedule{
+					Schedule: &v1.Schedule_Disabled{Disabled: true},
+				},
+				Retention: &v1.RetentionPolicy{
+					Policy: &v1.RetentionPolicy_PolicyKeepAll{PolicyKeepAll: true},
+				},
+			},
+		},
+	}))
+
+	ctx, cancel := testutil.WithDeadlineFromTest(t, context.Background())
+	defer cancel()
+
+	go func() {
+		sut.orch.Run(ctx)
+	}()
+
+	for i := 0; i < 2; i++ {
+		if _, err := sut.handler.Backup(ctx, connect.NewRequest(&types.StringValue{Value: "test"})); err != nil {
+			t.Fatalf("Backup() error = %v", err)
+		}
+	}
+
+	resp, err := sut.handler.PreviewRetention(ctx, connect.NewRequest(&v1.PreviewRetentionRequest{
+		PlanId: "test",
+		Retention: &v1.RetentionPolicy{
+			Policy: &v1.RetentionPolicy_PolicyKeepLastN{PolicyKeepLastN: 1},
+		},
+	}))
+	if err != nil {
+		t.Fatalf("PreviewRetention() error = %v", err)
+	}
+	snapshots := resp.Msg.Snapshots
+	if len(snapshots) != 2 || !snapshots[0].Keep || snapshots[1].Keep {
+		t.Fatalf("expected the newest of 2 snapshots to be kept, got: %v", snapshots)
+	}
+	if !slices.Contains(snapshots[0].Reasons, "last snapshot") {
+		t.Errorf("expected the kept snapshot to be kept as the last snapshot, got reasons: %v", snapshots[0].Reasons)
+	}
+
+	// the preview doesn't forget anything.
+	list, err := sut.handler.ListSnapshots(ctx, connect.NewRequest(&v1.ListSnapshotsRequest{RepoId: "local", PlanId: "test"}))
+	if err != nil {
+		t.Fatalf("ListSnapshots() error = %v", err)
+	}
+	if len(list.Msg.Snapshots) != 2 {
+		t.Errorf("expected 2 snapshots after the preview, got %d", len(list.Msg.Snapshots))
+	}
+
+	if _, err := sut.handler.PreviewRetention(ctx, connect.NewRequest(&v1.PreviewRetentionRequest{
+		PlanId:    "test",
+		Retention: &v1.RetentionPolicy{Policy: &v1.RetentionPolicy_PolicyTimeBucketed{PolicyTimeBucketed: &v1.RetentionPolicy_TimeBucketedCounts{}}},
+	})); connect.CodeOf(err) != connect.CodeInvalidArgument {
+		t.Errorf("expected an invalid argument error for an empty policy, got: %v", err)
+	}
+}
+
+func TestDiffSnapshots(t *testing.T) {
+	t.Parallel()
+	if runtime.GOOS == "windows" {
+		t.Skip("skipping test on windows")
+	}
+
+	dir := t.TempDir()
+	if err := os.WriteFile(filepath.Join(dir, "changed"), []byte("before"), 0644); err != nil {
+		t.Fatalf("write file: %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(dir, "removed"), []byte("removed"), 0644); err != nil {
+		t.Fatalf("write file: %v", err)
+	}
+
+	sut := createSystemUnderTest(t, createConfigManager(&v1.Config{
+		Modno:    1234,
+		Instance: "test",
+		Repos: []*v1.Repo{
+			{
+				Id:       "local",
+				Guid:     cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+				Uri:      t.TempDir(),
+				Password: "test",
+				Flags:    []string{"--no-cache"},
+			},
+		},
+		Plans: []*v1.Plan{
+			{
+				Id:    "test",
+				Repo:  "local",
+				Paths: []string{dir},
+				Schedule: &v1.Schedule{
+					Schedule: &v1.Schedule_Disabled{Disabled: true},
+				},
+			},
+		},
+	}))
+
+	ctx, cancel := testutil.WithDeadlineFromTest(t, context.Background())
+	defer cancel()
+
+	go func() {
+		sut.orch.Run(ctx)
+	}()
+
+	if _, err := sut.handler.Backup(ctx, connect.NewRequest(&types.StringValue{Value: "test"})); err != nil {
+		t.Fatalf("Backup() error = %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(dir, "changed"), []byte("after the change"), 0644); err != nil {
+		t.Fatalf("write file: %v", err)
+	}
+	if err := os.Remove(filepath.Join(dir, "removed")); err != nil {
+		t.Fatalf("remove file: %v", err)
+	}
+	if _, err := sut.handler.Backup(ctx, connect.NewRequest(&types.StringValue{Value: "test"})); err != nil {
+		t.Fatalf("Backup() error = %v", err)
+	}
+
+	list, err := sut.handler.ListSnapshots(ctx, connect.NewRequest(&v1.ListSnapshotsRequest{RepoId: "local", PlanId: "test"}))
+	if err != nil {
+		t.Fatalf("ListSnapshots() error = %v", err)
+	}
+	snapshots := list.Msg.Snapshots
+	if len(snapshots) != 2 {
+		t.Fatalf("expected 2 snapshots, got %d", len(snapshots))
+	}
+	slices.SortFunc(snapshots, func(a, b *v1.ResticSnapshot) int {
+		return int(a.UnixTimeMs - b.UnixTimeMs)
+	})
+
+	resp, err := sut.handler.DiffSnapshots(ctx, connect.NewRequest(&v1.DiffSnapshotsRequest{
+		RepoId:         "local",
+		PlanId:         "test",
+		FromSnapshotId: snapshots[0].Id,
+		ToSnapshotId:   snapshots[1].Id,
+	}))
+	if err != nil {
+		t.Fatalf("DiffSnapshots() error = %v", err)
+	}
+
+	changes := make(map[string]*v1.DiffSnapshotsResponse_Change)
+	for _, c := range resp.Msg.Changes {
+		changes[filepath.Base(c.Path)] = c
+	}
+	if c := changes["changed"]; c == nil || c.Type != v1.DiffSnapshotsResponse_CHANGE_TYPE_MODIFIED || c.SizeBefore != 6 || c.SizeAfter != 16 {
+		t.Errorf("expected changed to be modified from 6 to 16 bytes, got: %v", c)
+	}
+	if c := changes["removed"]; c == nil || c.Type != v1.DiffSnapshotsResponse_CHANGE_TYPE_REMOVED || c.SizeBefore != 7 || c.SizeAfter != 0 {
+		t.Errorf("expected removed to be removed with a size of 7 bytes, got: %v", c)
+	}
+	if resp.Msg.FilesRemoved != 1 || resp.Msg.Truncated {
+		t.Errorf("expected 1 file removed and no truncation, got: %v", resp.Msg)
+	}
+
+	// the diff stops at the change limit, the statistics of the incomplete diff aren't reported.
+	repo, err := sut.orch.GetRepoOrchestrator("local")
+	if err != nil {
+		t.Fatalf("GetRepoOrchestrator() error = %v", err)
+	}
+	truncated, err := repo.DiffSnapshots(ctx, snapshots[0].Id, snapshots[1].Id, 1)
+	if err != nil {
+		t.Fatalf("DiffSnapshots() with a limit error = %v", err)
+	}
+	if len(truncated.Changes) != 1 || !truncated.Truncated || truncated.FilesRemoved != 0 {
+		t.Errorf("expected 1 change, truncation and no statistics, got: %v", truncated)
+	}
+
+	if _, err := sut.handler.DiffSnapshots(ctx, connect.NewRequest(&v1.DiffSnapshotsRequest{
+		RepoId:         "local",
+		FromSnapshotId: "--help",
+		ToSnapshotId:   snapshots[1].Id,
+	})); connect.CodeOf(err) != connect.CodeInvalidArgument {
+		t.Errorf("expected an invalid argument error for a bad snapshot id, got: %v", err)
+	}
+}
+
+func TestFindFiles(t *testing.T) {
+	t.Parallel()
+
+	dir := t.TempDir()
+	if err := os.WriteFile(filepath.Join(dir, "report.xlsx"), []byte("v1"), 0644); err != nil {
+		t.Fatalf("write file: %v", err)
+	}
+
+	sut := createSystemUnderTest(t, createConfigManager(&v1.Config{
+		Modno:    1234,
+		Instance: "test",
+		Repos: []*v1.Repo{
+			{
+				Id:       "local",
+				Guid:     cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+				Uri:      t.TempDir(),
+				Password: "test",
+				Flags:    []string{"--no-cache"},
+			},
+		},
+		Plans: []*v1.Plan{
+			{
+				Id:    "test",
+				Repo:  "local",
+				Paths: []string{dir},
+				Schedule: &v1.Schedule{
+					Schedule: &v1.Schedule_Disabled{Disabled: true},
+				},
+			},
+		},
+	}))
+
+	ctx, cancel := testutil.WithDeadlineFromTest(t, context.Background())
+	defer cancel()
+
+	go func() {
+		sut.orch.Run(ctx)
+	}()
+
+	for i := 0; i < 2; i++ {
+		if _, err := sut.handler.Backup(ctx, connect.NewRequest(&types.StringValue{Value: "test"})); err != nil {
+			t.Fatalf("Backup() error = %v", err)
+		}
+	}
+
+	resp, err := sut.handler.FindFiles(ctx, connect.NewRequest(&v1.FindFilesRequest{
+		RepoId:     "local",
+		PlanId:     "test",
+		Pattern:    "REPORT.*",
+		IgnoreCase: true,
+	}))
+	if err != nil {
+		t.Fatalf("FindFiles() error = %v", err)
+	}
+	if len(resp.Msg.Snapshots) != 2 {
+		t.Fatalf("expected matches in 2 snapshots, got: %v", resp.Msg.Snapshots)
+	}
+	newest := resp.Msg.Snapshots[0]
+	if newest.Snapshot.UnixTimeMs < resp.Msg.Snapshots[1].Snapshot.UnixTimeMs {
+		t.Errorf("expected the newest snapshot first, got: %v", resp.Msg.Snapshots)
+	}
+	if len(newest.Matches) != 1 || newest.Matches[0].Name != "report.xlsx" {
+		t.Errorf("expected report.xlsx to match, got: %v", newest.Matches)
+	}
+
+	// only snapshots in the time range are searched.
+	resp, err = sut.handler.FindFiles(ctx, connect.NewRequest(&v1.FindFilesRequest{
+		RepoId:          "local",
+		PlanId:          "test",
+		Pattern:         "report.xlsx",
+		AfterUnixTimeMs: newest.Snapshot.UnixTimeMs,
+	}))
+	if err != nil {
+		t.Fatalf("FindFiles() error = %v", err)
+	}
+	if len(resp.Msg.Snapshots) != 1 || resp.Msg.Snapshots[0].Snapshot.Id != newest.Snapshot.Id {
+		t.Errorf("expected matches only in snapshot %v, got: %v", newest.Snapshot.Id, resp.Msg.Snapshots)
+	}
+
+	if _, err := sut.handler.FindFiles(ctx, connect.NewRequest(&v1.FindFilesRequest{RepoId: "local"})); connect.CodeOf(err) != connect.CodeInvalidArgument {
+		t.Errorf("expected an invalid argument error for an empty pattern, got: %v", err)
+	}
+}
+
+func TestGetFileHistory(t *testing.T) {
+	t.Parallel()
+	if runtime.GOOS == "windows" {
+		t.Skip("skipping test on windows")
+	}
+
+	dir := t.TempDir()
+	file := filepath.Join(dir, "notes [draft].txt")
+	if err := os.WriteFile(file, []byte("v1"), 0644); err != nil {
+		t.Fatalf("write file: %v", err)
+	}
+
+	sut := createSystemUnderTest(t, createConfigManager(&v1.Config{
+		Modno:    1234,
+		Instance: "test",
+		Repos: []*v1.Repo{
+			{
+				Id:       "local",
+				Guid:     cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+				Uri:      t.TempDir(),
+				Password: "test",
+				Flags:    []string{"--no-cache"},
+			},
+		},
+		Plans: []*v1.Plan{
+			{
+				Id:    "test",
+				Repo:  "local",
+				Paths: []string{dir},
+				Schedule: &v1.Schedule{
+					Schedule: &v1.Schedule_Disabled{Disabled: true},
+				},
+			},
+		},
+	}))
+
+	ctx, cancel := testutil.WithDeadlineFromTest(t, context.Background())
+	defer cancel()
+
+	go func() {
+		sut.orch.Run(ctx)
+	}()
+
+	// the file is unchanged in the second snapshot and modified in the third.
+	for i := 0; i < 3; i++ {
+		if i == 2 {
+			if err := os.WriteFile(file, []byte("version 2"), 0644); err != nil {
+				t.Fatalf("write file: %v", err)
+			}
+		}
+		if _, err := sut.handler.Backup(ctx, connect.NewRequest(&types.StringValue{Value: "test"})); err != nil {
+			t.Fatalf("Backup() error = %v", err)
+		}
+	}
+
+	if err := testutil.Retry(t, ctx, func() error {
+		count := 0
+		for _, op := range getOperations(t, sut.oplog) {
+			if _, ok := op.GetOp().(*v1.Operation_OperationIndexSnapshot); ok {
+				count++
+			}
+		}
+		if count != 3 {
+			return fmt.Errorf("expected 3 indexed snapshots, got %d", count)
+		}
+		return nil
+	}); err != nil {
+		t.Fatalf("Couldn't find indexed snapshots in oplog: %v", err)
+	}
+
+	resp, err := sut.handler.GetFileHistory(ctx, connect.NewRequest(&v1.GetFileHistoryRequest{
+		RepoId: "local",
+		PlanId: "test",
+		Path:   file,
+	}))
+	if err != nil {
+		t.Fatalf("GetFileHistory() error = %v", err)
+	}
+	var changed []bool
+	for _, version := range resp.Msg.Versions {
+		changed = append(changed, version.Changed)
+	}
+	if !slices.Equal(changed, []bool{true, false, true}) {
+		t.Fatalf("expected 3 versions with the newest and oldest changed, got: %v", resp.Msg.Versions)
+	}
+	if resp.Msg.Versions[0].Entry.Size != int64(len("version 2")) || resp.Msg.Versions[2].Entry.Size != int64(len("v1")) {
+		t.Errorf("expected the versions' sizes to be newest first, got: %v", resp.Msg.Versions)
+	}
+	if resp.Msg.NextBeforeSnapshotId != "" {
+		t.Errorf("expected no further pages, got next before snapshot %q", resp.Msg.NextBeforeSnapshotId)
+	}
+
+	// a page lists the snapshots older than the given one, the oldest version is compared with the next snapshot.
+	older, err := sut.handler.GetFileHistory(ctx, connect.NewRequest(&v1.GetFileHistoryRequest{
+		RepoId:           "local",
+		PlanId:           "test",
+		Path:             file,
+		BeforeSnapshotId: resp.Msg.Versions[0].Snapshot.Id,
+	}))
+	if err != nil {
+		t.Fatalf("GetFileHistory() with a before snapshot error = %v", err)
+	}
+	changed = nil
+	for _, version := range older.Msg.Versions {
+		changed = append(changed, version.Changed)
+	}
+	if !slices.Equal(changed, []bool{false, true}) {
+		t.Errorf("expected the 2 older versions with the oldest changed, got: %v", older.Msg.Versions)
+	}
+
+	if _, err := sut.handler.GetFileHistory(ctx, connect.NewRequest(&v1.GetFileHistoryRequest{RepoId: "local", Path: file})); connect.CodeOf(err) != connect.CodeInvalidArgument {
+		t.Errorf("expected an invalid argument error without a plan, got: %v", err)
+	}
+
+	if _, err := sut.handler.GetFileHistory(ctx, connect.NewRequest(&v1.GetFileHistoryRequest{RepoId: "local", PlanId: "test", Path: "relative"})); connect.CodeOf(err) != connect.CodeInvalidArgument {
+		t.Errorf("expected an invalid argument error for a relative path, got: %v", err)
+	}
+}
+
 func TestHookExecution(t *testing.T) {
 	t.Parallel()
 	dir := t.TempDir()
@@ -842,6 +1242,87 @@ func TestRestore(t *testing.T) {
 	}
 }
 
+func TestRestoreDatabaseChecksPlan(t *testing.T) {
+	t.Parallel()
+
+	dir := t.TempDir()
+	sut := createSystemUnderTest(t, createConfigManager(&v1.Config{
+		Modno:    1234,
+		Instance: "test",
+		Repos: []*v1.Repo{
+			{
+				Id:       "local",
+				Guid:     cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+				Uri:      t.TempDir(),
+				Password: "test",
+				Flags:    []string{"--no-cache"},
+			},
+			{
+				Id:       "other",
+				Guid:     cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+				Uri:      t.TempDir(),
+				Password: "test",
+				Flags:    []string{"--no-cache"},
+			},
+		},
+		Plans: []*v1.Plan{
+			{
+				Id:       "db",
+				Repo:     "local",
+				Schedule: &v1.Schedule{Schedule: &v1.Schedule_Disabled{Disabled: true}},
+				DatabaseSource: &v1.DatabaseSource{
+					Engine:    v1.DatabaseSource_ENGINE_POSTGRES,
+					Databases: []string{"app"},
+				},
+			},
+			{
+				Id:       "files",
+				Repo:     "local",
+				Paths:    []string{dir},
+				Schedule: &v1.Schedule{Schedule: &v1.Schedule_Disabled{Disabled: true}},
+			},
+		},
+	}))
+
+	ctx, cancel := testutil.WithDeadlineFromTest(t, context.Background())
+	defer cancel()
+
+	go func() {
+		sut.orch.Run(ctx)
+	}()
+
+	if _, err := sut.handler.Backup(ctx, connect.NewRequest(&types.StringValue{Value: "files"})); err != nil {
+		t.Fatalf("Backup() error = %v", err)
+	}
+	list, err := sut.handler.ListSnapshots(ctx, connect.NewRequest(&v1.ListSnapshotsRequest{RepoId: "local", PlanId: "files"}))
+	if err != nil || len(list.Msg.Snapshots) != 1 {
+		t.Fatalf("ListSnapshots() = %v, %v, want 1 snapshot", list, err)
+	}
+	filesSnapshot := list.Msg.Snapshots[0].Id
+
+	tcs := []struct {
+		name   string
+		repoID string
+	}{
+		{name: "repo the plan doesn't back up to", repoID: "other"},
+		{name: "snapshot of another plan", repoID: "local"},
+	}
+	for _, tc := range tcs {
+		t.Run(tc.name, func(t *testing.T) {
+			_, err := sut.handler.Restore(ctx, connect.NewRequest(&v1.RestoreSnapshotRequest{
+				RepoId:         tc.repoID,
+				PlanId:         "db",
+				SnapshotId:     filesSnapshot,
+				Path:           "/app.sql",
+				TargetDatabase: "app",
+			}))
+			if connect.CodeOf(err) != connect.CodeInvalidArgument {
+				t.Errorf("Restore() error = %v, want an invalid argument error", err)
+			}
+		})
+	}
+}
+
 func TestRunCommand(t *testing.T) {
 	sut := createSystemUnderTest(t, createConfigManager(&v1.Config{
 		Modno:    1234,
@@ -905,6 +1386,187 @@ func TestRunCommand(t *testing.T) {
 	}
 }
 
+func TestRolePermissions(t *testing.T) {
+	t.Parallel()
+
+	sut := createSystemUnderTest(t, createConfigManager(&v1.Config{
+		Modno:    1234,
+		Instance: "test",
+		Repos: []*v1.Repo{
+			{
+				Id:       "local",
+				Guid:     cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+				Uri:      t.TempDir(),
+				Password: "test",
+				Flags:    []string{"--no-cache"},
+			},
+			{
+				Id:       "other",
+				Guid:     cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+				Uri:      t.TempDir(),
+				Password: "test",
+				Flags:    []string{"--no-cache"},
+			},
+		},
+	}))
+
+	helpdesk := &v1.User{
+		Name:         "helpdesk",
+		Role:         v1.User_ROLE_RESTORE_ONLY,
+		AllowedRepos: []string{"local"},
+	}
+	ctx := context.WithValue(context.Background(), auth.UserContextKey, helpdesk)
+
+	if _, err := sut.handler.RunCommand(ctx, connect.NewRequest(&v1.RunCommandRequest{
+		RepoId:  "local",
+		Command: "help",
+	})); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("RunCommand() error = %v, want permission denied", err)
+	}
+
+	if _, err := sut.handler.SetConfig(ctx, connect.NewRequest(sut.config)); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("SetConfig() error = %v, want permission denied", err)
+	}
+
+	if _, err := sut.handler.ListSnapshots(ctx, connect.NewRequest(&v1.ListSnapshotsRequest{
+		RepoId: "other",
+	})); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("ListSnapshots() on a repo outside the allowlist error = %v, want permission denied", err)
+	}
+
+	if _, err := sut.handler.ListSnapshots(ctx, connect.NewRequest(&v1.ListSnapshotsRequest{
+		RepoId: "local",
+	})); err != nil {
+		t.Errorf("ListSnapshots() error = %v", err)
+	}
+
+	res, err := sut.handler.GetConfig(ctx, connect.NewRequest(&v1.GetConfigRequest{}))
+	if err != nil {
+		t.Fatalf("GetConfig() error = %v", err)
+	}
+	if len(res.Msg.Repos) != 1 || res.Msg.Repos[0].Id != "local" {
+		t.Errorf("GetConfig() repos = %v, want only the allowed repo", res.Msg.Repos)
+	}
+}
+
+func TestPlanAllowlistSnapshotAccess(t *testing.T) {
+	t.Parallel()
+
+	dir := t.TempDir()
+	if err := os.WriteFile(filepath.Join(dir, "file.txt"), []byte("hello"), 0644); err != nil {
+		t.Fatalf("write file: %v", err)
+	}
+	sut := createSystemUnderTest(t, createConfigManager(&v1.Config{
+		Modno:    1234,
+		Instance: "test",
+		Repos: []*v1.Repo{
+			{
+				Id:       "local",
+				Guid:     cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+				Uri:      t.TempDir(),
+				Password: "test",
+				Flags:    []string{"--no-cache"},
+			},
+		},
+		Plans: []*v1.Plan{
+			{
+				Id:       "allowed",
+				Repo:     "local",
+				Paths:    []string{dir},
+				Schedule: &v1.Schedule{Schedule: &v1.Schedule_Disabled{Disabled: true}},
+			},
+			{
+				Id:       "denied",
+				Repo:     "local",
+				Paths:    []string{dir},
+				Schedule: &v1.Schedule{Schedule: &v1.Schedule_Disabled{Disabled: true}},
+			},
+		},
+	}))
+
+	ctx, cancel := testutil.WithDeadlineFromTest(t, context.Background())
+	defer cancel()
+
+	go func() {
+		sut.orch.Run(ctx)
+	}()
+
+	snapshotIDs := make(map[string]string)
+	for _, planID := range []string{"allowed", "denied"} {
+		if _, err := sut.handler.Backup(ctx, connect.NewRequest(&types.StringValue{Value: planID})); err != nil {
+			t.Fatalf("Backup(%q) error = %v", planID, err)
+		}
+		list, err := sut.handler.ListSnapshots(ctx, connect.NewRequest(&v1.ListSnapshotsRequest{RepoId: "local", PlanId: planID}))
+		if err != nil || len(list.Msg.Snapshots) != 1 {
+			t.Fatalf("ListSnapshots(%q) = %v, %v, want 1 snapshot", planID, list, err)
+		}
+		snapshotIDs[planID] = list.Msg.Snapshots[0].Id
+	}
+
+	userCtx := context.WithValue(ctx, auth.UserContextKey, &v1.User{
+		Name:         "helpdesk",
+		Role:         v1.User_ROLE_RESTORE_ONLY,
+		AllowedPlans: []string{"allowed"},
+	})
+
+	if _, err := sut.handler.ListSnapshotFiles(userCtx, connect.NewRequest(&v1.ListSnapshotFilesRequest{RepoId: "local", SnapshotId: snapshotIDs["allowed"], Path: "/"})); err != nil {
+		t.Errorf("ListSnapshotFiles() of an allowed snapshot error = %v", err)
+	}
+	if _, err := sut.handler.ListSnapshotFiles(userCtx, connect.NewRequest(&v1.ListSnapshotFilesRequest{RepoId: "local", SnapshotId: snapshotIDs["denied"], Path: "/"})); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("ListSnapshotFiles() of another plan's snapshot error = %v, want permission denied", err)
+	}
+	if _, err := sut.handler.Restore(userCtx, connect.NewRequest(&v1.RestoreSnapshotRequest{RepoId: "local", PlanId: "allowed", SnapshotId: snapshotIDs["denied"], Target: filepath.Join(t.TempDir(), "restore")})); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("Restore() of another plan's snapshot error = %v, want permission denied", err)
+	}
+	if _, err := sut.handler.GetSnapshotDownloadURL(userCtx, connect.NewRequest(&v1.GetSnapshotDownloadURLRequest{RepoId: "local", PlanId: "allowed", SnapshotId: snapshotIDs["denied"], Path: dir})); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("GetSnapshotDownloadURL() of another plan's snapshot error = %v, want permission denied", err)
+	}
+	if _, err := sut.handler.DiffSnapshots(userCtx, connect.NewRequest(&v1.DiffSnapshotsRequest{RepoId: "local", FromSnapshotId: snapshotIDs["denied"], ToSnapshotId: snapshotIDs["allowed"]})); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("DiffSnapshots() with another plan's snapshot error = %v, want permission denied", err)
+	}
+	if _, err := sut.handler.FindFiles(userCtx, connect.NewRequest(&v1.FindFilesRequest{RepoId: "local", Pattern: "file.txt"})); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("FindFiles() without a plan error = %v, want permission denied", err)
+	}
+	if _, err := sut.handler.GetFileHistory(userCtx, connect.NewRequest(&v1.GetFileHistoryRequest{RepoId: "local", Path: filepath.Join(dir, "file.txt")})); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("GetFileHistory() without a plan error = %v, want permission denied", err)
+	}
+	if _, err := sut.handler.FindFiles(userCtx, connect.NewRequest(&v1.FindFilesRequest{RepoId: "local", PlanId: "allowed", Pattern: "file.txt"})); err != nil {
+		t.Errorf("FindFiles() in an allowed plan error = %v", err)
+	}
+	if _, err := sut.handler.FindFiles(userCtx, connect.NewRequest(&v1.FindFilesRequest{RepoId: "other", PlanId: "allowed", Pattern: "file.txt"})); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("FindFiles() in a repo the plan doesn't back up to error = %v, want permission denied", err)
+	}
+
+	list, err := sut.handler.ListSnapshots(userCtx, connect.NewRequest(&v1.ListSnapshotsRequest{RepoId: "local"}))
+	if err != nil {
+		t.Fatalf("ListSnapshots() without a plan error = %v", err)
+	}
+	if len(list.Msg.Snapshots) != 1 || list.Msg.Snapshots[0].Id != snapshotIDs["allowed"] {
+		t.Errorf("ListSnapshots() without a plan = %v, want only the allowed plan's snapshot", list.Msg.Snapshots)
+	}
+
+	// the denied plan's backup ran last, the last visible operation is returned in its place.
+	ops, err := sut.handler.GetOperations(userCtx, connect.NewRequest(&v1.GetOperationsRequest{Selector: &v1.OpSelector{}, LastN: 1}))
+	if err != nil {
+		t.Fatalf("GetOperations() error = %v", err)
+	}
+	if len(ops.Msg.Operations) != 1 || ops.Msg.Operations[0].PlanId == "denied" {
+		t.Errorf("GetOperations() last 1 = %v, want one operation the user may see", ops.Msg.Operations)
+	}
+
+	operatorCtx := context.WithValue(ctx, auth.UserContextKey, &v1.User{
+		Name:         "operator",
+		Role:         v1.User_ROLE_OPERATOR,
+		AllowedPlans: []string{"allowed"},
+	})
+	if _, err := sut.handler.Forget(operatorCtx, connect.NewRequest(&v1.ForgetRequest{RepoId: "local", PlanId: "allowed", SnapshotId: snapshotIDs["denied"]})); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("Forget() of another plan's snapshot error = %v, want permission denied", err)
+	}
+	if _, err := sut.handler.Backup(operatorCtx, connect.NewRequest(&types.StringValue{Value: "does-not-exist"})); connect.CodeOf(err) != connect.CodePermissionDenied {
+		t.Errorf("Backup() of an unknown plan error = %v, want permission denied", err)
+	}
+}
+
 type systemUnderTest struct {
 	handler  *BackrestHandler
 	oplog    *oplog.OpLog
@@ -958,7 +1620,19 @@ func createSystemUnderTest(t *testing.T, config *config.ConfigManager) systemUnd
 		}
 	}
 
-	h := NewBackrestHandler(config, remoteConfigStore, orch, oplog, logStore)
+	auditLog, err := auditlog.NewAuditLog(filepath.Join(dir, "audit.sqlite"))
+	if err != nil {
+		t.Fatalf("Failed to create audit log: %v", err)
+	}
+	t.Cleanup(func() { auditLog.Close() })
+
+	sessions, err := auth.NewSessionStore(filepath.Join(dir, "sessions.sqlite"))
+	if err != nil {
+		t.Fatalf("Failed to create session store: %v", err)
+	}
+	t.Cleanup(func() { sessions.Close() })
+
+	h := NewBackrestHandler(config, remoteConfigStore, orch, oplog, logStore, auth.NewAuthenticator([]byte("key"), config, sessions), auditLog, nil)
 
 	return systemUnderTest{
 		handler:  h,
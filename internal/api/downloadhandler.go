@@ -2,13 +2,19 @@ package api
 
 import (
 	"archive/tar"
+	"archive/zip"
 	"compress/gzip"
+	"context"
 	"crypto/hmac"
 	"encoding/hex"
+	"errors"
 	"fmt"
 	"io"
+	"io/fs"
+	"mime"
 	"net/http"
 	"os"
+	"path"
 	"path/filepath"
 	"strconv"
 	"strings"
@@ -16,13 +22,21 @@ import (
 
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
 	"github.com/garethgeorge/backrest/internal/oplog"
+	"github.com/garethgeorge/backrest/internal/orchestrator"
+	"github.com/garethgeorge/backrest/internal/orchestrator/repo"
 	"go.uber.org/zap"
 )
 
-func NewDownloadHandler(oplog *oplog.OpLog) http.Handler {
+func NewDownloadHandler(oplog *oplog.OpLog, orchestrator *orchestrator.Orchestrator) http.Handler {
 	return http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
 		p := r.URL.Path[1:]
 
+		if signature, ok := strings.CutPrefix(p, "snapshot/"); ok {
+			signature, _, _ = strings.Cut(signature, "/") // the rest of the path is the file name shown by the browser.
+			serveSnapshotDownload(w, r, orchestrator, signature)
+			return
+		}
+
 		opID, signature, filePath, err := parseDownloadPath(p)
 		if err != nil {
 			http.Error(w, "invalid path", http.StatusBadRequest)
@@ -49,9 +63,52 @@ func NewDownloadHandler(oplog *oplog.OpLog) http.Handler {
 			http.Error(w, "restore target not found", http.StatusNotFound)
 			return
 		}
-		fullPath := filepath.Join(targetPath, filePath)
+		filePath = filepath.FromSlash(strings.TrimSuffix(filePath, "/"))
+		if filePath != "" && !filepath.IsLocal(filePath) {
+			http.Error(w, "invalid path", http.StatusBadRequest)
+			return
+		}
+		fullPath, err := resolveInDir(targetPath, filePath)
+		if errors.Is(err, errPathOutsideDir) {
+			http.Error(w, "invalid path", http.StatusBadRequest)
+			return
+		} else if err != nil {
+			http.Error(w, "path not found in restore target", http.StatusNotFound)
+			return
+		}
+		info, err := os.Stat(fullPath)
+		if err != nil {
+			http.Error(w, "path not found in restore target", http.StatusNotFound)
+			return
+		}
+
+		if !info.IsDir() {
+			// a single file is downloaded as is, ServeContent handles range requests.
+			file, err := os.Open(fullPath)
+			if err != nil {
+				zap.S().Errorf("error opening %v: %v", fullPath, err)
+				http.Error(w, "error opening file", http.StatusInternalServerError)
+				return
+			}
+			defer file.Close()
+			w.Header().Set("Content-Disposition", mime.FormatMediaType("attachment", map[string]string{"filename": info.Name()}))
+			http.ServeContent(w, r, info.Name(), info.ModTime(), file)
+			return
+		}
 
-		w.Header().Set("Content-Disposition", fmt.Sprintf("attachment; filename=archive-%v.tar.gz", time.Now().Format("2006-01-02-15-04-05")))
+		// once an archive is being streamed the status can't change, errors are only logged and the archive is cut short.
+		archiveName := fmt.Sprintf("archive-%v", time.Now().Format("2006-01-02-15-04-05"))
+		if r.URL.Query().Get("format") == "zip" {
+			w.Header().Set("Content-Disposition", fmt.Sprintf("attachment; filename=%v.zip", archiveName))
+			w.Header().Set("Content-Type", "application/zip")
+			w.Header().Set("Content-Transfer-Encoding", "binary")
+			if err := zipDirectory(w, fullPath); err != nil {
+				zap.S().Errorf("error creating zip archive: %v", err)
+			}
+			return
+		}
+
+		w.Header().Set("Content-Disposition", fmt.Sprintf("attachment; filename=%v.tar.gz", archiveName))
 		w.Header().Set("Content-Type", "application/gzip")
 		w.Header().Set("Content-Transfer-Encoding", "binary")
 
@@ -63,15 +120,34 @@ func NewDownloadHandler(oplog *oplog.OpLog) http.Handler {
 		}
 		if err := tarDirectory(gzw, fullPath); err != nil {
 			zap.S().Errorf("error creating tar archive: %v", err)
-			http.Error(w, "error creating tar archive", http.StatusInternalServerError)
 			return
 		}
 		if err := gzw.Close(); err != nil {
-			http.Error(w, "error creating tar archive", http.StatusInternalServerError)
+			zap.S().Errorf("error closing gzip writer: %v", err)
 		}
 	})
 }
 
+var errPathOutsideDir = errors.New("path is outside of the directory")
+
+// resolveInDir resolves the symlinks in the local path rel below dir and returns the resulting path, or
+// errPathOutsideDir if it leads out of dir e.g. through a restored symlink to an absolute path.
+func resolveInDir(dir string, rel string) (string, error) {
+	resolvedDir, err := filepath.EvalSymlinks(dir)
+	if err != nil {
+		return "", err
+	}
+	resolved, err := filepath.EvalSymlinks(filepath.Join(resolvedDir, rel))
+	if err != nil {
+		return "", err
+	}
+	relResolved, err := filepath.Rel(resolvedDir, resolved)
+	if err != nil || (relResolved != "." && !filepath.IsLocal(relResolved)) {
+		return "", errPathOutsideDir
+	}
+	return resolved, nil
+}
+
 func parseDownloadPath(p string) (int64, string, string, error) {
 	sep := strings.Index(p, "/")
 	if sep == -1 {
@@ -104,41 +180,298 @@ func checkDownloadURLSignature(id int64, signature string) (bool, error) {
 	return hmac.Equal(wantSignatureBytes, signatureBytes), nil
 }
 
+// serveSnapshotDownload streams a file, or a directory as an archive, out of a snapshot without writing it to disk.
+func serveSnapshotDownload(w http.ResponseWriter, r *http.Request, orchestrator *orchestrator.Orchestrator, signature string) {
+	q := r.URL.Query()
+	repoID, snapshotID, snapshotPath, format := q.Get("repo"), q.Get("snapshot"), q.Get("path"), q.Get("format")
+	expires, err := strconv.ParseInt(q.Get("expires"), 10, 64)
+	if err != nil {
+		http.Error(w, "invalid expiry", http.StatusBadRequest)
+		return
+	}
+	if ok, err := checkSnapshotDownloadSignature(repoID, snapshotID, snapshotPath, format, expires, signature); err != nil || !ok {
+		http.Error(w, fmt.Sprintf("invalid signature: %v", err), http.StatusForbidden)
+		return
+	}
+	if time.Now().Unix() > expires {
+		http.Error(w, "download link expired", http.StatusForbidden)
+		return
+	}
+
+	repoOrch, err := orchestrator.GetRepoOrchestrator(repoID)
+	if err != nil {
+		http.Error(w, "repo not found", http.StatusNotFound)
+		return
+	}
+	entry, err := repoOrch.StatSnapshotPath(r.Context(), snapshotID, snapshotPath)
+	if errors.Is(err, repo.ErrPathNotFound) {
+		http.Error(w, "path not found in snapshot", http.StatusNotFound)
+		return
+	} else if err != nil {
+		zap.S().Errorf("error finding path %q in snapshot %v: %v", snapshotPath, snapshotID, err)
+		http.Error(w, "error finding path in snapshot", http.StatusInternalServerError)
+		return
+	}
+
+	switch entry.Type {
+	case "file":
+		serveSnapshotFile(w, r, repoOrch, snapshotID, entry.Path, entry.Size)
+	case "dir":
+		name := entry.Name
+		if name == "" || name == "/" {
+			name = "snapshot-" + snapshotID[:min(len(snapshotID), 8)]
+		}
+		serveSnapshotArchive(w, r, repoOrch, snapshotID, entry.Path, name, format)
+	default:
+		http.Error(w, "only files and directories can be downloaded", http.StatusBadRequest)
+	}
+}
+
+func serveSnapshotFile(w http.ResponseWriter, r *http.Request, repoOrch *repo.RepoOrchestrator, snapshotID, filePath string, size int64) {
+	contentType := mime.TypeByExtension(path.Ext(filePath))
+	if contentType == "" {
+		contentType = "application/octet-stream"
+	}
+	w.Header().Set("Content-Type", contentType)
+	w.Header().Set("Content-Disposition", mime.FormatMediaType("attachment", map[string]string{"filename": path.Base(filePath)}))
+	w.Header().Set("Accept-Ranges", "bytes")
+
+	status := http.StatusOK
+	start, length := int64(0), size
+	if header := r.Header.Get("Range"); header != "" {
+		var ok bool
+		var err error
+		start, length, ok, err = parseByteRange(header, size)
+		if err != nil {
+			w.Header().Set("Content-Range", fmt.Sprintf("bytes */%d", size))
+			http.Error(w, err.Error(), http.StatusRequestedRangeNotSatisfiable)
+			return
+		}
+		if ok {
+			status = http.StatusPartialContent
+			w.Header().Set("Content-Range", fmt.Sprintf("bytes %d-%d/%d", start, start+length-1, size))
+		} else {
+			start, length = 0, size
+		}
+	}
+	w.Header().Set("Content-Length", strconv.FormatInt(length, 10))
+	w.WriteHeader(status)
+	if r.Method == http.MethodHead {
+		return
+	}
+
+	// restic dump can't seek, the bytes before the range are read and discarded.
+	ctx, cancel := context.WithCancel(r.Context())
+	defer cancel()
+	rw := &rangeWriter{w: w, skip: start, remaining: length, done: cancel}
+	if err := repoOrch.DumpSnapshotPath(ctx, snapshotID, filePath, "", rw); err != nil && rw.remaining != 0 {
+		zap.S().Errorf("error streaming %q from snapshot %v: %v", filePath, snapshotID, err)
+	}
+}
+
+func serveSnapshotArchive(w http.ResponseWriter, r *http.Request, repoOrch *repo.RepoOrchestrator, snapshotID, dirPath, name, format string) {
+	if format == "zip" {
+		w.Header().Set("Content-Disposition", mime.FormatMediaType("attachment", map[string]string{"filename": name + ".zip"}))
+		w.Header().Set("Content-Type", "application/zip")
+		if err := repoOrch.DumpSnapshotPath(r.Context(), snapshotID, dirPath, "zip", w); err != nil {
+			zap.S().Errorf("error streaming %q from snapshot %v as zip: %v", dirPath, snapshotID, err)
+		}
+		return
+	}
+
+	w.Header().Set("Content-Disposition", mime.FormatMediaType("attachment", map[string]string{"filename": name + ".tar.gz"}))
+	w.Header().Set("Content-Type", "application/gzip")
+	gzw, err := gzip.NewWriterLevel(w, gzip.BestSpeed)
+	if err != nil {
+		zap.S().Errorf("error creating gzip writer: %v", err)
+		http.Error(w, "error creating gzip writer", http.StatusInternalServerError)
+		return
+	}
+	if err := repoOrch.DumpSnapshotPath(r.Context(), snapshotID, dirPath, "tar", gzw); err != nil {
+		zap.S().Errorf("error streaming %q from snapshot %v as tar: %v", dirPath, snapshotID, err)
+		return
+	}
+	if err := gzw.Close(); err != nil {
+		zap.S().Errorf("error closing gzip writer: %v", err)
+	}
+}
+
+var errRangeWritten = errors.New("range written")
+
+// rangeWriter writes remaining bytes to w after skipping the first skip bytes, done is called once the range is written.
+type rangeWriter struct {
+	w         io.Writer
+	skip      int64
+	remaining int64
+	done      func()
+}
+
+func (rw *rangeWriter) Write(p []byte) (int, error) {
+	n := len(p)
+	if rw.skip > 0 {
+		skipped := min(rw.skip, int64(len(p)))
+		rw.skip -= skipped
+		p = p[skipped:]
+	}
+	if rw.remaining <= 0 {
+		rw.done()
+		return 0, errRangeWritten
+	}
+	if int64(len(p)) > rw.remaining {
+		p = p[:rw.remaining]
+	}
+	written, err := rw.w.Write(p)
+	rw.remaining -= int64(written)
+	if err != nil {
+		return 0, err
+	}
+	return n, nil
+}
+
+// parseByteRange parses a Range header with a single byte range and returns its start and length. ok is false if the
+// header isn't a single byte range, in which case the whole file is served.
+func parseByteRange(header string, size int64) (start, length int64, ok bool, err error) {
+	spec, found := strings.CutPrefix(header, "bytes=")
+	if !found || strings.Contains(spec, ",") {
+		return 0, 0, false, nil
+	}
+	first, last, found := strings.Cut(strings.TrimSpace(spec), "-")
+	if !found {
+		return 0, 0, false, errors.New("invalid range")
+	}
+	if first == "" {
+		// a suffix range e.g. bytes=-500 is the last 500 bytes.
+		n, err := strconv.ParseInt(last, 10, 64)
+		if err != nil || n <= 0 {
+			return 0, 0, false, errors.New("invalid range")
+		}
+		if size == 0 {
+			return 0, 0, false, errors.New("range not satisfiable")
+		}
+		n = min(n, size)
+		return size - n, n, true, nil
+	}
+	start, err = strconv.ParseInt(first, 10, 64)
+	if err != nil || start < 0 || start >= size {
+		return 0, 0, false, errors.New("range not satisfiable")
+	}
+	end := size - 1
+	if last != "" {
+		end, err = strconv.ParseInt(last, 10, 64)
+		if err != nil || end < start {
+			return 0, 0, false, errors.New("invalid range")
+		}
+		end = min(end, size-1)
+	}
+	return start, end - start + 1, true, nil
+}
+
+func checkSnapshotDownloadSignature(repoID, snapshotID, snapshotPath, format string, expires int64, signature string) (bool, error) {
+	wantSignatureBytes, err := signSnapshotPath(repoID, snapshotID, snapshotPath, format, expires)
+	if err != nil {
+		return false, err
+	}
+	signatureBytes, err := hex.DecodeString(signature)
+	if err != nil {
+		return false, err
+	}
+	return hmac.Equal(wantSignatureBytes, signatureBytes), nil
+}
+
+// tarDirectory writes the contents of dirpath to w as a tar archive. Directories, symlinks, permissions and ownership
+// are preserved, paths are relative to dirpath.
 func tarDirectory(w io.Writer, dirpath string) error {
 	t := tar.NewWriter(w)
-	if err := filepath.Walk(dirpath, func(path string, info os.FileInfo, err error) error {
+	if err := walkArchiveEntries(dirpath, func(name string, path string, info fs.FileInfo, link string) error {
+		hdr, err := tar.FileInfoHeader(info, link) // also fills in the owner's ids and names where the platform has them.
 		if err != nil {
+			return fmt.Errorf("tar header for %v: %w", path, err)
+		}
+		hdr.Name = name
+		if err := t.WriteHeader(hdr); err != nil {
 			return err
 		}
-		if info.IsDir() {
+		if !info.Mode().IsRegular() {
 			return nil
 		}
-		stat, err := os.Stat(path)
+		return copyFileTo(t, path, info.Size())
+	}); err != nil {
+		return err
+	}
+	return t.Close()
+}
+
+// zipDirectory writes the contents of dirpath to w as a zip archive. Symlinks are stored with the link target as their
+// content, which is how zip tools that support symlinks expect them. Zip archives don't record ownership.
+func zipDirectory(w io.Writer, dirpath string) error {
+	z := zip.NewWriter(w)
+	if err := walkArchiveEntries(dirpath, func(name string, path string, info fs.FileInfo, link string) error {
+		hdr, err := zip.FileInfoHeader(info)
 		if err != nil {
-			return fmt.Errorf("stat %v: %w", path, err)
+			return fmt.Errorf("zip header for %v: %w", path, err)
 		}
-		file, err := os.OpenFile(path, os.O_RDONLY, 0)
-		if err != nil {
-			return fmt.Errorf("open %v: %w", path, err)
+		hdr.Name = name
+		if info.Mode().IsRegular() {
+			hdr.Method = zip.Deflate
 		}
-		defer file.Close()
-
-		if err := t.WriteHeader(&tar.Header{
-			Name:    path[len(dirpath)+1:],
-			Size:    stat.Size(),
-			Mode:    int64(stat.Mode()),
-			ModTime: stat.ModTime(),
-		}); err != nil {
+		fw, err := z.CreateHeader(hdr)
+		if err != nil {
 			return err
 		}
-		if n, err := io.CopyN(t, file, stat.Size()); err != nil {
-			zap.L().Warn("error copying file to tar archive", zap.String("path", path), zap.Error(err))
-		} else if n != stat.Size() {
-			zap.L().Warn("error copying file to tar archive: short write", zap.String("path", path))
+		switch {
+		case link != "":
+			_, err = io.WriteString(fw, link)
+			return err
+		case info.Mode().IsRegular():
+			return copyFileTo(fw, path, info.Size())
 		}
 		return nil
 	}); err != nil {
 		return err
 	}
-	return t.Flush()
+	return z.Close()
+}
+
+// walkArchiveEntries calls fn for everything below dirpath without following symlinks. name is the slash separated
+// path relative to dirpath with a trailing slash for directories and link is the target of symlinks.
+func walkArchiveEntries(dirpath string, fn func(name string, path string, info fs.FileInfo, link string) error) error {
+	return filepath.WalkDir(dirpath, func(path string, d fs.DirEntry, err error) error {
+		if err != nil {
+			return err
+		}
+		if path == dirpath {
+			return nil
+		}
+		info, err := d.Info()
+		if err != nil {
+			return fmt.Errorf("stat %v: %w", path, err)
+		}
+		rel, err := filepath.Rel(dirpath, path)
+		if err != nil {
+			return err
+		}
+		name := filepath.ToSlash(rel)
+		if info.IsDir() {
+			name += "/"
+		}
+		var link string
+		if info.Mode()&fs.ModeSymlink != 0 {
+			if link, err = os.Readlink(path); err != nil {
+				return fmt.Errorf("readlink %v: %w", path, err)
+			}
+		}
+		return fn(name, path, info, link)
+	})
+}
+
+func copyFileTo(w io.Writer, path string, size int64) error {
+	file, err := os.Open(path)
+	if err != nil {
+		return fmt.Errorf("open %v: %w", path, err)
+	}
+	defer file.Close()
+	if _, err := io.CopyN(w, file, size); err != nil {
+		return fmt.Errorf("copy %v: %w", path, err)
+	}
+	return nil
 }
@@ -0,0 +1,317 @@
+package api
+
+import (
+	"archive/tar"
+	"archive/zip"
+	"bytes"
+	"context"
+	"encoding/hex"
+	"errors"
+	"fmt"
+	"io"
+	"net/http"
+	"net/http/httptest"
+	"net/url"
+	"os"
+	"path/filepath"
+	"runtime"
+	"slices"
+	"strconv"
+	"strings"
+	"testing"
+	"time"
+
+	"connectrpc.com/connect"
+	"github.com/garethgeorge/backrest/gen/go/types"
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/cryptoutil"
+	"github.com/garethgeorge/backrest/internal/testutil"
+)
+
+func TestParseByteRange(t *testing.T) {
+	tcs := []struct {
+		header     string
+		wantStart  int64
+		wantLength int64
+		wantOk     bool
+		wantErr    bool
+	}{
+		{header: "bytes=0-9", wantStart: 0, wantLength: 10, wantOk: true},
+		{header: "bytes=10-", wantStart: 10, wantLength: 90, wantOk: true},
+		{header: "bytes=90-200", wantStart: 90, wantLength: 10, wantOk: true},
+		{header: "bytes=-5", wantStart: 95, wantLength: 5, wantOk: true},
+		{header: "bytes=-500", wantStart: 0, wantLength: 100, wantOk: true},
+		{header: "bytes=0-1,5-6"},
+		{header: "items=0-1"},
+		{header: "bytes=100-", wantErr: true},
+		{header: "bytes=5-1", wantErr: true},
+		{header: "bytes=abc", wantErr: true},
+	}
+	for _, tc := range tcs {
+		t.Run(tc.header, func(t *testing.T) {
+			start, length, ok, err := parseByteRange(tc.header, 100)
+			if (err != nil) != tc.wantErr {
+				t.Fatalf("parseByteRange() error = %v, wantErr %v", err, tc.wantErr)
+			}
+			if start != tc.wantStart || length != tc.wantLength || ok != tc.wantOk {
+				t.Errorf("parseByteRange() = %d, %d, %v, want %d, %d, %v", start, length, ok, tc.wantStart, tc.wantLength, tc.wantOk)
+			}
+		})
+	}
+}
+
+func TestArchiveDirectory(t *testing.T) {
+	if runtime.GOOS == "windows" {
+		t.Skip("symlinks require extra privileges on windows")
+	}
+
+	dir := t.TempDir()
+	if err := os.MkdirAll(filepath.Join(dir, "sub", "empty"), 0755); err != nil {
+		t.Fatalf("mkdir: %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(dir, "sub", "file.txt"), []byte("hello"), 0640); err != nil {
+		t.Fatalf("write file: %v", err)
+	}
+	if err := os.Symlink("sub/file.txt", filepath.Join(dir, "link")); err != nil {
+		t.Fatalf("symlink: %v", err)
+	}
+	wantNames := []string{"link", "sub/", "sub/empty/", "sub/file.txt"}
+
+	t.Run("tar", func(t *testing.T) {
+		var buf bytes.Buffer
+		if err := tarDirectory(&buf, dir); err != nil {
+			t.Fatalf("tarDirectory() error = %v", err)
+		}
+		var names []string
+		tr := tar.NewReader(&buf)
+		for {
+			hdr, err := tr.Next()
+			if err == io.EOF {
+				break
+			} else if err != nil {
+				t.Fatalf("read tar: %v", err)
+			}
+			names = append(names, hdr.Name)
+			switch hdr.Name {
+			case "link":
+				if hdr.Typeflag != tar.TypeSymlink || hdr.Linkname != "sub/file.txt" {
+					t.Errorf("link header = %+v, want a symlink to sub/file.txt", hdr)
+				}
+			case "sub/file.txt":
+				if data, _ := io.ReadAll(tr); string(data) != "hello" || hdr.Mode&0777 != 0640 || hdr.Uid != os.Getuid() {
+					t.Errorf("file header = %+v with content %q, want mode 0640, uid %d and content hello", hdr, data, os.Getuid())
+				}
+			}
+		}
+		slices.Sort(names)
+		if !slices.Equal(names, wantNames) {
+			t.Errorf("tar entries = %v, want %v", names, wantNames)
+		}
+	})
+
+	t.Run("zip", func(t *testing.T) {
+		var buf bytes.Buffer
+		if err := zipDirectory(&buf, dir); err != nil {
+			t.Fatalf("zipDirectory() error = %v", err)
+		}
+		zr, err := zip.NewReader(bytes.NewReader(buf.Bytes()), int64(buf.Len()))
+		if err != nil {
+			t.Fatalf("read zip: %v", err)
+		}
+		var names []string
+		for _, f := range zr.File {
+			names = append(names, f.Name)
+			if f.Name == "sub/file.txt" || f.Name == "link" {
+				rc, err := f.Open()
+				if err != nil {
+					t.Fatalf("open %v: %v", f.Name, err)
+				}
+				data, _ := io.ReadAll(rc)
+				rc.Close()
+				if want := map[string]string{"sub/file.txt": "hello", "link": "sub/file.txt"}[f.Name]; string(data) != want {
+					t.Errorf("%v contains %q, want %q", f.Name, data, want)
+				}
+			}
+		}
+		slices.Sort(names)
+		if !slices.Equal(names, wantNames) {
+			t.Errorf("zip entries = %v, want %v", names, wantNames)
+		}
+	})
+}
+
+func TestResolveInDir(t *testing.T) {
+	if runtime.GOOS == "windows" {
+		t.Skip("symlinks require extra privileges on windows")
+	}
+
+	outside := t.TempDir()
+	if err := os.WriteFile(filepath.Join(outside, "secret"), []byte("secret"), 0600); err != nil {
+		t.Fatalf("write file: %v", err)
+	}
+	dir := t.TempDir()
+	if err := os.WriteFile(filepath.Join(dir, "file.txt"), []byte("hello"), 0644); err != nil {
+		t.Fatalf("write file: %v", err)
+	}
+	for link, target := range map[string]string{"inside": "file.txt", "outside": outside, "secret": filepath.Join(outside, "secret")} {
+		if err := os.Symlink(target, filepath.Join(dir, link)); err != nil {
+			t.Fatalf("symlink: %v", err)
+		}
+	}
+
+	for _, rel := range []string{"", "file.txt", "inside"} {
+		if _, err := resolveInDir(dir, rel); err != nil {
+			t.Errorf("resolveInDir(%q) error = %v, want nil", rel, err)
+		}
+	}
+	for _, rel := range []string{"outside", "outside/secret", "secret"} {
+		if _, err := resolveInDir(dir, rel); !errors.Is(err, errPathOutsideDir) {
+			t.Errorf("resolveInDir(%q) error = %v, want %v", rel, err, errPathOutsideDir)
+		}
+	}
+}
+
+func TestSnapshotDownload(t *testing.T) {
+	t.Parallel()
+	if runtime.GOOS == "windows" {
+		t.Skip("skipping test on windows")
+	}
+
+	dir := t.TempDir()
+	file := filepath.Join(dir, "hello.txt")
+	if err := os.WriteFile(file, []byte("hello world"), 0644); err != nil {
+		t.Fatalf("write file: %v", err)
+	}
+
+	sut := createSystemUnderTest(t, createConfigManager(&v1.Config{
+		Modno:    1234,
+		Instance: "test",
+		Repos: []*v1.Repo{
+			{
+				Id:       "local",
+				Guid:     cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+				Uri:      t.TempDir(),
+				Password: "test",
+				Flags:    []string{"--no-cache"},
+			},
+		},
+		Plans: []*v1.Plan{
+			{
+				Id:    "test",
+				Repo:  "local",
+				Paths: []string{dir},
+				Schedule: &v1.Schedule{
+					Schedule: &v1.Schedule_Disabled{Disabled: true},
+				},
+			},
+		},
+	}))
+
+	ctx, cancel := testutil.WithDeadlineFromTest(t, context.Background())
+	defer cancel()
+
+	go func() {
+		sut.orch.Run(ctx)
+	}()
+
+	if _, err := sut.handler.Backup(ctx, connect.NewRequest(&types.StringValue{Value: "test"})); err != nil {
+		t.Fatalf("Backup() error = %v", err)
+	}
+	list, err := sut.handler.ListSnapshots(ctx, connect.NewRequest(&v1.ListSnapshotsRequest{RepoId: "local", PlanId: "test"}))
+	if err != nil || len(list.Msg.Snapshots) != 1 {
+		t.Fatalf("ListSnapshots() = %v, %v, want 1 snapshot", list, err)
+	}
+	snapshotID := list.Msg.Snapshots[0].Id
+
+	handler := http.StripPrefix("/download", NewDownloadHandler(sut.oplog, sut.orch))
+	download := func(req *v1.GetSnapshotDownloadURLRequest, header http.Header) *httptest.ResponseRecorder {
+		t.Helper()
+		resp, err := sut.handler.GetSnapshotDownloadURL(ctx, connect.NewRequest(req))
+		if err != nil {
+			t.Fatalf("GetSnapshotDownloadURL() error = %v", err)
+		}
+		r := httptest.NewRequest(http.MethodGet, strings.TrimPrefix(resp.Msg.Value, "."), nil)
+		for k, v := range header {
+			r.Header[k] = v
+		}
+		w := httptest.NewRecorder()
+		handler.ServeHTTP(w, r)
+		return w
+	}
+
+	w := download(&v1.GetSnapshotDownloadURLRequest{RepoId: "local", SnapshotId: snapshotID, Path: file}, nil)
+	if w.Code != http.StatusOK || w.Body.String() != "hello world" {
+		t.Errorf("file download = %d %q, want 200 %q", w.Code, w.Body.String(), "hello world")
+	}
+	if ct := w.Header().Get("Content-Type"); !strings.HasPrefix(ct, "text/plain") {
+		t.Errorf("file download content type = %q, want text/plain", ct)
+	}
+
+	w = download(&v1.GetSnapshotDownloadURLRequest{RepoId: "local", SnapshotId: snapshotID, Path: file}, http.Header{"Range": {"bytes=6-"}})
+	if w.Code != http.StatusPartialContent || w.Body.String() != "world" || w.Header().Get("Content-Range") != "bytes 6-10/11" {
+		t.Errorf("range download = %d %q (%v), want 206 %q", w.Code, w.Body.String(), w.Header().Get("Content-Range"), "world")
+	}
+
+	w = download(&v1.GetSnapshotDownloadURLRequest{RepoId: "local", SnapshotId: snapshotID, Path: dir, ArchiveFormat: v1.GetSnapshotDownloadURLRequest_ARCHIVE_FORMAT_ZIP}, nil)
+	if w.Code != http.StatusOK || !strings.HasPrefix(w.Body.String(), "PK") {
+		t.Errorf("directory download = %d, want a zip archive", w.Code)
+	}
+
+	// the signature covers the path.
+	resp, err := sut.handler.GetSnapshotDownloadURL(ctx, connect.NewRequest(&v1.GetSnapshotDownloadURLRequest{RepoId: "local", SnapshotId: snapshotID, Path: file}))
+	if err != nil {
+		t.Fatalf("GetSnapshotDownloadURL() error = %v", err)
+	}
+	tampered := strings.Replace(strings.TrimPrefix(resp.Msg.Value, "."), "hello.txt", "other.txt", -1)
+	rec := httptest.NewRecorder()
+	handler.ServeHTTP(rec, httptest.NewRequest(http.MethodGet, tampered, nil))
+	if rec.Code != http.StatusForbidden {
+		t.Errorf("download with a tampered path = %d, want 403", rec.Code)
+	}
+
+	// the signature covers the archive format and the expiry.
+	resp, err = sut.handler.GetSnapshotDownloadURL(ctx, connect.NewRequest(&v1.GetSnapshotDownloadURLRequest{RepoId: "local", SnapshotId: snapshotID, Path: dir}))
+	if err != nil {
+		t.Fatalf("GetSnapshotDownloadURL() error = %v", err)
+	}
+	rec = httptest.NewRecorder()
+	handler.ServeHTTP(rec, httptest.NewRequest(http.MethodGet, strings.TrimPrefix(resp.Msg.Value, ".")+"&format=zip", nil))
+	if rec.Code != http.StatusForbidden {
+		t.Errorf("download with a tampered format = %d, want 403", rec.Code)
+	}
+	u, err := url.Parse(strings.TrimPrefix(resp.Msg.Value, "."))
+	if err != nil {
+		t.Fatalf("parse download URL: %v", err)
+	}
+	q := u.Query()
+	q.Set("expires", strconv.FormatInt(time.Now().Add(48*time.Hour).Unix(), 10))
+	u.RawQuery = q.Encode()
+	rec = httptest.NewRecorder()
+	handler.ServeHTTP(rec, httptest.NewRequest(http.MethodGet, u.String(), nil))
+	if rec.Code != http.StatusForbidden {
+		t.Errorf("download with a tampered expiry = %d, want 403", rec.Code)
+	}
+
+	// an expired link is refused even with a valid signature.
+	expires := time.Now().Add(-time.Minute).Unix()
+	signature, err := signSnapshotPath("local", snapshotID, file, "", expires)
+	if err != nil {
+		t.Fatalf("signSnapshotPath() error = %v", err)
+	}
+	expired := fmt.Sprintf("/download/snapshot/%s/hello.txt?%s", hex.EncodeToString(signature), url.Values{
+		"repo":     {"local"},
+		"snapshot": {snapshotID},
+		"path":     {file},
+		"expires":  {strconv.FormatInt(expires, 10)},
+	}.Encode())
+	rec = httptest.NewRecorder()
+	handler.ServeHTTP(rec, httptest.NewRequest(http.MethodGet, expired, nil))
+	if rec.Code != http.StatusForbidden || !strings.Contains(rec.Body.String(), "expired") {
+		t.Errorf("download with an expired link = %d %q, want 403 expired", rec.Code, rec.Body.String())
+	}
+
+	// fields can't contain the separator, moving it between fields would keep the signed bytes the same.
+	if _, err := signSnapshotPath("local", snapshotID+"\x00"+file, "", "", expires); !errors.Is(err, errNULInSignedField) {
+		t.Errorf("signSnapshotPath() with NUL in a field error = %v, want %v", err, errNULInSignedField)
+	}
+}
@@ -5,6 +5,9 @@ import (
 	"crypto/hmac"
 	"crypto/rand"
 	"encoding/binary"
+	"errors"
+	"strconv"
+	"strings"
 )
 
 var (
@@ -31,3 +34,16 @@ func signInt64(data int64) ([]byte, error) {
 	binary.BigEndian.PutUint64(dataBytes, uint64(data))
 	return sign(dataBytes)
 }
+
+var errNULInSignedField = errors.New("signed fields must not contain NUL")
+
+// signSnapshotPath signs a download of a path in a snapshot of a repo in an archive format that expires at a unix time.
+// The fields are separated by NUL, fields containing NUL are rejected so that one field can't spill into the next.
+func signSnapshotPath(repoID, snapshotID, path, format string, expires int64) ([]byte, error) {
+	for _, field := range []string{repoID, snapshotID, path, format} {
+		if strings.ContainsRune(field, 0) {
+			return nil, errNULInSignedField
+		}
+	}
+	return sign([]byte(repoID + "\x00" + snapshotID + "\x00" + path + "\x00" + format + "\x00" + strconv.FormatInt(expires, 10)))
+}
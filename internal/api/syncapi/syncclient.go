@@ -3,11 +3,13 @@ package syncapi
 import (
 	"context"
 	"crypto/tls"
+	"crypto/x509"
 	"errors"
 	"fmt"
 	"io"
 	"net"
 	"net/http"
+	"net/url"
 	"sync"
 	"time"
 
@@ -18,6 +20,7 @@ import (
 	"github.com/garethgeorge/backrest/internal/config"
 	"github.com/garethgeorge/backrest/internal/oplog"
 	"github.com/garethgeorge/backrest/internal/protoutil"
+	"github.com/garethgeorge/backrest/internal/tlsutil"
 	"go.uber.org/zap"
 	"golang.org/x/net/http2"
 	"google.golang.org/protobuf/proto"
@@ -52,13 +55,58 @@ func newInsecureClient() *http.Client {
 	}
 }
 
+// newTLSClient returns a client for peers served over HTTPS. If a fingerprint is set the peer's certificate must match
+// it, which allows self-signed certificates, otherwise the certificate is verified against the system roots.
+func newTLSClient(fingerprint string) *http.Client {
+	tlsConfig := &tls.Config{MinVersion: tls.VersionTLS12}
+	if fingerprint != "" {
+		want := tlsutil.NormalizeFingerprint(fingerprint)
+		tlsConfig.InsecureSkipVerify = true // replaced by the fingerprint check below.
+		tlsConfig.VerifyPeerCertificate = func(rawCerts [][]byte, _ [][]*x509.Certificate) error {
+			if len(rawCerts) == 0 {
+				return errors.New("peer presented no certificate")
+			}
+			if got := tlsutil.Fingerprint(rawCerts[0]); got != want {
+				return fmt.Errorf("peer certificate fingerprint %v does not match the pinned fingerprint %v", got, want)
+			}
+			return nil
+		}
+	}
+	return &http.Client{
+		Transport: &http2.Transport{
+			TLSClientConfig: tlsConfig,
+			IdleConnTimeout: 300 * time.Second,
+			ReadIdleTimeout: 60 * time.Second,
+		},
+	}
+}
+
+func newPeerClient(peer *v1.Multihost_Peer) (*http.Client, error) {
+	u, err := url.Parse(peer.GetInstanceUrl())
+	if err != nil {
+		return nil, fmt.Errorf("parse peer instance URL: %w", err)
+	}
+	switch u.Scheme {
+	case "https":
+		return newTLSClient(peer.GetTlsFingerprint()), nil
+	case "http":
+		return newInsecureClient(), nil
+	default:
+		return nil, fmt.Errorf("unsupported peer instance URL scheme %q", u.Scheme)
+	}
+}
+
 func NewSyncClient(mgr *SyncManager, localInstanceID string, peer *v1.Multihost_Peer, oplog *oplog.OpLog) (*SyncClient, error) {
 	if peer.GetInstanceUrl() == "" {
 		return nil, errors.New("peer instance URL is required")
 	}
 
+	httpClient, err := newPeerClient(peer)
+	if err != nil {
+		return nil, err
+	}
 	client := v1connect.NewBackrestSyncServiceClient(
-		newInsecureClient(),
+		httpClient,
 		peer.GetInstanceUrl(),
 	)
 
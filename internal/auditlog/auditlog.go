@@ -0,0 +1,135 @@
+package auditlog
+
+import (
+	"context"
+	"fmt"
+	"strings"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/sqliteutil"
+	"google.golang.org/protobuf/proto"
+	"zombiezen.com/go/sqlite"
+	"zombiezen.com/go/sqlite/sqlitex"
+)
+
+const defaultQueryLimit = 1000
+
+// AuditLog is an append-only record of mutating API calls. Entries can be added and queried but never modified or deleted.
+type AuditLog struct {
+	dbpool *sqlitex.Pool
+}
+
+// migrations build up the audit log's schema, see sqliteutil.OpenPool.
+var migrations = []string{
+	`
+	CREATE TABLE IF NOT EXISTS audit_log (
+		id INTEGER PRIMARY KEY AUTOINCREMENT,
+		unix_time_ms INTEGER NOT NULL,
+		user TEXT NOT NULL,
+		rpc TEXT NOT NULL,
+		entry BLOB NOT NULL -- serialized AuditLogEntry proto
+	);
+
+	CREATE INDEX IF NOT EXISTS audit_log_unix_time_ms_idx ON audit_log (unix_time_ms);
+
+	-- reject modification of existing entries, the audit log is append only.
+	CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+	BEGIN SELECT RAISE(ABORT, 'audit log is append only'); END;
+	CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+	BEGIN SELECT RAISE(ABORT, 'audit log is append only'); END;
+	`,
+}
+
+func NewAuditLog(dbpath string) (*AuditLog, error) {
+	dbpool, err := sqliteutil.OpenPool(dbpath, 4, migrations)
+	if err != nil {
+		return nil, fmt.Errorf("init audit log: %v", err)
+	}
+	return &AuditLog{dbpool: dbpool}, nil
+}
+
+func (l *AuditLog) Close() error {
+	return l.dbpool.Close()
+}
+
+// Append adds an entry to the audit log.
+func (l *AuditLog) Append(entry *v1.AuditLogEntry) error {
+	data, err := proto.Marshal(entry)
+	if err != nil {
+		return fmt.Errorf("marshal entry: %v", err)
+	}
+
+	conn, err := l.dbpool.Take(context.Background())
+	if err != nil {
+		return fmt.Errorf("take connection: %v", err)
+	}
+	defer l.dbpool.Put(conn)
+
+	if err := sqlitex.ExecuteTransient(conn, "INSERT INTO audit_log (unix_time_ms, user, rpc, entry) VALUES (?, ?, ?, ?)", &sqlitex.ExecOptions{
+		Args: []any{entry.UnixTimeMs, entry.User, entry.Rpc, data},
+	}); err != nil {
+		return fmt.Errorf("insert entry: %v", err)
+	}
+	return nil
+}
+
+// Query selects audit log entries, all set fields must match.
+type Query struct {
+	User    string
+	RPC     string
+	SinceMs int64
+	Limit   int // defaults to 1000.
+}
+
+// Query returns the entries matching the query newest first.
+func (l *AuditLog) Query(q Query) ([]*v1.AuditLogEntry, error) {
+	var where []string
+	var args []any
+	if q.User != "" {
+		where = append(where, "user = ?")
+		args = append(args, q.User)
+	}
+	if q.RPC != "" {
+		where = append(where, "rpc = ?")
+		args = append(args, q.RPC)
+	}
+	if q.SinceMs != 0 {
+		where = append(where, "unix_time_ms >= ?")
+		args = append(args, q.SinceMs)
+	}
+	limit := q.Limit
+	if limit <= 0 {
+		limit = defaultQueryLimit
+	}
+	args = append(args, limit)
+
+	query := "SELECT entry FROM audit_log"
+	if len(where) > 0 {
+		query += " WHERE " + strings.Join(where, " AND ")
+	}
+	query += " ORDER BY id DESC LIMIT ?"
+
+	conn, err := l.dbpool.Take(context.Background())
+	if err != nil {
+		return nil, fmt.Errorf("take connection: %v", err)
+	}
+	defer l.dbpool.Put(conn)
+
+	var entries []*v1.AuditLogEntry
+	if err := sqlitex.ExecuteTransient(conn, query, &sqlitex.ExecOptions{
+		Args: args,
+		ResultFunc: func(stmt *sqlite.Stmt) error {
+			data := make([]byte, stmt.ColumnLen(0))
+			stmt.ColumnBytes(0, data)
+			entry := &v1.AuditLogEntry{}
+			if err := proto.Unmarshal(data, entry); err != nil {
+				return fmt.Errorf("unmarshal entry: %v", err)
+			}
+			entries = append(entries, entry)
+			return nil
+		},
+	}); err != nil {
+		return nil, fmt.Errorf("query entries: %v", err)
+	}
+	return entries, nil
+}
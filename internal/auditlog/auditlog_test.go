@@ -0,0 +1,120 @@
+package auditlog
+
+import (
+	"path/filepath"
+	"testing"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"zombiezen.com/go/sqlite/sqlitex"
+)
+
+func TestAuditLog(t *testing.T) {
+	log, err := NewAuditLog(filepath.Join(t.TempDir(), "audit.sqlite"))
+	if err != nil {
+		t.Fatalf("NewAuditLog() error = %v", err)
+	}
+	defer log.Close()
+
+	entries := []*v1.AuditLogEntry{
+		{UnixTimeMs: 1000, User: "alice", Rpc: "/v1.Backrest/Backup", Summary: `plan "p1"`, Result: "ok"},
+		{UnixTimeMs: 2000, User: "bob", Rpc: "/v1.Backrest/SetConfig", Summary: `added repo "r1"`, Result: "ok"},
+		{UnixTimeMs: 3000, User: "alice", Rpc: "/v1.Backrest/RunCommand", Summary: `repo "r1" command "unlock"`, Result: "permission_denied"},
+	}
+	for _, e := range entries {
+		if err := log.Append(e); err != nil {
+			t.Fatalf("Append() error = %v", err)
+		}
+	}
+
+	tests := []struct {
+		name      string
+		query     Query
+		wantTimes []int64
+	}{
+		{"all newest first", Query{}, []int64{3000, 2000, 1000}},
+		{"by user", Query{User: "alice"}, []int64{3000, 1000}},
+		{"by rpc", Query{RPC: "/v1.Backrest/SetConfig"}, []int64{2000}},
+		{"since", Query{SinceMs: 2000}, []int64{3000, 2000}},
+		{"limit", Query{Limit: 1}, []int64{3000}},
+	}
+
+	for _, tc := range tests {
+		t.Run(tc.name, func(t *testing.T) {
+			got, err := log.Query(tc.query)
+			if err != nil {
+				t.Fatalf("Query() error = %v", err)
+			}
+			var gotTimes []int64
+			for _, e := range got {
+				gotTimes = append(gotTimes, e.UnixTimeMs)
+			}
+			if len(gotTimes) != len(tc.wantTimes) {
+				t.Fatalf("Query() returned entries at %v, want %v", gotTimes, tc.wantTimes)
+			}
+			for i := range gotTimes {
+				if gotTimes[i] != tc.wantTimes[i] {
+					t.Errorf("Query() returned entries at %v, want %v", gotTimes, tc.wantTimes)
+					break
+				}
+			}
+		})
+	}
+
+	conn, err := log.dbpool.Take(t.Context())
+	if err != nil {
+		t.Fatalf("take connection: %v", err)
+	}
+	defer log.dbpool.Put(conn)
+	if err := sqlitex.ExecuteTransient(conn, "DELETE FROM audit_log", nil); err == nil {
+		t.Errorf("expected deleting audit log entries to fail")
+	}
+}
+
+func TestDiffConfig(t *testing.T) {
+	before := &v1.Config{
+		Modno:    1,
+		Instance: "test",
+		Repos:    []*v1.Repo{{Id: "r1", Password: "secret"}, {Id: "r2"}},
+		Plans:    []*v1.Plan{{Id: "p1", Repo: "r1"}},
+	}
+
+	tests := []struct {
+		name  string
+		after *v1.Config
+		want  string
+	}{
+		{
+			name:  "no changes",
+			after: &v1.Config{Modno: 2, Instance: "test", Repos: before.Repos, Plans: before.Plans},
+			want:  "no changes",
+		},
+		{
+			name: "repo changes omit values",
+			after: &v1.Config{
+				Modno:    2,
+				Instance: "test",
+				Repos:    []*v1.Repo{{Id: "r1", Password: "changed"}, {Id: "r3"}},
+				Plans:    before.Plans,
+			},
+			want: `modified repo "r1" (password); removed repo "r2"; added repo "r3"`,
+		},
+		{
+			name: "plan and top level changes",
+			after: &v1.Config{
+				Modno:    2,
+				Instance: "test",
+				Repos:    before.Repos,
+				Auth:     &v1.Auth{Disabled: true},
+			},
+			want: `removed plan "p1"; modified auth`,
+		},
+	}
+
+	for _, tc := range tests {
+		t.Run(tc.name, func(t *testing.T) {
+			if got := DiffConfig(before, tc.after); got != tc.want {
+				t.Errorf("DiffConfig() = %q, want %q", got, tc.want)
+			}
+		})
+	}
+}
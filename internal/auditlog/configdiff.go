@@ -0,0 +1,77 @@
+package auditlog
+
+import (
+	"fmt"
+	"slices"
+	"strings"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"google.golang.org/protobuf/proto"
+	"google.golang.org/protobuf/reflect/protoreflect"
+)
+
+// DiffConfig summarizes the differences between two configs e.g. "added repo r1; modified plan p1 (retention, schedule)".
+// Only the names of changed fields are reported, values are omitted so that secrets never reach the audit log.
+func DiffConfig(before, after *v1.Config) string {
+	if before == nil {
+		before = &v1.Config{}
+	}
+	if after == nil {
+		after = &v1.Config{}
+	}
+
+	var changes []string
+	changes = append(changes, diffByID("repo", before.Repos, after.Repos, func(r *v1.Repo) string { return r.Id })...)
+	changes = append(changes, diffByID("plan", before.Plans, after.Plans, func(p *v1.Plan) string { return p.Id })...)
+
+	if fields := changedFields(before, after, "modno", "repos", "plans"); len(fields) > 0 {
+		changes = append(changes, fmt.Sprintf("modified %v", strings.Join(fields, ", ")))
+	}
+
+	if len(changes) == 0 {
+		return "no changes"
+	}
+	return strings.Join(changes, "; ")
+}
+
+func diffByID[T proto.Message](kind string, before, after []T, id func(T) string) []string {
+	var changes []string
+	for _, b := range before {
+		idx := slices.IndexFunc(after, func(a T) bool { return id(a) == id(b) })
+		if idx == -1 {
+			changes = append(changes, fmt.Sprintf("removed %v %q", kind, id(b)))
+		} else if fields := changedFields(b, after[idx]); len(fields) > 0 {
+			changes = append(changes, fmt.Sprintf("modified %v %q (%v)", kind, id(b), strings.Join(fields, ", ")))
+		}
+	}
+	for _, a := range after {
+		if !slices.ContainsFunc(before, func(b T) bool { return id(a) == id(b) }) {
+			changes = append(changes, fmt.Sprintf("added %v %q", kind, id(a)))
+		}
+	}
+	return changes
+}
+
+// changedFields returns the names of the top level fields that differ between two messages of the same type.
+func changedFields(before, after proto.Message, ignore ...string) []string {
+	b, a := before.ProtoReflect(), after.ProtoReflect()
+	var fields []string
+	descs := b.Descriptor().Fields()
+	for i := 0; i < descs.Len(); i++ {
+		fd := descs.Get(i)
+		if slices.Contains(ignore, string(fd.Name())) {
+			continue
+		}
+		if !fieldEqual(fd, b, a) {
+			fields = append(fields, string(fd.Name()))
+		}
+	}
+	return fields
+}
+
+func fieldEqual(fd protoreflect.FieldDescriptor, b, a protoreflect.Message) bool {
+	if b.Has(fd) != a.Has(fd) {
+		return false
+	}
+	return b.Get(fd).Equal(a.Get(fd))
+}
@@ -0,0 +1,216 @@
+package auth
+
+import (
+	"context"
+	"crypto/sha256"
+	"crypto/subtle"
+	"encoding/hex"
+	"errors"
+	"fmt"
+	"slices"
+	"strings"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/cryptoutil"
+	"go.uber.org/zap"
+	"google.golang.org/protobuf/proto"
+)
+
+// APIKeyHeader is the HTTP header clients use to present an API key. Keys are also accepted as bearer tokens.
+const APIKeyHeader = "X-API-Key"
+
+const apiKeyPrefix = "brk_"
+
+// lastUsedPersistInterval is how stale the persisted last used time of a key may become before a use is written back to the config.
+const lastUsedPersistInterval = time.Hour
+
+var ErrInvalidAPIKey = errors.New("invalid api key")
+var ErrAPIKeyExpired = errors.New("api key expired")
+var ErrAPIKeyNotFound = errors.New("api key not found")
+
+// IsAPIKey returns true if the token has the format of an API key rather than a JWT.
+func IsAPIKey(token string) bool {
+	return strings.HasPrefix(token, apiKeyPrefix)
+}
+
+// APIKeyFromContext returns the API key used to authenticate a request or nil if the request did not use one.
+func APIKeyFromContext(ctx context.Context) *v1.APIKey {
+	key, _ := ctx.Value(APIKeyContextKey).(*v1.APIKey)
+	return key
+}
+
+// CreateAPIKey mints a new key acting as the named user and stores its hash in the config. The returned secret is the
+// only copy of the key, it cannot be recovered later.
+func (a *Authenticator) CreateAPIKey(username, name string, expiresAt time.Time) (string, *v1.APIKey, error) {
+	if name == "" {
+		return "", nil, errors.New("api key name is required")
+	}
+
+	id, err := cryptoutil.RandomID(64)
+	if err != nil {
+		return "", nil, fmt.Errorf("generate key id: %w", err)
+	}
+	secret, err := cryptoutil.RandomID(cryptoutil.DefaultIDBits)
+	if err != nil {
+		return "", nil, fmt.Errorf("generate key secret: %w", err)
+	}
+
+	key := &v1.APIKey{
+		Id:          id,
+		Name:        name,
+		User:        username,
+		KeyHash:     hashAPIKeySecret(secret),
+		CreatedAtMs: time.Now().UnixMilli(),
+	}
+	if !expiresAt.IsZero() {
+		key.ExpiresAtMs = expiresAt.UnixMilli()
+	}
+
+	if err := a.updateAPIKeys(func(keys []*v1.APIKey) ([]*v1.APIKey, error) {
+		return append(keys, key), nil
+	}); err != nil {
+		return "", nil, err
+	}
+
+	return apiKeyPrefix + id + "_" + secret, redactAPIKey(key), nil
+}
+
+// RevokeAPIKey removes the key with the given ID from the config, requests presenting it are rejected immediately.
+func (a *Authenticator) RevokeAPIKey(id string) error {
+	return a.updateAPIKeys(func(keys []*v1.APIKey) ([]*v1.APIKey, error) {
+		idx := slices.IndexFunc(keys, func(k *v1.APIKey) bool { return k.Id == id })
+		if idx == -1 {
+			return nil, ErrAPIKeyNotFound
+		}
+		return slices.Delete(keys, idx, idx+1), nil
+	})
+}
+
+// GetAPIKey returns the key with the given ID with its hash omitted.
+func (a *Authenticator) GetAPIKey(id string) (*v1.APIKey, error) {
+	keys, err := a.ListAPIKeys()
+	if err != nil {
+		return nil, err
+	}
+	idx := slices.IndexFunc(keys, func(k *v1.APIKey) bool { return k.Id == id })
+	if idx == -1 {
+		return nil, ErrAPIKeyNotFound
+	}
+	return keys[idx], nil
+}
+
+// ListAPIKeys returns all configured keys with their hashes omitted and the most recent last used time.
+func (a *Authenticator) ListAPIKeys() ([]*v1.APIKey, error) {
+	config, err := a.config.Get()
+	if err != nil {
+		return nil, fmt.Errorf("get config: %w", err)
+	}
+
+	a.mu.Lock()
+	defer a.mu.Unlock()
+	var keys []*v1.APIKey
+	for _, key := range config.GetAuth().GetApiKeys() {
+		key = redactAPIKey(key)
+		if lastUsed := a.apiKeyLastUsed[key.Id]; lastUsed > key.LastUsedAtMs {
+			key.LastUsedAtMs = lastUsed
+		}
+		keys = append(keys, key)
+	}
+	return keys, nil
+}
+
+// VerifyAPIKey returns the user the key acts as and the key itself if the key is valid.
+func (a *Authenticator) VerifyAPIKey(token string) (*v1.User, *v1.APIKey, error) {
+	id, secret, ok := strings.Cut(strings.TrimPrefix(token, apiKeyPrefix), "_")
+	if !ok || !IsAPIKey(token) {
+		return nil, nil, ErrInvalidAPIKey
+	}
+
+	config, err := a.config.Get()
+	if err != nil {
+		return nil, nil, fmt.Errorf("get config: %w", err)
+	}
+	auth := config.GetAuth()
+
+	idx := slices.IndexFunc(auth.GetApiKeys(), func(k *v1.APIKey) bool { return k.Id == id })
+	if idx == -1 {
+		return nil, nil, ErrInvalidAPIKey
+	}
+	key := auth.GetApiKeys()[idx]
+	if subtle.ConstantTimeCompare([]byte(key.KeyHash), []byte(hashAPIKeySecret(secret))) != 1 {
+		return nil, nil, ErrInvalidAPIKey
+	}
+	now := time.Now()
+	if key.ExpiresAtMs != 0 && now.UnixMilli() > key.ExpiresAtMs {
+		return nil, nil, ErrAPIKeyExpired
+	}
+
+	userIdx := slices.IndexFunc(auth.GetUsers(), func(u *v1.User) bool { return u.Name == key.User })
+	if userIdx == -1 {
+		return nil, nil, fmt.Errorf("api key %q: %w", key.Name, ErrUserNotFound)
+	}
+
+	a.recordAPIKeyUse(key, now)
+	return auth.GetUsers()[userIdx], key, nil
+}
+
+// recordAPIKeyUse tracks the last use of a key in memory and only occasionally writes it back to the config,
+// writing the config on every request would churn config backups and reschedule tasks.
+func (a *Authenticator) recordAPIKeyUse(key *v1.APIKey, now time.Time) {
+	a.mu.Lock()
+	a.apiKeyLastUsed[key.Id] = now.UnixMilli()
+	persistedAt := max(key.LastUsedAtMs, a.apiKeyPersistedAt[key.Id])
+	shouldPersist := now.Sub(time.UnixMilli(persistedAt)) >= lastUsedPersistInterval
+	if shouldPersist {
+		a.apiKeyPersistedAt[key.Id] = now.UnixMilli()
+	}
+	a.mu.Unlock()
+
+	if !shouldPersist {
+		return
+	}
+	go func() {
+		if err := a.updateAPIKeys(func(keys []*v1.APIKey) ([]*v1.APIKey, error) { return keys, nil }); err != nil {
+			zap.L().Warn("failed to persist api key last used time", zap.String("key", key.Name), zap.Error(err))
+		}
+	}()
+}
+
+// updateAPIKeys applies fn to the configured keys and writes the result back to the config. Last used times tracked
+// in memory are persisted as part of every update.
+func (a *Authenticator) updateAPIKeys(fn func(keys []*v1.APIKey) ([]*v1.APIKey, error)) error {
+	return a.updateConfig(func(config *v1.Config) (bool, error) {
+		if config.Auth == nil {
+			config.Auth = &v1.Auth{}
+		}
+
+		keys, err := fn(config.Auth.ApiKeys)
+		if err != nil {
+			return false, err
+		}
+
+		a.mu.Lock()
+		for _, key := range keys {
+			if lastUsed := a.apiKeyLastUsed[key.Id]; lastUsed > key.LastUsedAtMs {
+				key.LastUsedAtMs = lastUsed
+			}
+		}
+		a.mu.Unlock()
+
+		config.Auth.ApiKeys = keys
+		return true, nil
+	})
+}
+
+func hashAPIKeySecret(secret string) string {
+	// keys are high entropy random values so a fast hash is sufficient, unlike passwords.
+	h := sha256.Sum256([]byte(secret))
+	return hex.EncodeToString(h[:])
+}
+
+func redactAPIKey(key *v1.APIKey) *v1.APIKey {
+	key = proto.Clone(key).(*v1.APIKey)
+	key.KeyHash = ""
+	return key
+}
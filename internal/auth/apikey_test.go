@@ -0,0 +1,128 @@
+package auth
+
+import (
+	"errors"
+	"net/http"
+	"net/http/httptest"
+	"testing"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/config"
+)
+
+func TestAPIKey(t *testing.T) {
+	store := &config.MemoryStore{
+		Config: &v1.Config{
+			Auth: &v1.Auth{
+				Users: []*v1.User{
+					{Name: "test", Password: &v1.User_PasswordBcrypt{PasswordBcrypt: makePass(t, "testPass")}},
+				},
+			},
+		},
+	}
+	auth := NewAuthenticator([]byte("key"), store, newTestSessionStore(t))
+
+	secret, key, err := auth.CreateAPIKey("test", "ci", time.Time{})
+	if err != nil {
+		t.Fatalf("CreateAPIKey() error = %v", err)
+	}
+	if key.KeyHash != "" {
+		t.Errorf("expected key hash to be omitted from the returned key")
+	}
+	expiredSecret, _, err := auth.CreateAPIKey("test", "expired", time.Now().Add(-time.Minute))
+	if err != nil {
+		t.Fatalf("CreateAPIKey() error = %v", err)
+	}
+
+	tests := []struct {
+		name    string
+		token   string
+		wantErr error
+	}{
+		{"valid key", secret, nil},
+		{"wrong secret", secret[:len(secret)-4] + "0000", ErrInvalidAPIKey},
+		{"unknown id", apiKeyPrefix + "0000000000000000_abcd", ErrInvalidAPIKey},
+		{"malformed key", apiKeyPrefix + "nosecret", ErrInvalidAPIKey},
+		{"expired key", expiredSecret, ErrAPIKeyExpired},
+	}
+
+	for _, tc := range tests {
+		t.Run(tc.name, func(t *testing.T) {
+			user, _, err := auth.VerifyAPIKey(tc.token)
+			if !errors.Is(err, tc.wantErr) {
+				t.Fatalf("VerifyAPIKey() error = %v, want %v", err, tc.wantErr)
+			}
+			if err == nil && user.Name != "test" {
+				t.Errorf("VerifyAPIKey() user = %q, want %q", user.Name, "test")
+			}
+		})
+	}
+
+	keys, err := auth.ListAPIKeys()
+	if err != nil {
+		t.Fatalf("ListAPIKeys() error = %v", err)
+	}
+	if len(keys) != 2 || keys[0].LastUsedAtMs == 0 {
+		t.Errorf("ListAPIKeys() = %v, want 2 keys with the first one used", keys)
+	}
+
+	if err := auth.RevokeAPIKey(key.Id); err != nil {
+		t.Fatalf("RevokeAPIKey() error = %v", err)
+	}
+	if _, _, err := auth.VerifyAPIKey(secret); !errors.Is(err, ErrInvalidAPIKey) {
+		t.Errorf("VerifyAPIKey() after revoke error = %v, want %v", err, ErrInvalidAPIKey)
+	}
+	if err := auth.RevokeAPIKey(key.Id); !errors.Is(err, ErrAPIKeyNotFound) {
+		t.Errorf("RevokeAPIKey() twice error = %v, want %v", err, ErrAPIKeyNotFound)
+	}
+}
+
+func TestRequireAuthenticationAPIKey(t *testing.T) {
+	store := &config.MemoryStore{
+		Config: &v1.Config{
+			Auth: &v1.Auth{
+				Users: []*v1.User{
+					{Name: "test", Password: &v1.User_PasswordBcrypt{PasswordBcrypt: makePass(t, "testPass")}},
+				},
+			},
+		},
+	}
+	auth := NewAuthenticator([]byte("key"), store, newTestSessionStore(t))
+	secret, _, err := auth.CreateAPIKey("test", "ci", time.Time{})
+	if err != nil {
+		t.Fatalf("CreateAPIKey() error = %v", err)
+	}
+
+	handler := RequireAuthentication(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if UserFromContext(r.Context()).GetName() != "test" || APIKeyFromContext(r.Context()) == nil {
+			t.Errorf("expected the user and api key to be set on the request context")
+		}
+	}), auth)
+
+	tests := []struct {
+		name   string
+		header string
+		value  string
+		want   int
+	}{
+		{"api key header", APIKeyHeader, secret, http.StatusOK},
+		{"api key as bearer token", "Authorization", "Bearer " + secret, http.StatusOK},
+		{"bad api key", APIKeyHeader, apiKeyPrefix + "bad_key", http.StatusUnauthorized},
+		{"no credentials", "", "", http.StatusUnauthorized},
+	}
+
+	for _, tc := range tests {
+		t.Run(tc.name, func(t *testing.T) {
+			req := httptest.NewRequest(http.MethodGet, "/", nil)
+			if tc.header != "" {
+				req.Header.Set(tc.header, tc.value)
+			}
+			rec := httptest.NewRecorder()
+			handler.ServeHTTP(rec, req)
+			if rec.Code != tc.want {
+				t.Errorf("status = %d, want %d", rec.Code, tc.want)
+			}
+		})
+	}
+}
@@ -4,23 +4,42 @@ import (
 	"encoding/base64"
 	"errors"
 	"fmt"
+	"sync"
 	"time"
 
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
 	"github.com/garethgeorge/backrest/internal/config"
 	"github.com/golang-jwt/jwt/v5"
+	"go.uber.org/zap"
 	"golang.org/x/crypto/bcrypt"
+	"google.golang.org/protobuf/proto"
 )
 
 type Authenticator struct {
-	config config.ConfigStore
-	key    []byte
+	config   config.ConfigStore
+	key      []byte
+	sessions *SessionStore
+
+	configMu sync.Mutex // serializes read-modify-write updates of the auth config.
+
+	limiter        *loginLimiter
+	lockoutHandler func(LoginLockoutEvent) // optional, notified when repeated failed logins lock out a user or address.
+
+	mu                sync.Mutex
+	apiKeyLastUsed    map[string]int64 // key ID to unix millis of the most recent use.
+	apiKeyPersistedAt map[string]int64 // key ID to unix millis of the last use written to the config.
+	totpLastStep      map[string]int64 // username to the time step of the last accepted one time code.
 }
 
-func NewAuthenticator(key []byte, config config.ConfigStore) *Authenticator {
+func NewAuthenticator(key []byte, config config.ConfigStore, sessions *SessionStore) *Authenticator {
 	return &Authenticator{
-		config: config,
-		key:    key,
+		config:            config,
+		key:               key,
+		sessions:          sessions,
+		limiter:           newLoginLimiter(),
+		apiKeyLastUsed:    make(map[string]int64),
+		apiKeyPersistedAt: make(map[string]int64),
+		totpLastStep:      make(map[string]int64),
 	}
 }
 
@@ -28,7 +47,37 @@ var ErrUserNotFound = errors.New("user not found")
 var ErrInvalidPassword = errors.New("invalid password")
 var ErrInvalidKey = errors.New("invalid key")
 
-func (a *Authenticator) Login(username, password string) (*v1.User, error) {
+// Login checks the user's password and, if the user enrolled TOTP, the one time code or a recovery code. Failed
+// attempts are rate limited per user and per client address, clientAddr may be empty if it is unknown.
+func (a *Authenticator) Login(clientAddr, username, password, totpCode string) (*v1.User, error) {
+	if err := a.limiter.check(username, clientAddr); err != nil {
+		return nil, err
+	}
+
+	user, err := a.login(username, password, totpCode)
+	if err == nil {
+		a.limiter.recordSuccess(username, clientAddr)
+	} else if errors.Is(err, ErrInvalidPassword) || errors.Is(err, ErrUserNotFound) || errors.Is(err, ErrInvalidTOTPCode) {
+		a.recordLoginFailure(username, clientAddr)
+	}
+	return user, err
+}
+
+func (a *Authenticator) recordLoginFailure(username, clientAddr string) {
+	for _, event := range a.limiter.recordFailure(username, clientAddr) {
+		zap.S().Warnf("login lockout: %v", event)
+		if a.lockoutHandler != nil {
+			a.lockoutHandler(event)
+		}
+	}
+}
+
+// SetLockoutHandler registers a function that is called when repeated failed logins lock out a user or client address.
+func (a *Authenticator) SetLockoutHandler(fn func(LoginLockoutEvent)) {
+	a.lockoutHandler = fn
+}
+
+func (a *Authenticator) login(username, password, totpCode string) (*v1.User, error) {
 	config, err := a.config.Get()
 	if err != nil {
 		return nil, fmt.Errorf("get config: %w", err)
@@ -47,51 +96,66 @@ func (a *Authenticator) Login(username, password string) (*v1.User, error) {
 			return nil, err
 		}
 
+		if err := a.checkSecondFactor(user, totpCode); err != nil {
+			return nil, err
+		}
+
 		return user, nil
 	}
 
 	return nil, ErrUserNotFound
 }
 
-func (a *Authenticator) VerifyJWT(token string) (*v1.User, error) {
+// accessClaims are the claims of the short lived access tokens issued for a session.
+type accessClaims struct {
+	SessionID string `json:"sid"`
+	jwt.RegisteredClaims
+}
+
+// VerifyJWT checks an access token and returns its user and the ID of its session. Tokens of revoked sessions, or of
+// sessions created before the user's password changed, are rejected.
+func (a *Authenticator) VerifyJWT(token string) (*v1.User, string, error) {
 	config, err := a.config.Get()
 	if err != nil {
-		return nil, fmt.Errorf("get config: %w", err)
+		return nil, "", fmt.Errorf("get config: %w", err)
 	}
 	auth := config.GetAuth()
 	if auth == nil {
-		return nil, fmt.Errorf("auth config not set")
+		return nil, "", fmt.Errorf("auth config not set")
 	}
 
-	t, err := jwt.Parse(token, func(t *jwt.Token) (interface{}, error) {
+	claims := &accessClaims{}
+	t, err := jwt.ParseWithClaims(token, claims, func(t *jwt.Token) (interface{}, error) {
 		return a.key, nil
-	})
-
+	}, jwt.WithValidMethods([]string{jwt.SigningMethodHS256.Alg()}), jwt.WithExpirationRequired())
 	if err != nil {
-		return nil, fmt.Errorf("parse token: %w", err)
+		return nil, "", fmt.Errorf("parse token: %w", err)
 	}
 	if !t.Valid {
-		return nil, fmt.Errorf("invalid token")
+		return nil, "", fmt.Errorf("invalid token")
 	}
-
-	subject, err := t.Claims.GetSubject()
-	if err != nil {
-		return nil, fmt.Errorf("get subject: %w", err)
+	if claims.SessionID == "" {
+		return nil, "", fmt.Errorf("invalid token: no session")
 	}
 
-	for _, user := range auth.GetUsers() {
-		if user.Name == subject {
-			return user, nil
-		}
+	user := findUser(config, claims.Subject)
+	if user == nil {
+		return nil, "", ErrUserNotFound
 	}
-
-	return nil, ErrUserNotFound
+	if err := a.verifySession(claims.SessionID, user); err != nil {
+		return nil, "", err
+	}
+	return user, claims.SessionID, nil
 }
 
-func (a *Authenticator) CreateJWT(user *v1.User) (string, error) {
-	claims := &jwt.RegisteredClaims{
-		ExpiresAt: jwt.NewNumericDate(time.Now().Add(7 * 24 * time.Hour)),
-		Subject:   user.Name,
+func (a *Authenticator) createAccessToken(user *v1.User, sessionID string, expiresAt time.Time) (string, error) {
+	claims := &accessClaims{
+		SessionID: sessionID,
+		RegisteredClaims: jwt.RegisteredClaims{
+			ExpiresAt: jwt.NewNumericDate(expiresAt),
+			IssuedAt:  jwt.NewNumericDate(time.Now()),
+			Subject:   user.Name,
+		},
 	}
 
 	t := jwt.NewWithClaims(jwt.SigningMethodHS256, claims)
@@ -103,9 +167,34 @@ func (a *Authenticator) CreateJWT(user *v1.User) (string, error) {
 	return s, nil
 }
 
+// updateConfig applies fn to a copy of the config and writes it back if fn reports a change.
+func (a *Authenticator) updateConfig(fn func(config *v1.Config) (bool, error)) error {
+	a.configMu.Lock()
+	defer a.configMu.Unlock()
+
+	config, err := a.config.Get()
+	if err != nil {
+		return fmt.Errorf("get config: %w", err)
+	}
+	config = proto.Clone(config).(*v1.Config)
+
+	changed, err := fn(config)
+	if err != nil || !changed {
+		return err
+	}
+
+	config.Modno++
+	if err := a.config.Update(config); err != nil {
+		return fmt.Errorf("update config: %w", err)
+	}
+	return nil
+}
+
 // checkPassword returns nil if the password is correct, or an error if it is not.
 func checkPassword(user *v1.User, password string) error {
 	switch pw := user.Password.(type) {
+	case *v1.User_OidcSubject:
+		return fmt.Errorf("%w: user %q must log in with single sign-on", ErrInvalidPassword, user.Name)
 	case *v1.User_PasswordBcrypt:
 		pwHash, err := base64.StdEncoding.DecodeString(pw.PasswordBcrypt)
 		if err != nil {
@@ -33,7 +33,7 @@ func TestLogin(t *testing.T) {
 		},
 	}
 
-	auth := NewAuthenticator([]byte("key"), config)
+	auth := NewAuthenticator([]byte("key"), config, newTestSessionStore(t))
 
 	tests := []struct {
 		name     string
@@ -49,7 +49,7 @@ func TestLogin(t *testing.T) {
 
 	for _, test := range tests {
 		t.Run(test.name, func(t *testing.T) {
-			user, err := auth.Login(test.username, test.password)
+			user, err := auth.Login("", test.username, test.password, "")
 			if !errors.Is(err, test.wantErr) {
 				t.Fatalf("Expected error %v, got %v", test.wantErr, err)
 			}
@@ -2,6 +2,8 @@ package auth
 
 import (
 	"context"
+	"errors"
+	"net"
 	"net/http"
 
 	"go.uber.org/zap"
@@ -15,6 +17,7 @@ func (k contextKey) String() string {
 
 const UserContextKey contextKey = "user"
 const APIKeyContextKey contextKey = "api_key"
+const SessionContextKey contextKey = "session"
 
 func RequireAuthentication(h http.Handler, auth *Authenticator) http.Handler {
 	return http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
@@ -29,25 +32,44 @@ func RequireAuthentication(h http.Handler, auth *Authenticator) http.Handler {
 			return
 		}
 
+		// basic auth can't carry a one time code, users that enrolled TOTP must use a token or an API key.
 		username, password, usesBasicAuth := r.BasicAuth()
 		if usesBasicAuth {
-			user, err := auth.Login(username, password)
+			user, err := auth.Login(clientAddr(r), username, password, "")
 			if err == nil {
 				ctx := context.WithValue(r.Context(), UserContextKey, user)
 				h.ServeHTTP(w, r.WithContext(ctx))
 				return
+			} else if errors.Is(err, ErrLoginLockedOut) {
+				http.Error(w, "Too Many Requests ("+err.Error()+")", http.StatusTooManyRequests)
+				return
 			}
 		}
 
-		// TODO: process the API Key
-
+		apiKey := r.Header.Get(APIKeyHeader)
 		token, err := ParseBearerToken(r.Header.Get("Authorization"))
+		if apiKey == "" && err == nil && IsAPIKey(token) {
+			apiKey = token
+		}
+		if apiKey != "" {
+			user, key, err := auth.VerifyAPIKey(apiKey)
+			if err != nil {
+				zap.S().Warnf("auth middleware blocked bad API key: %v", err)
+				http.Error(w, "Unauthorized (Bad API Key)", http.StatusUnauthorized)
+				return
+			}
+			ctx := context.WithValue(r.Context(), UserContextKey, user)
+			ctx = context.WithValue(ctx, APIKeyContextKey, key)
+			h.ServeHTTP(w, r.WithContext(ctx))
+			return
+		}
+
 		if err != nil {
 			http.Error(w, "Unauthorized (No Authorization Header)", http.StatusUnauthorized)
 			return
 		}
 
-		user, err := auth.VerifyJWT(token)
+		user, sessionID, err := auth.VerifyJWT(token)
 		if err != nil {
 			zap.S().Warnf("auth middleware blocked bad JWT: %v", err)
 			http.Error(w, "Unauthorized (Bad Token)", http.StatusUnauthorized)
@@ -55,6 +77,16 @@ func RequireAuthentication(h http.Handler, auth *Authenticator) http.Handler {
 		}
 
 		ctx := context.WithValue(r.Context(), UserContextKey, user)
+		ctx = context.WithValue(ctx, SessionContextKey, sessionID)
 		h.ServeHTTP(w, r.WithContext(ctx))
 	})
 }
+
+// clientAddr returns the IP address of the client that sent the request. Forwarding headers are ignored as they can be
+// set by the client, behind a reverse proxy all requests share the proxy's address.
+func clientAddr(r *http.Request) string {
+	if host, _, err := net.SplitHostPort(r.RemoteAddr); err == nil {
+		return host
+	}
+	return r.RemoteAddr
+}
@@ -0,0 +1,534 @@
+package auth
+
+import (
+	"context"
+	"crypto/ecdsa"
+	"crypto/elliptic"
+	"crypto/rsa"
+	"crypto/sha256"
+	"crypto/subtle"
+	"encoding/base64"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"math/big"
+	"net/http"
+	"net/url"
+	"slices"
+	"strings"
+	"sync"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/cryptoutil"
+	"github.com/golang-jwt/jwt/v5"
+	"go.uber.org/zap"
+	"google.golang.org/protobuf/proto"
+)
+
+const (
+	OIDCPathPrefix   = "/auth/oidc"
+	OIDCLoginPath    = OIDCPathPrefix + "/login"
+	OIDCCallbackPath = OIDCPathPrefix + "/callback"
+
+	// oidcTokenFragment and oidcRefreshTokenFragment are the URL fragment parameters used to hand the issued tokens to
+	// the web UI.
+	oidcTokenFragment        = "sso_token"
+	oidcRefreshTokenFragment = "sso_refresh_token"
+
+	// oidcStateCookie binds a login attempt to the browser that started it, a callback is only accepted from the same
+	// browser to prevent login CSRF.
+	oidcStateCookie = "backrest_oidc_state"
+
+	oidcLoginTimeout = 10 * time.Minute
+)
+
+var ErrOIDCNotConfigured = errors.New("single sign-on is not configured")
+
+var defaultOIDCScopes = []string{"openid", "profile", "email"}
+
+// OIDCHandler implements the OpenID Connect authorization code flow with PKCE. It serves the login and callback
+// endpoints under OIDCPathPrefix and completes the flow by starting a session like a password login.
+type OIDCHandler struct {
+	auth   *Authenticator
+	client *http.Client
+
+	mu        sync.Mutex
+	pending   map[string]*pendingOIDCLogin // keyed by the state parameter.
+	discovery map[string]*oidcDiscovery    // keyed by issuer.
+	jwks      map[string]any               // keyed by "<jwks uri>#<key id>".
+}
+
+type pendingOIDCLogin struct {
+	verifier string
+	nonce    string
+	expires  time.Time
+}
+
+type oidcDiscovery struct {
+	Issuer                string `json:"issuer"`
+	AuthorizationEndpoint string `json:"authorization_endpoint"`
+	TokenEndpoint         string `json:"token_endpoint"`
+	JWKSURI               string `json:"jwks_uri"`
+}
+
+func NewOIDCHandler(auth *Authenticator) *OIDCHandler {
+	return &OIDCHandler{
+		auth:      auth,
+		client:    &http.Client{Timeout: 30 * time.Second},
+		pending:   make(map[string]*pendingOIDCLogin),
+		discovery: make(map[string]*oidcDiscovery),
+		jwks:      make(map[string]any),
+	}
+}
+
+// ServeHTTP handles requests with OIDCPathPrefix stripped.
+func (h *OIDCHandler) ServeHTTP(w http.ResponseWriter, r *http.Request) {
+	provider, err := h.auth.OIDCProvider()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusNotFound)
+		return
+	}
+
+	switch r.URL.Path {
+	case strings.TrimPrefix(OIDCLoginPath, OIDCPathPrefix):
+		h.login(w, r, provider)
+	case strings.TrimPrefix(OIDCCallbackPath, OIDCPathPrefix):
+		h.callback(w, r, provider)
+	default:
+		http.NotFound(w, r)
+	}
+}
+
+func (h *OIDCHandler) login(w http.ResponseWriter, r *http.Request, provider *v1.OIDCProvider) {
+	disc, err := h.discover(r.Context(), provider.Issuer)
+	if err != nil {
+		zap.S().Errorf("oidc discovery for issuer %q failed: %v", provider.Issuer, err)
+		http.Error(w, "Failed to contact the single sign-on provider", http.StatusBadGateway)
+		return
+	}
+
+	state := cryptoutil.MustRandomID(cryptoutil.DefaultIDBits)
+	login := &pendingOIDCLogin{
+		verifier: cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+		nonce:    cryptoutil.MustRandomID(cryptoutil.DefaultIDBits),
+		expires:  time.Now().Add(oidcLoginTimeout),
+	}
+
+	h.mu.Lock()
+	for s, p := range h.pending {
+		if time.Now().After(p.expires) {
+			delete(h.pending, s)
+		}
+	}
+	h.pending[state] = login
+	h.mu.Unlock()
+
+	http.SetCookie(w, &http.Cookie{
+		Name:     oidcStateCookie,
+		Value:    state,
+		Path:     OIDCPathPrefix,
+		MaxAge:   int(oidcLoginTimeout.Seconds()),
+		HttpOnly: true,
+		Secure:   r.TLS != nil,
+		SameSite: http.SameSiteLaxMode,
+	})
+
+	scopes := provider.Scopes
+	if len(scopes) == 0 {
+		scopes = defaultOIDCScopes
+	}
+	challenge := sha256.Sum256([]byte(login.verifier))
+
+	authURL, err := url.Parse(disc.AuthorizationEndpoint)
+	if err != nil {
+		http.Error(w, "Invalid authorization endpoint", http.StatusBadGateway)
+		return
+	}
+	q := authURL.Query()
+	q.Set("response_type", "code")
+	q.Set("client_id", provider.ClientId)
+	q.Set("redirect_uri", provider.RedirectUrl)
+	q.Set("scope", strings.Join(scopes, " "))
+	q.Set("state", state)
+	q.Set("nonce", login.nonce)
+	q.Set("code_challenge", base64.RawURLEncoding.EncodeToString(challenge[:]))
+	q.Set("code_challenge_method", "S256")
+	authURL.RawQuery = q.Encode()
+
+	http.Redirect(w, r, authURL.String(), http.StatusFound)
+}
+
+func (h *OIDCHandler) callback(w http.ResponseWriter, r *http.Request, provider *v1.OIDCProvider) {
+	q := r.URL.Query()
+	if e := q.Get("error"); e != "" {
+		zap.S().Warnf("oidc provider returned error %q: %v", e, q.Get("error_description"))
+		http.Error(w, "Single sign-on failed: "+e, http.StatusUnauthorized)
+		return
+	}
+
+	state := q.Get("state")
+	cookie, err := r.Cookie(oidcStateCookie)
+	http.SetCookie(w, &http.Cookie{Name: oidcStateCookie, Path: OIDCPathPrefix, MaxAge: -1, HttpOnly: true})
+	if err != nil || state == "" || subtle.ConstantTimeCompare([]byte(cookie.Value), []byte(state)) != 1 {
+		http.Error(w, "Single sign-on failed: the login was started in a different browser, please try again", http.StatusBadRequest)
+		return
+	}
+
+	h.mu.Lock()
+	login, ok := h.pending[state]
+	delete(h.pending, state)
+	h.mu.Unlock()
+	if !ok || time.Now().After(login.expires) {
+		http.Error(w, "Single sign-on failed: unknown or expired login attempt, please try again", http.StatusBadRequest)
+		return
+	}
+
+	user, err := h.completeLogin(r.Context(), provider, q.Get("code"), login)
+	if err != nil {
+		zap.S().Warnf("oidc login failed: %v", err)
+		http.Error(w, "Single sign-on failed: "+err.Error(), http.StatusUnauthorized)
+		return
+	}
+
+	tokens, err := h.auth.CreateSession(user, r.UserAgent(), clientAddr(r))
+	if err != nil {
+		zap.S().Errorf("oidc login failed to create session: %v", err)
+		http.Error(w, "Internal Server Error", http.StatusInternalServerError)
+		return
+	}
+
+	zap.S().Infof("user %q logged in with single sign-on", user.Name)
+	uiURL := strings.TrimSuffix(provider.RedirectUrl, OIDCCallbackPath) + "/#" + url.Values{
+		oidcTokenFragment:        {tokens.AccessToken},
+		oidcRefreshTokenFragment: {tokens.RefreshToken},
+	}.Encode()
+	http.Redirect(w, r, uiURL, http.StatusFound)
+}
+
+// completeLogin exchanges the authorization code for an ID token, verifies it, and resolves the backrest user.
+func (h *OIDCHandler) completeLogin(ctx context.Context, provider *v1.OIDCProvider, code string, login *pendingOIDCLogin) (*v1.User, error) {
+	if code == "" {
+		return nil, errors.New("missing authorization code")
+	}
+
+	disc, err := h.discover(ctx, provider.Issuer)
+	if err != nil {
+		return nil, fmt.Errorf("discovery: %w", err)
+	}
+
+	rawIDToken, err := h.exchangeCode(ctx, provider, disc, code, login.verifier)
+	if err != nil {
+		return nil, fmt.Errorf("exchange code: %w", err)
+	}
+
+	claims := jwt.MapClaims{}
+	if _, err := jwt.ParseWithClaims(rawIDToken, claims, func(t *jwt.Token) (interface{}, error) {
+		kid, _ := t.Header["kid"].(string)
+		return h.signingKey(ctx, disc.JWKSURI, kid)
+	},
+		jwt.WithIssuer(provider.Issuer),
+		jwt.WithAudience(provider.ClientId),
+		jwt.WithExpirationRequired(),
+		jwt.WithValidMethods([]string{"RS256", "RS384", "RS512", "ES256", "ES384", "ES512"}),
+	); err != nil {
+		return nil, fmt.Errorf("verify id token: %w", err)
+	}
+	if nonce, _ := claims["nonce"].(string); nonce != login.nonce {
+		return nil, errors.New("verify id token: nonce mismatch")
+	}
+
+	return h.auth.userForOIDCClaims(provider, claims)
+}
+
+func (h *OIDCHandler) exchangeCode(ctx context.Context, provider *v1.OIDCProvider, disc *oidcDiscovery, code, verifier string) (string, error) {
+	form := url.Values{
+		"grant_type":    {"authorization_code"},
+		"code":          {code},
+		"redirect_uri":  {provider.RedirectUrl},
+		"client_id":     {provider.ClientId},
+		"code_verifier": {verifier},
+	}
+	req, err := http.NewRequestWithContext(ctx, http.MethodPost, disc.TokenEndpoint, strings.NewReader(form.Encode()))
+	if err != nil {
+		return "", err
+	}
+	req.Header.Set("Content-Type", "application/x-www-form-urlencoded")
+	req.Header.Set("Accept", "application/json")
+	if provider.ClientSecret != "" {
+		req.SetBasicAuth(url.QueryEscape(provider.ClientId), url.QueryEscape(provider.ClientSecret))
+	}
+
+	resp, err := h.client.Do(req)
+	if err != nil {
+		return "", err
+	}
+	defer resp.Body.Close()
+
+	var tokenResp struct {
+		IDToken          string `json:"id_token"`
+		Error            string `json:"error"`
+		ErrorDescription string `json:"error_description"`
+	}
+	if err := json.NewDecoder(resp.Body).Decode(&tokenResp); err != nil {
+		return "", fmt.Errorf("decode token response (status %d): %w", resp.StatusCode, err)
+	}
+	if tokenResp.Error != "" {
+		return "", fmt.Errorf("token endpoint: %v: %v", tokenResp.Error, tokenResp.ErrorDescription)
+	}
+	if resp.StatusCode != http.StatusOK {
+		return "", fmt.Errorf("token endpoint: unexpected status %d", resp.StatusCode)
+	}
+	if tokenResp.IDToken == "" {
+		return "", errors.New("token endpoint did not return an id_token")
+	}
+	return tokenResp.IDToken, nil
+}
+
+func (h *OIDCHandler) discover(ctx context.Context, issuer string) (*oidcDiscovery, error) {
+	h.mu.Lock()
+	disc, ok := h.discovery[issuer]
+	h.mu.Unlock()
+	if ok {
+		return disc, nil
+	}
+
+	disc = &oidcDiscovery{}
+	if err := h.getJSON(ctx, strings.TrimSuffix(issuer, "/")+"/.well-known/openid-configuration", disc); err != nil {
+		return nil, err
+	}
+	if disc.Issuer != issuer {
+		return nil, fmt.Errorf("discovery document issuer %q does not match configured issuer %q", disc.Issuer, issuer)
+	}
+	if disc.AuthorizationEndpoint == "" || disc.TokenEndpoint == "" || disc.JWKSURI == "" {
+		return nil, errors.New("discovery document is missing required endpoints")
+	}
+
+	h.mu.Lock()
+	h.discovery[issuer] = disc
+	h.mu.Unlock()
+	return disc, nil
+}
+
+// signingKey returns the provider's public key with the given ID, the key set is refetched if the ID is unknown e.g. after key rotation.
+func (h *OIDCHandler) signingKey(ctx context.Context, jwksURI, kid string) (any, error) {
+	cacheKey := jwksURI + "#" + kid
+	h.mu.Lock()
+	key, ok := h.jwks[cacheKey]
+	h.mu.Unlock()
+	if ok {
+		return key, nil
+	}
+
+	var set struct {
+		Keys []jsonWebKey `json:"keys"`
+	}
+	if err := h.getJSON(ctx, jwksURI, &set); err != nil {
+		return nil, fmt.Errorf("fetch jwks: %w", err)
+	}
+
+	h.mu.Lock()
+	defer h.mu.Unlock()
+	var signingKeys []any
+	for _, jwk := range set.Keys {
+		if jwk.Use != "" && jwk.Use != "sig" {
+			continue
+		}
+		pub, err := jwk.publicKey()
+		if err != nil {
+			zap.S().Debugf("skipping jwk %q: %v", jwk.Kid, err)
+			continue
+		}
+		h.jwks[jwksURI+"#"+jwk.Kid] = pub
+		signingKeys = append(signingKeys, pub)
+	}
+	if key, ok := h.jwks[cacheKey]; ok {
+		return key, nil
+	}
+	if kid == "" && len(signingKeys) == 1 {
+		return signingKeys[0], nil
+	}
+	return nil, fmt.Errorf("no signing key with id %q", kid)
+}
+
+func (h *OIDCHandler) getJSON(ctx context.Context, url string, v any) error {
+	req, err := http.NewRequestWithContext(ctx, http.MethodGet, url, nil)
+	if err != nil {
+		return err
+	}
+	resp, err := h.client.Do(req)
+	if err != nil {
+		return err
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode != http.StatusOK {
+		return fmt.Errorf("GET %v: unexpected status %d", url, resp.StatusCode)
+	}
+	return json.NewDecoder(resp.Body).Decode(v)
+}
+
+type jsonWebKey struct {
+	Kty string `json:"kty"`
+	Kid string `json:"kid"`
+	Use string `json:"use"`
+	N   string `json:"n"`
+	E   string `json:"e"`
+	Crv string `json:"crv"`
+	X   string `json:"x"`
+	Y   string `json:"y"`
+}
+
+func (k *jsonWebKey) publicKey() (any, error) {
+	decode := func(s string) (*big.Int, error) {
+		b, err := base64.RawURLEncoding.DecodeString(s)
+		if err != nil {
+			return nil, err
+		}
+		return new(big.Int).SetBytes(b), nil
+	}
+
+	switch k.Kty {
+	case "RSA":
+		n, err := decode(k.N)
+		if err != nil {
+			return nil, fmt.Errorf("decode modulus: %w", err)
+		}
+		e, err := decode(k.E)
+		if err != nil {
+			return nil, fmt.Errorf("decode exponent: %w", err)
+		}
+		return &rsa.PublicKey{N: n, E: int(e.Int64())}, nil
+	case "EC":
+		var curve elliptic.Curve
+		switch k.Crv {
+		case "P-256":
+			curve = elliptic.P256()
+		case "P-384":
+			curve = elliptic.P384()
+		case "P-521":
+			curve = elliptic.P521()
+		default:
+			return nil, fmt.Errorf("unsupported curve %q", k.Crv)
+		}
+		x, err := decode(k.X)
+		if err != nil {
+			return nil, fmt.Errorf("decode x: %w", err)
+		}
+		y, err := decode(k.Y)
+		if err != nil {
+			return nil, fmt.Errorf("decode y: %w", err)
+		}
+		return &ecdsa.PublicKey{Curve: curve, X: x, Y: y}, nil
+	default:
+		return nil, fmt.Errorf("unsupported key type %q", k.Kty)
+	}
+}
+
+// OIDCProvider returns the configured single sign-on provider or ErrOIDCNotConfigured.
+func (a *Authenticator) OIDCProvider() (*v1.OIDCProvider, error) {
+	config, err := a.config.Get()
+	if err != nil {
+		return nil, fmt.Errorf("get config: %w", err)
+	}
+	auth := config.GetAuth()
+	if auth.GetDisabled() || auth.GetOidc() == nil {
+		return nil, ErrOIDCNotConfigured
+	}
+	return auth.GetOidc(), nil
+}
+
+// userForOIDCClaims maps verified ID token claims to a backrest user. Users are matched by the token's subject, an
+// administrator links an existing user to an identity by setting its oidc_subject. Unknown identities are provisioned
+// with the first matching role mapping unless their username is already taken. The role of linked users is updated on
+// every login to track changes at the provider.
+func (a *Authenticator) userForOIDCClaims(provider *v1.OIDCProvider, claims jwt.MapClaims) (*v1.User, error) {
+	usernameClaim := provider.UsernameClaim
+	if usernameClaim == "" {
+		usernameClaim = "preferred_username"
+	}
+	username, _ := claims[usernameClaim].(string)
+	if username == "" {
+		return nil, fmt.Errorf("id token has no %q claim", usernameClaim)
+	}
+	subject, err := claims.GetSubject()
+	if err != nil || subject == "" {
+		return nil, errors.New("id token has no subject")
+	}
+	mapping := matchOIDCRoleMapping(provider, claims)
+
+	var user *v1.User
+	err = a.updateConfig(func(config *v1.Config) (bool, error) {
+		auth := config.GetAuth()
+		idx := slices.IndexFunc(auth.GetUsers(), func(u *v1.User) bool { return u.GetOidcSubject() == subject })
+		if idx != -1 {
+			existing := auth.Users[idx]
+			if mapping == nil {
+				return false, fmt.Errorf("user %q no longer matches any role mapping", existing.Name)
+			}
+			user = proto.Clone(existing).(*v1.User)
+			user.Role = mappedRole(mapping)
+			user.AllowedRepos = slices.Clone(mapping.AllowedRepos)
+			user.AllowedPlans = slices.Clone(mapping.AllowedPlans)
+			if proto.Equal(user, existing) {
+				return false, nil
+			}
+			auth.Users[idx] = user
+			return true, nil
+		}
+
+		if slices.ContainsFunc(auth.GetUsers(), func(u *v1.User) bool { return u.Name == username }) {
+			return false, fmt.Errorf("username %q is taken by another user, an administrator may link the identity %q to it", username, subject)
+		}
+		if mapping == nil {
+			return false, fmt.Errorf("%w: %q does not exist and matches no role mapping", ErrUserNotFound, username)
+		}
+		user = oidcUser(username, subject, mapping)
+		auth.Users = append(auth.Users, user)
+		zap.S().Infof("provisioning single sign-on user %q with role %v", username, user.Role)
+		return true, nil
+	})
+	if err != nil {
+		return nil, err
+	}
+	return user, nil
+}
+
+func oidcUser(username, subject string, mapping *v1.OIDCProvider_RoleMapping) *v1.User {
+	return &v1.User{
+		Name:         username,
+		Password:     &v1.User_OidcSubject{OidcSubject: subject},
+		Role:         mappedRole(mapping),
+		AllowedRepos: slices.Clone(mapping.AllowedRepos),
+		AllowedPlans: slices.Clone(mapping.AllowedPlans),
+	}
+}
+
+// mappedRole returns the role granted by a mapping. An unset role would be resolved to admin like for users created
+// before roles existed, mappings without a role are rejected by validation and grant read only access here.
+func mappedRole(mapping *v1.OIDCProvider_RoleMapping) v1.User_Role {
+	if mapping.Role == v1.User_ROLE_DEFAULT {
+		return v1.User_ROLE_READ_ONLY
+	}
+	return mapping.Role
+}
+
+func matchOIDCRoleMapping(provider *v1.OIDCProvider, claims jwt.MapClaims) *v1.OIDCProvider_RoleMapping {
+	var values []string
+	switch v := claims[provider.RolesClaim].(type) {
+	case string:
+		values = []string{v}
+	case []interface{}:
+		for _, item := range v {
+			if s, ok := item.(string); ok {
+				values = append(values, s)
+			}
+		}
+	}
+
+	for _, mapping := range provider.RoleMappings {
+		if mapping.Value == "*" || slices.Contains(values, mapping.Value) {
+			return mapping
+		}
+	}
+	return nil
+}
@@ -0,0 +1,263 @@
+package auth
+
+import (
+	"crypto/rand"
+	"crypto/rsa"
+	"crypto/sha256"
+	"encoding/base64"
+	"encoding/json"
+	"math/big"
+	"net/http"
+	"net/http/httptest"
+	"net/url"
+	"sync"
+	"testing"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/config"
+	"github.com/golang-jwt/jwt/v5"
+)
+
+func TestOIDCLogin(t *testing.T) {
+	provider := newFakeOIDCProvider(t)
+
+	store := &config.MemoryStore{
+		Config: &v1.Config{
+			Auth: &v1.Auth{
+				Users: []*v1.User{
+					{Name: "admin", Password: &v1.User_PasswordBcrypt{PasswordBcrypt: makePass(t, "testPass")}},
+					{Name: "carol-admin", Password: &v1.User_OidcSubject{OidcSubject: "subject-carol"}, Role: v1.User_ROLE_READ_ONLY},
+				},
+				Oidc: &v1.OIDCProvider{
+					Issuer:      provider.server.URL,
+					ClientId:    "backrest",
+					RedirectUrl: "http://backrest.local/auth/oidc/callback",
+					RolesClaim:  "groups",
+					RoleMappings: []*v1.OIDCProvider_RoleMapping{
+						{Value: "backup-operators", Role: v1.User_ROLE_OPERATOR},
+						{Value: "legacy", Role: v1.User_ROLE_DEFAULT},
+					},
+				},
+			},
+		},
+	}
+	auth := NewAuthenticator([]byte("key"), store, newTestSessionStore(t))
+	handler := http.StripPrefix(OIDCPathPrefix, NewOIDCHandler(auth))
+
+	tests := []struct {
+		name     string
+		username string
+		groups   []string
+		wantUser string       // only checked if the login succeeds.
+		wantRole v1.User_Role // only checked if the login succeeds.
+		wantOK   bool
+	}{
+		{"provisioned by role mapping", "alice", []string{"backup-operators"}, "alice", v1.User_ROLE_OPERATOR, true},
+		{"linked user matched by subject", "carol", []string{"backup-operators"}, "carol-admin", v1.User_ROLE_OPERATOR, true},
+		{"local username is not taken over", "admin", []string{"backup-operators"}, "", 0, false},
+		{"mapping without a role grants read only", "dave", []string{"legacy"}, "dave", v1.User_ROLE_READ_ONLY, true},
+		{"unmapped user is rejected", "mallory", []string{"other"}, "", 0, false},
+	}
+
+	for _, tc := range tests {
+		t.Run(tc.name, func(t *testing.T) {
+			provider.setIdentity(tc.username, tc.groups)
+
+			// start the login, backrest redirects to the provider's authorization endpoint.
+			rec := httptest.NewRecorder()
+			handler.ServeHTTP(rec, httptest.NewRequest(http.MethodGet, OIDCLoginPath, nil))
+			if rec.Code != http.StatusFound {
+				t.Fatalf("login status = %d, want %d: %s", rec.Code, http.StatusFound, rec.Body.String())
+			}
+
+			// the provider authenticates the user and redirects back to the callback.
+			noRedirectClient := &http.Client{CheckRedirect: func(*http.Request, []*http.Request) error { return http.ErrUseLastResponse }}
+			resp, err := noRedirectClient.Get(rec.Header().Get("Location"))
+			if err != nil {
+				t.Fatalf("authorize request failed: %v", err)
+			}
+			resp.Body.Close()
+			callbackURL, err := url.Parse(resp.Header.Get("Location"))
+			if err != nil {
+				t.Fatalf("parse callback url: %v", err)
+			}
+
+			cookies := rec.Result().Cookies()
+			rec = httptest.NewRecorder()
+			callbackReq := httptest.NewRequest(http.MethodGet, callbackURL.RequestURI(), nil)
+			for _, c := range cookies {
+				callbackReq.AddCookie(c)
+			}
+			handler.ServeHTTP(rec, callbackReq)
+			if !tc.wantOK {
+				if rec.Code != http.StatusUnauthorized {
+					t.Fatalf("callback status = %d, want %d", rec.Code, http.StatusUnauthorized)
+				}
+				return
+			}
+			if rec.Code != http.StatusFound {
+				t.Fatalf("callback status = %d, want %d: %s", rec.Code, http.StatusFound, rec.Body.String())
+			}
+
+			uiURL, err := url.Parse(rec.Header().Get("Location"))
+			if err != nil {
+				t.Fatalf("parse ui url: %v", err)
+			}
+			fragment, _ := url.ParseQuery(uiURL.Fragment)
+			user, _, err := auth.VerifyJWT(fragment.Get(oidcTokenFragment))
+			if err != nil {
+				t.Fatalf("VerifyJWT() error = %v", err)
+			}
+			if user.Name != tc.wantUser || user.Role != tc.wantRole {
+				t.Errorf("logged in as %q with role %v, want %q with role %v", user.Name, user.Role, tc.wantUser, tc.wantRole)
+			}
+		})
+	}
+
+	t.Run("callback with unknown state is rejected", func(t *testing.T) {
+		rec := httptest.NewRecorder()
+		req := httptest.NewRequest(http.MethodGet, OIDCCallbackPath+"?code=abc&state=unknown", nil)
+		req.AddCookie(&http.Cookie{Name: oidcStateCookie, Value: "unknown"})
+		handler.ServeHTTP(rec, req)
+		if rec.Code != http.StatusBadRequest {
+			t.Errorf("callback status = %d, want %d", rec.Code, http.StatusBadRequest)
+		}
+	})
+
+	t.Run("callback from another browser is rejected", func(t *testing.T) {
+		provider.setIdentity("alice", []string{"backup-operators"})
+		rec := httptest.NewRecorder()
+		handler.ServeHTTP(rec, httptest.NewRequest(http.MethodGet, OIDCLoginPath, nil))
+		authURL, err := url.Parse(rec.Header().Get("Location"))
+		if err != nil {
+			t.Fatalf("parse authorization url: %v", err)
+		}
+
+		// the callback carries a valid state but not the cookie set for the browser that started the login.
+		rec = httptest.NewRecorder()
+		handler.ServeHTTP(rec, httptest.NewRequest(http.MethodGet, OIDCCallbackPath+"?code=abc&state="+url.QueryEscape(authURL.Query().Get("state")), nil))
+		if rec.Code != http.StatusBadRequest {
+			t.Errorf("callback status = %d, want %d", rec.Code, http.StatusBadRequest)
+		}
+	})
+
+	t.Run("provisioned users cannot log in with a password", func(t *testing.T) {
+		if _, err := auth.Login("", "alice", "", ""); err == nil {
+			t.Errorf("expected password login of a single sign-on user to fail")
+		}
+	})
+}
+
+// fakeOIDCProvider is a minimal stand-in OpenID Connect provider that immediately authenticates a configured identity.
+type fakeOIDCProvider struct {
+	t      *testing.T
+	server *httptest.Server
+	key    *rsa.PrivateKey
+
+	mu       sync.Mutex
+	username string
+	groups   []string
+	codes    map[string]fakeOIDCCode
+}
+
+type fakeOIDCCode struct {
+	challenge string
+	nonce     string
+	username  string
+	groups    []string
+}
+
+func newFakeOIDCProvider(t *testing.T) *fakeOIDCProvider {
+	key, err := rsa.GenerateKey(rand.Reader, 2048)
+	if err != nil {
+		t.Fatalf("generate key: %v", err)
+	}
+	p := &fakeOIDCProvider{t: t, key: key, codes: make(map[string]fakeOIDCCode)}
+
+	mux := http.NewServeMux()
+	mux.HandleFunc("/.well-known/openid-configuration", func(w http.ResponseWriter, r *http.Request) {
+		json.NewEncoder(w).Encode(map[string]string{
+			"issuer":                 p.server.URL,
+			"authorization_endpoint": p.server.URL + "/authorize",
+			"token_endpoint":         p.server.URL + "/token",
+			"jwks_uri":               p.server.URL + "/jwks",
+		})
+	})
+	mux.HandleFunc("/jwks", func(w http.ResponseWriter, r *http.Request) {
+		json.NewEncoder(w).Encode(map[string]any{
+			"keys": []map[string]string{{
+				"kty": "RSA",
+				"kid": "test-key",
+				"use": "sig",
+				"n":   base64.RawURLEncoding.EncodeToString(key.N.Bytes()),
+				"e":   base64.RawURLEncoding.EncodeToString(big.NewInt(int64(key.E)).Bytes()),
+			}},
+		})
+	})
+	mux.HandleFunc("/authorize", p.authorize)
+	mux.HandleFunc("/token", p.token)
+	p.server = httptest.NewServer(mux)
+	t.Cleanup(p.server.Close)
+	return p
+}
+
+func (p *fakeOIDCProvider) setIdentity(username string, groups []string) {
+	p.mu.Lock()
+	defer p.mu.Unlock()
+	p.username = username
+	p.groups = groups
+}
+
+func (p *fakeOIDCProvider) authorize(w http.ResponseWriter, r *http.Request) {
+	q := r.URL.Query()
+	if q.Get("code_challenge_method") != "S256" || q.Get("client_id") != "backrest" {
+		http.Error(w, "bad request", http.StatusBadRequest)
+		return
+	}
+
+	p.mu.Lock()
+	code := "code-" + q.Get("state")
+	p.codes[code] = fakeOIDCCode{
+		challenge: q.Get("code_challenge"),
+		nonce:     q.Get("nonce"),
+		username:  p.username,
+		groups:    p.groups,
+	}
+	p.mu.Unlock()
+
+	redirect, _ := url.Parse(q.Get("redirect_uri"))
+	redirect.RawQuery = url.Values{"code": {code}, "state": {q.Get("state")}}.Encode()
+	http.Redirect(w, r, redirect.String(), http.StatusFound)
+}
+
+func (p *fakeOIDCProvider) token(w http.ResponseWriter, r *http.Request) {
+	p.mu.Lock()
+	code, ok := p.codes[r.FormValue("code")]
+	delete(p.codes, r.FormValue("code"))
+	p.mu.Unlock()
+
+	verifierHash := sha256.Sum256([]byte(r.FormValue("code_verifier")))
+	if !ok || base64.RawURLEncoding.EncodeToString(verifierHash[:]) != code.challenge {
+		w.WriteHeader(http.StatusBadRequest)
+		json.NewEncoder(w).Encode(map[string]string{"error": "invalid_grant"})
+		return
+	}
+
+	idToken := jwt.NewWithClaims(jwt.SigningMethodRS256, jwt.MapClaims{
+		"iss":                p.server.URL,
+		"aud":                "backrest",
+		"sub":                "subject-" + code.username,
+		"exp":                time.Now().Add(time.Minute).Unix(),
+		"nonce":              code.nonce,
+		"preferred_username": code.username,
+		"groups":             code.groups,
+	})
+	idToken.Header["kid"] = "test-key"
+	signed, err := idToken.SignedString(p.key)
+	if err != nil {
+		p.t.Errorf("sign id token: %v", err)
+		return
+	}
+	json.NewEncoder(w).Encode(map[string]string{"id_token": signed, "token_type": "Bearer"})
+}
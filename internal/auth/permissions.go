@@ -0,0 +1,135 @@
+package auth
+
+import (
+	"context"
+	"errors"
+	"slices"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/config"
+	"google.golang.org/protobuf/proto"
+)
+
+var ErrPermissionDenied = errors.New("permission denied")
+
+// Permission is a class of actions that a user's role may grant.
+type Permission int
+
+const (
+	PermissionRead    Permission = iota // view the config, operations, snapshots and logs.
+	PermissionRestore                   // restore snapshots and download restored files.
+	PermissionOperate                   // run backups, forgets, and repo maintenance tasks.
+	PermissionAdmin                     // modify the config and run arbitrary restic commands.
+)
+
+func (p Permission) String() string {
+	switch p {
+	case PermissionRead:
+		return "read"
+	case PermissionRestore:
+		return "restore"
+	case PermissionOperate:
+		return "operate"
+	case PermissionAdmin:
+		return "admin"
+	default:
+		return "unknown"
+	}
+}
+
+var rolePermissions = map[v1.User_Role][]Permission{
+	v1.User_ROLE_ADMIN:        {PermissionRead, PermissionRestore, PermissionOperate, PermissionAdmin},
+	v1.User_ROLE_OPERATOR:     {PermissionRead, PermissionRestore, PermissionOperate},
+	v1.User_ROLE_RESTORE_ONLY: {PermissionRead, PermissionRestore},
+	v1.User_ROLE_READ_ONLY:    {PermissionRead},
+}
+
+// UserFromContext returns the authenticated user for a request or nil if authentication is disabled.
+func UserFromContext(ctx context.Context) *v1.User {
+	user, _ := ctx.Value(UserContextKey).(*v1.User)
+	return user
+}
+
+// RoleOf returns the effective role of the user, ROLE_DEFAULT is resolved to ROLE_ADMIN.
+func RoleOf(user *v1.User) v1.User_Role {
+	if user.GetRole() == v1.User_ROLE_DEFAULT {
+		return v1.User_ROLE_ADMIN
+	}
+	return user.GetRole()
+}
+
+// HasPermission returns true if the user's role grants the permission. A nil user
+// indicates that authentication is disabled and is granted every permission.
+func HasPermission(user *v1.User, perm Permission) bool {
+	if user == nil {
+		return true
+	}
+	return slices.Contains(rolePermissions[RoleOf(user)], perm)
+}
+
+// IsUnrestricted returns true if the user is not limited to a subset of repos or plans.
+func IsUnrestricted(user *v1.User) bool {
+	return len(user.GetAllowedRepos()) == 0 && len(user.GetAllowedPlans()) == 0
+}
+
+// CanAccessRepo returns true if the user's allowlist permits access to the repo.
+func CanAccessRepo(user *v1.User, repoID string) bool {
+	allowed := user.GetAllowedRepos()
+	return len(allowed) == 0 || slices.Contains(allowed, repoID)
+}
+
+// CanAccessPlan returns true if the user's allowlist permits access to the plan.
+func CanAccessPlan(user *v1.User, planID string) bool {
+	allowed := user.GetAllowedPlans()
+	return len(allowed) == 0 || slices.Contains(allowed, planID)
+}
+
+// FilterConfigForUser returns a copy of the config containing only the repos and plans the user may
+// access, a plan is included if the user may access any of the repos it backs up to. Credentials of other users, repo passwords and repo env vars are stripped for users that are not
+// unrestricted admins.
+func FilterConfigForUser(cfg *v1.Config, user *v1.User) *v1.Config {
+	if user == nil || (HasPermission(user, PermissionAdmin) && IsUnrestricted(user)) {
+		return cfg
+	}
+
+	filtered := &v1.Config{
+		Modno:     cfg.Modno,
+		Version:   cfg.Version,
+		Instance:  cfg.Instance,
+		Multihost: cfg.Multihost,
+	}
+	for _, repo := range cfg.Repos {
+		if CanAccessRepo(user, repo.Id) {
+			repo = proto.Clone(repo).(*v1.Repo)
+			repo.Password = ""
+			repo.Env = nil
+			filtered.Repos = append(filtered.Repos, repo)
+		}
+	}
+	canAccessRepo := func(repoID string) bool { return CanAccessRepo(user, repoID) }
+	for _, plan := range cfg.Plans {
+		if !CanAccessPlan(user, plan.Id) || !slices.ContainsFunc(config.PlanRepos(plan), canAccessRepo) {
+			continue
+		}
+		// destinations in repos the user may not access are hidden, the plan's own repo is kept as the plan needs one.
+		if slices.ContainsFunc(plan.Destinations, func(d *v1.PlanDestination) bool { return !canAccessRepo(d.Repo) }) {
+			plan = proto.Clone(plan).(*v1.Plan)
+			plan.Destinations = slices.DeleteFunc(plan.Destinations, func(d *v1.PlanDestination) bool { return !canAccessRepo(d.Repo) })
+		}
+		filtered.Plans = append(filtered.Plans, plan)
+	}
+	if cfg.Auth != nil {
+		filtered.Auth = &v1.Auth{
+			Disabled: cfg.Auth.Disabled,
+			Users: []*v1.User{
+				{
+					Name:         user.Name,
+					Role:         user.Role,
+					AllowedRepos: user.AllowedRepos,
+					AllowedPlans: user.AllowedPlans,
+				},
+			},
+		}
+	}
+	return filtered
+}
@@ -0,0 +1,88 @@
+package auth
+
+import (
+	"testing"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+)
+
+func TestHasPermission(t *testing.T) {
+	tests := []struct {
+		name string
+		user *v1.User
+		perm Permission
+		want bool
+	}{
+		{"auth disabled", nil, PermissionAdmin, true},
+		{"default role is admin", &v1.User{Name: "legacy"}, PermissionAdmin, true},
+		{"operator can operate", &v1.User{Role: v1.User_ROLE_OPERATOR}, PermissionOperate, true},
+		{"operator cannot admin", &v1.User{Role: v1.User_ROLE_OPERATOR}, PermissionAdmin, false},
+		{"restore only can restore", &v1.User{Role: v1.User_ROLE_RESTORE_ONLY}, PermissionRestore, true},
+		{"restore only cannot operate", &v1.User{Role: v1.User_ROLE_RESTORE_ONLY}, PermissionOperate, false},
+		{"read only can read", &v1.User{Role: v1.User_ROLE_READ_ONLY}, PermissionRead, true},
+		{"read only cannot restore", &v1.User{Role: v1.User_ROLE_READ_ONLY}, PermissionRestore, false},
+	}
+
+	for _, tc := range tests {
+		t.Run(tc.name, func(t *testing.T) {
+			if got := HasPermission(tc.user, tc.perm); got != tc.want {
+				t.Errorf("HasPermission(%v, %v) = %v, want %v", tc.user, tc.perm, got, tc.want)
+			}
+		})
+	}
+}
+
+func TestFilterConfigForUser(t *testing.T) {
+	cfg := &v1.Config{
+		Instance: "test",
+		Repos: []*v1.Repo{
+			{Id: "repo1", Password: "repo-password", Env: []string{"AWS_SECRET_ACCESS_KEY=secret"}},
+			{Id: "repo2"},
+		},
+		Plans: []*v1.Plan{
+			{Id: "plan1", Repo: "repo1"},
+			{Id: "plan2", Repo: "repo1", Destinations: []*v1.PlanDestination{{Repo: "repo2"}}},
+			{Id: "plan3", Repo: "repo2"},
+			{Id: "plan4", Repo: "repo2", Destinations: []*v1.PlanDestination{{Repo: "repo1"}}},
+		},
+		Auth: &v1.Auth{
+			Users: []*v1.User{
+				{Name: "admin", Password: &v1.User_PasswordBcrypt{PasswordBcrypt: "secret"}},
+			},
+		},
+	}
+
+	user := &v1.User{
+		Name:         "helpdesk",
+		Role:         v1.User_ROLE_RESTORE_ONLY,
+		AllowedRepos: []string{"repo1"},
+		AllowedPlans: []string{"plan2", "plan3", "plan4"},
+	}
+
+	filtered := FilterConfigForUser(cfg, user)
+	if len(filtered.Repos) != 1 || filtered.Repos[0].Id != "repo1" {
+		t.Errorf("expected only repo1, got %v", filtered.Repos)
+	} else if filtered.Repos[0].Password != "" || len(filtered.Repos[0].Env) != 0 {
+		t.Errorf("expected repo credentials to be stripped, got %v", filtered.Repos[0])
+	}
+	if cfg.Repos[0].Password != "repo-password" {
+		t.Errorf("expected the original config to be unmodified")
+	}
+	if len(filtered.Plans) != 2 || filtered.Plans[0].Id != "plan2" || filtered.Plans[1].Id != "plan4" {
+		t.Errorf("expected plan2 and plan4, got %v", filtered.Plans)
+	} else if len(filtered.Plans[0].Destinations) != 0 || len(filtered.Plans[1].Destinations) != 1 {
+		t.Errorf("expected only destinations in repo1, got %v", filtered.Plans)
+	}
+	if len(cfg.Plans[1].Destinations) != 1 {
+		t.Errorf("expected the original plan's destinations to be unmodified")
+	}
+	for _, u := range filtered.Auth.GetUsers() {
+		if u.GetPasswordBcrypt() != "" {
+			t.Errorf("expected password hashes to be stripped, got user %v", u)
+		}
+	}
+
+	if got := FilterConfigForUser(cfg, &v1.User{Name: "admin"}); got != cfg {
+		t.Errorf("expected unrestricted admin to receive the full config")
+	}
+}
@@ -0,0 +1,157 @@
+package auth
+
+import (
+	"errors"
+	"fmt"
+	"sync"
+	"time"
+
+	lru "github.com/hashicorp/golang-lru/v2"
+)
+
+const (
+	userFreeAttempts     = 5  // failed logins for a user from one client address before the pair is locked out.
+	addressFreeAttempts  = 20 // failed logins from a client address across all users before the address is locked out.
+	globalFreeAttempts   = 50 // failed logins for a user across all client addresses before the user's logins are slowed down.
+	lockoutBase          = 30 * time.Second
+	lockoutMax           = 15 * time.Minute
+	globalBackoffMax     = 2 * time.Minute // cap of the user wide backoff, low enough that attackers can't keep the user out.
+	failedAttemptsExpiry = time.Hour // failures are forgotten after this long without another failure.
+	maxTrackedAttempts   = 10000     // bound on the tracked user and address pairs, and separately on the tracked addresses.
+)
+
+var ErrLoginLockedOut = errors.New("too many failed login attempts")
+
+// LoginLockoutEvent describes a user or client address that was locked out after repeated failed logins.
+type LoginLockoutEvent struct {
+	Username       string // set if the user was locked out at the client address or at every address.
+	ClientAddr     string // the locked out client address, or the address the user was locked out at. Empty if the user was locked out at every address.
+	FailedAttempts int
+	LockedUntil    time.Time
+}
+
+func (e LoginLockoutEvent) String() string {
+	subject := fmt.Sprintf("client address %q", e.ClientAddr)
+	if e.Username != "" && e.ClientAddr != "" {
+		subject = fmt.Sprintf("user %q at client address %q", e.Username, e.ClientAddr)
+	} else if e.Username != "" {
+		subject = fmt.Sprintf("user %q at every client address", e.Username)
+	}
+	return fmt.Sprintf("%v locked out until %v after %d failed login attempts", subject, e.LockedUntil.Format(time.RFC3339), e.FailedAttempts)
+}
+
+type failedAttempts struct {
+	count       int
+	lastFailure time.Time
+	lockedUntil time.Time
+}
+
+// loginLimiter tracks failed logins per user and client address pair and per client address. Once the free attempts
+// are used up each further failure locks the pair or address out for exponentially longer, up to lockoutMax. Locking
+// out pairs rather than users means failures from one address can't lock the user out everywhere else.
+//
+// Guessing a password from many addresses is slowed down by a per user limit across all addresses. It allows more
+// free attempts and its backoff is capped at globalBackoffMax rather than lockoutMax, an attacker can delay the user's
+// logins but can't lock them out for long.
+//
+// All are tracked in bounded LRU caches so that guessing many usernames or addresses can't grow memory without
+// limit, the least recently failed entries are evicted first.
+type loginLimiter struct {
+	mu        sync.Mutex
+	pairs     *lru.Cache[limiterPair, *failedAttempts]
+	addresses *lru.Cache[string, *failedAttempts]
+	users     *lru.Cache[string, *failedAttempts]
+	now       func() time.Time
+}
+
+type limiterPair struct {
+	username   string
+	clientAddr string
+}
+
+func newLoginLimiter() *loginLimiter {
+	pairs, _ := lru.New[limiterPair, *failedAttempts](maxTrackedAttempts)
+	addresses, _ := lru.New[string, *failedAttempts](maxTrackedAttempts)
+	users, _ := lru.New[string, *failedAttempts](maxTrackedAttempts)
+	return &loginLimiter{
+		pairs:     pairs,
+		addresses: addresses,
+		users:     users,
+		now:       time.Now,
+	}
+}
+
+// check returns an error if the user is locked out at the client address or at every address, or the client address
+// is locked out.
+func (l *loginLimiter) check(username, clientAddr string) error {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+
+	now := l.now()
+	for _, a := range l.lookup(username, clientAddr) {
+		if a != nil && now.Before(a.lockedUntil) {
+			return fmt.Errorf("%w, try again in %v", ErrLoginLockedOut, a.lockedUntil.Sub(now).Round(time.Second))
+		}
+	}
+	return nil
+}
+
+func (l *loginLimiter) lookup(username, clientAddr string) []*failedAttempts {
+	pair, _ := l.pairs.Peek(limiterPair{username, clientAddr})
+	user, _ := l.users.Peek(username)
+	if clientAddr == "" {
+		return []*failedAttempts{pair, user}
+	}
+	addr, _ := l.addresses.Peek(clientAddr)
+	return []*failedAttempts{pair, user, addr}
+}
+
+// recordFailure records a failed login and returns events for the pair or address if it is now locked out for the
+// first time since its failures were last reset.
+func (l *loginLimiter) recordFailure(username, clientAddr string) []LoginLockoutEvent {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+
+	now := l.now()
+	var events []LoginLockoutEvent
+	record := func(a *failedAttempts, freeAttempts int, maxLockout time.Duration, event LoginLockoutEvent) *failedAttempts {
+		if a == nil || now.Sub(a.lastFailure) > failedAttemptsExpiry {
+			a = &failedAttempts{}
+		}
+		a.count++
+		a.lastFailure = now
+
+		if a.count < freeAttempts {
+			return a
+		}
+		lockout := min(lockoutBase<<min(a.count-freeAttempts, 10), maxLockout)
+		a.lockedUntil = now.Add(lockout)
+
+		if a.count == freeAttempts {
+			event.FailedAttempts = a.count
+			event.LockedUntil = a.lockedUntil
+			events = append(events, event)
+		}
+		return a
+	}
+
+	pairKey := limiterPair{username, clientAddr}
+	pair, _ := l.pairs.Get(pairKey)
+	l.pairs.Add(pairKey, record(pair, userFreeAttempts, lockoutMax, LoginLockoutEvent{Username: username, ClientAddr: clientAddr}))
+	user, _ := l.users.Get(username)
+	l.users.Add(username, record(user, globalFreeAttempts, globalBackoffMax, LoginLockoutEvent{Username: username}))
+	if clientAddr != "" {
+		addr, _ := l.addresses.Get(clientAddr)
+		l.addresses.Add(clientAddr, record(addr, addressFreeAttempts, lockoutMax, LoginLockoutEvent{ClientAddr: clientAddr}))
+	}
+	return events
+}
+
+// recordSuccess forgets the failed logins of the user at the client address. Failures from the address are kept so
+// that an attacker with one valid account can't reset the limit of the address, and failures of the user at other
+// addresses are kept so that a successful login doesn't reset a distributed guessing attempt.
+func (l *loginLimiter) recordSuccess(username, clientAddr string) {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	l.pairs.Remove(limiterPair{username, clientAddr})
+}
@@ -0,0 +1,129 @@
+package auth
+
+import (
+	"errors"
+	"fmt"
+	"testing"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/config"
+)
+
+func TestLoginLimiter(t *testing.T) {
+	now := time.Unix(1000, 0)
+	l := newLoginLimiter()
+	l.now = func() time.Time { return now }
+
+	for i := 1; i < userFreeAttempts; i++ {
+		if events := l.recordFailure("alice", "10.0.0.1"); len(events) != 0 {
+			t.Fatalf("recordFailure() attempt %d returned lockout events %v", i, events)
+		}
+		if err := l.check("alice", "10.0.0.1"); err != nil {
+			t.Fatalf("check() after %d failures error = %v, want nil", i, err)
+		}
+	}
+
+	events := l.recordFailure("alice", "10.0.0.1")
+	if len(events) != 1 || events[0].Username != "alice" || events[0].ClientAddr != "10.0.0.1" || events[0].FailedAttempts != userFreeAttempts {
+		t.Fatalf("recordFailure() events = %v, want a lockout of alice at 10.0.0.1", events)
+	}
+	if err := l.check("alice", "10.0.0.1"); !errors.Is(err, ErrLoginLockedOut) {
+		t.Errorf("check() for a locked out user error = %v, want %v", err, ErrLoginLockedOut)
+	}
+	if err := l.check("alice", "10.0.0.2"); err != nil {
+		t.Errorf("check() for a locked out user from another address error = %v, want nil", err)
+	}
+	if err := l.check("bob", "10.0.0.1"); err != nil {
+		t.Errorf("check() for another user error = %v, want nil", err)
+	}
+
+	// each further failure doubles the lockout.
+	now = now.Add(lockoutBase)
+	if err := l.check("alice", "10.0.0.1"); err != nil {
+		t.Fatalf("check() after the lockout expired error = %v, want nil", err)
+	}
+	if events := l.recordFailure("alice", "10.0.0.1"); len(events) != 0 {
+		t.Errorf("recordFailure() while already locked out returned events %v, want none", events)
+	}
+	now = now.Add(lockoutBase)
+	if err := l.check("alice", "10.0.0.1"); !errors.Is(err, ErrLoginLockedOut) {
+		t.Errorf("check() during the doubled lockout error = %v, want %v", err, ErrLoginLockedOut)
+	}
+
+	l.recordSuccess("alice", "10.0.0.1")
+	if err := l.check("alice", "10.0.0.1"); err != nil {
+		t.Errorf("check() after a successful login error = %v, want nil", err)
+	}
+
+	// failures from one address across many users lock out the address.
+	var addrEvents []LoginLockoutEvent
+	for i := 0; i < addressFreeAttempts; i++ {
+		addrEvents = append(addrEvents, l.recordFailure(fmt.Sprintf("user%d", i), "10.0.0.3")...)
+	}
+	if len(addrEvents) != 1 || addrEvents[0].Username != "" || addrEvents[0].ClientAddr != "10.0.0.3" {
+		t.Errorf("recordFailure() events = %v, want a lockout of the address", addrEvents)
+	}
+	if err := l.check("carol", "10.0.0.3"); !errors.Is(err, ErrLoginLockedOut) {
+		t.Errorf("check() from a locked out address error = %v, want %v", err, ErrLoginLockedOut)
+	}
+
+	// failures for a user from many addresses slow down the user's logins everywhere, for a bounded time.
+	var userEvents []LoginLockoutEvent
+	for i := 0; i < globalFreeAttempts; i++ {
+		userEvents = append(userEvents, l.recordFailure("dave", fmt.Sprintf("10.2.0.%d", i))...)
+	}
+	if len(userEvents) != 1 || userEvents[0].Username != "dave" || userEvents[0].ClientAddr != "" {
+		t.Errorf("recordFailure() events = %v, want a lockout of dave at every address", userEvents)
+	}
+	if err := l.check("dave", "10.0.0.9"); !errors.Is(err, ErrLoginLockedOut) {
+		t.Errorf("check() for a user locked out at every address error = %v, want %v", err, ErrLoginLockedOut)
+	}
+	for i := 0; i < 20; i++ {
+		l.recordFailure("dave", "10.2.1.1")
+		now = now.Add(time.Second)
+	}
+	now = now.Add(globalBackoffMax)
+	if err := l.check("dave", "10.0.0.9"); err != nil {
+		t.Errorf("check() after the capped backoff error = %v, want nil", err)
+	}
+
+	// the tracked failures are bounded no matter how many usernames are guessed.
+	for i := 0; i < maxTrackedAttempts+100; i++ {
+		l.recordFailure(fmt.Sprintf("guess%d", i), fmt.Sprintf("10.1.%d.%d", i/256, i%256))
+	}
+	if l.pairs.Len() > maxTrackedAttempts || l.addresses.Len() > maxTrackedAttempts || l.users.Len() > maxTrackedAttempts {
+		t.Errorf("tracked %d pairs, %d addresses and %d users, want at most %d each", l.pairs.Len(), l.addresses.Len(), l.users.Len(), maxTrackedAttempts)
+	}
+}
+
+func TestLoginLockout(t *testing.T) {
+	store := &config.MemoryStore{
+		Config: &v1.Config{
+			Auth: &v1.Auth{
+				Users: []*v1.User{
+					{Name: "test", Password: &v1.User_PasswordBcrypt{PasswordBcrypt: makePass(t, "testPass")}},
+				},
+			},
+		},
+	}
+	auth := NewAuthenticator([]byte("key"), store, newTestSessionStore(t))
+	var events []LoginLockoutEvent
+	auth.SetLockoutHandler(func(e LoginLockoutEvent) { events = append(events, e) })
+
+	for i := 0; i < userFreeAttempts; i++ {
+		if _, err := auth.Login("10.0.0.1", "test", "wrongPass", ""); !errors.Is(err, ErrInvalidPassword) {
+			t.Fatalf("Login() error = %v, want %v", err, ErrInvalidPassword)
+		}
+	}
+	if len(events) != 1 || events[0].Username != "test" || events[0].ClientAddr != "10.0.0.1" {
+		t.Errorf("lockout events = %v, want one lockout of user test at 10.0.0.1", events)
+	}
+
+	if _, err := auth.Login("10.0.0.1", "test", "testPass", ""); !errors.Is(err, ErrLoginLockedOut) {
+		t.Errorf("Login() with the correct password while locked out error = %v, want %v", err, ErrLoginLockedOut)
+	}
+	if _, err := auth.Login("10.0.0.2", "test", "testPass", ""); err != nil {
+		t.Errorf("Login() from another address while locked out error = %v, want nil", err)
+	}
+}
@@ -0,0 +1,173 @@
+package auth
+
+import (
+	"errors"
+	"path/filepath"
+	"testing"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/config"
+	"google.golang.org/protobuf/proto"
+)
+
+func newTestSessionStore(t *testing.T) *SessionStore {
+	t.Helper()
+	store, err := NewSessionStore(filepath.Join(t.TempDir(), "sessions.sqlite"))
+	if err != nil {
+		t.Fatalf("NewSessionStore() error = %v", err)
+	}
+	t.Cleanup(func() { store.Close() })
+	return store
+}
+
+func TestSessions(t *testing.T) {
+	store := &config.MemoryStore{
+		Config: &v1.Config{
+			Auth: &v1.Auth{
+				Users: []*v1.User{
+					{Name: "test", Password: &v1.User_PasswordBcrypt{PasswordBcrypt: makePass(t, "testPass")}},
+				},
+			},
+		},
+	}
+	auth := NewAuthenticator([]byte("key"), store, newTestSessionStore(t))
+	user := store.Config.Auth.Users[0]
+
+	tokens, err := auth.CreateSession(user, "test-agent", "10.0.0.1")
+	if err != nil {
+		t.Fatalf("CreateSession() error = %v", err)
+	}
+	got, sessionID, err := auth.VerifyJWT(tokens.AccessToken)
+	if err != nil || got.Name != "test" || sessionID == "" {
+		t.Fatalf("VerifyJWT() = %v, %q, %v, want user test", got, sessionID, err)
+	}
+
+	refreshed, err := auth.RefreshSession(tokens.RefreshToken, "")
+	if err != nil {
+		t.Fatalf("RefreshSession() error = %v", err)
+	}
+	if _, _, err := auth.VerifyJWT(refreshed.AccessToken); err != nil {
+		t.Errorf("VerifyJWT() of the refreshed token error = %v", err)
+	}
+	if _, err := auth.RefreshSession(sessionID+".wrong", ""); !errors.Is(err, ErrInvalidRefreshToken) {
+		t.Errorf("RefreshSession() with a wrong secret error = %v, want %v", err, ErrInvalidRefreshToken)
+	}
+
+	sessions, err := auth.ListSessions("test")
+	if err != nil || len(sessions) != 1 || sessions[0].UserAgent != "test-agent" || sessions[0].ClientAddr != "10.0.0.1" {
+		t.Fatalf("ListSessions() = %v, %v, want the created session", sessions, err)
+	}
+
+	// revoking the session rejects its access token before it expires.
+	if err := auth.RevokeSession(sessionID); err != nil {
+		t.Fatalf("RevokeSession() error = %v", err)
+	}
+	if _, _, err := auth.VerifyJWT(tokens.AccessToken); !errors.Is(err, ErrSessionRevoked) {
+		t.Errorf("VerifyJWT() of a revoked session error = %v, want %v", err, ErrSessionRevoked)
+	}
+	if _, err := auth.RefreshSession(tokens.RefreshToken, ""); !errors.Is(err, ErrInvalidRefreshToken) {
+		t.Errorf("RefreshSession() of a revoked session error = %v, want %v", err, ErrInvalidRefreshToken)
+	}
+
+	// logging out ends the session.
+	tokens, err = auth.CreateSession(user, "", "")
+	if err != nil {
+		t.Fatalf("CreateSession() error = %v", err)
+	}
+	if err := auth.EndSession(tokens.RefreshToken); err != nil {
+		t.Fatalf("EndSession() error = %v", err)
+	}
+	if _, _, err := auth.VerifyJWT(tokens.AccessToken); !errors.Is(err, ErrSessionRevoked) {
+		t.Errorf("VerifyJWT() after logout error = %v, want %v", err, ErrSessionRevoked)
+	}
+}
+
+func TestSessionsRevokedByPasswordChange(t *testing.T) {
+	store := &config.MemoryStore{
+		Config: &v1.Config{
+			Auth: &v1.Auth{
+				Users: []*v1.User{
+					{Name: "test", Password: &v1.User_PasswordBcrypt{PasswordBcrypt: makePass(t, "testPass")}},
+				},
+			},
+		},
+	}
+	auth := NewAuthenticator([]byte("key"), store, newTestSessionStore(t))
+
+	tokens, err := auth.CreateSession(store.Config.Auth.Users[0], "", "")
+	if err != nil {
+		t.Fatalf("CreateSession() error = %v", err)
+	}
+
+	config := proto.Clone(store.Config).(*v1.Config)
+	config.Auth.Users[0].Password = &v1.User_PasswordBcrypt{PasswordBcrypt: makePass(t, "newPass")}
+	if err := store.Update(config); err != nil {
+		t.Fatalf("Update() error = %v", err)
+	}
+
+	if _, _, err := auth.VerifyJWT(tokens.AccessToken); !errors.Is(err, ErrSessionRevoked) {
+		t.Errorf("VerifyJWT() after a password change error = %v, want %v", err, ErrSessionRevoked)
+	}
+	if _, err := auth.RefreshSession(tokens.RefreshToken, ""); !errors.Is(err, ErrInvalidRefreshToken) {
+		t.Errorf("RefreshSession() after a password change error = %v, want %v", err, ErrInvalidRefreshToken)
+	}
+}
+
+func TestRefreshTokenRotation(t *testing.T) {
+	store := &config.MemoryStore{
+		Config: &v1.Config{
+			Auth: &v1.Auth{
+				Users: []*v1.User{
+					{Name: "test", Password: &v1.User_PasswordBcrypt{PasswordBcrypt: makePass(t, "testPass")}},
+				},
+			},
+		},
+	}
+	auth := NewAuthenticator([]byte("key"), store, newTestSessionStore(t))
+
+	tokens, err := auth.CreateSession(store.Config.Auth.Users[0], "", "")
+	if err != nil {
+		t.Fatalf("CreateSession() error = %v", err)
+	}
+	refreshed, err := auth.RefreshSession(tokens.RefreshToken, "")
+	if err != nil {
+		t.Fatalf("RefreshSession() error = %v", err)
+	}
+	if refreshed.RefreshToken == tokens.RefreshToken {
+		t.Fatalf("RefreshSession() returned the same refresh token, want a new one")
+	}
+
+	// a replaced token used right after the refresh is rejected without ending the session.
+	if _, err := auth.RefreshSession(tokens.RefreshToken, ""); !errors.Is(err, ErrInvalidRefreshToken) || errors.Is(err, ErrRefreshTokenReused) {
+		t.Errorf("RefreshSession() with the replaced token during the grace period error = %v, want %v", err, ErrInvalidRefreshToken)
+	}
+	replaced := refreshed
+	refreshed, err = auth.RefreshSession(replaced.RefreshToken, "")
+	if err != nil {
+		t.Fatalf("RefreshSession() with the new token error = %v", err)
+	}
+
+	// reuse of the replaced token after the grace period revokes the session.
+	_, sessionID, err := auth.VerifyJWT(refreshed.AccessToken)
+	if err != nil {
+		t.Fatalf("VerifyJWT() error = %v", err)
+	}
+	ss, err := auth.sessions.get(sessionID)
+	if err != nil {
+		t.Fatalf("get session: %v", err)
+	}
+	ss.rotatedAtMs -= (refreshReuseGrace + time.Second).Milliseconds()
+	if err := auth.sessions.put(ss); err != nil {
+		t.Fatalf("put session: %v", err)
+	}
+	if _, err := auth.RefreshSession(replaced.RefreshToken, ""); !errors.Is(err, ErrRefreshTokenReused) {
+		t.Errorf("RefreshSession() with a reused token error = %v, want %v", err, ErrRefreshTokenReused)
+	}
+	if _, err := auth.RefreshSession(refreshed.RefreshToken, ""); !errors.Is(err, ErrInvalidRefreshToken) {
+		t.Errorf("RefreshSession() with the current token after a reuse error = %v, want %v", err, ErrInvalidRefreshToken)
+	}
+	if _, _, err := auth.VerifyJWT(refreshed.AccessToken); !errors.Is(err, ErrSessionRevoked) {
+		t.Errorf("VerifyJWT() after a reuse error = %v, want %v", err, ErrSessionRevoked)
+	}
+}
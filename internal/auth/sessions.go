@@ -0,0 +1,281 @@
+package auth
+
+import (
+	"context"
+	"crypto/sha256"
+	"crypto/subtle"
+	"encoding/hex"
+	"errors"
+	"fmt"
+	"strings"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/cryptoutil"
+	"go.uber.org/zap"
+)
+
+const (
+	accessTokenTTL         = 15 * time.Minute
+	sessionTTL             = 30 * 24 * time.Hour // sessions end after this long without a refresh.
+	lastSeenUpdateInterval = time.Minute         // how stale the stored last seen time of a session may become.
+	refreshReuseGrace      = 10 * time.Second    // a replaced refresh token is rejected without revoking the session this soon after the refresh, e.g. when two browser tabs refresh at once.
+)
+
+var ErrInvalidRefreshToken = errors.New("invalid refresh token")
+var ErrSessionRevoked = errors.New("session revoked")
+var ErrRefreshTokenReused = errors.New("refresh token reused")
+
+// Tokens are issued when a session is created or refreshed.
+type Tokens struct {
+	AccessToken  string
+	RefreshToken string
+	ExpiresAt    time.Time // expiry of the access token.
+}
+
+// SessionIDFromContext returns the ID of the session that authenticated a request, or "" if the request did not
+// use an access token.
+func SessionIDFromContext(ctx context.Context) string {
+	id, _ := ctx.Value(SessionContextKey).(string)
+	return id
+}
+
+// CreateSession starts a session for a user that logged in and issues its first tokens.
+func (a *Authenticator) CreateSession(user *v1.User, userAgent, clientAddr string) (*Tokens, error) {
+	id, err := cryptoutil.RandomID(64)
+	if err != nil {
+		return nil, fmt.Errorf("generate session id: %w", err)
+	}
+	secret, err := cryptoutil.RandomID(cryptoutil.DefaultIDBits)
+	if err != nil {
+		return nil, fmt.Errorf("generate refresh token: %w", err)
+	}
+
+	now := time.Now()
+	if err := a.sessions.deleteExpired(now.UnixMilli()); err != nil {
+		zap.L().Warn("failed to delete expired sessions", zap.Error(err))
+	}
+
+	ss := &storedSession{
+		session: &v1.Session{
+			Id:           id,
+			User:         user.Name,
+			UserAgent:    userAgent,
+			ClientAddr:   clientAddr,
+			CreatedAtMs:  now.UnixMilli(),
+			LastSeenAtMs: now.UnixMilli(),
+			ExpiresAtMs:  now.Add(sessionTTL).UnixMilli(),
+		},
+		refreshHash: hashSecret(secret),
+		credential:  credentialFingerprint(user),
+	}
+	if err := a.sessions.put(ss); err != nil {
+		return nil, err
+	}
+
+	return a.issueTokens(user, id, id+"."+secret, now)
+}
+
+// RefreshSession issues a new access token and a new refresh token for the session of the refresh token and extends
+// the session. The refresh token is single use, presenting a replaced refresh token again means it was stolen from
+// the user or the user's new one was stolen, either way the session is revoked.
+func (a *Authenticator) RefreshSession(refreshToken, clientAddr string) (*Tokens, error) {
+	ss, user, err := a.sessionForRefreshToken(refreshToken)
+	if err != nil {
+		return nil, err
+	}
+	secret, err := cryptoutil.RandomID(cryptoutil.DefaultIDBits)
+	if err != nil {
+		return nil, fmt.Errorf("generate refresh token: %w", err)
+	}
+
+	now := time.Now()
+	previousHash := ss.refreshHash
+	ss.previousRefreshHash = previousHash
+	ss.refreshHash = hashSecret(secret)
+	ss.rotatedAtMs = now.UnixMilli()
+	ss.session.LastSeenAtMs = now.UnixMilli()
+	ss.session.ExpiresAtMs = now.Add(sessionTTL).UnixMilli()
+	if clientAddr != "" {
+		ss.session.ClientAddr = clientAddr
+	}
+	if err := a.sessions.replace(ss, previousHash); err != nil {
+		return nil, err
+	}
+
+	return a.issueTokens(user, ss.session.Id, ss.session.Id+"."+secret, now)
+}
+
+// EndSession ends the session of the refresh token e.g. when the user logs out.
+func (a *Authenticator) EndSession(refreshToken string) error {
+	ss, _, err := a.sessionForRefreshToken(refreshToken)
+	if err != nil {
+		return err
+	}
+	return a.sessions.delete(ss.session.Id)
+}
+
+// RevokeSession ends the session with the given ID, its access tokens are rejected immediately.
+func (a *Authenticator) RevokeSession(id string) error {
+	if _, err := a.sessions.get(id); err != nil {
+		return err
+	}
+	return a.sessions.delete(id)
+}
+
+// GetSession returns the session with the given ID.
+func (a *Authenticator) GetSession(id string) (*v1.Session, error) {
+	ss, err := a.sessions.get(id)
+	if err != nil {
+		return nil, err
+	}
+	return ss.session, nil
+}
+
+// ListSessions returns the active sessions of the user, or of all users if username is empty. Sessions invalidated by
+// a credential change are removed.
+func (a *Authenticator) ListSessions(username string) ([]*v1.Session, error) {
+	config, err := a.config.Get()
+	if err != nil {
+		return nil, fmt.Errorf("get config: %w", err)
+	}
+	stored, err := a.sessions.list(username)
+	if err != nil {
+		return nil, err
+	}
+
+	nowMs := time.Now().UnixMilli()
+	var sessions []*v1.Session
+	var revoked []string
+	for _, ss := range stored {
+		if ss.session.ExpiresAtMs < nowMs {
+			continue
+		}
+		if user := findUser(config, ss.session.User); user == nil || credentialFingerprint(user) != ss.credential {
+			revoked = append(revoked, ss.session.Id)
+			continue
+		}
+		sessions = append(sessions, ss.session)
+	}
+	if err := a.sessions.delete(revoked...); err != nil {
+		return nil, err
+	}
+	return sessions, nil
+}
+
+// verifySession checks that the session is still valid for the user and records it as seen.
+func (a *Authenticator) verifySession(id string, user *v1.User) error {
+	ss, err := a.sessions.get(id)
+	if errors.Is(err, ErrSessionNotFound) {
+		return ErrSessionRevoked
+	} else if err != nil {
+		return err
+	}
+	if err := a.checkSessionCredential(ss, user); err != nil {
+		return err
+	}
+
+	now := time.Now()
+	if now.Sub(time.UnixMilli(ss.session.LastSeenAtMs)) > lastSeenUpdateInterval {
+		ss.session.LastSeenAtMs = now.UnixMilli()
+		// conditional on the refresh hash so that a concurrent refresh isn't overwritten, the refresh updates it too.
+		if err := a.sessions.replace(ss, ss.refreshHash); err != nil && !errors.Is(err, ErrInvalidRefreshToken) {
+			zap.L().Warn("failed to update session last seen time", zap.Error(err))
+		}
+	}
+	return nil
+}
+
+func (a *Authenticator) sessionForRefreshToken(refreshToken string) (*storedSession, *v1.User, error) {
+	id, secret, ok := strings.Cut(refreshToken, ".")
+	if !ok {
+		return nil, nil, ErrInvalidRefreshToken
+	}
+	ss, err := a.sessions.get(id)
+	if errors.Is(err, ErrSessionNotFound) {
+		return nil, nil, ErrInvalidRefreshToken
+	} else if err != nil {
+		return nil, nil, err
+	}
+	hash := hashSecret(secret)
+	if subtle.ConstantTimeCompare([]byte(hash), []byte(ss.refreshHash)) != 1 {
+		if ss.previousRefreshHash == "" || subtle.ConstantTimeCompare([]byte(hash), []byte(ss.previousRefreshHash)) != 1 {
+			return nil, nil, ErrInvalidRefreshToken
+		}
+		if time.Since(time.UnixMilli(ss.rotatedAtMs)) <= refreshReuseGrace {
+			return nil, nil, fmt.Errorf("%w: session was already refreshed", ErrInvalidRefreshToken)
+		}
+		if err := a.sessions.delete(ss.session.Id); err != nil {
+			return nil, nil, err
+		}
+		zap.S().Warnf("revoked session %q of user %q after its replaced refresh token was reused", ss.session.Id, ss.session.User)
+		return nil, nil, fmt.Errorf("%w: %w", ErrInvalidRefreshToken, ErrRefreshTokenReused)
+	}
+	if ss.session.ExpiresAtMs < time.Now().UnixMilli() {
+		return nil, nil, fmt.Errorf("%w: session expired", ErrInvalidRefreshToken)
+	}
+
+	config, err := a.config.Get()
+	if err != nil {
+		return nil, nil, fmt.Errorf("get config: %w", err)
+	}
+	user := findUser(config, ss.session.User)
+	if user == nil {
+		return nil, nil, ErrUserNotFound
+	}
+	if err := a.checkSessionCredential(ss, user); err != nil {
+		return nil, nil, err
+	}
+	return ss, user, nil
+}
+
+// checkSessionCredential ends the session if the user's password changed since it was created.
+func (a *Authenticator) checkSessionCredential(ss *storedSession, user *v1.User) error {
+	if credentialFingerprint(user) == ss.credential {
+		return nil
+	}
+	if err := a.sessions.delete(ss.session.Id); err != nil {
+		return err
+	}
+	zap.S().Infof("revoked session %q of user %q after its credentials changed", ss.session.Id, user.Name)
+	return fmt.Errorf("%w: credentials changed", ErrSessionRevoked)
+}
+
+func (a *Authenticator) issueTokens(user *v1.User, sessionID, refreshToken string, now time.Time) (*Tokens, error) {
+	expiresAt := now.Add(accessTokenTTL)
+	accessToken, err := a.createAccessToken(user, sessionID, expiresAt)
+	if err != nil {
+		return nil, err
+	}
+	return &Tokens{
+		AccessToken:  accessToken,
+		RefreshToken: refreshToken,
+		ExpiresAt:    expiresAt,
+	}, nil
+}
+
+// credentialFingerprint identifies the user's current password (or single sign-on subject).
+func credentialFingerprint(user *v1.User) string {
+	var credential string
+	switch pw := user.Password.(type) {
+	case *v1.User_PasswordBcrypt:
+		credential = "password:" + pw.PasswordBcrypt
+	case *v1.User_OidcSubject:
+		credential = "oidc:" + pw.OidcSubject
+	}
+	return hashSecret(credential)
+}
+
+func findUser(config *v1.Config, username string) *v1.User {
+	for _, user := range config.GetAuth().GetUsers() {
+		if user.Name == username {
+			return user
+		}
+	}
+	return nil
+}
+
+func hashSecret(secret string) string {
+	sum := sha256.Sum256([]byte(secret))
+	return hex.EncodeToString(sum[:])
+}
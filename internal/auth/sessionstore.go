@@ -0,0 +1,195 @@
+package auth
+
+import (
+	"context"
+	"errors"
+	"fmt"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/sqliteutil"
+	"google.golang.org/protobuf/proto"
+	"zombiezen.com/go/sqlite"
+	"zombiezen.com/go/sqlite/sqlitex"
+)
+
+var ErrSessionNotFound = errors.New("session not found")
+
+// storedSession is a session with the secrets that are only kept server side.
+type storedSession struct {
+	session     *v1.Session
+	refreshHash string // sha256 of the refresh token secret.
+	credential  string // fingerprint of the user's credential at login, the session ends if it changes.
+
+	previousRefreshHash string // sha256 of the secret replaced by the last refresh, presenting it again is a reuse.
+	rotatedAtMs         int64  // time of the last refresh.
+}
+
+// SessionStore persists login sessions so that they survive restarts and can be listed and revoked.
+type SessionStore struct {
+	dbpool *sqlitex.Pool
+}
+
+// sessionMigrations build up the session store's schema, see sqliteutil.OpenPool.
+var sessionMigrations = []string{
+	`
+	CREATE TABLE IF NOT EXISTS sessions (
+		id TEXT PRIMARY KEY,
+		user TEXT NOT NULL,
+		refresh_hash TEXT NOT NULL,
+		credential TEXT NOT NULL,
+		expires_at_ms INTEGER NOT NULL,
+		session BLOB NOT NULL -- serialized Session proto
+	);
+
+	CREATE INDEX IF NOT EXISTS sessions_user_idx ON sessions (user);
+	`,
+	`
+	ALTER TABLE sessions ADD COLUMN previous_refresh_hash TEXT NOT NULL DEFAULT '';
+	ALTER TABLE sessions ADD COLUMN rotated_at_ms INTEGER NOT NULL DEFAULT 0;
+	`,
+}
+
+func NewSessionStore(dbpath string) (*SessionStore, error) {
+	dbpool, err := sqliteutil.OpenPool(dbpath, 4, sessionMigrations)
+	if err != nil {
+		return nil, fmt.Errorf("init session store: %v", err)
+	}
+	return &SessionStore{dbpool: dbpool}, nil
+}
+
+func (s *SessionStore) Close() error {
+	return s.dbpool.Close()
+}
+
+// put creates or replaces a session.
+func (s *SessionStore) put(ss *storedSession) error {
+	data, err := proto.Marshal(ss.session)
+	if err != nil {
+		return fmt.Errorf("marshal session: %v", err)
+	}
+
+	conn, err := s.dbpool.Take(context.Background())
+	if err != nil {
+		return fmt.Errorf("take connection: %v", err)
+	}
+	defer s.dbpool.Put(conn)
+
+	if err := sqlitex.ExecuteTransient(conn, "INSERT OR REPLACE INTO sessions (id, user, refresh_hash, credential, expires_at_ms, session, previous_refresh_hash, rotated_at_ms) VALUES (?, ?, ?, ?, ?, ?, ?, ?)", &sqlitex.ExecOptions{
+		Args: []any{ss.session.Id, ss.session.User, ss.refreshHash, ss.credential, ss.session.ExpiresAtMs, data, ss.previousRefreshHash, ss.rotatedAtMs},
+	}); err != nil {
+		return fmt.Errorf("insert session: %v", err)
+	}
+	return nil
+}
+
+// replace updates the stored session if its refresh hash is still expectedHash, it returns ErrInvalidRefreshToken if a
+// concurrent refresh rotated the session first.
+func (s *SessionStore) replace(ss *storedSession, expectedHash string) error {
+	data, err := proto.Marshal(ss.session)
+	if err != nil {
+		return fmt.Errorf("marshal session: %v", err)
+	}
+
+	conn, err := s.dbpool.Take(context.Background())
+	if err != nil {
+		return fmt.Errorf("take connection: %v", err)
+	}
+	defer s.dbpool.Put(conn)
+
+	if err := sqlitex.ExecuteTransient(conn, "UPDATE sessions SET refresh_hash = ?, expires_at_ms = ?, session = ?, previous_refresh_hash = ?, rotated_at_ms = ? WHERE id = ? AND refresh_hash = ?", &sqlitex.ExecOptions{
+		Args: []any{ss.refreshHash, ss.session.ExpiresAtMs, data, ss.previousRefreshHash, ss.rotatedAtMs, ss.session.Id, expectedHash},
+	}); err != nil {
+		return fmt.Errorf("update session: %v", err)
+	}
+	if conn.Changes() == 0 {
+		return fmt.Errorf("%w: session was already refreshed", ErrInvalidRefreshToken)
+	}
+	return nil
+}
+
+// get returns the session with the given ID or ErrSessionNotFound.
+func (s *SessionStore) get(id string) (*storedSession, error) {
+	sessions, err := s.query("SELECT refresh_hash, credential, session, previous_refresh_hash, rotated_at_ms FROM sessions WHERE id = ?", id)
+	if err != nil {
+		return nil, err
+	}
+	if len(sessions) == 0 {
+		return nil, ErrSessionNotFound
+	}
+	return sessions[0], nil
+}
+
+// list returns all sessions, or only those of the user if username is set.
+func (s *SessionStore) list(username string) ([]*storedSession, error) {
+	if username == "" {
+		return s.query("SELECT refresh_hash, credential, session, previous_refresh_hash, rotated_at_ms FROM sessions ORDER BY expires_at_ms DESC")
+	}
+	return s.query("SELECT refresh_hash, credential, session, previous_refresh_hash, rotated_at_ms FROM sessions WHERE user = ? ORDER BY expires_at_ms DESC", username)
+}
+
+func (s *SessionStore) query(query string, args ...any) ([]*storedSession, error) {
+	conn, err := s.dbpool.Take(context.Background())
+	if err != nil {
+		return nil, fmt.Errorf("take connection: %v", err)
+	}
+	defer s.dbpool.Put(conn)
+
+	var sessions []*storedSession
+	if err := sqlitex.ExecuteTransient(conn, query, &sqlitex.ExecOptions{
+		Args: args,
+		ResultFunc: func(stmt *sqlite.Stmt) error {
+			data := make([]byte, stmt.ColumnLen(2))
+			stmt.ColumnBytes(2, data)
+			session := &v1.Session{}
+			if err := proto.Unmarshal(data, session); err != nil {
+				return fmt.Errorf("unmarshal session: %v", err)
+			}
+			sessions = append(sessions, &storedSession{
+				session:     session,
+				refreshHash: stmt.ColumnText(0),
+				credential:  stmt.ColumnText(1),
+
+				previousRefreshHash: stmt.ColumnText(3),
+				rotatedAtMs:         stmt.ColumnInt64(4),
+			})
+			return nil
+		},
+	}); err != nil {
+		return nil, fmt.Errorf("query sessions: %v", err)
+	}
+	return sessions, nil
+}
+
+// delete removes the sessions with the given IDs, missing sessions are ignored.
+func (s *SessionStore) delete(ids ...string) error {
+	conn, err := s.dbpool.Take(context.Background())
+	if err != nil {
+		return fmt.Errorf("take connection: %v", err)
+	}
+	defer s.dbpool.Put(conn)
+
+	for _, id := range ids {
+		if err := sqlitex.ExecuteTransient(conn, "DELETE FROM sessions WHERE id = ?", &sqlitex.ExecOptions{
+			Args: []any{id},
+		}); err != nil {
+			return fmt.Errorf("delete session: %v", err)
+		}
+	}
+	return nil
+}
+
+// deleteExpired removes sessions that expired before nowMs.
+func (s *SessionStore) deleteExpired(nowMs int64) error {
+	conn, err := s.dbpool.Take(context.Background())
+	if err != nil {
+		return fmt.Errorf("take connection: %v", err)
+	}
+	defer s.dbpool.Put(conn)
+
+	if err := sqlitex.ExecuteTransient(conn, "DELETE FROM sessions WHERE expires_at_ms < ?", &sqlitex.ExecOptions{
+		Args: []any{nowMs},
+	}); err != nil {
+		return fmt.Errorf("delete expired sessions: %v", err)
+	}
+	return nil
+}
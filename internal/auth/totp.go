@@ -0,0 +1,230 @@
+package auth
+
+import (
+	"crypto/hmac"
+	"crypto/rand"
+	"crypto/sha1"
+	"crypto/sha256"
+	"crypto/subtle"
+	"encoding/base32"
+	"encoding/binary"
+	"encoding/hex"
+	"errors"
+	"fmt"
+	"net/url"
+	"slices"
+	"strings"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+)
+
+const (
+	totpIssuer        = "Backrest"
+	totpPeriod        = 30 // seconds per time step, RFC 6238 default.
+	totpDigits        = 6
+	totpModulo        = 1000000 // 10^totpDigits
+	totpSkew          = 1       // steps before and after the current step that are accepted to tolerate clock drift.
+	recoveryCodeCount = 10
+)
+
+var ErrTOTPRequired = errors.New("one time code required")
+var ErrInvalidTOTPCode = errors.New("invalid one time code")
+var ErrCurrentCredentialRequired = errors.New("current password or one time code required")
+
+var totpEncoding = base32.StdEncoding.WithPadding(base32.NoPadding)
+
+// GenerateTOTPSecret returns a new random secret and the otpauth:// URI that provisions it in authenticator apps.
+func GenerateTOTPSecret(username, instance string) (secret string, uri string, err error) {
+	buf := make([]byte, 20)
+	if _, err := rand.Read(buf); err != nil {
+		return "", "", fmt.Errorf("generate totp secret: %w", err)
+	}
+	secret = totpEncoding.EncodeToString(buf)
+
+	issuer := totpIssuer
+	if instance != "" {
+		issuer += " " + instance
+	}
+	params := url.Values{}
+	params.Set("secret", secret)
+	params.Set("issuer", issuer)
+	params.Set("algorithm", "SHA1")
+	params.Set("digits", fmt.Sprintf("%d", totpDigits))
+	params.Set("period", fmt.Sprintf("%d", totpPeriod))
+	uri = (&url.URL{
+		Scheme:   "otpauth",
+		Host:     "totp",
+		Path:     "/" + issuer + ":" + username,
+		RawQuery: params.Encode(),
+	}).String()
+	return secret, uri, nil
+}
+
+// EnrollTOTP enables TOTP for the user if the code was generated from the secret. The returned recovery codes are the
+// only copy, each may be used once in place of a one time code.
+func (a *Authenticator) EnrollTOTP(username, secret, code string) ([]string, error) {
+	if _, ok := matchTOTPCode(secret, code, time.Now()); !ok {
+		return nil, ErrInvalidTOTPCode
+	}
+
+	var codes, hashes []string
+	for i := 0; i < recoveryCodeCount; i++ {
+		buf := make([]byte, 8)
+		if _, err := rand.Read(buf); err != nil {
+			return nil, fmt.Errorf("generate recovery code: %w", err)
+		}
+		c := strings.ToLower(hex.EncodeToString(buf))
+		c = c[:8] + "-" + c[8:]
+		codes = append(codes, c)
+		hashes = append(hashes, hashRecoveryCode(c))
+	}
+
+	if err := a.updateUser(username, func(user *v1.User) error {
+		if _, ok := user.Password.(*v1.User_OidcSubject); ok {
+			return fmt.Errorf("user %q logs in with single sign-on, configure two factor authentication with the identity provider", username)
+		}
+		user.Totp = &v1.User_TOTP{
+			Secret:             secret,
+			RecoveryCodeHashes: hashes,
+		}
+		return nil
+	}); err != nil {
+		return nil, err
+	}
+	return codes, nil
+}
+
+// DisableTOTP removes TOTP from the user, logins only require the password afterwards.
+func (a *Authenticator) DisableTOTP(username string) error {
+	return a.updateUser(username, func(user *v1.User) error {
+		user.Totp = nil
+		return nil
+	})
+}
+
+// VerifyCurrentCredential re-authenticates a logged in user before they replace or remove their second factor, a
+// stolen session alone must not be enough. The credential is the user's password or, if the user enrolled TOTP, a one
+// time code or recovery code. Failures are rate limited like logins.
+func (a *Authenticator) VerifyCurrentCredential(clientAddr, username, credential string) error {
+	if credential == "" {
+		return ErrCurrentCredentialRequired
+	}
+	if err := a.limiter.check(username, clientAddr); err != nil {
+		return err
+	}
+	config, err := a.config.Get()
+	if err != nil {
+		return fmt.Errorf("get config: %w", err)
+	}
+	idx := slices.IndexFunc(config.GetAuth().GetUsers(), func(u *v1.User) bool { return u.Name == username })
+	if idx == -1 {
+		return ErrUserNotFound
+	}
+	user := config.Auth.Users[idx]
+
+	err = checkPassword(user, credential)
+	if err != nil && user.GetTotp() != nil {
+		if a.checkSecondFactor(user, credential) == nil {
+			err = nil
+		}
+	}
+	if err != nil {
+		a.recordLoginFailure(username, clientAddr)
+		return fmt.Errorf("%w: %w", ErrInvalidPassword, ErrCurrentCredentialRequired)
+	}
+	return nil
+}
+
+// checkSecondFactor verifies the one time code or recovery code for users that enrolled TOTP. Recovery codes are
+// consumed and one time codes may not be replayed.
+func (a *Authenticator) checkSecondFactor(user *v1.User, code string) error {
+	totp := user.GetTotp()
+	if totp == nil {
+		return nil
+	}
+	code = strings.ReplaceAll(strings.TrimSpace(code), " ", "")
+	if code == "" {
+		return ErrTOTPRequired
+	}
+
+	if step, ok := matchTOTPCode(totp.Secret, code, time.Now()); ok {
+		// the step is persisted so that a code can't be replayed after a restart, the in memory copy also covers a
+		// config saved from a stale copy of the user.
+		return a.updateUser(user.Name, func(user *v1.User) error {
+			totp := user.GetTotp()
+			if totp == nil {
+				return ErrInvalidTOTPCode // disabled concurrently.
+			}
+			a.mu.Lock()
+			defer a.mu.Unlock()
+			if step <= max(totp.LastUsedStep, a.totpLastStep[user.Name]) {
+				return fmt.Errorf("%w: code was already used", ErrInvalidTOTPCode)
+			}
+			totp.LastUsedStep = step
+			a.totpLastStep[user.Name] = step
+			return nil
+		})
+	}
+
+	hash := hashRecoveryCode(code)
+	if !slices.Contains(totp.RecoveryCodeHashes, hash) {
+		return ErrInvalidTOTPCode
+	}
+	return a.updateUser(user.Name, func(user *v1.User) error {
+		codes := user.GetTotp().GetRecoveryCodeHashes()
+		idx := slices.Index(codes, hash)
+		if idx == -1 {
+			return ErrInvalidTOTPCode // used concurrently.
+		}
+		user.Totp.RecoveryCodeHashes = slices.Delete(codes, idx, idx+1)
+		return nil
+	})
+}
+
+// updateUser applies fn to the named user and writes the config back.
+func (a *Authenticator) updateUser(username string, fn func(user *v1.User) error) error {
+	return a.updateConfig(func(config *v1.Config) (bool, error) {
+		idx := slices.IndexFunc(config.GetAuth().GetUsers(), func(u *v1.User) bool { return u.Name == username })
+		if idx == -1 {
+			return false, ErrUserNotFound
+		}
+		if err := fn(config.Auth.Users[idx]); err != nil {
+			return false, err
+		}
+		return true, nil
+	})
+}
+
+// matchTOTPCode returns the time step the code was generated for if it matches any step within the allowed skew.
+func matchTOTPCode(secret, code string, now time.Time) (int64, bool) {
+	key, err := totpEncoding.DecodeString(strings.ToUpper(strings.TrimRight(secret, "=")))
+	if err != nil || len(code) != totpDigits {
+		return 0, false
+	}
+	current := now.Unix() / totpPeriod
+	for step := current - totpSkew; step <= current+totpSkew; step++ {
+		if subtle.ConstantTimeCompare([]byte(totpCode(key, step)), []byte(code)) == 1 {
+			return step, true
+		}
+	}
+	return 0, false
+}
+
+// totpCode computes the HOTP value (RFC 4226) of the key for a time step.
+func totpCode(key []byte, step int64) string {
+	var msg [8]byte
+	binary.BigEndian.PutUint64(msg[:], uint64(step))
+	mac := hmac.New(sha1.New, key)
+	mac.Write(msg[:])
+	sum := mac.Sum(nil)
+
+	offset := sum[len(sum)-1] & 0x0f
+	value := binary.BigEndian.Uint32(sum[offset:offset+4]) & 0x7fffffff
+	return fmt.Sprintf("%0*d", totpDigits, value%totpModulo)
+}
+
+func hashRecoveryCode(code string) string {
+	sum := sha256.Sum256([]byte(strings.ToLower(code)))
+	return hex.EncodeToString(sum[:])
+}
@@ -0,0 +1,134 @@
+package auth
+
+import (
+	"errors"
+	"strings"
+	"testing"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/config"
+)
+
+func TestTOTPCode(t *testing.T) {
+	// test vectors from RFC 6238 appendix B truncated to 6 digits.
+	key := []byte("12345678901234567890")
+	tests := []struct {
+		unixTime int64
+		want     string
+	}{
+		{59, "287082"},
+		{1111111109, "081804"},
+		{1234567890, "005924"},
+		{2000000000, "279037"},
+	}
+
+	for _, tc := range tests {
+		if got := totpCode(key, tc.unixTime/totpPeriod); got != tc.want {
+			t.Errorf("totpCode() at %d = %q, want %q", tc.unixTime, got, tc.want)
+		}
+	}
+
+	secret := totpEncoding.EncodeToString(key)
+	now := time.Unix(1111111109, 0)
+	if _, ok := matchTOTPCode(secret, "081804", now.Add(totpPeriod*time.Second)); !ok {
+		t.Errorf("matchTOTPCode() rejected a code from the previous time step")
+	}
+	if _, ok := matchTOTPCode(secret, "081804", now.Add(5*totpPeriod*time.Second)); ok {
+		t.Errorf("matchTOTPCode() accepted an expired code")
+	}
+}
+
+func TestLoginTOTP(t *testing.T) {
+	store := &config.MemoryStore{
+		Config: &v1.Config{
+			Auth: &v1.Auth{
+				Users: []*v1.User{
+					{Name: "test", Password: &v1.User_PasswordBcrypt{PasswordBcrypt: makePass(t, "testPass")}},
+				},
+			},
+		},
+	}
+	auth := NewAuthenticator([]byte("key"), store, newTestSessionStore(t))
+
+	secret, uri, err := GenerateTOTPSecret("test", "instance")
+	if err != nil {
+		t.Fatalf("GenerateTOTPSecret() error = %v", err)
+	}
+	if !strings.HasPrefix(uri, "otpauth://totp/") || !strings.Contains(uri, "secret="+secret) {
+		t.Errorf("GenerateTOTPSecret() uri = %q, want an otpauth uri with the secret", uri)
+	}
+
+	key, err := totpEncoding.DecodeString(secret)
+	if err != nil {
+		t.Fatalf("decode secret: %v", err)
+	}
+	code := totpCode(key, time.Now().Unix()/totpPeriod)
+
+	if _, err := auth.EnrollTOTP("test", secret, "abcdef"); !errors.Is(err, ErrInvalidTOTPCode) {
+		t.Errorf("EnrollTOTP() with a wrong code error = %v, want %v", err, ErrInvalidTOTPCode)
+	}
+	recoveryCodes, err := auth.EnrollTOTP("test", secret, code)
+	if err != nil {
+		t.Fatalf("EnrollTOTP() error = %v", err)
+	}
+	if len(recoveryCodes) != recoveryCodeCount {
+		t.Errorf("EnrollTOTP() returned %d recovery codes, want %d", len(recoveryCodes), recoveryCodeCount)
+	}
+
+	tests := []struct {
+		name     string
+		password string
+		code     string
+		wantErr  error
+	}{
+		{"password only", "testPass", "", ErrTOTPRequired},
+		{"wrong password", "wrongPass", code, ErrInvalidPassword},
+		{"valid code", "testPass", code, nil},
+		{"replayed code", "testPass", code, ErrInvalidTOTPCode},
+		{"recovery code", "testPass", recoveryCodes[0], nil},
+		{"reused recovery code", "testPass", recoveryCodes[0], ErrInvalidTOTPCode},
+	}
+
+	for _, tc := range tests {
+		t.Run(tc.name, func(t *testing.T) {
+			_, err := auth.Login("", "test", tc.password, tc.code)
+			if !errors.Is(err, tc.wantErr) {
+				t.Fatalf("Login() error = %v, want %v", err, tc.wantErr)
+			}
+		})
+	}
+
+	// the last used step is persisted, a restarted server doesn't accept the code again.
+	restarted := NewAuthenticator([]byte("key"), store, newTestSessionStore(t))
+	if _, err := restarted.Login("", "test", "testPass", code); !errors.Is(err, ErrInvalidTOTPCode) {
+		t.Errorf("Login() with a replayed code after restart error = %v, want %v", err, ErrInvalidTOTPCode)
+	}
+
+	reauthTests := []struct {
+		name       string
+		credential string
+		wantErr    error
+	}{
+		{"no credential", "", ErrCurrentCredentialRequired},
+		{"wrong password", "wrongPass", ErrInvalidPassword},
+		{"password", "testPass", nil},
+		{"recovery code", recoveryCodes[1], nil},
+		{"reused recovery code", recoveryCodes[1], ErrInvalidPassword},
+	}
+	for _, tc := range reauthTests {
+		t.Run("reauthenticate with "+tc.name, func(t *testing.T) {
+			err := auth.VerifyCurrentCredential("", "test", tc.credential)
+			if !errors.Is(err, tc.wantErr) {
+				t.Fatalf("VerifyCurrentCredential() error = %v, want %v", err, tc.wantErr)
+			}
+		})
+	}
+
+	if err := auth.DisableTOTP("test"); err != nil {
+		t.Fatalf("DisableTOTP() error = %v", err)
+	}
+	if _, err := auth.Login("", "test", "testPass", ""); err != nil {
+		t.Errorf("Login() after DisableTOTP() error = %v", err)
+	}
+}
@@ -80,6 +80,39 @@ func TestConfig(t *testing.T) {
 			wantErr:         true,
 			wantErrContains: "repo \"test-repo\" not found",
 		},
+		{
+			name: "plan destination duplicates its repo",
+			config: &v1.Config{
+				Repos: []*v1.Repo{testRepo},
+				Plans: []*v1.Plan{
+					{
+						Id:           "test-plan",
+						Repo:         "test-repo",
+						Paths:        []string{"/tmp/foo"},
+						Destinations: []*v1.PlanDestination{{Repo: "test-repo"}},
+					},
+				},
+			},
+			store:           &CachingValidatingStore{ConfigStore: &JsonFileStore{Path: dir + "/invalid-config-destination.json"}},
+			wantErr:         true,
+			wantErrContains: "repo \"test-repo\" is listed more than once",
+		},
+		{
+			name: "repo copies to itself",
+			config: &v1.Config{
+				Repos: []*v1.Repo{
+					{
+						Id:         "test-repo",
+						Guid:       testRepo.Guid,
+						Uri:        "/tmp/test",
+						CopyPolicy: &v1.CopyPolicy{ToRepo: "test-repo"},
+					},
+				},
+			},
+			store:           &CachingValidatingStore{ConfigStore: &JsonFileStore{Path: dir + "/invalid-config-copy.json"}},
+			wantErr:         true,
+			wantErrContains: "can't copy a repo to itself",
+		},
 		{
 			name: "repo with duplicate id",
 			config: &v1.Config{
@@ -115,6 +148,32 @@ func TestConfig(t *testing.T) {
 			wantErr:         true,
 			wantErrContains: "invalid cron \"bad cron\"",
 		},
+		{
+			name: "plan with bad keep within duration",
+			config: &v1.Config{
+				Repos: []*v1.Repo{
+					testRepo,
+				},
+				Plans: []*v1.Plan{
+					{
+						Id:    "test-plan",
+						Repo:  "test-repo",
+						Paths: []string{"/tmp/foo"},
+						Retention: &v1.RetentionPolicy{
+							Policy: &v1.RetentionPolicy_PolicyTimeBucketed{
+								PolicyTimeBucketed: &v1.RetentionPolicy_TimeBucketedCounts{
+									Monthly: 12,
+									Within:  "7 days",
+								},
+							},
+						},
+					},
+				},
+			},
+			store:           &CachingValidatingStore{ConfigStore: &JsonFileStore{Path: dir + "/invalid-config-retention.json"}},
+			wantErr:         true,
+			wantErrContains: "keep within duration \"7 days\" is invalid",
+		},
 		{
 			name: "plan with bad interval days",
 			config: &v1.Config{
@@ -138,6 +197,89 @@ func TestConfig(t *testing.T) {
 			wantErr:         true,
 			wantErrContains: "invalid max frequency days",
 		},
+		{
+			name: "auth without an unrestricted admin",
+			config: &v1.Config{
+				Repos: []*v1.Repo{testRepo},
+				Auth: &v1.Auth{
+					Users: []*v1.User{
+						{
+							Name:     "helpdesk",
+							Password: &v1.User_PasswordBcrypt{PasswordBcrypt: "hash"},
+							Role:     v1.User_ROLE_RESTORE_ONLY,
+						},
+						{
+							Name:         "repoadmin",
+							Password:     &v1.User_PasswordBcrypt{PasswordBcrypt: "hash"},
+							Role:         v1.User_ROLE_ADMIN,
+							AllowedRepos: []string{"test-repo"},
+						},
+					},
+				},
+			},
+			store:           &CachingValidatingStore{ConfigStore: &JsonFileStore{Path: dir + "/invalid-config4.json"}},
+			wantErr:         true,
+			wantErrContains: "at least one admin user without repo or plan restrictions is required",
+		},
+		{
+			name: "auth user allowed repo does not exist",
+			config: &v1.Config{
+				Auth: &v1.Auth{
+					Users: []*v1.User{
+						{
+							Name:     "admin",
+							Password: &v1.User_PasswordBcrypt{PasswordBcrypt: "hash"},
+						},
+						{
+							Name:         "helpdesk",
+							Password:     &v1.User_PasswordBcrypt{PasswordBcrypt: "hash"},
+							Role:         v1.User_ROLE_RESTORE_ONLY,
+							AllowedRepos: []string{"missing-repo"},
+						},
+					},
+				},
+			},
+			store:           &CachingValidatingStore{ConfigStore: &JsonFileStore{Path: dir + "/invalid-config5.json"}},
+			wantErr:         true,
+			wantErrContains: "allowed repo \"missing-repo\" not found",
+		},
+		{
+			name: "oidc role mapping without a role",
+			config: &v1.Config{
+				Auth: &v1.Auth{
+					Users: []*v1.User{{Name: "admin", Password: &v1.User_PasswordBcrypt{PasswordBcrypt: "hash"}}},
+					Oidc: &v1.OIDCProvider{
+						Issuer:       "https://idp.example.com",
+						ClientId:     "backrest",
+						RedirectUrl:  "https://backrest.example.com/auth/oidc/callback",
+						RoleMappings: []*v1.OIDCProvider_RoleMapping{{Value: "staff"}},
+					},
+				},
+			},
+			store:           &CachingValidatingStore{ConfigStore: &JsonFileStore{Path: dir + "/invalid-config6.json"}},
+			wantErr:         true,
+			wantErrContains: "role mapping \"staff\": a role is required",
+		},
+		{
+			name: "oidc wildcard role mapping before other mappings",
+			config: &v1.Config{
+				Auth: &v1.Auth{
+					Users: []*v1.User{{Name: "admin", Password: &v1.User_PasswordBcrypt{PasswordBcrypt: "hash"}}},
+					Oidc: &v1.OIDCProvider{
+						Issuer:      "https://idp.example.com",
+						ClientId:    "backrest",
+						RedirectUrl: "https://backrest.example.com/auth/oidc/callback",
+						RoleMappings: []*v1.OIDCProvider_RoleMapping{
+							{Value: "*", Role: v1.User_ROLE_READ_ONLY},
+							{Value: "admins", Role: v1.User_ROLE_ADMIN},
+						},
+					},
+				},
+			},
+			store:           &CachingValidatingStore{ConfigStore: &JsonFileStore{Path: dir + "/invalid-config7.json"}},
+			wantErr:         true,
+			wantErrContains: "must be the last mapping",
+		},
 	}
 
 	for _, tc := range tests {
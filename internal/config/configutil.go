@@ -13,6 +13,26 @@ func FindPlan(cfg *v1.Config, planID string) *v1.Plan {
 	return nil
 }
 
+// PlanRepos returns the IDs of every repo a plan backs up to, its repo followed by its destinations.
+func PlanRepos(plan *v1.Plan) []string {
+	repos := []string{plan.Repo}
+	for _, d := range plan.GetDestinations() {
+		repos = append(repos, d.Repo)
+	}
+	return repos
+}
+
+// RetentionForRepo returns the retention policy of a plan's snapshots in a repo, a destination's policy overrides the
+// plan's.
+func RetentionForRepo(plan *v1.Plan, repoID string) *v1.RetentionPolicy {
+	for _, d := range plan.GetDestinations() {
+		if d.Repo == repoID && d.Retention != nil {
+			return d.Retention
+		}
+	}
+	return plan.GetRetention()
+}
+
 func FindRepo(cfg *v1.Config, repoID string) *v1.Repo {
 	for _, repo := range cfg.Repos {
 		if repo.Id == repoID {
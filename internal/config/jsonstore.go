@@ -11,7 +11,9 @@ import (
 
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
 	"github.com/natefinch/atomic"
+	"go.uber.org/zap"
 	"google.golang.org/protobuf/encoding/protojson"
+	"google.golang.org/protobuf/proto"
 )
 
 var (
@@ -19,8 +21,9 @@ var (
 )
 
 type JsonFileStore struct {
-	Path string
-	mu   sync.Mutex
+	Path   string
+	Cipher *SecretCipher // optional, encrypts secrets at rest if set.
+	mu     sync.Mutex
 }
 
 var _ ConfigStore = &JsonFileStore{}
@@ -29,26 +32,89 @@ func (f *JsonFileStore) Get() (*v1.Config, error) {
 	f.mu.Lock()
 	defer f.mu.Unlock()
 
+	config, _, err := f.read()
+	return config, err
+}
+
+// EncryptPlaintextSecrets rewrites the config file with its secrets encrypted if it still contains plaintext secrets
+// e.g. because it was written before a config key was set. The file is backed up before it is rewritten, the backup
+// keeps the plaintext secrets so that the migration can be undone.
+func (f *JsonFileStore) EncryptPlaintextSecrets() error {
+	f.mu.Lock()
+	defer f.mu.Unlock()
+
+	if f.Cipher == nil {
+		return nil
+	}
+	config, plaintext, err := f.read()
+	if err != nil {
+		if errors.Is(err, ErrConfigNotFound) {
+			return nil
+		}
+		return err
+	}
+	if !plaintext {
+		return nil
+	}
+
+	if err := f.makeBackup(); err != nil {
+		return fmt.Errorf("backup config file: %w", err)
+	}
+	zap.S().Infof("encrypting plaintext secrets in config file %q, backups %s.bak.* still contain plaintext secrets", f.Path, f.Path)
+	if err := f.write(config); err != nil {
+		return fmt.Errorf("encrypt config secrets: %w", err)
+	}
+	return nil
+}
+
+// read loads the config file and decrypts its secrets, it also reports whether the file contains plaintext secrets.
+func (f *JsonFileStore) read() (*v1.Config, bool, error) {
 	data, err := os.ReadFile(f.Path)
 	if err != nil {
 		if errors.Is(err, os.ErrNotExist) {
-			return nil, ErrConfigNotFound
+			return nil, false, ErrConfigNotFound
 		}
-		return nil, fmt.Errorf("failed to read config file: %w", err)
+		return nil, false, fmt.Errorf("failed to read config file: %w", err)
 	}
 
 	var config v1.Config
 	if err = (protojson.UnmarshalOptions{DiscardUnknown: true}).Unmarshal(data, &config); err != nil {
-		return nil, fmt.Errorf("failed to unmarshal config: %w", err)
+		return nil, false, fmt.Errorf("failed to unmarshal config: %w", err)
 	}
 
-	return &config, nil
+	encrypted, plaintext := hasSecrets(&config)
+	if encrypted {
+		if f.Cipher == nil {
+			return nil, false, ErrNoConfigKey
+		}
+		if err := f.Cipher.DecryptSecrets(&config); err != nil {
+			return nil, false, fmt.Errorf("failed to decrypt config secrets: %w", err)
+		}
+	}
+
+	return &config, plaintext, nil
 }
 
 func (f *JsonFileStore) Update(config *v1.Config) error {
 	f.mu.Lock()
 	defer f.mu.Unlock()
 
+	// backup the old config file
+	if err := f.makeBackup(); err != nil {
+		return fmt.Errorf("backup config file: %w", err)
+	}
+
+	return f.write(config)
+}
+
+func (f *JsonFileStore) write(config *v1.Config) error {
+	if f.Cipher != nil {
+		config = proto.Clone(config).(*v1.Config)
+		if err := f.Cipher.EncryptSecrets(config); err != nil {
+			return fmt.Errorf("encrypt secrets: %w", err)
+		}
+	}
+
 	data, err := protojson.MarshalOptions{
 		Indent:    "  ",
 		Multiline: true,
@@ -62,11 +128,6 @@ func (f *JsonFileStore) Update(config *v1.Config) error {
 		return fmt.Errorf("create config directory: %w", err)
 	}
 
-	// backup the old config file
-	if err := f.makeBackup(); err != nil {
-		return fmt.Errorf("backup config file: %w", err)
-	}
-
 	err = atomic.WriteFile(f.Path, bytes.NewReader(data))
 	if err != nil {
 		return fmt.Errorf("write config file: %w", err)
@@ -0,0 +1,223 @@
+package config
+
+import (
+	"crypto/aes"
+	"crypto/cipher"
+	"crypto/rand"
+	"crypto/sha256"
+	"encoding/base64"
+	"encoding/hex"
+	"errors"
+	"fmt"
+	"strings"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"google.golang.org/protobuf/proto"
+)
+
+// RedactedSecret replaces secret values in configs returned to clients. Configs sent back with this value keep the
+// stored secret, see RestoreRedactedSecrets.
+const RedactedSecret = "**redacted**"
+
+const encryptedSecretPrefix = "enc:v1:"
+
+var ErrNoConfigKey = errors.New("config contains encrypted secrets but no config key is configured")
+
+// secretField is a secret value in the config. Paths identify the same field across versions of a config.
+type secretField struct {
+	path string
+	get  func() string
+	set  func(string)
+}
+
+func stringField(path string, s *string) secretField {
+	return secretField{path: path, get: func() string { return *s }, set: func(v string) { *s = v }}
+}
+
+// secretFields returns every secret in the config: repo passwords, the values of repo env vars, database passwords,
+// hook credentials, the OIDC client secret and users' TOTP secrets.
+func secretFields(config *v1.Config) []secretField {
+	var fields []secretField
+	for _, repo := range config.GetRepos() {
+		prefix := "repo/" + repo.Id
+		fields = append(fields, stringField(prefix+"/password", &repo.Password))
+		for i := range repo.Env {
+			key, _, _ := strings.Cut(repo.Env[i], "=")
+			fields = append(fields, secretField{
+				path: prefix + "/env/" + key,
+				get: func() string {
+					_, value, _ := strings.Cut(repo.Env[i], "=")
+					return value
+				},
+				set: func(v string) { repo.Env[i] = key + "=" + v },
+			})
+		}
+		fields = append(fields, hookSecretFields(prefix, repo.Hooks)...)
+	}
+	for _, plan := range config.GetPlans() {
+		if db := plan.GetDatabaseSource(); db != nil {
+			fields = append(fields, stringField("plan/"+plan.Id+"/database_password", &db.Password))
+		}
+		fields = append(fields, hookSecretFields("plan/"+plan.Id, plan.Hooks)...)
+	}
+	if oidc := config.GetAuth().GetOidc(); oidc != nil {
+		fields = append(fields, stringField("auth/oidc/client_secret", &oidc.ClientSecret))
+	}
+	for _, user := range config.GetAuth().GetUsers() {
+		if totp := user.GetTotp(); totp != nil {
+			fields = append(fields, stringField("user/"+user.Name+"/totp", &totp.Secret))
+		}
+	}
+	return fields
+}
+
+// hookSecretFields returns the hooks' credentials. Hooks have no ID, a hook is identified by a fingerprint of its
+// fields other than the secret so that a redacted secret follows its hook when hooks are reordered and is not restored
+// into a hook that was edited.
+func hookSecretFields(prefix string, hooks []*v1.Hook) []secretField {
+	var fields []secretField
+	seen := make(map[string]int)
+	for _, hook := range hooks {
+		secret := hookSecret(hook)
+		if secret == nil {
+			continue
+		}
+		fingerprint := hookFingerprint(hook)
+		path := fmt.Sprintf("%v/hooks/%v/%d", prefix, fingerprint, seen[fingerprint])
+		seen[fingerprint]++
+		fields = append(fields, stringField(path, secret))
+	}
+	return fields
+}
+
+// hookSecret returns a pointer to the credential of the hook's action or nil if the action has none.
+func hookSecret(hook *v1.Hook) *string {
+	switch action := hook.Action.(type) {
+	case *v1.Hook_ActionWebhook:
+		return &action.ActionWebhook.WebhookUrl
+	case *v1.Hook_ActionDiscord:
+		return &action.ActionDiscord.WebhookUrl
+	case *v1.Hook_ActionGotify:
+		return &action.ActionGotify.Token
+	case *v1.Hook_ActionSlack:
+		return &action.ActionSlack.WebhookUrl
+	case *v1.Hook_ActionShoutrrr:
+		return &action.ActionShoutrrr.ShoutrrrUrl
+	case *v1.Hook_ActionHealthchecks:
+		return &action.ActionHealthchecks.WebhookUrl
+	}
+	return nil
+}
+
+func hookFingerprint(hook *v1.Hook) string {
+	hook = proto.Clone(hook).(*v1.Hook)
+	*hookSecret(hook) = ""
+	data, err := proto.MarshalOptions{Deterministic: true}.Marshal(hook)
+	if err != nil {
+		panic(fmt.Sprintf("marshal hook: %v", err))
+	}
+	sum := sha256.Sum256(data)
+	return hex.EncodeToString(sum[:8])
+}
+
+// RedactSecrets returns a copy of the config with every non-empty secret replaced by RedactedSecret.
+func RedactSecrets(config *v1.Config) *v1.Config {
+	config = proto.Clone(config).(*v1.Config)
+	for _, f := range secretFields(config) {
+		if f.get() != "" {
+			f.set(RedactedSecret)
+		}
+	}
+	return config
+}
+
+// RestoreRedactedSecrets replaces redacted secrets in the config with the values stored in the existing config.
+func RestoreRedactedSecrets(config, existing *v1.Config) error {
+	existingValues := make(map[string]string)
+	for _, f := range secretFields(existing) {
+		existingValues[f.path] = f.get()
+	}
+	for _, f := range secretFields(config) {
+		if f.get() != RedactedSecret {
+			continue
+		}
+		value, ok := existingValues[f.path]
+		if !ok {
+			return fmt.Errorf("secret %v is redacted but has no stored value, re-enter the secret of new or edited hooks", f.path)
+		}
+		f.set(value)
+	}
+	return nil
+}
+
+// SecretCipher encrypts secret fields of the config at rest with AES-256-GCM.
+type SecretCipher struct {
+	aead cipher.AEAD
+}
+
+// NewSecretCipher creates a cipher from key material, typically 32 or more random bytes read from a key file.
+func NewSecretCipher(keyMaterial []byte) (*SecretCipher, error) {
+	if len(keyMaterial) == 0 {
+		return nil, errors.New("empty config key")
+	}
+	key := sha256.Sum256(keyMaterial)
+	block, err := aes.NewCipher(key[:])
+	if err != nil {
+		return nil, err
+	}
+	aead, err := cipher.NewGCM(block)
+	if err != nil {
+		return nil, err
+	}
+	return &SecretCipher{aead: aead}, nil
+}
+
+// EncryptSecrets encrypts every plaintext secret of the config in place.
+func (c *SecretCipher) EncryptSecrets(config *v1.Config) error {
+	for _, f := range secretFields(config) {
+		value := f.get()
+		if value == "" || strings.HasPrefix(value, encryptedSecretPrefix) {
+			continue
+		}
+		nonce := make([]byte, c.aead.NonceSize())
+		if _, err := rand.Read(nonce); err != nil {
+			return fmt.Errorf("generate nonce: %w", err)
+		}
+		sealed := c.aead.Seal(nonce, nonce, []byte(value), nil)
+		f.set(encryptedSecretPrefix + base64.StdEncoding.EncodeToString(sealed))
+	}
+	return nil
+}
+
+// DecryptSecrets decrypts every encrypted secret of the config in place.
+func (c *SecretCipher) DecryptSecrets(config *v1.Config) error {
+	for _, f := range secretFields(config) {
+		value, ok := strings.CutPrefix(f.get(), encryptedSecretPrefix)
+		if !ok {
+			continue
+		}
+		sealed, err := base64.StdEncoding.DecodeString(value)
+		if err != nil || len(sealed) < c.aead.NonceSize() {
+			return fmt.Errorf("secret %v: malformed ciphertext", f.path)
+		}
+		plaintext, err := c.aead.Open(nil, sealed[:c.aead.NonceSize()], sealed[c.aead.NonceSize():], nil)
+		if err != nil {
+			return fmt.Errorf("secret %v: decrypt failed, is the config key correct? %w", f.path, err)
+		}
+		f.set(string(plaintext))
+	}
+	return nil
+}
+
+// hasSecrets reports whether the config contains any encrypted secrets and any plaintext secrets.
+func hasSecrets(config *v1.Config) (encrypted bool, plaintext bool) {
+	for _, f := range secretFields(config) {
+		value := f.get()
+		if strings.HasPrefix(value, encryptedSecretPrefix) {
+			encrypted = true
+		} else if value != "" {
+			plaintext = true
+		}
+	}
+	return
+}
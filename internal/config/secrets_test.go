@@ -0,0 +1,181 @@
+package config
+
+import (
+	"errors"
+	"os"
+	"path/filepath"
+	"slices"
+	"strings"
+	"testing"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"google.golang.org/protobuf/proto"
+)
+
+func configWithSecrets() *v1.Config {
+	return &v1.Config{
+		Modno:    1,
+		Instance: "test",
+		Repos: []*v1.Repo{
+			{
+				Id:       "repo1",
+				Uri:      "s3:bucket/path",
+				Password: "repo-password",
+				Env:      []string{"AWS_SECRET_ACCESS_KEY=aws-secret"},
+				Hooks: []*v1.Hook{
+					{
+						Action: &v1.Hook_ActionGotify{
+							ActionGotify: &v1.Hook_Gotify{BaseUrl: "https://gotify.example.com", Token: "gotify-token"},
+						},
+					},
+				},
+			},
+		},
+		Plans: []*v1.Plan{
+			{
+				Id:   "plan1",
+				Repo: "repo1",
+				Hooks: []*v1.Hook{
+					{
+						Action: &v1.Hook_ActionDiscord{
+							ActionDiscord: &v1.Hook_Discord{WebhookUrl: "https://discord.example.com/webhook-secret"},
+						},
+					},
+				},
+			},
+		},
+	}
+}
+
+func TestSecretCipher(t *testing.T) {
+	c, err := NewSecretCipher([]byte("test-key"))
+	if err != nil {
+		t.Fatalf("NewSecretCipher() error = %v", err)
+	}
+
+	config := configWithSecrets()
+	if err := c.EncryptSecrets(config); err != nil {
+		t.Fatalf("EncryptSecrets() error = %v", err)
+	}
+	for _, f := range secretFields(config) {
+		if !strings.HasPrefix(f.get(), encryptedSecretPrefix) {
+			t.Errorf("secret %v = %q, want encrypted", f.path, f.get())
+		}
+	}
+	if config.Repos[0].Uri != "s3:bucket/path" {
+		t.Errorf("non-secret field was modified, uri = %q", config.Repos[0].Uri)
+	}
+
+	other, err := NewSecretCipher([]byte("wrong-key"))
+	if err != nil {
+		t.Fatalf("NewSecretCipher() error = %v", err)
+	}
+	if err := other.DecryptSecrets(proto.Clone(config).(*v1.Config)); err == nil {
+		t.Errorf("DecryptSecrets() with the wrong key succeeded, want error")
+	}
+
+	if err := c.DecryptSecrets(config); err != nil {
+		t.Fatalf("DecryptSecrets() error = %v", err)
+	}
+	if !proto.Equal(config, configWithSecrets()) {
+		t.Errorf("DecryptSecrets() = %v, want %v", config, configWithSecrets())
+	}
+}
+
+func TestJsonFileStoreEncryptsSecrets(t *testing.T) {
+	path := filepath.Join(t.TempDir(), "config.json")
+	c, err := NewSecretCipher([]byte("test-key"))
+	if err != nil {
+		t.Fatalf("NewSecretCipher() error = %v", err)
+	}
+
+	// a config written without a key is readable with one and migrated to encrypted secrets on request.
+	if err := (&JsonFileStore{Path: path}).Update(configWithSecrets()); err != nil {
+		t.Fatalf("Update() error = %v", err)
+	}
+	store := &JsonFileStore{Path: path, Cipher: c}
+	if got, err := store.Get(); err != nil || !proto.Equal(got, configWithSecrets()) {
+		t.Fatalf("Get() before migration = %v, %v, want %v", got, err, configWithSecrets())
+	}
+	if err := store.EncryptPlaintextSecrets(); err != nil {
+		t.Fatalf("EncryptPlaintextSecrets() error = %v", err)
+	}
+	backups, err := filepath.Glob(path + ".bak.*")
+	if err != nil || len(backups) == 0 {
+		t.Errorf("no backup of the plaintext config file was written: %v", err)
+	}
+	got, err := store.Get()
+	if err != nil {
+		t.Fatalf("Get() error = %v", err)
+	}
+	if !proto.Equal(got, configWithSecrets()) {
+		t.Errorf("Get() = %v, want %v", got, configWithSecrets())
+	}
+
+	data, err := os.ReadFile(path)
+	if err != nil {
+		t.Fatalf("read config file: %v", err)
+	}
+	for _, secret := range []string{"repo-password", "aws-secret", "gotify-token", "webhook-secret"} {
+		if strings.Contains(string(data), secret) {
+			t.Errorf("config file contains plaintext secret %q", secret)
+		}
+	}
+
+	if _, err := (&JsonFileStore{Path: path}).Get(); !errors.Is(err, ErrNoConfigKey) {
+		t.Errorf("Get() without a key error = %v, want %v", err, ErrNoConfigKey)
+	}
+}
+
+func TestRedactSecrets(t *testing.T) {
+	existing := configWithSecrets()
+
+	redacted := RedactSecrets(existing)
+	if redacted.Repos[0].Password != RedactedSecret {
+		t.Errorf("RedactSecrets() password = %q, want %q", redacted.Repos[0].Password, RedactedSecret)
+	}
+	if redacted.Repos[0].Env[0] != "AWS_SECRET_ACCESS_KEY="+RedactedSecret {
+		t.Errorf("RedactSecrets() env = %q, want the key to be kept", redacted.Repos[0].Env[0])
+	}
+	if existing.Repos[0].Password != "repo-password" {
+		t.Errorf("RedactSecrets() modified its input")
+	}
+
+	// an unchanged redacted config restores to the stored secrets, edited secrets are kept.
+	redacted.Plans[0].Hooks[0].GetActionDiscord().WebhookUrl = "https://discord.example.com/new"
+	if err := RestoreRedactedSecrets(redacted, existing); err != nil {
+		t.Fatalf("RestoreRedactedSecrets() error = %v", err)
+	}
+	want := configWithSecrets()
+	want.Plans[0].Hooks[0].GetActionDiscord().WebhookUrl = "https://discord.example.com/new"
+	if !proto.Equal(redacted, want) {
+		t.Errorf("RestoreRedactedSecrets() = %v, want %v", redacted, want)
+	}
+
+	// redacted hook secrets follow their hooks when the hooks are reordered.
+	existing.Plans[0].Hooks = append(existing.Plans[0].Hooks, &v1.Hook{
+		Action: &v1.Hook_ActionDiscord{
+			ActionDiscord: &v1.Hook_Discord{WebhookUrl: "https://discord.example.com/second", Template: "second"},
+		},
+	})
+	reordered := RedactSecrets(existing)
+	slices.Reverse(reordered.Plans[0].Hooks)
+	if err := RestoreRedactedSecrets(reordered, existing); err != nil {
+		t.Fatalf("RestoreRedactedSecrets() of reordered hooks error = %v", err)
+	}
+	if got := reordered.Plans[0].Hooks[0].GetActionDiscord().WebhookUrl; got != "https://discord.example.com/second" {
+		t.Errorf("RestoreRedactedSecrets() of reordered hooks restored %q into the second hook", got)
+	}
+
+	// a redacted secret is not restored into a hook that was edited.
+	edited := RedactSecrets(existing)
+	edited.Plans[0].Hooks[1].GetActionDiscord().Template = "edited"
+	if err := RestoreRedactedSecrets(edited, existing); err == nil {
+		t.Errorf("RestoreRedactedSecrets() of an edited hook succeeded, want error")
+	}
+
+	newRepo := &v1.Config{Repos: []*v1.Repo{{Id: "repo2", Password: RedactedSecret}}}
+	if err := RestoreRedactedSecrets(newRepo, existing); err == nil {
+		t.Errorf("RestoreRedactedSecrets() for a repo without a stored secret succeeded, want error")
+	}
+}
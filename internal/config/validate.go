@@ -3,12 +3,17 @@ package config
 import (
 	"errors"
 	"fmt"
+	"net/url"
+	"regexp"
 	"slices"
 	"strings"
 
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
 	"github.com/garethgeorge/backrest/internal/config/validationutil"
+	"github.com/garethgeorge/backrest/internal/dbdump"
+	"github.com/garethgeorge/backrest/internal/fssnapshot"
 	"github.com/garethgeorge/backrest/internal/protoutil"
+	"github.com/google/shlex"
 	"github.com/hashicorp/go-multierror"
 	"go.uber.org/zap"
 	"google.golang.org/protobuf/proto"
@@ -25,10 +30,6 @@ func ValidateConfig(c *v1.Config) error {
 		}
 	}
 
-	if e := validateAuth(c.Auth); e != nil {
-		err = multierror.Append(err, fmt.Errorf("auth: %w", e))
-	}
-
 	repos := make(map[string]*v1.Repo)
 	if c.Repos != nil {
 		for _, repo := range c.Repos {
@@ -48,8 +49,8 @@ func ValidateConfig(c *v1.Config) error {
 		})
 	}
 
+	plans := make(map[string]*v1.Plan)
 	if c.Plans != nil {
-		plans := make(map[string]*v1.Plan)
 		for _, plan := range c.Plans {
 			if _, ok := plans[plan.Id]; ok {
 				err = multierror.Append(err, fmt.Errorf("plan %s: duplicate id", plan.GetId()))
@@ -67,10 +68,24 @@ func ValidateConfig(c *v1.Config) error {
 		})
 	}
 
+	for _, repo := range c.Repos {
+		if e := validateCopyPolicy(repo, repos, plans); e != nil {
+			err = multierror.Append(err, fmt.Errorf("repo %s: copy policy: %w", repo.GetId(), e))
+		}
+	}
+
+	if e := validateAuth(c.Auth, repos, plans); e != nil {
+		err = multierror.Append(err, fmt.Errorf("auth: %w", e))
+	}
+
 	if e := validateMultihost(c); e != nil {
 		err = multierror.Append(err, fmt.Errorf("multihost: %w", e))
 	}
 
+	if tls := c.GetTls(); tls != nil && (tls.CertFile == "") != (tls.KeyFile == "") {
+		err = multierror.Append(err, errors.New("tls: cert file and key file must be set together"))
+	}
+
 	return err
 }
 
@@ -109,6 +124,12 @@ func validateRepo(repo *v1.Repo) error {
 		}
 	}
 
+	if repo.CopyPolicy.GetSchedule() != nil {
+		if e := protoutil.ValidateSchedule(repo.CopyPolicy.GetSchedule()); e != nil {
+			err = multierror.Append(err, fmt.Errorf("copy policy schedule: %w", e))
+		}
+	}
+
 	for _, env := range repo.Env {
 		if !strings.Contains(env, "=") {
 			err = multierror.Append(err, fmt.Errorf("invalid env var %s, must take format KEY=VALUE", env))
@@ -120,6 +141,31 @@ func validateRepo(repo *v1.Repo) error {
 	return err
 }
 
+func validateCopyPolicy(repo *v1.Repo, repos map[string]*v1.Repo, plans map[string]*v1.Plan) error {
+	policy := repo.GetCopyPolicy()
+	if policy == nil {
+		return nil
+	}
+
+	var err error
+	if policy.ToRepo == "" {
+		err = multierror.Append(err, errors.New("to repo is required"))
+	} else if policy.ToRepo == repo.Id {
+		err = multierror.Append(err, errors.New("can't copy a repo to itself"))
+	} else if _, ok := repos[policy.ToRepo]; !ok {
+		err = multierror.Append(err, fmt.Errorf("to repo %q not found", policy.ToRepo))
+	}
+	for _, planID := range policy.Plans {
+		plan, ok := plans[planID]
+		if !ok {
+			err = multierror.Append(err, fmt.Errorf("plan %q not found", planID))
+		} else if !slices.Contains(PlanRepos(plan), repo.Id) {
+			err = multierror.Append(err, fmt.Errorf("plan %q doesn't back up to repo %q", planID, repo.Id))
+		}
+	}
+	return err
+}
+
 func validatePlan(plan *v1.Plan, repos map[string]*v1.Repo) error {
 	var err error
 	if e := validationutil.ValidateID(plan.Id, 0); e != nil {
@@ -146,20 +192,95 @@ func validatePlan(plan *v1.Plan, repos map[string]*v1.Repo) error {
 		err = multierror.Append(err, fmt.Errorf("repo %q not found", plan.Repo))
 	}
 
-	if plan.Retention != nil && plan.Retention.Policy == nil {
-		err = multierror.Append(err, errors.New("retention policy must be nil or must specify a policy"))
-	} else if policyTimeBucketed, ok := plan.Retention.GetPolicy().(*v1.RetentionPolicy_PolicyTimeBucketed); ok {
-		if proto.Equal(policyTimeBucketed.PolicyTimeBucketed, &v1.RetentionPolicy_TimeBucketedCounts{}) {
-			err = multierror.Append(err, errors.New("time bucketed policy must specify a non-empty bucket"))
+	if e := ValidateRetention(plan.Retention); e != nil {
+		err = multierror.Append(err, e)
+	}
+
+	seenRepos := map[string]bool{plan.Repo: true}
+	for idx, d := range plan.Destinations {
+		if _, ok := repos[d.Repo]; !ok {
+			err = multierror.Append(err, fmt.Errorf("destination[%d]: repo %q not found", idx, d.Repo))
+		} else if seenRepos[d.Repo] {
+			err = multierror.Append(err, fmt.Errorf("destination[%d]: repo %q is listed more than once", idx, d.Repo))
+		}
+		seenRepos[d.Repo] = true
+		if e := ValidateRetention(d.Retention); e != nil {
+			err = multierror.Append(err, fmt.Errorf("destination[%d]: %w", idx, e))
 		}
 	}
 
+	if plan.FsSnapshot != nil {
+		if e := fssnapshot.Validate(plan.FsSnapshot); e != nil {
+			err = multierror.Append(err, fmt.Errorf("filesystem snapshot: %w", e))
+		} else if e := fssnapshot.ValidatePaths(plan.FsSnapshot, plan.Paths); e != nil {
+			err = multierror.Append(err, fmt.Errorf("filesystem snapshot: %w", e))
+		}
+	}
+
+	if src := plan.CommandSource; src != nil {
+		if len(plan.Paths) > 0 || plan.FsSnapshot != nil || plan.DatabaseSource != nil {
+			err = multierror.Append(err, errors.New("a command source cannot be combined with paths, a filesystem snapshot or a database source"))
+		}
+		if args, e := shlex.Split(src.Command); e != nil || len(args) == 0 {
+			err = multierror.Append(err, fmt.Errorf("command source: command %q is invalid", src.Command))
+		}
+		if src.Filename == "" {
+			err = multierror.Append(err, errors.New("command source: filename is required"))
+		}
+	}
+
+	if src := plan.DatabaseSource; src != nil {
+		if len(plan.Paths) > 0 || plan.FsSnapshot != nil {
+			err = multierror.Append(err, errors.New("a database source cannot be combined with paths or a filesystem snapshot"))
+		}
+		if e := dbdump.Validate(src); e != nil {
+			err = multierror.Append(err, fmt.Errorf("database source: %w", e))
+		}
+	}
+
+	if plan.MaxSnapshotAgeHours < 0 {
+		err = multierror.Append(err, errors.New("max snapshot age must not be negative"))
+	}
+
 	slices.Sort(plan.Paths)
 
 	return err
 }
 
-func validateAuth(auth *v1.Auth) error {
+// resticDurationRegex matches the durations accepted by restic's --keep-within flags e.g. 1y2m7d12h.
+var resticDurationRegex = regexp.MustCompile(`^(\d+[ymdh])+$`)
+
+// ValidateRetention checks that a retention policy, if set, specifies a non-empty policy.
+func ValidateRetention(retention *v1.RetentionPolicy) error {
+	if retention != nil && retention.Policy == nil {
+		return errors.New("retention policy must be nil or must specify a policy")
+	} else if policyTimeBucketed, ok := retention.GetPolicy().(*v1.RetentionPolicy_PolicyTimeBucketed); ok {
+		counts := policyTimeBucketed.PolicyTimeBucketed
+		if proto.Equal(counts, &v1.RetentionPolicy_TimeBucketedCounts{}) {
+			return errors.New("time bucketed policy must specify a non-empty bucket")
+		}
+		for name, d := range map[string]string{
+			"within":         counts.Within,
+			"within hourly":  counts.WithinHourly,
+			"within daily":   counts.WithinDaily,
+			"within weekly":  counts.WithinWeekly,
+			"within monthly": counts.WithinMonthly,
+			"within yearly":  counts.WithinYearly,
+		} {
+			if d != "" && !resticDurationRegex.MatchString(d) {
+				return fmt.Errorf("keep %s duration %q is invalid, expected e.g. 1y2m7d12h", name, d)
+			}
+		}
+		for _, tags := range counts.KeepTags {
+			if tags == "" || slices.Contains(strings.Split(tags, ","), "") {
+				return fmt.Errorf("keep tags %q is invalid, expected a comma separated list of tags", tags)
+			}
+		}
+	}
+	return nil
+}
+
+func validateAuth(auth *v1.Auth, repos map[string]*v1.Repo, plans map[string]*v1.Plan) error {
 	if auth == nil || auth.Disabled {
 		return nil
 	}
@@ -168,18 +289,105 @@ func validateAuth(auth *v1.Auth) error {
 		return errors.New("auth enabled but no users")
 	}
 
+	hasUnrestrictedAdmin := false
 	for _, user := range auth.Users {
 		if e := validationutil.ValidateID(user.Name, 0); e != nil {
 			return fmt.Errorf("user %q: %w", user.Name, e)
 		}
-		if user.GetPasswordBcrypt() == "" {
+		if user.GetPasswordBcrypt() == "" && user.GetOidcSubject() == "" {
 			return fmt.Errorf("user %q: password is required", user.Name)
 		}
+		if user.Totp != nil && user.Totp.Secret == "" {
+			return fmt.Errorf("user %q: totp secret is required if totp is enabled", user.Name)
+		}
+		if _, ok := v1.User_Role_name[int32(user.Role)]; !ok {
+			return fmt.Errorf("user %q: unknown role %v", user.Name, user.Role)
+		}
+		for _, repoID := range user.AllowedRepos {
+			if _, ok := repos[repoID]; !ok {
+				return fmt.Errorf("user %q: allowed repo %q not found", user.Name, repoID)
+			}
+		}
+		for _, planID := range user.AllowedPlans {
+			if _, ok := plans[planID]; !ok {
+				return fmt.Errorf("user %q: allowed plan %q not found", user.Name, planID)
+			}
+		}
+		isAdmin := user.Role == v1.User_ROLE_DEFAULT || user.Role == v1.User_ROLE_ADMIN
+		if isAdmin && len(user.AllowedRepos) == 0 && len(user.AllowedPlans) == 0 {
+			hasUnrestrictedAdmin = true
+		}
+	}
+
+	if !hasUnrestrictedAdmin {
+		return errors.New("at least one admin user without repo or plan restrictions is required")
+	}
+
+	if oidc := auth.Oidc; oidc != nil {
+		if e := validateOIDC(oidc, repos, plans); e != nil {
+			return fmt.Errorf("oidc: %w", e)
+		}
+	}
+
+	keyIDs := make(map[string]struct{})
+	for _, key := range auth.ApiKeys {
+		if key.Id == "" || key.KeyHash == "" {
+			return fmt.Errorf("api key %q: id and key hash are required", key.Name)
+		}
+		if _, ok := keyIDs[key.Id]; ok {
+			return fmt.Errorf("api key %q: duplicate id %q", key.Name, key.Id)
+		}
+		keyIDs[key.Id] = struct{}{}
+		if key.Name == "" {
+			return fmt.Errorf("api key %q: name is required", key.Id)
+		}
+		if !slices.ContainsFunc(auth.Users, func(u *v1.User) bool { return u.Name == key.User }) {
+			return fmt.Errorf("api key %q: user %q not found, revoke the key before removing its user", key.Name, key.User)
+		}
 	}
 
 	return nil
 }
 
+func validateOIDC(oidc *v1.OIDCProvider, repos map[string]*v1.Repo, plans map[string]*v1.Plan) error {
+	if oidc.Issuer == "" || oidc.ClientId == "" || oidc.RedirectUrl == "" {
+		return errors.New("issuer, client ID and redirect URL are required")
+	}
+	for _, u := range []string{oidc.Issuer, oidc.RedirectUrl} {
+		if parsed, err := url.Parse(u); err != nil || (parsed.Scheme != "https" && parsed.Scheme != "http") || parsed.Host == "" {
+			return fmt.Errorf("invalid URL %q", u)
+		}
+	}
+	if !strings.HasSuffix(oidc.RedirectUrl, "/auth/oidc/callback") {
+		return errors.New("redirect URL must end with /auth/oidc/callback")
+	}
+	for i, mapping := range oidc.RoleMappings {
+		if mapping.Value == "" {
+			return errors.New("role mapping value is required")
+		}
+		if mapping.Value == "*" && i != len(oidc.RoleMappings)-1 {
+			return errors.New(`role mapping "*" matches every user and must be the last mapping`)
+		}
+		if mapping.Role == v1.User_ROLE_DEFAULT {
+			return fmt.Errorf("role mapping %q: a role is required", mapping.Value)
+		}
+		if _, ok := v1.User_Role_name[int32(mapping.Role)]; !ok {
+			return fmt.Errorf("role mapping %q: unknown role %v", mapping.Value, mapping.Role)
+		}
+		for _, repoID := range mapping.AllowedRepos {
+			if _, ok := repos[repoID]; !ok {
+				return fmt.Errorf("role mapping %q: allowed repo %q not found", mapping.Value, repoID)
+			}
+		}
+		for _, planID := range mapping.AllowedPlans {
+			if _, ok := plans[planID]; !ok {
+				return fmt.Errorf("role mapping %q: allowed plan %q not found", mapping.Value, planID)
+			}
+		}
+	}
+	return nil
+}
+
 func validateMultihost(config *v1.Config) (err error) {
 	multihost := config.GetMultihost()
 	if multihost == nil {
@@ -210,6 +418,9 @@ func validatePeer(peer *v1.Multihost_Peer, isKnownHost bool) error {
 		if peer.InstanceUrl == "" {
 			return errors.New("instance URL is required for known hosts")
 		}
+		if peer.TlsFingerprint != "" && !strings.HasPrefix(peer.InstanceUrl, "https://") {
+			return errors.New("a TLS fingerprint requires an https:// instance URL")
+		}
 	}
 
 	if peer.PublicKeyVerified && peer.GetPublicKey() == nil {
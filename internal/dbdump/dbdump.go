@@ -0,0 +1,213 @@
+// Package dbdump builds the commands that dump PostgreSQL, MySQL and SQLite databases for backup and restores a dump by
+// piping it back into the database's client.
+package dbdump
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strconv"
+	"strings"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+)
+
+// Dump is the command that writes a dump of one database to stdout.
+type Dump struct {
+	Database string   // database name, or file path for SQLite.
+	Filename string   // name of the dump file in the snapshot.
+	Tag      string   // tag added to the database's snapshots.
+	Command  []string // command and arguments.
+	Env      []string // KEY=VALUE environment variables for the command e.g. the password.
+}
+
+// TagForDatabase returns the tag added to the snapshots of a database.
+func TagForDatabase(name string) string {
+	return "db:" + name
+}
+
+// Dumps returns the dump command for each database of the source.
+func Dumps(src *v1.DatabaseSource) ([]Dump, error) {
+	if err := Validate(src); err != nil {
+		return nil, err
+	}
+
+	var dumps []Dump
+	for _, db := range src.Databases {
+		d := Dump{Database: db, Tag: TagForDatabase(databaseName(src, db)), Env: passwordEnv(src)}
+		switch src.Engine {
+		case v1.DatabaseSource_ENGINE_POSTGRES:
+			d.Command = append([]string{"pg_dump"}, postgresConnArgs(src)...)
+			if src.Format == v1.DatabaseSource_FORMAT_POSTGRES_CUSTOM {
+				d.Command = append(d.Command, "--format=custom")
+				d.Filename = db + ".dump"
+			} else {
+				d.Command = append(d.Command, "--format=plain")
+				d.Filename = db + ".sql"
+			}
+		case v1.DatabaseSource_ENGINE_MYSQL:
+			d.Command = append([]string{"mysqldump"}, mysqlConnArgs(src)...)
+			d.Command = append(d.Command, "--single-transaction", "--routines", "--triggers")
+			d.Filename = db + ".sql"
+		case v1.DatabaseSource_ENGINE_SQLITE:
+			d.Command = []string{"sqlite3", "-readonly"}
+			d.Filename = databaseName(src, db) + ".sql"
+		}
+		d.Command = append(d.Command, src.ExtraArgs...)
+		d.Command = append(d.Command, db)
+		if src.Engine == v1.DatabaseSource_ENGINE_SQLITE {
+			d.Command = append(d.Command, ".dump")
+		}
+		dumps = append(dumps, d)
+	}
+	return dumps, nil
+}
+
+// RestoreCommand returns the command that restores the dump file read from stdin into the database, and the
+// environment variables it requires.
+func RestoreCommand(src *v1.DatabaseSource, dumpFile string, database string) ([]string, []string, error) {
+	if err := validateDatabase(src, database); err != nil {
+		return nil, nil, err
+	}
+
+	var cmd []string
+	switch src.Engine {
+	case v1.DatabaseSource_ENGINE_POSTGRES:
+		if strings.HasSuffix(dumpFile, ".dump") {
+			cmd = append([]string{"pg_restore"}, postgresConnArgs(src)...)
+			cmd = append(cmd, "--exit-on-error", "--dbname", database)
+		} else {
+			cmd = append([]string{"psql"}, postgresConnArgs(src)...)
+			cmd = append(cmd, "--quiet", "--set", "ON_ERROR_STOP=1", "--dbname", database)
+		}
+	case v1.DatabaseSource_ENGINE_MYSQL:
+		cmd = append([]string{"mysql"}, mysqlConnArgs(src)...)
+		cmd = append(cmd, database)
+	case v1.DatabaseSource_ENGINE_SQLITE:
+		cmd = []string{"sqlite3", database}
+	}
+	return cmd, passwordEnv(src), nil
+}
+
+// Restore restores the dump file read from r into the database, the client's output is written to w. SQLite databases
+// are only restored to a new file.
+func Restore(ctx context.Context, src *v1.DatabaseSource, dumpFile string, database string, r io.Reader, w io.Writer) error {
+	args, env, err := RestoreCommand(src, dumpFile, database)
+	if err != nil {
+		return err
+	}
+	if src.Engine == v1.DatabaseSource_ENGINE_SQLITE {
+		if _, err := os.Stat(database); err == nil {
+			return fmt.Errorf("database file %q already exists", database)
+		}
+	}
+
+	fmt.Fprintf(w, "command: %v\n", strings.Join(args, " "))
+	cmd := exec.CommandContext(ctx, args[0], args[1:]...)
+	cmd.Env = append(os.Environ(), env...)
+	cmd.Stdin = r
+	cmd.Stdout = w
+	cmd.Stderr = w
+	if err := cmd.Run(); err != nil {
+		return fmt.Errorf("restore database %q with %v: %w", database, args[0], err)
+	}
+	return nil
+}
+
+// Validate checks that a database source is complete.
+func Validate(src *v1.DatabaseSource) error {
+	switch src.GetEngine() {
+	case v1.DatabaseSource_ENGINE_POSTGRES, v1.DatabaseSource_ENGINE_MYSQL, v1.DatabaseSource_ENGINE_SQLITE:
+	default:
+		return errors.New("a database engine (postgres, mysql or sqlite) is required")
+	}
+	if src.Format == v1.DatabaseSource_FORMAT_POSTGRES_CUSTOM && src.Engine != v1.DatabaseSource_ENGINE_POSTGRES {
+		return errors.New("the custom dump format is only supported for postgres")
+	}
+	if src.Port < 0 || src.Port > 65535 {
+		return fmt.Errorf("port %d is invalid", src.Port)
+	}
+	if len(src.Databases) == 0 {
+		return errors.New("at least one database is required")
+	}
+	names := make(map[string]bool)
+	for _, db := range src.Databases {
+		if err := validateDatabase(src, db); err != nil {
+			return err
+		}
+		name := databaseName(src, db)
+		if names[name] {
+			return fmt.Errorf("database %q is listed more than once", name)
+		}
+		names[name] = true
+	}
+	return nil
+}
+
+func validateDatabase(src *v1.DatabaseSource, db string) error {
+	if src.GetEngine() == v1.DatabaseSource_ENGINE_SQLITE {
+		if !filepath.IsAbs(db) || strings.Contains(filepath.Base(db), ",") {
+			return fmt.Errorf("sqlite database %q must be an absolute path to a file", db)
+		}
+		return nil
+	}
+	// names become file names and restic tags, which can't contain commas.
+	if db == "" || strings.HasPrefix(db, "-") || strings.ContainsAny(db, ",/\\") {
+		return fmt.Errorf("database %q is invalid", db)
+	}
+	return nil
+}
+
+// databaseName returns the name a database's snapshots are tagged with, the file name without extension for SQLite.
+func databaseName(src *v1.DatabaseSource, db string) string {
+	if src.Engine == v1.DatabaseSource_ENGINE_SQLITE {
+		base := filepath.Base(db)
+		return strings.TrimSuffix(base, filepath.Ext(base))
+	}
+	return db
+}
+
+func postgresConnArgs(src *v1.DatabaseSource) []string {
+	args := []string{"--no-password"} // never prompt, the password is passed in PGPASSWORD.
+	if src.Host != "" {
+		args = append(args, "--host", src.Host)
+	}
+	if src.Port != 0 {
+		args = append(args, "--port", strconv.Itoa(int(src.Port)))
+	}
+	if src.Username != "" {
+		args = append(args, "--username", src.Username)
+	}
+	return args
+}
+
+func mysqlConnArgs(src *v1.DatabaseSource) []string {
+	var args []string
+	if src.Host != "" {
+		args = append(args, "--host", src.Host)
+	}
+	if src.Port != 0 {
+		args = append(args, "--port", strconv.Itoa(int(src.Port)))
+	}
+	if src.Username != "" {
+		args = append(args, "--user", src.Username)
+	}
+	return args
+}
+
+func passwordEnv(src *v1.DatabaseSource) []string {
+	if src.Password == "" {
+		return nil
+	}
+	switch src.Engine {
+	case v1.DatabaseSource_ENGINE_POSTGRES:
+		return []string{"PGPASSWORD=" + src.Password}
+	case v1.DatabaseSource_ENGINE_MYSQL:
+		return []string{"MYSQL_PWD=" + src.Password}
+	}
+	return nil
+}
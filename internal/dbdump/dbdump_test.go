@@ -0,0 +1,226 @@
+package dbdump
+
+import (
+	"bytes"
+	"context"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"runtime"
+	"slices"
+	"strings"
+	"testing"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+)
+
+func TestPostgresDumps(t *testing.T) {
+	src := &v1.DatabaseSource{
+		Engine:    v1.DatabaseSource_ENGINE_POSTGRES,
+		Host:      "db.local",
+		Port:      5433,
+		Username:  "backup",
+		Password:  "secret",
+		Databases: []string{"app", "auth"},
+		Format:    v1.DatabaseSource_FORMAT_POSTGRES_CUSTOM,
+		ExtraArgs: []string{"--exclude-table=logs"},
+	}
+
+	dumps, err := Dumps(src)
+	if err != nil {
+		t.Fatalf("Dumps() error = %v", err)
+	}
+	if len(dumps) != 2 {
+		t.Fatalf("got %d dumps, want 2", len(dumps))
+	}
+	d := dumps[0]
+	wantCmd := []string{"pg_dump", "--no-password", "--host", "db.local", "--port", "5433", "--username", "backup", "--format=custom", "--exclude-table=logs", "app"}
+	if !slices.Equal(d.Command, wantCmd) {
+		t.Errorf("command = %q, want %q", d.Command, wantCmd)
+	}
+	if d.Filename != "app.dump" || d.Tag != "db:app" || !slices.Equal(d.Env, []string{"PGPASSWORD=secret"}) {
+		t.Errorf("got dump %+v, want filename app.dump, tag db:app and the password in PGPASSWORD", d)
+	}
+
+	// custom format dumps are restored with pg_restore, plain SQL with psql.
+	cmd, _, err := RestoreCommand(src, "/app.dump", "app_restored")
+	if err != nil || cmd[0] != "pg_restore" || cmd[len(cmd)-1] != "app_restored" {
+		t.Errorf("RestoreCommand() = %q, %v, want pg_restore into app_restored", cmd, err)
+	}
+	if cmd, _, _ := RestoreCommand(src, "/app.sql", "app"); cmd[0] != "psql" {
+		t.Errorf("RestoreCommand() of a plain dump = %q, want psql", cmd)
+	}
+}
+
+func TestValidate(t *testing.T) {
+	tcs := []struct {
+		name    string
+		src     *v1.DatabaseSource
+		wantErr bool
+	}{
+		{name: "no engine", src: &v1.DatabaseSource{Databases: []string{"app"}}, wantErr: true},
+		{name: "no databases", src: &v1.DatabaseSource{Engine: v1.DatabaseSource_ENGINE_MYSQL}, wantErr: true},
+		{name: "mysql", src: &v1.DatabaseSource{Engine: v1.DatabaseSource_ENGINE_MYSQL, Databases: []string{"app"}}},
+		{name: "mysql custom format", src: &v1.DatabaseSource{Engine: v1.DatabaseSource_ENGINE_MYSQL, Databases: []string{"app"}, Format: v1.DatabaseSource_FORMAT_POSTGRES_CUSTOM}, wantErr: true},
+		{name: "flag as database", src: &v1.DatabaseSource{Engine: v1.DatabaseSource_ENGINE_POSTGRES, Databases: []string{"--help"}}, wantErr: true},
+		{name: "sqlite relative path", src: &v1.DatabaseSource{Engine: v1.DatabaseSource_ENGINE_SQLITE, Databases: []string{"app.db"}}, wantErr: true},
+		{name: "sqlite duplicate name", src: &v1.DatabaseSource{Engine: v1.DatabaseSource_ENGINE_SQLITE, Databases: []string{"/a/app.db", "/b/app.db"}}, wantErr: true},
+	}
+	for _, tc := range tcs {
+		t.Run(tc.name, func(t *testing.T) {
+			if err := Validate(tc.src); (err != nil) != tc.wantErr {
+				t.Errorf("Validate() error = %v, wantErr %v", err, tc.wantErr)
+			}
+		})
+	}
+}
+
+func TestSQLiteDumpAndRestore(t *testing.T) {
+	if _, err := exec.LookPath("sqlite3"); err != nil {
+		t.Skip("sqlite3 is not installed")
+	}
+	dir := t.TempDir()
+	dbPath := filepath.Join(dir, "app.db")
+	if out, err := exec.Command("sqlite3", dbPath, "CREATE TABLE t (v TEXT); INSERT INTO t VALUES ('hello');").CombinedOutput(); err != nil {
+		t.Fatalf("create database: %v: %s", err, out)
+	}
+
+	src := &v1.DatabaseSource{Engine: v1.DatabaseSource_ENGINE_SQLITE, Databases: []string{dbPath}}
+	dumps, err := Dumps(src)
+	if err != nil {
+		t.Fatalf("Dumps() error = %v", err)
+	}
+	if dumps[0].Filename != "app.sql" || dumps[0].Tag != "db:app" {
+		t.Errorf("got dump %+v, want filename app.sql and tag db:app", dumps[0])
+	}
+	dump, err := exec.Command(dumps[0].Command[0], dumps[0].Command[1:]...).Output()
+	if err != nil {
+		t.Fatalf("dump database: %v", err)
+	}
+
+	restored := filepath.Join(dir, "restored.db")
+	var log bytes.Buffer
+	if err := Restore(context.Background(), src, "/app.sql", restored, bytes.NewReader(dump), &log); err != nil {
+		t.Fatalf("Restore() error = %v, output: %s", err, log.String())
+	}
+	out, err := exec.Command("sqlite3", restored, "SELECT v FROM t;").Output()
+	if err != nil || strings.TrimSpace(string(out)) != "hello" {
+		t.Errorf("restored database contains %q (err: %v), want hello", out, err)
+	}
+
+	// restoring over an existing database is refused.
+	if err := Restore(context.Background(), src, "/app.sql", dbPath, bytes.NewReader(dump), &log); err == nil {
+		t.Errorf("Restore() over an existing database succeeded, want an error")
+	}
+	if _, err := os.Stat(dbPath); err != nil {
+		t.Errorf("original database: %v", err)
+	}
+}
+
+// installFakeClients puts scripts named after the database clients first on PATH. Each records its arguments, its
+// password environment variable and its stdin in dir, and dumps write "dump of <last argument>" to stdout.
+func installFakeClients(t *testing.T, dir string) {
+	t.Helper()
+	if runtime.GOOS == "windows" {
+		t.Skip("fake clients are shell scripts")
+	}
+	for _, name := range []string{"pg_dump", "pg_restore", "psql", "mysqldump", "mysql"} {
+		script := `#!/bin/sh
+echo "$@" > "` + dir + `/` + name + `.args"
+echo "$PGPASSWORD$MYSQL_PWD" > "` + dir + `/` + name + `.password"
+case "` + name + `" in
+*dump) for last; do :; done; echo "dump of $last" ;;
+*) cat > "` + dir + `/` + name + `.stdin" ;;
+esac
+`
+		if err := os.WriteFile(filepath.Join(dir, name), []byte(script), 0755); err != nil {
+			t.Fatalf("write fake %v: %v", name, err)
+		}
+	}
+	t.Setenv("PATH", dir+string(os.PathListSeparator)+os.Getenv("PATH"))
+}
+
+func TestClientDumpAndRestore(t *testing.T) {
+	dir := t.TempDir()
+	installFakeClients(t, dir)
+	readFile := func(name string) string {
+		data, err := os.ReadFile(filepath.Join(dir, name))
+		if err != nil {
+			t.Fatalf("read %v: %v", name, err)
+		}
+		return strings.TrimSpace(string(data))
+	}
+
+	tcs := []struct {
+		name        string
+		src         *v1.DatabaseSource
+		dumpFile    string
+		dumpClient  string
+		wantDumpArg string
+		client      string
+		wantArgs    string
+	}{
+		{
+			name:        "postgres custom format",
+			src:         &v1.DatabaseSource{Engine: v1.DatabaseSource_ENGINE_POSTGRES, Host: "db.local", Username: "backup", Password: "pgsecret", Databases: []string{"app"}, Format: v1.DatabaseSource_FORMAT_POSTGRES_CUSTOM},
+			dumpFile:    "/app.dump",
+			dumpClient:  "pg_dump",
+			wantDumpArg: "--no-password --host db.local --username backup --format=custom app",
+			client:      "pg_restore",
+			wantArgs:    "--no-password --host db.local --username backup --exit-on-error --dbname app_restored",
+		},
+		{
+			name:        "postgres plain format",
+			src:         &v1.DatabaseSource{Engine: v1.DatabaseSource_ENGINE_POSTGRES, Password: "pgsecret", Databases: []string{"app"}},
+			dumpFile:    "/app.sql",
+			dumpClient:  "pg_dump",
+			wantDumpArg: "--no-password --format=plain app",
+			client:      "psql",
+			wantArgs:    "--no-password --quiet --set ON_ERROR_STOP=1 --dbname app_restored",
+		},
+		{
+			name:        "mysql",
+			src:         &v1.DatabaseSource{Engine: v1.DatabaseSource_ENGINE_MYSQL, Host: "db.local", Port: 3307, Username: "backup", Password: "mysecret", Databases: []string{"app"}},
+			dumpFile:    "/app.sql",
+			dumpClient:  "mysqldump",
+			wantDumpArg: "--host db.local --port 3307 --user backup --single-transaction --routines --triggers app",
+			client:      "mysql",
+			wantArgs:    "--host db.local --port 3307 --user backup app_restored",
+		},
+	}
+	for _, tc := range tcs {
+		t.Run(tc.name, func(t *testing.T) {
+			dumps, err := Dumps(tc.src)
+			if err != nil {
+				t.Fatalf("Dumps() error = %v", err)
+			}
+			d := dumps[0]
+			cmd := exec.Command(d.Command[0], d.Command[1:]...)
+			cmd.Env = append(os.Environ(), d.Env...)
+			dump, err := cmd.Output()
+			if err != nil {
+				t.Fatalf("run dump command: %v", err)
+			}
+			if got := readFile(tc.dumpClient + ".args"); got != tc.wantDumpArg {
+				t.Errorf("%v arguments = %q, want %q", tc.dumpClient, got, tc.wantDumpArg)
+			}
+			if got := readFile(tc.dumpClient + ".password"); got != tc.src.Password {
+				t.Errorf("%v password = %q, want %q", tc.dumpClient, got, tc.src.Password)
+			}
+
+			var log bytes.Buffer
+			if err := Restore(context.Background(), tc.src, tc.dumpFile, "app_restored", bytes.NewReader(dump), &log); err != nil {
+				t.Fatalf("Restore() error = %v, output: %s", err, log.String())
+			}
+			if got := readFile(tc.client + ".args"); got != tc.wantArgs {
+				t.Errorf("%v arguments = %q, want %q", tc.client, got, tc.wantArgs)
+			}
+			if got := readFile(tc.client + ".password"); got != tc.src.Password {
+				t.Errorf("%v password = %q, want %q", tc.client, got, tc.src.Password)
+			}
+			if got := readFile(tc.client + ".stdin"); got != "dump of app" {
+				t.Errorf("%v read %q from stdin, want the dump", tc.client, got)
+			}
+		})
+	}
+}
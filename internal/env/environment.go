@@ -11,16 +11,25 @@ import (
 )
 
 var (
-	EnvVarConfigPath  = "BACKREST_CONFIG"         // path to config file
-	EnvVarDataDir     = "BACKREST_DATA"           // path to data directory
-	EnvVarBindAddress = "BACKREST_PORT"           // port to bind to (default 9898)
-	EnvVarBinPath     = "BACKREST_RESTIC_COMMAND" // path to restic binary (default restic)
+	EnvVarConfigPath    = "BACKREST_CONFIG"          // path to config file
+	EnvVarDataDir       = "BACKREST_DATA"            // path to data directory
+	EnvVarBindAddress   = "BACKREST_PORT"            // port to bind to (default 9898)
+	EnvVarBinPath       = "BACKREST_RESTIC_COMMAND"  // path to restic binary (default restic)
+	EnvVarConfigKey     = "BACKREST_CONFIG_KEY"      // key used to encrypt secrets in the config file
+	EnvVarConfigKeyFile = "BACKREST_CONFIG_KEY_FILE" // path to a file containing the config key
+	EnvVarTLSCert       = "BACKREST_TLS_CERT"        // path to the PEM encoded TLS certificate (chain) to serve
+	EnvVarTLSKey        = "BACKREST_TLS_KEY"         // path to the PEM encoded private key of the TLS certificate
+	EnvVarTraceFile     = "BACKREST_TRACE_FILE"      // path to a file that trace spans are appended to as JSON
 )
 
 var flagDataDir = flag.String("data-dir", "", "path to data directory, defaults to XDG_DATA_HOME/.local/backrest. Overrides BACKREST_DATA environment variable.")
 var flagConfigPath = flag.String("config-file", "", "path to config file, defaults to XDG_CONFIG_HOME/backrest/config.json. Overrides BACKREST_CONFIG environment variable.")
 var flagBindAddress = flag.String("bind-address", "", "address to bind to, defaults to 127.0.0.1:9898. Use :9898 to listen on all interfaces. Overrides BACKREST_PORT environment variable.")
 var flagResticBinPath = flag.String("restic-cmd", "", "path to restic binary, defaults to a backrest managed version of restic. Overrides BACKREST_RESTIC_COMMAND environment variable.")
+var flagTLSCert = flag.String("tls-cert", "", "path to a PEM encoded TLS certificate, enables HTTPS. Overrides the config and BACKREST_TLS_CERT environment variable.")
+var flagTLSKey = flag.String("tls-key", "", "path to the PEM encoded private key of the TLS certificate. Overrides the config and BACKREST_TLS_KEY environment variable.")
+var flagTraceFile = flag.String("trace-file", "", "path to a file that OpenTelemetry trace spans are appended to as JSON lines. Overrides BACKREST_TRACE_FILE environment variable.")
+var flagConfigKeyFile = flag.String("config-key-file", "", "path to a file containing the key used to encrypt secrets in the config file. Overrides BACKREST_CONFIG_KEY_FILE and BACKREST_CONFIG_KEY environment variables.")
 
 // ConfigFilePath
 // - *nix systems use $XDG_CONFIG_HOME/backrest/config.json
@@ -75,6 +84,57 @@ func ResticBinPath() string {
 	return ""
 }
 
+// TLSCertFiles returns the TLS certificate and key files set by flags or environment variables, empty if the config
+// decides whether to serve TLS.
+func TLSCertFiles() (string, string) {
+	if *flagTLSCert != "" || *flagTLSKey != "" {
+		return *flagTLSCert, *flagTLSKey
+	}
+	return os.Getenv(EnvVarTLSCert), os.Getenv(EnvVarTLSKey)
+}
+
+// TraceFile returns the file that trace spans are written to, empty if spans are not written to a file.
+func TraceFile() string {
+	if *flagTraceFile != "" {
+		return *flagTraceFile
+	}
+	return os.Getenv(EnvVarTraceFile)
+}
+
+// ConfigKey returns the key used to encrypt secrets in the config file, or nil if secrets are stored in plaintext.
+// The key is read from the first of --config-key-file, BACKREST_CONFIG_KEY_FILE, BACKREST_CONFIG_KEY or the
+// backrest-config-key credential in $CREDENTIALS_DIRECTORY (e.g. a systemd credential backed by the OS keyring).
+func ConfigKey() ([]byte, error) {
+	keyFile := *flagConfigKeyFile
+	if keyFile == "" {
+		keyFile = os.Getenv(EnvVarConfigKeyFile)
+	}
+	if keyFile == "" {
+		if val := os.Getenv(EnvVarConfigKey); val != "" {
+			return []byte(val), nil
+		}
+		if dir := os.Getenv("CREDENTIALS_DIRECTORY"); dir != "" {
+			credFile := filepath.Join(dir, "backrest-config-key")
+			if _, err := os.Stat(credFile); err == nil {
+				keyFile = credFile
+			}
+		}
+	}
+	if keyFile == "" {
+		return nil, nil
+	}
+
+	data, err := os.ReadFile(keyFile)
+	if err != nil {
+		return nil, fmt.Errorf("read config key file: %w", err)
+	}
+	key := []byte(strings.TrimSpace(string(data)))
+	if len(key) == 0 {
+		return nil, fmt.Errorf("config key file %q is empty", keyFile)
+	}
+	return key, nil
+}
+
 func LogsPath() string {
 	dataDir := DataDir()
 	return filepath.Join(dataDir, "processlogs")
@@ -0,0 +1,313 @@
+// Package fssnapshot creates read-only LVM, Btrfs and ZFS snapshots of the filesystem a plan backs up so that the
+// backup reads a consistent view of data that changes while it runs e.g. databases and VM images.
+package fssnapshot
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strings"
+	"sync"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+)
+
+// CommandRunner runs a command and returns its combined output.
+type CommandRunner func(ctx context.Context, name string, args ...string) ([]byte, error)
+
+// ExecRunner returns a CommandRunner that runs commands on the host and logs them to w.
+func ExecRunner(w io.Writer) CommandRunner {
+	return func(ctx context.Context, name string, args ...string) ([]byte, error) {
+		fmt.Fprintf(w, "command: %v %v\n", name, strings.Join(args, " "))
+		out, err := exec.CommandContext(ctx, name, args...).CombinedOutput()
+		if len(out) > 0 {
+			w.Write(out)
+		}
+		if err != nil {
+			return out, fmt.Errorf("%v %v: %w: %s", name, strings.Join(args, " "), err, strings.TrimSpace(string(out)))
+		}
+		return out, nil
+	}
+}
+
+var (
+	inUseMu sync.Mutex
+	inUse   = make(map[string]struct{}) // plan IDs with a snapshot held by a running backup.
+)
+
+// Snapshot is a mounted read-only snapshot, it must be released once the backup finishes.
+type Snapshot struct {
+	SourcePath string // where the live filesystem is mounted.
+	Path       string // where the snapshot of SourcePath is accessible.
+
+	teardown []func(ctx context.Context) error // run in reverse order to release the snapshot.
+}
+
+// RewritePaths maps paths within the source filesystem to the same paths within the snapshot.
+func (s *Snapshot) RewritePaths(paths []string) ([]string, error) {
+	rewritten := make([]string, 0, len(paths))
+	for _, p := range paths {
+		rel, err := relativeTo(s.SourcePath, p)
+		if err != nil {
+			return nil, err
+		}
+		rewritten = append(rewritten, filepath.Join(s.Path, rel))
+	}
+	return rewritten, nil
+}
+
+// RewriteExcludes maps absolute exclude patterns within the source filesystem to the same patterns within the
+// snapshot, restic matches absolute patterns against the backed up paths so they would match nothing otherwise.
+// Relative patterns, e.g. "*.tmp", and patterns outside the source path are kept as is. A leading "!" negation is
+// preserved.
+func (s *Snapshot) RewriteExcludes(patterns []string) []string {
+	rewritten := make([]string, 0, len(patterns))
+	for _, p := range patterns {
+		negated := strings.HasPrefix(p, "!")
+		pattern := strings.TrimPrefix(p, "!")
+		if rel, err := relativeTo(s.SourcePath, pattern); err == nil {
+			pattern = filepath.Join(s.Path, rel)
+			if negated {
+				pattern = "!" + pattern
+			}
+			rewritten = append(rewritten, pattern)
+			continue
+		}
+		rewritten = append(rewritten, p)
+	}
+	return rewritten
+}
+
+// Release unmounts and deletes the snapshot. It attempts every step even if one fails.
+func (s *Snapshot) Release(ctx context.Context) error {
+	var err error
+	for i := len(s.teardown) - 1; i >= 0; i-- {
+		err = errors.Join(err, s.teardown[i](ctx))
+	}
+	s.teardown = nil
+	return err
+}
+
+// Create snapshots the filesystem described by cfg for the plan. mountRoot is a directory that snapshots which must
+// be mounted (LVM) are mounted under. Names and mount points are stable per plan so that restic sees the same paths
+// on every backup, a snapshot left behind by an interrupted backup is removed first. Only one snapshot per plan may be
+// held at a time, Create fails while another backup of the plan holds its snapshot.
+func Create(ctx context.Context, cfg *v1.FilesystemSnapshot, planID string, mountRoot string, run CommandRunner) (*Snapshot, error) {
+	if err := Validate(cfg); err != nil {
+		return nil, err
+	}
+
+	var p provider
+	switch prov := cfg.Provider.(type) {
+	case *v1.FilesystemSnapshot_ProviderLvm:
+		p = &lvmProvider{cfg: prov.ProviderLvm, planID: planID, mountPath: filepath.Join(mountRoot, planID)}
+	case *v1.FilesystemSnapshot_ProviderBtrfs:
+		p = &btrfsProvider{cfg: prov.ProviderBtrfs, planID: planID, sourcePath: cfg.SourcePath}
+	case *v1.FilesystemSnapshot_ProviderZfs:
+		p = &zfsProvider{cfg: prov.ProviderZfs, planID: planID, sourcePath: cfg.SourcePath}
+	}
+
+	inUseMu.Lock()
+	if _, ok := inUse[planID]; ok {
+		inUseMu.Unlock()
+		return nil, fmt.Errorf("filesystem snapshot for plan %q is in use by another backup", planID)
+	}
+	inUse[planID] = struct{}{}
+	inUseMu.Unlock()
+
+	snap := &Snapshot{SourcePath: filepath.Clean(cfg.SourcePath)}
+	// registered first so that it runs last, after the snapshot is removed or its removal failed. A leftover snapshot
+	// is removed by the cleanup of the next backup.
+	snap.teardown = append(snap.teardown, func(ctx context.Context) error {
+		inUseMu.Lock()
+		defer inUseMu.Unlock()
+		delete(inUse, planID)
+		return nil
+	})
+
+	p.cleanup(ctx, run)
+
+	path, err := p.create(ctx, run, func(step func(ctx context.Context) error) {
+		snap.teardown = append(snap.teardown, step)
+	})
+	if err != nil {
+		if releaseErr := snap.Release(ctx); releaseErr != nil {
+			err = fmt.Errorf("%w (cleanup also failed: %v)", err, releaseErr)
+		}
+		return nil, err
+	}
+	snap.Path = path
+	return snap, nil
+}
+
+// Validate checks that a filesystem snapshot config is complete.
+func Validate(cfg *v1.FilesystemSnapshot) error {
+	if !filepath.IsAbs(cfg.GetSourcePath()) {
+		return fmt.Errorf("source path %q must be absolute", cfg.GetSourcePath())
+	}
+	switch prov := cfg.GetProvider().(type) {
+	case *v1.FilesystemSnapshot_ProviderLvm:
+		if prov.ProviderLvm.GetVolumeGroup() == "" || prov.ProviderLvm.GetLogicalVolume() == "" {
+			return errors.New("lvm snapshots require a volume group and logical volume")
+		}
+	case *v1.FilesystemSnapshot_ProviderBtrfs:
+		if dir := prov.ProviderBtrfs.GetSnapshotDir(); dir != "" && !filepath.IsAbs(dir) {
+			return fmt.Errorf("btrfs snapshot dir %q must be absolute", dir)
+		}
+	case *v1.FilesystemSnapshot_ProviderZfs:
+		if prov.ProviderZfs.GetDataset() == "" || strings.Contains(prov.ProviderZfs.GetDataset(), "@") {
+			return fmt.Errorf("zfs dataset %q is invalid", prov.ProviderZfs.GetDataset())
+		}
+	default:
+		return errors.New("a snapshot provider (lvm, btrfs or zfs) is required")
+	}
+	return nil
+}
+
+// ValidatePaths checks that every path is within the snapshotted filesystem.
+func ValidatePaths(cfg *v1.FilesystemSnapshot, paths []string) error {
+	for _, p := range paths {
+		if _, err := relativeTo(cfg.GetSourcePath(), p); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+func relativeTo(base, p string) (string, error) {
+	rel, err := filepath.Rel(filepath.Clean(base), filepath.Clean(p))
+	if err != nil || !filepath.IsAbs(p) || rel == ".." || strings.HasPrefix(rel, ".."+string(filepath.Separator)) {
+		return "", fmt.Errorf("path %q is not within the snapshot source path %q", p, base)
+	}
+	return rel, nil
+}
+
+type provider interface {
+	// create creates the snapshot, registering the teardown of each step as it succeeds, and returns where the
+	// snapshot is accessible.
+	create(ctx context.Context, run CommandRunner, onTeardown func(func(ctx context.Context) error)) (string, error)
+	// cleanup removes a snapshot left behind by an interrupted backup, best effort.
+	cleanup(ctx context.Context, run CommandRunner)
+}
+
+func snapshotName(planID string) string {
+	return "backrest-" + planID
+}
+
+type lvmProvider struct {
+	cfg       *v1.FilesystemSnapshot_LVM
+	planID    string
+	mountPath string
+}
+
+func (p *lvmProvider) snapshotLV() string {
+	return p.cfg.VolumeGroup + "/" + p.cfg.LogicalVolume + "-" + snapshotName(p.planID)
+}
+
+func (p *lvmProvider) create(ctx context.Context, run CommandRunner, onTeardown func(func(ctx context.Context) error)) (string, error) {
+	args := []string{"--snapshot", "--setactivationskip", "n", "--name", p.cfg.LogicalVolume + "-" + snapshotName(p.planID)}
+	if p.cfg.Size != "" {
+		args = append(args, "--size", p.cfg.Size)
+	}
+	args = append(args, p.cfg.VolumeGroup+"/"+p.cfg.LogicalVolume)
+	if _, err := run(ctx, "lvcreate", args...); err != nil {
+		return "", fmt.Errorf("create lvm snapshot: %w", err)
+	}
+	onTeardown(func(ctx context.Context) error {
+		_, err := run(ctx, "lvremove", "--yes", p.snapshotLV())
+		return err
+	})
+
+	if _, err := run(ctx, "lvchange", "--activate", "y", p.snapshotLV()); err != nil {
+		return "", fmt.Errorf("activate lvm snapshot: %w", err)
+	}
+
+	if err := os.MkdirAll(p.mountPath, 0700); err != nil {
+		return "", fmt.Errorf("create mount point: %w", err)
+	}
+	opts := "ro"
+	if p.cfg.MountOptions != "" {
+		opts += "," + p.cfg.MountOptions
+	}
+	if _, err := run(ctx, "mount", "-o", opts, "/dev/"+p.snapshotLV(), p.mountPath); err != nil {
+		return "", fmt.Errorf("mount lvm snapshot: %w", err)
+	}
+	onTeardown(func(ctx context.Context) error {
+		_, err := run(ctx, "umount", p.mountPath)
+		return err
+	})
+	return p.mountPath, nil
+}
+
+func (p *lvmProvider) cleanup(ctx context.Context, run CommandRunner) {
+	if _, err := run(ctx, "lvs", p.snapshotLV()); err != nil {
+		return // no leftover snapshot.
+	}
+	_, _ = run(ctx, "umount", p.mountPath) // the snapshot may not be mounted.
+	_, _ = run(ctx, "lvremove", "--yes", p.snapshotLV()) // create reports the error if this fails.
+}
+
+type btrfsProvider struct {
+	cfg        *v1.FilesystemSnapshot_Btrfs
+	planID     string
+	sourcePath string
+}
+
+func (p *btrfsProvider) snapshotPath() string {
+	dir := p.cfg.SnapshotDir
+	if dir == "" {
+		dir = filepath.Dir(filepath.Clean(p.sourcePath))
+	}
+	return filepath.Join(dir, "."+snapshotName(p.planID))
+}
+
+func (p *btrfsProvider) create(ctx context.Context, run CommandRunner, onTeardown func(func(ctx context.Context) error)) (string, error) {
+	if _, err := run(ctx, "btrfs", "subvolume", "snapshot", "-r", p.sourcePath, p.snapshotPath()); err != nil {
+		return "", fmt.Errorf("create btrfs snapshot: %w", err)
+	}
+	onTeardown(func(ctx context.Context) error {
+		_, err := run(ctx, "btrfs", "subvolume", "delete", p.snapshotPath())
+		return err
+	})
+	return p.snapshotPath(), nil
+}
+
+func (p *btrfsProvider) cleanup(ctx context.Context, run CommandRunner) {
+	if _, err := os.Stat(p.snapshotPath()); err != nil {
+		return // no leftover snapshot.
+	}
+	_, _ = run(ctx, "btrfs", "subvolume", "delete", p.snapshotPath()) // create reports the error if this fails.
+}
+
+type zfsProvider struct {
+	cfg        *v1.FilesystemSnapshot_ZFS
+	planID     string
+	sourcePath string
+}
+
+func (p *zfsProvider) snapshot() string {
+	return p.cfg.Dataset + "@" + snapshotName(p.planID)
+}
+
+func (p *zfsProvider) create(ctx context.Context, run CommandRunner, onTeardown func(func(ctx context.Context) error)) (string, error) {
+	if _, err := run(ctx, "zfs", "snapshot", p.snapshot()); err != nil {
+		return "", fmt.Errorf("create zfs snapshot: %w", err)
+	}
+	onTeardown(func(ctx context.Context) error {
+		_, err := run(ctx, "zfs", "destroy", p.snapshot())
+		return err
+	})
+	// ZFS mounts snapshots read-only on access under the hidden .zfs directory, even if snapdir=hidden.
+	return filepath.Join(p.sourcePath, ".zfs", "snapshot", snapshotName(p.planID)), nil
+}
+
+func (p *zfsProvider) cleanup(ctx context.Context, run CommandRunner) {
+	if _, err := run(ctx, "zfs", "list", "-t", "snapshot", p.snapshot()); err != nil {
+		return // no leftover snapshot.
+	}
+	_, _ = run(ctx, "zfs", "destroy", p.snapshot()) // create reports the error if this fails.
+}
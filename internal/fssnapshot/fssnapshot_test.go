@@ -0,0 +1,177 @@
+package fssnapshot
+
+import (
+	"context"
+	"errors"
+	"path/filepath"
+	"slices"
+	"strings"
+	"testing"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+)
+
+// fakeRunner records commands and fails those whose command line starts with a prefix in fail.
+type fakeRunner struct {
+	commands []string
+	fail     []string
+}
+
+func (f *fakeRunner) run(ctx context.Context, name string, args ...string) ([]byte, error) {
+	cmd := strings.Join(append([]string{name}, args...), " ")
+	f.commands = append(f.commands, cmd)
+	for _, prefix := range f.fail {
+		if strings.HasPrefix(cmd, prefix) {
+			return nil, errors.New("command failed")
+		}
+	}
+	return nil, nil
+}
+
+func TestLVMSnapshot(t *testing.T) {
+	mountRoot := t.TempDir()
+	mountPath := filepath.Join(mountRoot, "plan1")
+	cfg := &v1.FilesystemSnapshot{
+		SourcePath: "/var/lib",
+		Provider: &v1.FilesystemSnapshot_ProviderLvm{ProviderLvm: &v1.FilesystemSnapshot_LVM{
+			VolumeGroup:   "vg0",
+			LogicalVolume: "data",
+			MountOptions:  "nouuid",
+		}},
+	}
+
+	runner := &fakeRunner{fail: []string{"lvs "}} // no leftover snapshot.
+	snap, err := Create(context.Background(), cfg, "plan1", mountRoot, runner.run)
+	if err != nil {
+		t.Fatalf("Create() error = %v", err)
+	}
+
+	paths, err := snap.RewritePaths([]string{"/var/lib/postgresql", "/var/lib"})
+	if err != nil {
+		t.Fatalf("RewritePaths() error = %v", err)
+	}
+	if want := []string{filepath.Join(mountPath, "postgresql"), mountPath}; !slices.Equal(paths, want) {
+		t.Errorf("RewritePaths() = %v, want %v", paths, want)
+	}
+	if _, err := snap.RewritePaths([]string{"/home"}); err == nil {
+		t.Errorf("RewritePaths() of a path outside the source path succeeded, want an error")
+	}
+
+	excludes := snap.RewriteExcludes([]string{"/var/lib/postgresql/*.tmp", "!/var/lib/postgresql/keep", "*.log", "/home/cache"})
+	if want := []string{filepath.Join(mountPath, "postgresql", "*.tmp"), "!" + filepath.Join(mountPath, "postgresql", "keep"), "*.log", "/home/cache"}; !slices.Equal(excludes, want) {
+		t.Errorf("RewriteExcludes() = %v, want %v", excludes, want)
+	}
+
+	if err := snap.Release(context.Background()); err != nil {
+		t.Fatalf("Release() error = %v", err)
+	}
+	want := []string{
+		"lvs vg0/data-backrest-plan1",
+		"lvcreate --snapshot --setactivationskip n --name data-backrest-plan1 vg0/data",
+		"lvchange --activate y vg0/data-backrest-plan1",
+		"mount -o ro,nouuid /dev/vg0/data-backrest-plan1 " + mountPath,
+		"umount " + mountPath,
+		"lvremove --yes vg0/data-backrest-plan1",
+	}
+	if !slices.Equal(runner.commands, want) {
+		t.Errorf("commands = %q, want %q", runner.commands, want)
+	}
+}
+
+func TestSnapshotInUse(t *testing.T) {
+	cfg := &v1.FilesystemSnapshot{
+		SourcePath: "/tank/data",
+		Provider:   &v1.FilesystemSnapshot_ProviderZfs{ProviderZfs: &v1.FilesystemSnapshot_ZFS{Dataset: "tank/data"}},
+	}
+
+	runner := &fakeRunner{fail: []string{"zfs list "}} // no leftover snapshot.
+	snap, err := Create(context.Background(), cfg, "plan1", t.TempDir(), runner.run)
+	if err != nil {
+		t.Fatalf("Create() error = %v", err)
+	}
+
+	// a concurrent backup of the plan must not remove the snapshot as a leftover.
+	commands := len(runner.commands)
+	if _, err := Create(context.Background(), cfg, "plan1", t.TempDir(), runner.run); err == nil {
+		t.Fatalf("Create() while the plan's snapshot is held succeeded, want an error")
+	}
+	if len(runner.commands) != commands {
+		t.Errorf("Create() while the plan's snapshot is held ran %q", runner.commands[commands:])
+	}
+
+	if err := snap.Release(context.Background()); err != nil {
+		t.Fatalf("Release() error = %v", err)
+	}
+	snap, err = Create(context.Background(), cfg, "plan1", t.TempDir(), runner.run)
+	if err != nil {
+		t.Fatalf("Create() after Release() error = %v", err)
+	}
+	if err := snap.Release(context.Background()); err != nil {
+		t.Fatalf("Release() error = %v", err)
+	}
+}
+
+func TestSnapshotTornDownOnFailure(t *testing.T) {
+	cfg := &v1.FilesystemSnapshot{
+		SourcePath: "/var/lib",
+		Provider: &v1.FilesystemSnapshot_ProviderLvm{ProviderLvm: &v1.FilesystemSnapshot_LVM{
+			VolumeGroup:   "vg0",
+			LogicalVolume: "data",
+			Size:          "5G",
+		}},
+	}
+
+	runner := &fakeRunner{fail: []string{"lvs ", "mount "}}
+	if _, err := Create(context.Background(), cfg, "plan1", t.TempDir(), runner.run); err == nil {
+		t.Fatalf("Create() succeeded, want the mount error")
+	}
+	if last := runner.commands[len(runner.commands)-1]; last != "lvremove --yes vg0/data-backrest-plan1" {
+		t.Errorf("last command = %q, want the snapshot to be removed", last)
+	}
+}
+
+func TestZFSSnapshot(t *testing.T) {
+	cfg := &v1.FilesystemSnapshot{
+		SourcePath: "/tank/data",
+		Provider:   &v1.FilesystemSnapshot_ProviderZfs{ProviderZfs: &v1.FilesystemSnapshot_ZFS{Dataset: "tank/data"}},
+	}
+
+	// a snapshot was left behind by an interrupted backup.
+	runner := &fakeRunner{}
+	snap, err := Create(context.Background(), cfg, "plan1", t.TempDir(), runner.run)
+	if err != nil {
+		t.Fatalf("Create() error = %v", err)
+	}
+	if want := "/tank/data/.zfs/snapshot/backrest-plan1"; snap.Path != want {
+		t.Errorf("snapshot path = %q, want %q", snap.Path, want)
+	}
+	want := []string{
+		"zfs list -t snapshot tank/data@backrest-plan1",
+		"zfs destroy tank/data@backrest-plan1",
+		"zfs snapshot tank/data@backrest-plan1",
+	}
+	if !slices.Equal(runner.commands, want) {
+		t.Errorf("commands = %q, want %q", runner.commands, want)
+	}
+}
+
+func TestValidate(t *testing.T) {
+	tcs := []struct {
+		name    string
+		cfg     *v1.FilesystemSnapshot
+		wantErr bool
+	}{
+		{name: "no provider", cfg: &v1.FilesystemSnapshot{SourcePath: "/data"}, wantErr: true},
+		{name: "relative source", cfg: &v1.FilesystemSnapshot{SourcePath: "data", Provider: &v1.FilesystemSnapshot_ProviderBtrfs{ProviderBtrfs: &v1.FilesystemSnapshot_Btrfs{}}}, wantErr: true},
+		{name: "btrfs", cfg: &v1.FilesystemSnapshot{SourcePath: "/data", Provider: &v1.FilesystemSnapshot_ProviderBtrfs{ProviderBtrfs: &v1.FilesystemSnapshot_Btrfs{}}}},
+		{name: "lvm without volume", cfg: &v1.FilesystemSnapshot{SourcePath: "/data", Provider: &v1.FilesystemSnapshot_ProviderLvm{ProviderLvm: &v1.FilesystemSnapshot_LVM{VolumeGroup: "vg0"}}}, wantErr: true},
+		{name: "zfs snapshot as dataset", cfg: &v1.FilesystemSnapshot{SourcePath: "/data", Provider: &v1.FilesystemSnapshot_ProviderZfs{ProviderZfs: &v1.FilesystemSnapshot_ZFS{Dataset: "tank@snap"}}}, wantErr: true},
+	}
+	for _, tc := range tcs {
+		t.Run(tc.name, func(t *testing.T) {
+			if err := Validate(tc.cfg); (err != nil) != tc.wantErr {
+				t.Errorf("Validate() error = %v, wantErr %v", err, tc.wantErr)
+			}
+		})
+	}
+}
@@ -13,6 +13,8 @@ import (
 	cfg "github.com/garethgeorge/backrest/internal/config"
 	"github.com/garethgeorge/backrest/internal/hook/types"
 	"github.com/garethgeorge/backrest/internal/orchestrator/tasks"
+	"github.com/garethgeorge/backrest/internal/tracing"
+	"go.opentelemetry.io/otel/attribute"
 )
 
 func TasksTriggeredByEvent(config *v1.Config, repoID string, planID string, parentOp *v1.Operation, events []v1.Hook_Condition, vars interface{}) ([]tasks.Task, error) {
@@ -55,6 +57,54 @@ func TasksTriggeredByEvent(config *v1.Config, repoID string, planID string, pare
 	return taskSet, nil
 }
 
+// TasksTriggeredByGlobalEvent returns tasks running the hooks of every repo and plan that subscribe to an event that is
+// not tied to a single repo or plan e.g. a login lockout.
+func TasksTriggeredByGlobalEvent(config *v1.Config, events []v1.Hook_Condition, vars tasks.HookVars) ([]tasks.Task, error) {
+	var taskSet []tasks.Task
+
+	for _, repo := range config.GetRepos() {
+		for idx, hook := range repo.GetHooks() {
+			event := firstMatchingCondition(hook, events)
+			if event == v1.Hook_CONDITION_UNKNOWN {
+				continue
+			}
+
+			vars.Repo = repo
+			vars.Plan = &v1.Plan{Id: tasks.PlanForSystemTasks}
+			name := fmt.Sprintf("repo/%v/hook/%v", repo.Id, idx)
+			task, err := newOneoffRunHookTask(name, config.Instance, repo, tasks.PlanForSystemTasks, nil, time.Now(), hook, event, vars)
+			if err != nil {
+				return nil, err
+			}
+			taskSet = append(taskSet, task)
+		}
+	}
+
+	for _, plan := range config.GetPlans() {
+		repo := cfg.FindRepo(config, plan.Repo)
+		if repo == nil {
+			continue
+		}
+		for idx, hook := range plan.GetHooks() {
+			event := firstMatchingCondition(hook, events)
+			if event == v1.Hook_CONDITION_UNKNOWN {
+				continue
+			}
+
+			vars.Repo = repo
+			vars.Plan = plan
+			name := fmt.Sprintf("plan/%v/hook/%v", plan.Id, idx)
+			task, err := newOneoffRunHookTask(name, config.Instance, repo, plan.Id, nil, time.Now(), hook, event, vars)
+			if err != nil {
+				return nil, err
+			}
+			taskSet = append(taskSet, task)
+		}
+	}
+
+	return taskSet, nil
+}
+
 func newOneoffRunHookTask(title, instanceID string, repo *v1.Repo, planID string, parentOp *v1.Operation, at time.Time, hook *v1.Hook, event v1.Hook_Condition, vars interface{}) (tasks.Task, error) {
 	h, err := types.DefaultRegistry().GetHandler(hook)
 	if err != nil {
@@ -93,7 +143,14 @@ func newOneoffRunHookTask(title, instanceID string, repo *v1.Repo, planID string
 				clone.FieldByName("Event").Set(reflect.ValueOf(event))
 			}
 
-			if err := h.Execute(ctx, hook, clone, taskRunner, event); err != nil {
+			ctx, span := tracing.Start(ctx, "hook "+h.Name(),
+				attribute.String("backrest.hook.name", title),
+				attribute.String("backrest.hook.condition", event.String()),
+				attribute.String("backrest.hook.on_error", hook.OnError.String()),
+			)
+			err := h.Execute(ctx, hook, clone, taskRunner, event)
+			tracing.End(span, err)
+			if err != nil {
 				err = applyHookErrorPolicy(hook.OnError, err)
 				return err
 			}
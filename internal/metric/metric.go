@@ -42,6 +42,35 @@ func initRegistry() *Registry {
 			Name: "backrest_last_task_status",
 			Help: "The status of the last task",
 		}, append(slices.Clone(commonDims), "task_type", "status")),
+		taskScheduleLag: prometheus.NewGaugeVec(prometheus.GaugeOpts{
+			Name: "backrest_task_schedule_lag_secs",
+			Help: "The delay between the scheduled time of the last run of a task and its start in seconds",
+		}, append(slices.Clone(commonDims), "task_type")),
+		lastSuccessfulBackup: prometheus.NewGaugeVec(prometheus.GaugeOpts{
+			Name: "backrest_last_successful_backup_timestamp",
+			Help: "The unix time in seconds at which the last successful backup completed",
+		}, commonDims),
+		snapshotCount: prometheus.NewGaugeVec(prometheus.GaugeOpts{
+			Name: "backrest_snapshot_count",
+			Help: "The number of snapshots in the repo created by the plan that have not been forgotten",
+		}, commonDims),
+		repoTotalSize: prometheus.NewGaugeVec(prometheus.GaugeOpts{
+			Name: "backrest_repo_total_size_bytes",
+			Help: "The size of the repo in bytes as of its last stats operation",
+		}, []string{"repo_id"}),
+		repoUncompressedSize: prometheus.NewGaugeVec(prometheus.GaugeOpts{
+			Name: "backrest_repo_total_uncompressed_size_bytes",
+			Help: "The uncompressed size of the repo's data in bytes as of its last stats operation",
+		}, []string{"repo_id"}),
+		repoCompressionRatio: prometheus.NewGaugeVec(prometheus.GaugeOpts{
+			Name: "backrest_repo_compression_ratio",
+			Help: "The compression ratio of the repo as of its last stats operation",
+		}, []string{"repo_id"}),
+		repoSnapshotCount: prometheus.NewGaugeVec(prometheus.GaugeOpts{
+			Name: "backrest_repo_snapshot_count",
+			Help: "The number of snapshots in the repo as of its last stats operation",
+		}, []string{"repo_id"}),
+		queue: &queueCollector{},
 	}
 
 	registry.reg.MustRegister(registry.backupBytesProcessed)
@@ -50,6 +79,14 @@ func initRegistry() *Registry {
 	registry.reg.MustRegister(registry.tasksDuration)
 	registry.reg.MustRegister(registry.tasksRun)
 	registry.reg.MustRegister(registry.lastTaskStatus)
+	registry.reg.MustRegister(registry.taskScheduleLag)
+	registry.reg.MustRegister(registry.lastSuccessfulBackup)
+	registry.reg.MustRegister(registry.snapshotCount)
+	registry.reg.MustRegister(registry.repoTotalSize)
+	registry.reg.MustRegister(registry.repoUncompressedSize)
+	registry.reg.MustRegister(registry.repoCompressionRatio)
+	registry.reg.MustRegister(registry.repoSnapshotCount)
+	registry.reg.MustRegister(registry.queue)
 
 	return registry
 }
@@ -66,6 +103,14 @@ type Registry struct {
 	tasksDuration        *prometheus.GaugeVec
 	tasksRun             *prometheus.CounterVec
 	lastTaskStatus       *prometheus.GaugeVec
+	taskScheduleLag      *prometheus.GaugeVec
+	lastSuccessfulBackup *prometheus.GaugeVec
+	snapshotCount        *prometheus.GaugeVec
+	repoTotalSize        *prometheus.GaugeVec
+	repoUncompressedSize *prometheus.GaugeVec
+	repoCompressionRatio *prometheus.GaugeVec
+	repoSnapshotCount    *prometheus.GaugeVec
+	queue                *queueCollector
 }
 
 func (r *Registry) Handler() http.Handler {
@@ -96,3 +141,12 @@ func (r *Registry) RecordBackupSummary(repoID, planID string, bytesProcessed, by
 	r.backupBytesAdded.WithLabelValues(repoID, planID).Set(float64(bytesAdded))
 	r.backupFileWarnings.WithLabelValues(repoID, planID).Set(float64(fileWarnings))
 }
+
+func (r *Registry) RecordScheduleLag(repoID, planID, taskType string, lagSecs float64) {
+	r.taskScheduleLag.WithLabelValues(labelOrUnassociated(repoID), labelOrUnassociated(planID), taskType).Set(max(lagSecs, 0))
+}
+
+// SetTaskQueueSource sets the function used to read the orchestrator's task queue when metrics are collected.
+func (r *Registry) SetTaskQueueSource(fn func() []QueuedTask) {
+	r.queue.setSource(fn)
+}
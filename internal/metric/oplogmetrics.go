@@ -0,0 +1,109 @@
+package metric
+
+import (
+	"fmt"
+	"sync"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/oplog"
+)
+
+type planKey struct {
+	repoID string
+	planID string
+}
+
+// opLogMetrics derives the metrics that describe the state of backups from the operations in the oplog, so that they
+// are restored when backrest restarts.
+type opLogMetrics struct {
+	r          *Registry
+	instanceID string
+
+	mu             sync.Mutex
+	lastBackupMs   map[planKey]int64 // end time of the last successful backup.
+	statsOpID      map[string]int64  // repo ID to the ID of the stats operation currently reported.
+	snapshots      map[int64]planKey // ID of each index operation of a snapshot that is not forgotten.
+	snapshotCounts map[planKey]int
+}
+
+// TrackOpLog restores the metrics derived from operations (last successful backup, repo stats and snapshot counts)
+// from the oplog and keeps them up to date as operations change. Operations synced from other instances are ignored.
+func (r *Registry) TrackOpLog(log *oplog.OpLog, instanceID string) error {
+	m := &opLogMetrics{
+		r:              r,
+		instanceID:     instanceID,
+		lastBackupMs:   make(map[planKey]int64),
+		statsOpID:      make(map[string]int64),
+		snapshots:      make(map[int64]planKey),
+		snapshotCounts: make(map[planKey]int),
+	}
+
+	// subscribe before scanning so that no change is missed, applying an operation more than once is harmless.
+	sub := oplog.Subscription(m.apply)
+	log.Subscribe(oplog.SelectAll, &sub)
+	if err := log.Query(oplog.SelectAll, func(op *v1.Operation) error {
+		m.apply([]*v1.Operation{op}, oplog.OPERATION_ADDED)
+		return nil
+	}); err != nil {
+		log.Unsubscribe(&sub)
+		return fmt.Errorf("restore metrics from oplog: %w", err)
+	}
+	return nil
+}
+
+func (m *opLogMetrics) apply(ops []*v1.Operation, event oplog.OperationEvent) {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+
+	changedCounts := make(map[planKey]struct{})
+	for _, op := range ops {
+		if op.InstanceId != "" && op.InstanceId != m.instanceID {
+			continue
+		}
+		key := planKey{repoID: labelOrUnassociated(op.RepoId), planID: labelOrUnassociated(op.PlanId)}
+
+		if event == oplog.OPERATION_DELETED {
+			if k, ok := m.snapshots[op.Id]; ok {
+				delete(m.snapshots, op.Id)
+				m.snapshotCounts[k]--
+				changedCounts[k] = struct{}{}
+			}
+			continue
+		}
+
+		switch o := op.Op.(type) {
+		case *v1.Operation_OperationBackup:
+			// backups with warnings skipped some files but still created a snapshot.
+			succeeded := op.Status == v1.OperationStatus_STATUS_SUCCESS || op.Status == v1.OperationStatus_STATUS_WARNING
+			if succeeded && op.UnixTimeEndMs > m.lastBackupMs[key] {
+				m.lastBackupMs[key] = op.UnixTimeEndMs
+				m.r.lastSuccessfulBackup.WithLabelValues(key.repoID, key.planID).Set(float64(op.UnixTimeEndMs) / 1000)
+			}
+		case *v1.Operation_OperationIndexSnapshot:
+			_, tracked := m.snapshots[op.Id]
+			if forgot := o.OperationIndexSnapshot.GetForgot(); forgot && tracked {
+				delete(m.snapshots, op.Id)
+				m.snapshotCounts[key]--
+				changedCounts[key] = struct{}{}
+			} else if !forgot && !tracked {
+				m.snapshots[op.Id] = key
+				m.snapshotCounts[key]++
+				changedCounts[key] = struct{}{}
+			}
+		case *v1.Operation_OperationStats:
+			stats := o.OperationStats.GetStats()
+			if op.Status != v1.OperationStatus_STATUS_SUCCESS || stats == nil || op.Id < m.statsOpID[key.repoID] {
+				continue
+			}
+			m.statsOpID[key.repoID] = op.Id
+			m.r.repoTotalSize.WithLabelValues(key.repoID).Set(float64(stats.TotalSize))
+			m.r.repoUncompressedSize.WithLabelValues(key.repoID).Set(float64(stats.TotalUncompressedSize))
+			m.r.repoCompressionRatio.WithLabelValues(key.repoID).Set(stats.CompressionRatio)
+			m.r.repoSnapshotCount.WithLabelValues(key.repoID).Set(float64(stats.SnapshotCount))
+		}
+	}
+
+	for k := range changedCounts {
+		m.r.snapshotCount.WithLabelValues(k.repoID, k.planID).Set(float64(m.snapshotCounts[k]))
+	}
+}
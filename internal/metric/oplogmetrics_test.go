@@ -0,0 +1,123 @@
+package metric
+
+import (
+	"strings"
+	"testing"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/oplog"
+	"github.com/garethgeorge/backrest/internal/oplog/sqlitestore"
+	"github.com/prometheus/client_golang/prometheus/testutil"
+)
+
+func TestTrackOpLog(t *testing.T) {
+	opstore, err := sqlitestore.NewMemorySqliteStore()
+	if err != nil {
+		t.Fatalf("failed to create opstore: %v", err)
+	}
+	t.Cleanup(func() { opstore.Close() })
+	log, err := oplog.NewOpLog(opstore)
+	if err != nil {
+		t.Fatalf("failed to create oplog: %v", err)
+	}
+
+	newOp := func(instanceID string, status v1.OperationStatus, endMs int64) *v1.Operation {
+		return &v1.Operation{
+			RepoId:          "repo1",
+			RepoGuid:        "repo1-guid",
+			PlanId:          "plan1",
+			InstanceId:      instanceID,
+			Status:          status,
+			UnixTimeStartMs: endMs - 1000,
+			UnixTimeEndMs:   endMs,
+		}
+	}
+	backup := func(instanceID string, status v1.OperationStatus, endMs int64) *v1.Operation {
+		op := newOp(instanceID, status, endMs)
+		op.Op = &v1.Operation_OperationBackup{OperationBackup: &v1.OperationBackup{}}
+		return op
+	}
+	snapshot := func() *v1.Operation {
+		op := newOp("local", v1.OperationStatus_STATUS_SUCCESS, 1000)
+		op.Op = &v1.Operation_OperationIndexSnapshot{OperationIndexSnapshot: &v1.OperationIndexSnapshot{}}
+		return op
+	}
+
+	// operations from before the restart.
+	forgotten := snapshot()
+	if err := log.Add(
+		backup("local", v1.OperationStatus_STATUS_SUCCESS, 10000),
+		backup("local", v1.OperationStatus_STATUS_ERROR, 20000),
+		backup("remote", v1.OperationStatus_STATUS_SUCCESS, 30000),
+		snapshot(),
+		forgotten,
+	); err != nil {
+		t.Fatalf("failed to add operations: %v", err)
+	}
+
+	r := initRegistry()
+	if err := r.TrackOpLog(log, "local"); err != nil {
+		t.Fatalf("TrackOpLog() error = %v", err)
+	}
+	if got := testutil.ToFloat64(r.lastSuccessfulBackup.WithLabelValues("repo1", "plan1")); got != 10 {
+		t.Errorf("last successful backup = %v, want 10", got)
+	}
+	if got := testutil.ToFloat64(r.snapshotCount.WithLabelValues("repo1", "plan1")); got != 2 {
+		t.Errorf("snapshot count = %v, want 2", got)
+	}
+
+	// later operations update the metrics.
+	stats := newOp("local", v1.OperationStatus_STATUS_SUCCESS, 50000)
+	stats.Op = &v1.Operation_OperationStats{OperationStats: &v1.OperationStats{
+		Stats: &v1.RepoStats{TotalSize: 1000, TotalUncompressedSize: 2000, CompressionRatio: 2, SnapshotCount: 1},
+	}}
+	if err := log.Add(backup("local", v1.OperationStatus_STATUS_WARNING, 40000), stats); err != nil {
+		t.Fatalf("failed to add operations: %v", err)
+	}
+	forgotten.GetOperationIndexSnapshot().Forgot = true
+	if err := log.Update(forgotten); err != nil {
+		t.Fatalf("failed to update operation: %v", err)
+	}
+
+	if got := testutil.ToFloat64(r.lastSuccessfulBackup.WithLabelValues("repo1", "plan1")); got != 40 {
+		t.Errorf("last successful backup = %v, want 40", got)
+	}
+	if got := testutil.ToFloat64(r.snapshotCount.WithLabelValues("repo1", "plan1")); got != 1 {
+		t.Errorf("snapshot count after forget = %v, want 1", got)
+	}
+	if got := testutil.ToFloat64(r.repoTotalSize.WithLabelValues("repo1")); got != 1000 {
+		t.Errorf("repo total size = %v, want 1000", got)
+	}
+	if got := testutil.ToFloat64(r.repoCompressionRatio.WithLabelValues("repo1")); got != 2 {
+		t.Errorf("repo compression ratio = %v, want 2", got)
+	}
+}
+
+func TestQueueCollector(t *testing.T) {
+	now := time.Unix(1000, 0)
+	c := &queueCollector{now: func() time.Time { return now }}
+	c.setSource(func() []QueuedTask {
+		return []QueuedTask{
+			{RepoID: "repo1", PlanID: "plan1", TaskType: "backup", RunAt: now.Add(-time.Minute)},
+			{RepoID: "repo1", PlanID: "plan1", TaskType: "backup", RunAt: now.Add(time.Hour)},
+			{RepoID: "repo1", TaskType: "prune", RunAt: now.Add(2 * time.Hour)},
+		}
+	})
+
+	// depth, overdue and one next run per task.
+	if got := testutil.CollectAndCount(c); got != 4 {
+		t.Errorf("collected %d metrics, want 4", got)
+	}
+	expected := `
+# HELP backrest_task_queue_depth The number of tasks waiting in the queue
+# TYPE backrest_task_queue_depth gauge
+backrest_task_queue_depth 3
+# HELP backrest_task_queue_overdue The number of queued tasks whose scheduled time has passed, these are waiting for other tasks to finish
+# TYPE backrest_task_queue_overdue gauge
+backrest_task_queue_overdue 1
+`
+	if err := testutil.CollectAndCompare(c, strings.NewReader(expected), "backrest_task_queue_depth", "backrest_task_queue_overdue"); err != nil {
+		t.Errorf("unexpected queue metrics: %v", err)
+	}
+}
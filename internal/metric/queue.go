@@ -0,0 +1,92 @@
+package metric
+
+import (
+	"sync"
+	"time"
+
+	"github.com/prometheus/client_golang/prometheus"
+)
+
+// QueuedTask describes a task waiting in the orchestrator's queue.
+type QueuedTask struct {
+	RepoID   string
+	PlanID   string
+	TaskType string
+	RunAt    time.Time
+}
+
+var (
+	queueDepthDesc = prometheus.NewDesc(
+		"backrest_task_queue_depth",
+		"The number of tasks waiting in the queue",
+		nil, nil)
+	queueOverdueDesc = prometheus.NewDesc(
+		"backrest_task_queue_overdue",
+		"The number of queued tasks whose scheduled time has passed, these are waiting for other tasks to finish",
+		nil, nil)
+	nextScheduledRunDesc = prometheus.NewDesc(
+		"backrest_next_scheduled_run_timestamp",
+		"The unix time in seconds at which the task is next scheduled to run",
+		[]string{"repo_id", "plan_id", "task_type"}, nil)
+)
+
+// queueCollector reports the orchestrator's task queue as it is at collection time.
+type queueCollector struct {
+	mu     sync.Mutex
+	source func() []QueuedTask
+	now    func() time.Time
+}
+
+var _ prometheus.Collector = &queueCollector{}
+
+func (c *queueCollector) setSource(fn func() []QueuedTask) {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	c.source = fn
+}
+
+func (c *queueCollector) Describe(ch chan<- *prometheus.Desc) {
+	ch <- queueDepthDesc
+	ch <- queueOverdueDesc
+	ch <- nextScheduledRunDesc
+}
+
+func (c *queueCollector) Collect(ch chan<- prometheus.Metric) {
+	c.mu.Lock()
+	source := c.source
+	now := time.Now()
+	if c.now != nil {
+		now = c.now()
+	}
+	c.mu.Unlock()
+	if source == nil {
+		return
+	}
+
+	queued := source()
+	overdue := 0
+	type taskKey struct{ repoID, planID, taskType string }
+	nextRuns := make(map[taskKey]time.Time)
+	for _, t := range queued {
+		if !t.RunAt.After(now) {
+			overdue++
+		}
+		key := taskKey{labelOrUnassociated(t.RepoID), labelOrUnassociated(t.PlanID), t.TaskType}
+		if next, ok := nextRuns[key]; !ok || t.RunAt.Before(next) {
+			nextRuns[key] = t.RunAt
+		}
+	}
+
+	ch <- prometheus.MustNewConstMetric(queueDepthDesc, prometheus.GaugeValue, float64(len(queued)))
+	ch <- prometheus.MustNewConstMetric(queueOverdueDesc, prometheus.GaugeValue, float64(overdue))
+	for key, runAt := range nextRuns {
+		ch <- prometheus.MustNewConstMetric(nextScheduledRunDesc, prometheus.GaugeValue, float64(runAt.Unix()), key.repoID, key.planID, key.taskType)
+	}
+}
+
+func labelOrUnassociated(v string) string {
+	if v == "" {
+		return "_unassociated_"
+	}
+	return v
+}
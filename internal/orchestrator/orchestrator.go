@@ -11,6 +11,7 @@ import (
 
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
 	"github.com/garethgeorge/backrest/internal/config"
+	"github.com/garethgeorge/backrest/internal/hook"
 	"github.com/garethgeorge/backrest/internal/logstore"
 	"github.com/garethgeorge/backrest/internal/metric"
 	"github.com/garethgeorge/backrest/internal/oplog"
@@ -18,7 +19,9 @@ import (
 	"github.com/garethgeorge/backrest/internal/orchestrator/repo"
 	"github.com/garethgeorge/backrest/internal/orchestrator/tasks"
 	"github.com/garethgeorge/backrest/internal/queue"
+	"github.com/garethgeorge/backrest/internal/tracing"
 	"github.com/google/uuid"
+	"go.opentelemetry.io/otel/attribute"
 	"go.uber.org/multierr"
 	"go.uber.org/zap"
 	"google.golang.org/protobuf/proto"
@@ -222,7 +225,7 @@ func (o *Orchestrator) applyConfig(cfg *v1.Config) error {
 }
 
 // rescheduleTasksIfNeeded checks if any tasks need to be rescheduled based on config changes.
-func (o *Orchestrator) ScheduleDefaultTasks(config *v1.Config) error {
+func (o *Orchestrator) ScheduleDefaultTasks(cfg *v1.Config) error {
 	if o.OpLog == nil {
 		return nil
 	}
@@ -248,24 +251,32 @@ func (o *Orchestrator) ScheduleDefaultTasks(config *v1.Config) error {
 	}
 
 	var repoByID = map[string]*v1.Repo{}
-	for _, repo := range config.Repos {
+	for _, repo := range cfg.Repos {
 		repoByID[repo.GetId()] = repo
 	}
 
-	for _, plan := range config.Plans {
-		// Schedule a backup task for the plan
-		repo := repoByID[plan.Repo]
-		if repo == nil {
-			return fmt.Errorf("repo %q not found for plan %q", plan.Repo, plan.Id)
-		}
+	for _, plan := range cfg.Plans {
+		// Schedule a backup task for each repo the plan backs up to
+		for _, repoID := range config.PlanRepos(plan) {
+			repo := repoByID[repoID]
+			if repo == nil {
+				return fmt.Errorf("repo %q not found for plan %q", repoID, plan.Id)
+			}
+
+			t := tasks.NewScheduledBackupTask(repo, plan)
+			if err := o.ScheduleTask(t, tasks.TaskPriorityDefault); err != nil {
+				return fmt.Errorf("schedule backup task for plan %q in repo %q: %w", plan.Id, repoID, err)
+			}
 
-		t := tasks.NewScheduledBackupTask(repo, plan)
-		if err := o.ScheduleTask(t, tasks.TaskPriorityDefault); err != nil {
-			return fmt.Errorf("schedule backup task for plan %q: %w", plan.Id, err)
+			if plan.GetMaxSnapshotAgeHours() > 0 {
+				if err := o.ScheduleTask(tasks.NewPlanStalenessWatchdogTask(repo, plan), tasks.TaskPriorityDefault); err != nil {
+					return fmt.Errorf("schedule staleness watchdog for plan %q in repo %q: %w", plan.Id, repoID, err)
+				}
+			}
 		}
 	}
 
-	for _, repo := range config.Repos {
+	for _, repo := range cfg.Repos {
 		// Schedule a prune task for the repo
 		t := tasks.NewPruneTask(repo, tasks.PlanForSystemTasks, false)
 		if err := o.ScheduleTask(t, tasks.TaskPriorityPrune); err != nil {
@@ -277,6 +288,14 @@ func (o *Orchestrator) ScheduleDefaultTasks(config *v1.Config) error {
 		if err := o.ScheduleTask(t, tasks.TaskPriorityCheck); err != nil {
 			return fmt.Errorf("schedule check task for repo %q: %w", repo.GetId(), err)
 		}
+
+		// Schedule a copy task for the repo
+		if repo.GetCopyPolicy().GetToRepo() != "" {
+			t = tasks.NewCopyTask(repo, tasks.PlanForSystemTasks, false)
+			if err := o.ScheduleTask(t, tasks.TaskPriorityDefault); err != nil {
+				return fmt.Errorf("schedule copy task for repo %q: %w", repo.GetId(), err)
+			}
+		}
 	}
 
 	return nil
@@ -421,6 +440,7 @@ func (o *Orchestrator) Run(ctx context.Context) {
 		if t.Task == nil {
 			continue
 		}
+		metric.GetRegistry().RecordScheduleLag(t.Task.RepoID(), t.Task.PlanID(), t.Task.Type(), time.Since(t.RunAt).Seconds())
 
 		// Clone the operation incase we need to reset changes and reschedule the task for a retry
 		originalOp := proto.Clone(t.Op).(*v1.Operation)
@@ -484,6 +504,13 @@ func (o *Orchestrator) RunTask(ctx context.Context, st tasks.ScheduledTask) erro
 	ctx, cancel := context.WithCancel(ctx)
 	defer cancel()
 
+	ctx, span := tracing.Start(ctx, "task "+st.Task.Type(),
+		attribute.String("backrest.task.name", st.Task.Name()),
+		attribute.String("backrest.task.type", st.Task.Type()),
+		attribute.String("backrest.repo_id", st.Task.RepoID()),
+		attribute.String("backrest.plan_id", st.Task.PlanID()),
+	)
+
 	zap.L().Info("running task", zap.String("task", st.Task.Name()), zap.String("runAt", st.RunAt.Format(time.RFC3339)))
 	var logWriter io.WriteCloser
 	op := st.Op
@@ -519,6 +546,7 @@ func (o *Orchestrator) RunTask(ctx context.Context, st tasks.ScheduledTask) erro
 				zap.S().Errorf("failed to add operation to oplog: %w", err)
 			}
 		}
+		span.SetAttributes(attribute.Int64("backrest.operation_id", op.Id))
 	} else {
 		ctx = logging.ContextWithWriter(ctx, io.Discard) // discard logs if no operation.
 	}
@@ -526,6 +554,7 @@ func (o *Orchestrator) RunTask(ctx context.Context, st tasks.ScheduledTask) erro
 	start := time.Now()
 	runner := newTaskRunnerImpl(o, st.Task, st.Op)
 	err := st.Task.Run(ctx, st, runner)
+	tracing.End(span, err)
 	if err != nil {
 		runner.Logger(ctx).Error("task failed", zap.Error(err), zap.Duration("duration", time.Since(start)))
 		metric.GetRegistry().RecordTaskRun(st.Task.RepoID(), st.Task.PlanID(), st.Task.Type(), time.Since(start).Seconds(), "failed")
@@ -572,6 +601,24 @@ func (o *Orchestrator) RunTask(ctx context.Context, st tasks.ScheduledTask) erro
 	return err
 }
 
+// QueuedTasks returns the tasks waiting in the queue, used to report the queue's metrics.
+func (o *Orchestrator) QueuedTasks() []metric.QueuedTask {
+	queued := o.taskQueue.GetAll()
+	res := make([]metric.QueuedTask, 0, len(queued))
+	for _, t := range queued {
+		if t.Task == nil {
+			continue
+		}
+		res = append(res, metric.QueuedTask{
+			RepoID:   t.Task.RepoID(),
+			PlanID:   t.Task.PlanID(),
+			TaskType: t.Task.Type(),
+			RunAt:    t.RunAt,
+		})
+	}
+	return res
+}
+
 // ScheduleTask schedules a task to run at the next available time.
 // note that o.mu must not be held when calling this function.
 func (o *Orchestrator) ScheduleTask(t tasks.Task, priority int, callbacks ...func(error)) error {
@@ -619,6 +666,29 @@ func (o *Orchestrator) CreateUnscheduledTask(t tasks.Task, priority int, curTime
 	return nextRun, nil
 }
 
+// RunGlobalHooks runs the hooks of every repo and plan subscribed to the events in the background, it is used for
+// events that do not originate from a task e.g. login lockouts.
+func (o *Orchestrator) RunGlobalHooks(events []v1.Hook_Condition, vars tasks.HookVars) error {
+	vars.CurTime = o.curTime()
+	hookTasks, err := hook.TasksTriggeredByGlobalEvent(o.Config(), events, vars)
+	if err != nil {
+		return err
+	}
+
+	for _, task := range hookTasks {
+		st, err := o.CreateUnscheduledTask(task, tasks.TaskPriorityInteractive, o.curTime())
+		if err != nil {
+			return fmt.Errorf("creating task for hook: %w", err)
+		}
+		go func() {
+			if err := o.RunTask(context.Background(), st); err != nil {
+				zap.L().Error("failed to run hook", zap.String("task", task.Name()), zap.Error(err))
+			}
+		}()
+	}
+	return nil
+}
+
 func (o *Orchestrator) Config() *v1.Config {
 	o.mu.Lock()
 	defer o.mu.Unlock()
@@ -2,9 +2,11 @@ package repo
 
 import (
 	"context"
+	"encoding/hex"
 	"errors"
 	"fmt"
 	"io"
+	"maps"
 	"path"
 	"slices"
 	"sort"
@@ -14,6 +16,7 @@ import (
 
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
 	"github.com/garethgeorge/backrest/internal/cryptoutil"
+	"github.com/garethgeorge/backrest/internal/dbdump"
 	"github.com/garethgeorge/backrest/internal/orchestrator/logging"
 	"github.com/garethgeorge/backrest/internal/protoutil"
 	"github.com/garethgeorge/backrest/pkg/restic"
@@ -21,6 +24,12 @@ import (
 	"go.uber.org/zap"
 )
 
+// ErrPathNotFound is returned when a path doesn't exist in a snapshot.
+var ErrPathNotFound = errors.New("path not found in snapshot")
+
+// ErrSnapshotNotFound is returned when a snapshot doesn't exist in the repo.
+var ErrSnapshotNotFound = errors.New("snapshot not found")
+
 // RepoOrchestrator implements higher level repository operations on top of
 // the restic package. It can be thought of as a controller for a repo.
 type RepoOrchestrator struct {
@@ -38,17 +47,13 @@ func NewRepoOrchestrator(config *v1.Config, repoConfig *v1.Repo, resticPath stri
 	}
 
 	var opts []restic.GenericOption
-	if p := repoConfig.GetPassword(); p != "" {
-		opts = append(opts, restic.WithEnv("RESTIC_PASSWORD="+p))
-	}
-
 	opts = append(opts, restic.WithEnviron())
+	opts = append(opts, restic.WithCommandHook(traceCommand))
 
-	if env := repoConfig.GetEnv(); len(env) != 0 {
-		for _, e := range env {
-			opts = append(opts, restic.WithEnv(ExpandEnv(e)))
-		}
-	}
+	// secret references are resolved for every restic command rather than once, resolved secrets are never cached.
+	opts = append(opts, restic.WithEnvFunc(func(ctx context.Context) ([]string, error) {
+		return resolveRepoEnv(ctx, repoConfig)
+	}))
 
 	for _, f := range repoConfig.GetFlags() {
 		args, err := shlex.Split(ExpandEnv(f))
@@ -81,6 +86,52 @@ func NewRepoOrchestrator(config *v1.Config, repoConfig *v1.Repo, resticPath stri
 	}, nil
 }
 
+// resolveRepoEnv returns the repo's RESTIC_PASSWORD, if it has a password, followed by its env vars.
+func resolveRepoEnv(ctx context.Context, repoConfig *v1.Repo) ([]string, error) {
+	var env []string
+	password, err := resolvePassword(ctx, repoConfig)
+	if err != nil {
+		return nil, err
+	} else if password != "" {
+		env = append(env, "RESTIC_PASSWORD="+password)
+	}
+	repoEnv, err := resolveEnv(ctx, repoConfig)
+	if err != nil {
+		return nil, err
+	}
+	return append(env, repoEnv...), nil
+}
+
+// resolvePassword returns the repo's password with any secret reference resolved.
+func resolvePassword(ctx context.Context, repoConfig *v1.Repo) (string, error) {
+	p := repoConfig.GetPassword()
+	if p == "" {
+		return "", nil
+	}
+	password, err := ResolveSecret(ctx, p)
+	if err != nil {
+		return "", fmt.Errorf("resolve password for repo %q: %w", repoConfig.Id, err)
+	}
+	return password, nil
+}
+
+// resolveEnv returns the repo's KEY=VALUE env vars with environment variables expanded and secret references resolved.
+func resolveEnv(ctx context.Context, repoConfig *v1.Repo) ([]string, error) {
+	var env []string
+	for _, e := range repoConfig.GetEnv() {
+		e = ExpandEnv(e)
+		if key, value, ok := strings.Cut(e, "="); ok {
+			value, err := ResolveSecret(ctx, value)
+			if err != nil {
+				return nil, fmt.Errorf("resolve env var %q for repo %q: %w", key, repoConfig.Id, err)
+			}
+			e = key + "=" + value
+		}
+		env = append(env, e)
+	}
+	return env, nil
+}
+
 func (r *RepoOrchestrator) logger(ctx context.Context) *zap.Logger {
 	return logging.Logger(ctx, "[repo-manager] ").With(zap.String("repo", r.repoConfig.Id))
 }
@@ -108,6 +159,36 @@ func (r *RepoOrchestrator) Snapshots(ctx context.Context) ([]*restic.Snapshot, e
 	return snapshots, nil
 }
 
+// SnapshotsByID returns the snapshots with the full IDs, an error is returned if any of the snapshots is not found.
+func (r *RepoOrchestrator) SnapshotsByID(ctx context.Context, snapshotIds []string) ([]*restic.Snapshot, error) {
+	ctx, flush := forwardResticLogs(ctx)
+	defer flush()
+
+	for _, id := range snapshotIds {
+		if err := restic.ValidateSnapshotId(id); err != nil {
+			return nil, fmt.Errorf("snapshot %q: %w", id, err)
+		}
+		if _, err := hex.DecodeString(id); err != nil {
+			return nil, fmt.Errorf("snapshot %q: not a hex ID", id)
+		}
+	}
+	snapshots, err := r.repo.Snapshots(ctx, restic.WithFlags(snapshotIds...))
+	if err != nil {
+		return nil, fmt.Errorf("get snapshots %v: %w", snapshotIds, err)
+	}
+
+	// restic skips IDs that match no snapshot.
+	found := make([]*restic.Snapshot, 0, len(snapshotIds))
+	for _, id := range snapshotIds {
+		idx := slices.IndexFunc(snapshots, func(s *restic.Snapshot) bool { return s.Id == id })
+		if idx == -1 {
+			return nil, fmt.Errorf("snapshot %q: %w", id, ErrSnapshotNotFound)
+		}
+		found = append(found, snapshots[idx])
+	}
+	return found, nil
+}
+
 func (r *RepoOrchestrator) SnapshotsForPlan(ctx context.Context, plan *v1.Plan) ([]*restic.Snapshot, error) {
 	ctx, flush := forwardResticLogs(ctx)
 	defer flush()
@@ -159,7 +240,7 @@ func (r *RepoOrchestrator) Backup(ctx context.Context, plan *v1.Plan, progressCa
 	for _, iexclude := range plan.Iexcludes {
 		opts = append(opts, restic.WithFlags("--iexclude", iexclude))
 	}
-	if len(snapshots) > 0 {
+	if len(snapshots) > 0 && plan.GetDatabaseSource() == nil { // each database's dumps are a separate history.
 		opts = append(opts, restic.WithFlags("--parent", snapshots[len(snapshots)-1].Id))
 	}
 
@@ -174,7 +255,18 @@ func (r *RepoOrchestrator) Backup(ctx context.Context, plan *v1.Plan, progressCa
 	ctx, flush := forwardResticLogs(ctx)
 	defer flush()
 	l.Debug("starting backup", zap.String("plan", plan.Id))
-	summary, err := r.repo.Backup(ctx, plan.Paths, progressCallback, opts...)
+	var summary *restic.BackupProgressEntry
+	if src := plan.GetCommandSource(); src != nil {
+		command, splitErr := shlex.Split(src.Command)
+		if splitErr != nil {
+			return nil, fmt.Errorf("failed to parse command source %q for plan %q: %w", src.Command, plan.Id, splitErr)
+		}
+		summary, err = r.repo.BackupFromCommand(ctx, command, src.Filename, progressCallback, opts...)
+	} else if src := plan.GetDatabaseSource(); src != nil {
+		summary, err = r.backupDatabases(ctx, src, progressCallback, opts)
+	} else {
+		summary, err = r.repo.Backup(ctx, plan.Paths, progressCallback, opts...)
+	}
 	if err != nil {
 		return summary, fmt.Errorf("failed to backup: %w", err)
 	}
@@ -183,6 +275,35 @@ func (r *RepoOrchestrator) Backup(ctx context.Context, plan *v1.Plan, progressCa
 	return summary, nil
 }
 
+// backupDatabases backs up a dump of each database in its own snapshot tagged with the database's name. The returned
+// summary totals the backups of every database and has the ID of the last snapshot.
+func (r *RepoOrchestrator) backupDatabases(ctx context.Context, src *v1.DatabaseSource, progressCallback func(event *restic.BackupProgressEntry), opts []restic.GenericOption) (*restic.BackupProgressEntry, error) {
+	dumps, err := dbdump.Dumps(src)
+	if err != nil {
+		return nil, err
+	}
+
+	total := &restic.BackupProgressEntry{MessageType: "summary"}
+	for _, d := range dumps {
+		dumpOpts := append(slices.Clone(opts), restic.WithFlags("--tag", d.Tag), restic.WithEnv(d.Env...))
+		summary, err := r.repo.BackupFromCommand(ctx, d.Command, d.Filename, progressCallback, dumpOpts...)
+		if err != nil {
+			return total, fmt.Errorf("database %q: %w", d.Database, err)
+		}
+		total.FilesNew += summary.FilesNew
+		total.FilesChanged += summary.FilesChanged
+		total.FilesUnmodified += summary.FilesUnmodified
+		total.DataBlobs += summary.DataBlobs
+		total.TreeBlobs += summary.TreeBlobs
+		total.DataAdded += summary.DataAdded
+		total.TotalFilesProcessed += summary.TotalFilesProcessed
+		total.TotalBytesProcessed += summary.TotalBytesProcessed
+		total.TotalDuration += summary.TotalDuration
+		total.SnapshotId = summary.SnapshotId
+	}
+	return total, nil
+}
+
 func (r *RepoOrchestrator) ListSnapshotFiles(ctx context.Context, snapshotId string, path string) ([]*v1.LsEntry, error) {
 	ctx, flush := forwardResticLogs(ctx)
 	defer flush()
@@ -200,6 +321,189 @@ func (r *RepoOrchestrator) ListSnapshotFiles(ctx context.Context, snapshotId str
 	return lsEnts, nil
 }
 
+// errResultLimit stops a streamed restic command once the requested number of results has been read.
+var errResultLimit = errors.New("result limit reached")
+
+// maxLsArgBytes bounds the length of the paths passed to a single restic ls command, more paths are split across
+// several commands.
+const maxLsArgBytes = 64 * 1024
+
+// DiffSnapshots returns up to maxChanges paths that changed between two snapshots with the size of each changed file
+// in both snapshots. restic diff is stopped at the limit, the diff's statistics are only set if it wasn't truncated.
+func (r *RepoOrchestrator) DiffSnapshots(ctx context.Context, fromSnapshotId string, toSnapshotId string, maxChanges int) (*v1.DiffSnapshotsResponse, error) {
+	ctx, flush := forwardResticLogs(ctx)
+	defer flush()
+
+	resp := &v1.DiffSnapshotsResponse{}
+	stats, err := r.repo.Diff(ctx, fromSnapshotId, toSnapshotId, func(c *restic.DiffChange) error {
+		if len(resp.Changes) >= maxChanges {
+			resp.Truncated = true
+			return errResultLimit
+		}
+		resp.Changes = append(resp.Changes, &v1.DiffSnapshotsResponse_Change{
+			Path: c.Path,
+			Type: diffChangeType(c.Modifier),
+		})
+		return nil
+	})
+	if err != nil && !errors.Is(err, errResultLimit) {
+		return nil, fmt.Errorf("diff snapshots: %w", err)
+	}
+	if stats != nil {
+		resp.FilesAdded = int64(stats.Added.Files)
+		resp.FilesRemoved = int64(stats.Removed.Files)
+		resp.BytesAdded = stats.Added.Bytes
+		resp.BytesRemoved = stats.Removed.Bytes
+	}
+
+	// restic diff doesn't report sizes, they're looked up by listing just the changed files in each snapshot.
+	before := make(map[string]*v1.DiffSnapshotsResponse_Change)
+	after := make(map[string]*v1.DiffSnapshotsResponse_Change)
+	for _, change := range resp.Changes {
+		if strings.HasSuffix(change.Path, "/") {
+			continue
+		}
+		if change.Type != v1.DiffSnapshotsResponse_CHANGE_TYPE_ADDED {
+			before[change.Path] = change
+		}
+		if change.Type != v1.DiffSnapshotsResponse_CHANGE_TYPE_REMOVED {
+			after[change.Path] = change
+		}
+	}
+	if err := r.listPaths(ctx, fromSnapshotId, slices.Sorted(maps.Keys(before)), func(entry *restic.LsEntry) error {
+		if change, ok := before[entry.Path]; ok && entry.Type == "file" {
+			change.SizeBefore = entry.Size
+		}
+		return nil
+	}); err != nil {
+		return nil, fmt.Errorf("list changed files of snapshot %v: %w", fromSnapshotId, err)
+	}
+	if err := r.listPaths(ctx, toSnapshotId, slices.Sorted(maps.Keys(after)), func(entry *restic.LsEntry) error {
+		if change, ok := after[entry.Path]; ok && entry.Type == "file" {
+			change.SizeAfter = entry.Size
+		}
+		return nil
+	}); err != nil {
+		return nil, fmt.Errorf("list changed files of snapshot %v: %w", toSnapshotId, err)
+	}
+	return resp, nil
+}
+
+// listPaths calls fn for the entries of the given paths in a snapshot, the paths are listed in batches of at most
+// maxLsArgBytes. Nothing is listed if paths is empty.
+func (r *RepoOrchestrator) listPaths(ctx context.Context, snapshotId string, paths []string, fn func(entry *restic.LsEntry) error) error {
+	for len(paths) > 0 {
+		n, size := 1, len(paths[0])
+		for n < len(paths) && size+len(paths[n]) <= maxLsArgBytes {
+			size += len(paths[n])
+			n++
+		}
+		if err := r.repo.ListFiles(ctx, snapshotId, paths[:n], fn); err != nil {
+			return err
+		}
+		paths = paths[n:]
+	}
+	return nil
+}
+
+// maxFindSnapshots bounds the snapshots searched by a single restic find command, more snapshots are searched by
+// several commands in order.
+const maxFindSnapshots = 100
+
+// FindFiles returns up to maxMatches files matching pattern in the given snapshots, grouped by snapshot. Snapshots are
+// searched in the given order and the search stops at the limit, truncated is true if matches were left out.
+func (r *RepoOrchestrator) FindFiles(ctx context.Context, snapshotIds []string, pattern string, ignoreCase bool, maxMatches int) (results []*restic.FindResult, truncated bool, err error) {
+	ctx, flush := forwardResticLogs(ctx)
+	defer flush()
+
+	remaining := maxMatches
+	for batch := range slices.Chunk(snapshotIds, maxFindSnapshots) {
+		if remaining == 0 {
+			truncated = true // the remaining snapshots may have matches.
+			break
+		}
+		var flags []string
+		for _, id := range batch {
+			flags = append(flags, "--snapshot", id)
+		}
+		if ignoreCase {
+			flags = append(flags, "--ignore-case")
+		}
+
+		err := r.repo.Find(ctx, []string{pattern}, remaining, func(result *restic.FindResult) error {
+			if remaining == 0 {
+				truncated = true
+				return errResultLimit
+			}
+			if len(result.Matches) > remaining {
+				result.Matches = result.Matches[:remaining]
+			}
+			if len(result.Matches) < result.Hits {
+				truncated = true
+			}
+			remaining -= len(result.Matches)
+			results = append(results, result)
+			return nil
+		}, restic.WithFlags(flags...))
+		if errors.Is(err, errResultLimit) {
+			break
+		} else if err != nil {
+			return nil, false, fmt.Errorf("find files: %w", err)
+		}
+	}
+	return results, truncated, nil
+}
+
+// StatSnapshotPath returns the entry for a path in a snapshot or ErrPathNotFound.
+func (r *RepoOrchestrator) StatSnapshotPath(ctx context.Context, snapshotId string, p string) (*restic.LsEntry, error) {
+	ctx, flush := forwardResticLogs(ctx)
+	defer flush()
+
+	_, entries, err := r.repo.ListDirectory(ctx, snapshotId, p)
+	if err != nil {
+		return nil, fmt.Errorf("list snapshot path %q: %w", p, err)
+	}
+	for _, entry := range entries {
+		if entry.Path == p {
+			return entry, nil
+		}
+	}
+	return nil, ErrPathNotFound
+}
+
+// DumpSnapshotPath writes a file in a snapshot to w. Directories are written as an archive in the format of restic
+// dump's --archive flag ("tar" or "zip"), the archive is ignored for files.
+func (r *RepoOrchestrator) DumpSnapshotPath(ctx context.Context, snapshotId string, p string, archive string, w io.Writer) error {
+	ctx, flush := forwardResticLogs(ctx)
+	defer flush()
+
+	var opts []restic.GenericOption
+	if archive != "" {
+		opts = append(opts, restic.WithFlags("--archive", archive))
+	}
+	if err := r.repo.Dump(ctx, snapshotId, p, w, opts...); err != nil {
+		return fmt.Errorf("dump snapshot path %q: %w", p, err)
+	}
+	return nil
+}
+
+func diffChangeType(modifier string) v1.DiffSnapshotsResponse_ChangeType {
+	switch {
+	case modifier == "+":
+		return v1.DiffSnapshotsResponse_CHANGE_TYPE_ADDED
+	case modifier == "-":
+		return v1.DiffSnapshotsResponse_CHANGE_TYPE_REMOVED
+	case strings.Contains(modifier, "T"):
+		return v1.DiffSnapshotsResponse_CHANGE_TYPE_FILE_TYPE
+	case strings.Contains(modifier, "M"):
+		return v1.DiffSnapshotsResponse_CHANGE_TYPE_MODIFIED
+	case strings.Contains(modifier, "U"):
+		return v1.DiffSnapshotsResponse_CHANGE_TYPE_METADATA
+	default:
+		return v1.DiffSnapshotsResponse_CHANGE_TYPE_UNKNOWN
+	}
+}
+
 func (r *RepoOrchestrator) Forget(ctx context.Context, plan *v1.Plan, tags []string) ([]*v1.ResticSnapshot, error) {
 	r.mu.Lock()
 	defer r.mu.Unlock()
@@ -214,7 +518,7 @@ func (r *RepoOrchestrator) Forget(ctx context.Context, plan *v1.Plan, tags []str
 	result, err := r.repo.Forget(
 		ctx, protoutil.RetentionPolicyFromProto(plan.Retention),
 		restic.WithFlags("--tag", strings.Join(tags, ",")),
-		restic.WithFlags("--group-by", ""),
+		restic.WithFlags("--group-by", forgetGroupBy(plan)),
 	)
 	if err != nil {
 		return nil, fmt.Errorf("get snapshots for repo %v: %w", r.repoConfig.Id, err)
@@ -234,6 +538,55 @@ func (r *RepoOrchestrator) Forget(ctx context.Context, plan *v1.Plan, tags []str
 	return forgotten, nil
 }
 
+// forgetGroupBy returns how restic groups a plan's snapshots when applying its retention policy. The snapshots of a
+// plan form one history, except for database plans where each database's snapshots, distinguished by their database
+// tag, are a separate history that must keep its own snapshots.
+func forgetGroupBy(plan *v1.Plan) string {
+	if plan.GetDatabaseSource() != nil {
+		return "tags"
+	}
+	return ""
+}
+
+// PreviewForget runs forget as a dry run with the given policy against the snapshots of a plan created by this
+// instance, nothing is removed. It doesn't lock the repo so it can run alongside other operations.
+func (r *RepoOrchestrator) PreviewForget(ctx context.Context, planID string, policy *v1.RetentionPolicy) (*restic.ForgetResult, error) {
+	ctx, flush := forwardResticLogs(ctx)
+	defer flush()
+
+	tags := strings.Join([]string{TagForPlan(planID), TagForInstance(r.config.Instance)}, ",")
+
+	resticPolicy := protoutil.RetentionPolicyFromProto(policy)
+	if resticPolicy == nil {
+		// keep all, there's nothing for restic to evaluate.
+		snapshots, err := r.repo.Snapshots(ctx, restic.WithFlags("--tag", tags))
+		if err != nil {
+			return nil, fmt.Errorf("get snapshots for repo %v: %w", r.repoConfig.Id, err)
+		}
+		result := &restic.ForgetResult{}
+		for _, snapshot := range snapshots {
+			result.Keep = append(result.Keep, *snapshot)
+			result.Reasons = append(result.Reasons, restic.KeepReason{Snapshot: *snapshot, Matches: []string{"keep all"}})
+		}
+		return result, nil
+	}
+
+	var plan *v1.Plan
+	if idx := slices.IndexFunc(r.config.GetPlans(), func(p *v1.Plan) bool { return p.Id == planID }); idx != -1 {
+		plan = r.config.Plans[idx]
+	}
+
+	result, err := r.repo.Forget(ctx, resticPolicy,
+		restic.WithFlags("--tag", tags),
+		restic.WithFlags("--group-by", forgetGroupBy(plan)),
+		restic.WithFlags("--dry-run", "--no-lock"),
+	)
+	if err != nil {
+		return nil, fmt.Errorf("preview forget for repo %v: %w", r.repoConfig.Id, err)
+	}
+	return result, nil
+}
+
 func (r *RepoOrchestrator) ForgetSnapshot(ctx context.Context, snapshotId string) error {
 	r.mu.Lock()
 	defer r.mu.Unlock()
@@ -298,6 +651,131 @@ func (r *RepoOrchestrator) Check(ctx context.Context, output io.Writer) error {
 	return nil
 }
 
+// copySourceEnvVars maps the restic env vars that configure a repo to the vars restic copy reads the source repo's
+// configuration from.
+var copySourceEnvVars = map[string]string{
+	"RESTIC_REPOSITORY":       "RESTIC_FROM_REPOSITORY",
+	"RESTIC_REPOSITORY_FILE":  "RESTIC_FROM_REPOSITORY_FILE",
+	"RESTIC_PASSWORD":         "RESTIC_FROM_PASSWORD",
+	"RESTIC_PASSWORD_FILE":    "RESTIC_FROM_PASSWORD_FILE",
+	"RESTIC_PASSWORD_COMMAND": "RESTIC_FROM_PASSWORD_COMMAND",
+	"RESTIC_KEY_HINT":         "RESTIC_FROM_KEY_HINT",
+}
+
+// copySourceFlags maps the restic flags that configure the source repo of restic copy to their --from-* equivalents.
+var copySourceFlags = map[string]string{
+	"--repository-file":      "--from-repository-file",
+	"--password-file":        "--from-password-file",
+	"--password-command":     "--from-password-command",
+	"--key-hint":             "--from-key-hint",
+	"--insecure-no-password": "--from-insecure-no-password",
+}
+
+// sourceFlagsForCopy returns the source repo's flags for restic copy run with dest's flags. Flags that configure the
+// repo itself are passed as their --from-* equivalents, other flags are global options that restic applies to both
+// repos e.g. -o extended options and are passed as is unless dest already sets them.
+func sourceFlagsForCopy(src, dest *v1.Repo) ([]string, error) {
+	var args []string
+	for _, f := range src.GetFlags() {
+		if slices.Contains(dest.GetFlags(), f) {
+			continue
+		}
+		split, err := shlex.Split(ExpandEnv(f))
+		if err != nil {
+			return nil, fmt.Errorf("parse flag %q for repo %q: %w", f, src.Id, err)
+		}
+		for i, arg := range split {
+			name, value, hasValue := strings.Cut(arg, "=")
+			if fromName, ok := copySourceFlags[name]; ok {
+				split[i] = fromName
+				if hasValue {
+					split[i] += "=" + value
+				}
+			}
+		}
+		args = append(args, split...)
+	}
+	return args, nil
+}
+
+// CopyTo copies the snapshots created by this instance to the dest repo with restic copy, snapshots that already exist
+// in dest are skipped. If planIDs is non-empty only the snapshots of those plans are copied.
+//
+// restic runs with dest's environment, this repo's env vars are added to it so that both backends can be reached. The
+// vars that configure this repo itself are passed as their RESTIC_FROM_* equivalents. Any other variable set to
+// different values by both repos can't be passed to restic and is an error. This repo's flags are passed the same way,
+// see sourceFlagsForCopy.
+func (r *RepoOrchestrator) CopyTo(ctx context.Context, dest *RepoOrchestrator, planIDs []string, output io.Writer) error {
+	if dest == r {
+		return errors.New("can't copy a repo to itself")
+	}
+	// both repos are locked in the order of their IDs so that copies in opposite directions can't deadlock.
+	first, second := r, dest
+	if dest.repoConfig.Id < r.repoConfig.Id {
+		first, second = dest, r
+	}
+	first.mu.Lock()
+	defer first.mu.Unlock()
+	second.mu.Lock()
+	defer second.mu.Unlock()
+	ctx, flush := forwardResticLogs(ctx)
+	defer flush()
+
+	var opts []restic.GenericOption
+	password, err := resolvePassword(ctx, r.repoConfig)
+	if err != nil {
+		return err
+	} else if password != "" {
+		opts = append(opts, restic.WithEnv("RESTIC_FROM_PASSWORD="+password))
+	}
+
+	srcFlags, err := sourceFlagsForCopy(r.repoConfig, dest.repoConfig)
+	if err != nil {
+		return err
+	}
+	opts = append(opts, restic.WithFlags(srcFlags...))
+
+	srcEnv, err := resolveEnv(ctx, r.repoConfig)
+	if err != nil {
+		return err
+	}
+	// dest's env vars are already set by dest.repo.
+	destEnv, err := resolveEnv(ctx, dest.repoConfig)
+	if err != nil {
+		return err
+	}
+	destValues := make(map[string]string)
+	for _, e := range destEnv {
+		key, value, _ := strings.Cut(e, "=")
+		destValues[key] = value
+	}
+	for _, e := range srcEnv {
+		key, value, _ := strings.Cut(e, "=")
+		if fromKey, ok := copySourceEnvVars[key]; ok {
+			// the source repo's own restic vars would apply to the destination, restic reads the source's from these.
+			opts = append(opts, restic.WithEnv(fromKey+"="+value))
+			continue
+		}
+		if destValue, ok := destValues[key]; ok && destValue != value {
+			return fmt.Errorf("env var %q is set to different values in repos %q and %q, restic copy shares the environment between both repos", key, r.repoConfig.Id, dest.repoConfig.Id)
+		}
+		opts = append(opts, restic.WithEnv(e))
+	}
+
+	if len(planIDs) == 0 {
+		opts = append(opts, restic.WithTags(TagForInstance(r.config.Instance)))
+	}
+	for _, planID := range planIDs {
+		opts = append(opts, restic.WithTags(TagForPlan(planID)+","+TagForInstance(r.config.Instance)))
+	}
+
+	r.logger(ctx).Debug("copying snapshots", zap.String("to", dest.repoConfig.Id), zap.Strings("plans", planIDs))
+	if err := dest.repo.Copy(ctx, r.repoConfig.GetUri(), output, opts...); err != nil {
+		return fmt.Errorf("copy repo %v to %v: %w", r.repoConfig.Id, dest.repoConfig.Id, err)
+	}
+	return nil
+}
+
 func (r *RepoOrchestrator) Restore(ctx context.Context, snapshotId string, snapshotPath string, target string, progressCallback func(event *v1.RestoreProgressEntry)) (*v1.RestoreProgressEntry, error) {
 	r.mu.Lock()
 	defer r.mu.Unlock()
@@ -336,6 +814,35 @@ func (r *RepoOrchestrator) Restore(ctx context.Context, snapshotId string, snaps
 	return protoutil.RestoreProgressEntryToProto(summary), nil
 }
 
+// RestoreDatabase restores the database dump at snapshotPath into database by piping it into the database's client.
+func (r *RepoOrchestrator) RestoreDatabase(ctx context.Context, snapshotId string, snapshotPath string, src *v1.DatabaseSource, database string) error {
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	ctx, flush := forwardResticLogs(ctx)
+	defer flush()
+
+	r.logger(ctx).Debug("restore database", zap.String("snapshot", snapshotId), zap.String("path", snapshotPath), zap.String("database", database))
+
+	reader, writer := io.Pipe()
+	dumpErr := make(chan error, 1)
+	go func() {
+		err := r.repo.Dump(ctx, snapshotId, snapshotPath, writer)
+		writer.CloseWithError(err)
+		dumpErr <- err
+	}()
+
+	var logWriter io.Writer = io.Discard
+	if w := logging.WriterFromContext(ctx); w != nil {
+		logWriter = w
+	}
+	restoreErr := dbdump.Restore(ctx, src, snapshotPath, database, reader, logWriter)
+	reader.Close() // stops the dump if the client exited early.
+	if err := <-dumpErr; err != nil && restoreErr == nil {
+		return fmt.Errorf("dump %q from snapshot %q: %w", snapshotPath, snapshotId, err)
+	}
+	return restoreErr
+}
+
 // UnlockIfAutoEnabled unlocks the repo if the auto unlock feature is enabled.
 func (r *RepoOrchestrator) UnlockIfAutoEnabled(ctx context.Context) error {
 	if !r.repoConfig.AutoUnlock {
@@ -14,6 +14,8 @@ import (
 	"testing"
 
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/dbdump"
+	"github.com/garethgeorge/backrest/pkg/restic"
 	"github.com/garethgeorge/backrest/test/helpers"
 	test "github.com/garethgeorge/backrest/test/helpers"
 	"golang.org/x/sync/errgroup"
@@ -261,6 +263,86 @@ func TestSnapshotParenting(t *testing.T) {
 	})
 }
 
+func TestForgetDatabasePlan(t *testing.T) {
+	t.Parallel()
+
+	testData := test.CreateTestData(t)
+	r := &v1.Repo{
+		Id:       "test",
+		Uri:      t.TempDir(),
+		Password: "test",
+		Flags:    []string{"--no-cache"},
+	}
+	plan := &v1.Plan{
+		Id:   "db",
+		Repo: "test",
+		DatabaseSource: &v1.DatabaseSource{
+			Engine:    v1.DatabaseSource_ENGINE_POSTGRES,
+			Databases: []string{"app", "billing"},
+		},
+		Retention: &v1.RetentionPolicy{
+			Policy: &v1.RetentionPolicy_PolicyKeepLastN{PolicyKeepLastN: 1},
+		},
+	}
+	cfg := &v1.Config{Instance: "test", Plans: []*v1.Plan{plan}}
+	orchestrator := initRepoHelper(t, cfg, r)
+
+	// two snapshots of each database, as backupDatabases would create them.
+	ids := map[string][]string{}
+	for i := 0; i < 2; i++ {
+		for _, db := range plan.DatabaseSource.Databases {
+			summary, err := orchestrator.repo.Backup(context.Background(), []string{testData}, nil, restic.WithFlags(
+				"--tag", TagForPlan(plan.Id),
+				"--tag", TagForInstance(cfg.Instance),
+				"--tag", dbdump.TagForDatabase(db),
+			))
+			if err != nil {
+				t.Fatalf("backup of database %q: %v", db, err)
+			}
+			ids[db] = append(ids[db], summary.SnapshotId)
+		}
+	}
+
+	preview, err := orchestrator.PreviewForget(context.Background(), plan.Id, plan.Retention)
+	if err != nil {
+		t.Fatalf("PreviewForget() error = %v", err)
+	}
+	if len(preview.Keep) != 2 || len(preview.Remove) != 2 {
+		t.Errorf("PreviewForget() keeps %d and removes %d snapshots, want 2 and 2", len(preview.Keep), len(preview.Remove))
+	}
+
+	forgotten, err := orchestrator.Forget(context.Background(), plan, []string{TagForPlan(plan.Id), TagForInstance(cfg.Instance)})
+	if err != nil {
+		t.Fatalf("Forget() error = %v", err)
+	}
+	var forgottenIds []string
+	for _, s := range forgotten {
+		forgottenIds = append(forgottenIds, s.Id)
+	}
+	slices.Sort(forgottenIds)
+	want := []string{ids["app"][0], ids["billing"][0]}
+	slices.Sort(want)
+	if !slices.Equal(forgottenIds, want) {
+		t.Errorf("Forget() removed %v, want the older snapshot of each database %v", forgottenIds, want)
+	}
+
+	// the latest snapshot of each database is kept.
+	snapshots, err := orchestrator.SnapshotsForPlan(context.Background(), plan)
+	if err != nil {
+		t.Fatalf("SnapshotsForPlan() error = %v", err)
+	}
+	var kept []string
+	for _, s := range snapshots {
+		kept = append(kept, s.Id)
+	}
+	slices.Sort(kept)
+	want = []string{ids["app"][1], ids["billing"][1]}
+	slices.Sort(want)
+	if !slices.Equal(kept, want) {
+		t.Errorf("snapshots after Forget() = %v, want %v", kept, want)
+	}
+}
+
 func TestEnvVarPropagation(t *testing.T) {
 	repo := t.TempDir()
 
@@ -350,6 +432,124 @@ func TestCheck(t *testing.T) {
 	}
 }
 
+func TestFindFilesLimit(t *testing.T) {
+	t.Parallel()
+
+	testData := test.CreateTestData(t)
+	orchestrator := initRepoHelper(t, configForTest, &v1.Repo{
+		Id:       "test",
+		Uri:      t.TempDir(),
+		Password: "test",
+		Flags:    []string{"--no-cache"},
+	})
+
+	var ids []string
+	for i := 0; i < 2; i++ {
+		summary, err := orchestrator.Backup(context.Background(), &v1.Plan{Id: "test", Repo: "test", Paths: []string{testData}}, nil)
+		if err != nil {
+			t.Fatalf("backup error: %v", err)
+		}
+		ids = append(ids, summary.SnapshotId)
+	}
+
+	// "file *" matches 10 files in each snapshot.
+	count := func(results []*restic.FindResult) int {
+		n := 0
+		for _, result := range results {
+			n += len(result.Matches)
+		}
+		return n
+	}
+	results, truncated, err := orchestrator.FindFiles(context.Background(), ids, "file *", false, 100)
+	if err != nil {
+		t.Fatalf("find files error: %v", err)
+	}
+	if count(results) != 20 || truncated {
+		t.Errorf("got %d matches (truncated: %v), want 20 and no truncation", count(results), truncated)
+	}
+
+	results, truncated, err = orchestrator.FindFiles(context.Background(), ids, "file *", false, 15)
+	if err != nil {
+		t.Fatalf("find files error: %v", err)
+	}
+	if count(results) != 15 || !truncated {
+		t.Errorf("got %d matches (truncated: %v), want 15 and truncation", count(results), truncated)
+	}
+}
+
+func TestCopyTo(t *testing.T) {
+	t.Parallel()
+
+	testData := test.CreateTestData(t)
+	src := initRepoHelper(t, configForTest, &v1.Repo{
+		Id:       "src",
+		Uri:      t.TempDir(),
+		Password: "test",
+		Flags:    []string{"--no-cache"},
+	})
+	dest := initRepoHelper(t, configForTest, &v1.Repo{
+		Id:       "dest",
+		Uri:      t.TempDir(),
+		Password: "other",
+		Flags:    []string{"--no-cache"},
+	})
+
+	for _, planID := range []string{"copied", "skipped"} {
+		if _, err := src.Backup(context.Background(), &v1.Plan{Id: planID, Repo: "src", Paths: []string{testData}}, nil); err != nil {
+			t.Fatalf("backup error: %v", err)
+		}
+	}
+
+	// copying twice must not duplicate snapshots.
+	for i := 0; i < 2; i++ {
+		if err := src.CopyTo(context.Background(), dest, []string{"copied"}, nil); err != nil {
+			t.Fatalf("copy error: %v", err)
+		}
+	}
+
+	snapshots, err := dest.SnapshotsForPlan(context.Background(), &v1.Plan{Id: "copied"})
+	if err != nil {
+		t.Fatalf("snapshots error: %v", err)
+	}
+	if len(snapshots) != 1 {
+		t.Errorf("got %d snapshots of the copied plan in the destination, want 1", len(snapshots))
+	}
+	if snapshots, err := dest.SnapshotsForPlan(context.Background(), &v1.Plan{Id: "skipped"}); err != nil || len(snapshots) != 0 {
+		t.Errorf("got %d snapshots of the skipped plan in the destination (err: %v), want 0", len(snapshots), err)
+	}
+
+	// the source's restic vars are passed as RESTIC_FROM_* vars and don't override dest's password.
+	passwordFile := path.Join(t.TempDir(), "password")
+	if err := os.WriteFile(passwordFile, []byte("test"), 0600); err != nil {
+		t.Fatalf("write password file: %v", err)
+	}
+	src.repoConfig.Password = ""
+	src.repoConfig.Env = []string{"RESTIC_PASSWORD_FILE=" + passwordFile}
+	if err := src.CopyTo(context.Background(), dest, nil, nil); err != nil {
+		t.Fatalf("copy with a source password file error: %v", err)
+	}
+	if snapshots, err := dest.SnapshotsForPlan(context.Background(), &v1.Plan{Id: "skipped"}); err != nil || len(snapshots) != 1 {
+		t.Errorf("got %d snapshots of the skipped plan in the destination after copying all plans (err: %v), want 1", len(snapshots), err)
+	}
+
+	// the source's flags that configure the repo are passed as --from-* flags.
+	src.repoConfig.Env = nil
+	src.repoConfig.Flags = []string{"--no-cache", "--password-file " + passwordFile}
+	if err := src.CopyTo(context.Background(), dest, nil, nil); err != nil {
+		t.Fatalf("copy with a source password file flag error: %v", err)
+	}
+	if got, err := sourceFlagsForCopy(src.repoConfig, dest.repoConfig); err != nil || !slices.Equal(got, []string{"--from-password-file", passwordFile}) {
+		t.Errorf("sourceFlagsForCopy() = %q, %v, want the password file as a --from-password-file flag", got, err)
+	}
+
+	// the repos' env vars are shared, conflicting values are an error.
+	src.repoConfig.Env = []string{"AWS_ACCESS_KEY_ID=a"}
+	dest.repoConfig.Env = []string{"AWS_ACCESS_KEY_ID=b"}
+	if err := src.CopyTo(context.Background(), dest, nil, nil); err == nil || !strings.Contains(err.Error(), "AWS_ACCESS_KEY_ID") {
+		t.Errorf("expected error about conflicting env vars, got: %v", err)
+	}
+}
+
 func initRepoHelper(t *testing.T, config *v1.Config, repo *v1.Repo) *RepoOrchestrator {
 	orchestrator, err := NewRepoOrchestrator(config, repo, helpers.ResticBinary(t))
 	if err != nil {
@@ -0,0 +1,65 @@
+package repo
+
+import (
+	"bytes"
+	"context"
+	"errors"
+	"fmt"
+	"os"
+	"os/exec"
+	"strings"
+	"time"
+
+	"github.com/google/shlex"
+)
+
+const secretCommandTimeout = 30 * time.Second
+
+// secretReferencePrefix opts a value into being resolved as a secret reference, values without it are always literal.
+const secretReferencePrefix = "secret:"
+
+// ResolveSecret resolves a secret reference to its value. Supported references are
+//   - secret:file:/path/to/secret reads the secret from a file e.g. a docker or kubernetes secret.
+//   - secret:env:NAME reads the secret from an environment variable of the backrest process.
+//   - secret:command:cmd args... runs a command e.g. a password manager CLI and uses its output.
+//
+// Trailing newlines are trimmed. Values that are not references are returned unchanged.
+func ResolveSecret(ctx context.Context, value string) (string, error) {
+	ref, ok := strings.CutPrefix(value, secretReferencePrefix)
+	if !ok {
+		return value, nil
+	}
+	if path, ok := strings.CutPrefix(ref, "file:"); ok {
+		data, err := os.ReadFile(path)
+		if err != nil {
+			return "", fmt.Errorf("read secret file: %w", err)
+		}
+		return strings.TrimRight(string(data), "\r\n"), nil
+	} else if name, ok := strings.CutPrefix(ref, "env:"); ok {
+		secret, ok := os.LookupEnv(name)
+		if !ok {
+			return "", fmt.Errorf("secret env var %q is not set", name)
+		}
+		return secret, nil
+	} else if command, ok := strings.CutPrefix(ref, "command:"); ok {
+		args, err := shlex.Split(command)
+		if err != nil {
+			return "", fmt.Errorf("parse secret command: %w", err)
+		}
+		if len(args) == 0 {
+			return "", errors.New("secret command is empty")
+		}
+
+		ctx, cancel := context.WithTimeout(ctx, secretCommandTimeout)
+		defer cancel()
+		var stderr bytes.Buffer
+		cmd := exec.CommandContext(ctx, args[0], args[1:]...)
+		cmd.Stderr = &stderr
+		output, err := cmd.Output()
+		if err != nil {
+			return "", fmt.Errorf("run secret command %q: %w: %s", args[0], err, strings.TrimSpace(stderr.String()))
+		}
+		return strings.TrimRight(string(output), "\r\n"), nil
+	}
+	return "", errors.New("unknown secret reference, expected secret:file:, secret:env: or secret:command:")
+}
@@ -0,0 +1,88 @@
+package repo
+
+import (
+	"context"
+	"os"
+	"path/filepath"
+	"runtime"
+	"testing"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/test/helpers"
+)
+
+func TestResolveSecret(t *testing.T) {
+	secretFile := filepath.Join(t.TempDir(), "secret")
+	if err := os.WriteFile(secretFile, []byte("from-file\n"), 0600); err != nil {
+		t.Fatalf("write secret file: %v", err)
+	}
+	t.Setenv("BACKREST_TEST_SECRET", "from-env")
+
+	tests := []struct {
+		name    string
+		value   string
+		want    string
+		wantErr bool
+		unix    bool
+	}{
+		{name: "plain value", value: "plain", want: "plain"},
+		{name: "value without the secret prefix", value: "env:BACKREST_TEST_SECRET", want: "env:BACKREST_TEST_SECRET"},
+		{name: "file", value: "secret:file:" + secretFile, want: "from-file"},
+		{name: "missing file", value: "secret:file:" + secretFile + ".missing", wantErr: true},
+		{name: "env", value: "secret:env:BACKREST_TEST_SECRET", want: "from-env"},
+		{name: "unset env", value: "secret:env:BACKREST_TEST_SECRET_UNSET", wantErr: true},
+		{name: "command", value: "secret:command:echo 'from command'", want: "from command", unix: true},
+		{name: "failing command", value: "secret:command:false", wantErr: true, unix: true},
+		{name: "empty command", value: "secret:command:", wantErr: true},
+		{name: "unknown reference", value: "secret:vault:x", wantErr: true},
+	}
+
+	for _, tc := range tests {
+		t.Run(tc.name, func(t *testing.T) {
+			if tc.unix && runtime.GOOS == "windows" {
+				t.Skip("test uses unix commands")
+			}
+			got, err := ResolveSecret(context.Background(), tc.value)
+			if (err != nil) != tc.wantErr {
+				t.Fatalf("ResolveSecret() error = %v, wantErr %v", err, tc.wantErr)
+			}
+			if got != tc.want {
+				t.Errorf("ResolveSecret() = %q, want %q", got, tc.want)
+			}
+		})
+	}
+}
+
+func TestSecretReferencePassword(t *testing.T) {
+	t.Setenv("BACKREST_TEST_REPO_PASSWORD", "test")
+
+	r := &v1.Repo{
+		Id:       "test",
+		Uri:      t.TempDir(),
+		Password: "secret:env:BACKREST_TEST_REPO_PASSWORD",
+		Flags:    []string{"--no-cache"},
+	}
+
+	orchestrator, err := NewRepoOrchestrator(configForTest, r, helpers.ResticBinary(t))
+	if err != nil {
+		t.Fatalf("failed to create repo orchestrator: %v", err)
+	}
+	if err := orchestrator.Init(context.Background()); err != nil {
+		t.Fatalf("init error: %v", err)
+	}
+
+	// the reference is resolved by each restic command, not when the orchestrator is created.
+	t.Setenv("BACKREST_TEST_REPO_PASSWORD", "changed")
+	if _, err := orchestrator.Snapshots(context.Background()); err == nil {
+		t.Errorf("Snapshots() after the referenced password changed succeeded, want error")
+	}
+
+	r.Password = "secret:env:BACKREST_TEST_REPO_PASSWORD_UNSET"
+	orchestrator, err = NewRepoOrchestrator(configForTest, r, helpers.ResticBinary(t))
+	if err != nil {
+		t.Fatalf("failed to create repo orchestrator: %v", err)
+	}
+	if _, err := orchestrator.Snapshots(context.Background()); err == nil {
+		t.Errorf("Snapshots() with an unresolvable password succeeded, want error")
+	}
+}
@@ -0,0 +1,20 @@
+package repo
+
+import (
+	"context"
+
+	"github.com/garethgeorge/backrest/internal/tracing"
+	"go.opentelemetry.io/otel/attribute"
+)
+
+// traceCommand records a trace span for a restic command. Only the restic subcommand and exit code are recorded,
+// arguments and environment may contain repository credentials.
+func traceCommand(ctx context.Context, subcommand string) func(exitCode int, err error) {
+	_, span := tracing.Start(ctx, "restic "+subcommand, attribute.String("restic.command", subcommand))
+	return func(exitCode int, err error) {
+		if exitCode >= 0 {
+			span.SetAttributes(attribute.Int("process.exit_code", exitCode))
+		}
+		tracing.End(span, err)
+	}
+}
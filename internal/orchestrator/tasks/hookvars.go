@@ -20,6 +20,7 @@ type HookVars struct {
 	Event         v1.Hook_Condition           // the event that triggered the hook.
 	Repo          *v1.Repo                    // the v1.Repo that triggered the hook.
 	Plan          *v1.Plan                    // the v1.Plan that triggered the hook.
+	CopyToRepo    *v1.Repo                    // the v1.Repo snapshots are copied to, set for copy hooks.
 	SnapshotId    string                      // the snapshot ID that triggered the hook.
 	SnapshotStats *restic.BackupProgressEntry // the summary of the backup operation.
 	CurTime       time.Time                   // the current time as time.Time
@@ -55,6 +56,16 @@ func (v HookVars) EventName(cond v1.Hook_Condition) string {
 		return "prune error"
 	case v1.Hook_CONDITION_PRUNE_SUCCESS:
 		return "prune success"
+	case v1.Hook_CONDITION_COPY_START:
+		return "copy start"
+	case v1.Hook_CONDITION_COPY_ERROR:
+		return "copy error"
+	case v1.Hook_CONDITION_COPY_SUCCESS:
+		return "copy success"
+	case v1.Hook_CONDITION_LOGIN_LOCKOUT:
+		return "login lockout"
+	case v1.Hook_CONDITION_PLAN_STALE:
+		return "plan stale"
 	default:
 		return "unknown"
 	}
@@ -95,7 +106,7 @@ func (v HookVars) FormatSizeBytes(val any) string {
 }
 
 func (v HookVars) IsError(cond v1.Hook_Condition) bool {
-	return cond == v1.Hook_CONDITION_ANY_ERROR || cond == v1.Hook_CONDITION_SNAPSHOT_ERROR
+	return cond == v1.Hook_CONDITION_ANY_ERROR || cond == v1.Hook_CONDITION_SNAPSHOT_ERROR || cond == v1.Hook_CONDITION_LOGIN_LOCKOUT || cond == v1.Hook_CONDITION_PLAN_STALE
 }
 
 func (v HookVars) ShellEscape(s string) string {
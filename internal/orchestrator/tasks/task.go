@@ -180,6 +180,7 @@ func curTimeMillis() int64 {
 type testTaskRunner struct {
 	config *v1.Config // the config to use for the task runner.
 	oplog  *oplog.OpLog
+	hooks  []v1.Hook_Condition // events that hooks were executed for.
 }
 
 var _ TaskRunner = &testTaskRunner{}
@@ -224,7 +225,8 @@ func (t *testTaskRunner) DeleteOperation(id ...int64) error {
 }
 
 func (t *testTaskRunner) ExecuteHooks(ctx context.Context, events []v1.Hook_Condition, vars HookVars) error {
-	panic("not implemented")
+	t.hooks = append(t.hooks, events...)
+	return nil
 }
 
 func (t *testTaskRunner) QueryOperations(q oplog.Query, fn func(*v1.Operation) error) error {
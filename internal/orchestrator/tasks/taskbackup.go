@@ -4,20 +4,29 @@ import (
 	"context"
 	"errors"
 	"fmt"
+	"io"
+	"path/filepath"
 	"slices"
 	"sync"
 	"time"
 
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/config"
+	"github.com/garethgeorge/backrest/internal/env"
+	"github.com/garethgeorge/backrest/internal/fssnapshot"
 	"github.com/garethgeorge/backrest/internal/metric"
 	"github.com/garethgeorge/backrest/internal/oplog"
+	"github.com/garethgeorge/backrest/internal/orchestrator/logging"
 	"github.com/garethgeorge/backrest/internal/protoutil"
 	"github.com/garethgeorge/backrest/pkg/restic"
 	"go.uber.org/zap"
+	"google.golang.org/protobuf/proto"
 )
 
 var maxBackupErrorHistoryLength = 20 // arbitrary limit on the number of file read errors recorded in a backup operation to prevent it from growing too large.
 
+const fsSnapshotTeardownTimeout = 5 * time.Minute
+
 // BackupTask is a scheduled backup operation.
 type BackupTask struct {
 	BaseTask
@@ -136,11 +145,24 @@ func (t *BackupTask) Run(ctx context.Context, st ScheduledTask, runner TaskRunne
 		return fmt.Errorf("snapshot start hook: %w", err)
 	}
 
+	backupPlan, releaseFsSnapshot, err := prepareFilesystemSnapshot(ctx, l, plan)
+	if err != nil {
+		runner.ExecuteHooks(ctx, []v1.Hook_Condition{
+			v1.Hook_CONDITION_SNAPSHOT_ERROR,
+			v1.Hook_CONDITION_ANY_ERROR,
+			v1.Hook_CONDITION_SNAPSHOT_END,
+		}, HookVars{
+			Task:  t.Name(),
+			Error: err.Error(),
+		})
+		return err
+	}
+
 	var sendWg sync.WaitGroup
 	lastSent := time.Now() // debounce progress updates, these can endup being very frequent.
 	var lastFiles []string
 	fileErrorCount := 0
-	summary, err := repo.Backup(ctx, plan, func(entry *restic.BackupProgressEntry) {
+	summary, err := repo.Backup(ctx, backupPlan, func(entry *restic.BackupProgressEntry) {
 		sendWg.Wait()
 		if entry.MessageType == "status" {
 			// prevents flickering output when a status entry omits the CurrentFiles property. Largely cosmetic.
@@ -184,6 +206,7 @@ func (t *BackupTask) Run(ctx context.Context, st ScheduledTask, runner TaskRunne
 		}()
 	})
 	sendWg.Wait()
+	releaseFsSnapshot()
 
 	if summary == nil {
 		summary = &restic.BackupProgressEntry{}
@@ -240,7 +263,8 @@ func (t *BackupTask) Run(ctx context.Context, st ScheduledTask, runner TaskRunne
 
 		// schedule followup tasks if a snapshot was added
 		at := time.Now()
-		if _, ok := plan.Retention.GetPolicy().(*v1.RetentionPolicy_PolicyKeepAll); plan.Retention != nil && !ok {
+		retention := config.RetentionForRepo(plan, t.RepoID())
+		if _, ok := retention.GetPolicy().(*v1.RetentionPolicy_PolicyKeepAll); retention != nil && !ok {
 			if err := runner.ScheduleTask(NewOneoffForgetTask(t.Repo(), t.PlanID(), op.FlowId, at), TaskPriorityForget); err != nil {
 				return fmt.Errorf("failed to schedule forget task: %w", err)
 			}
@@ -252,3 +276,43 @@ func (t *BackupTask) Run(ctx context.Context, st ScheduledTask, runner TaskRunne
 
 	return nil
 }
+
+// prepareFilesystemSnapshot creates the plan's filesystem snapshot, if any, and returns a copy of the plan backing up
+// the same paths, with the same excludes, within the snapshot. The returned release function must be called once the backup finishes, it tears
+// down the snapshot even if the task's context is cancelled.
+func prepareFilesystemSnapshot(ctx context.Context, l *zap.Logger, plan *v1.Plan) (*v1.Plan, func(), error) {
+	if plan.GetFsSnapshot() == nil {
+		return plan, func() {}, nil
+	}
+
+	w := logging.WriterFromContext(ctx)
+	if w == nil {
+		w = io.Discard
+	}
+	run := fssnapshot.ExecRunner(w)
+
+	snap, err := fssnapshot.Create(ctx, plan.FsSnapshot, plan.Id, filepath.Join(env.DataDir(), "fs-snapshots"), run)
+	if err != nil {
+		return nil, nil, fmt.Errorf("create filesystem snapshot: %w", err)
+	}
+	release := func() {
+		releaseCtx, cancel := context.WithTimeout(context.WithoutCancel(ctx), fsSnapshotTeardownTimeout)
+		defer cancel()
+		if err := snap.Release(releaseCtx); err != nil {
+			l.Error("failed to release filesystem snapshot, it will be removed before the next backup", zap.String("plan", plan.Id), zap.Error(err))
+		}
+	}
+
+	paths, err := snap.RewritePaths(plan.Paths)
+	if err != nil {
+		release()
+		return nil, nil, fmt.Errorf("filesystem snapshot: %w", err)
+	}
+	l.Info("backing up from filesystem snapshot", zap.String("plan", plan.Id), zap.String("snapshot_path", snap.Path))
+
+	backupPlan := proto.Clone(plan).(*v1.Plan)
+	backupPlan.Paths = paths
+	backupPlan.Excludes = snap.RewriteExcludes(plan.Excludes)
+	backupPlan.Iexcludes = snap.RewriteExcludes(plan.Iexcludes)
+	return backupPlan, release, nil
+}
@@ -47,6 +47,11 @@ var gcSettings = map[reflect.Type]gcSettingsForType{
 		keepMin: 1,
 		keepMax: 12,
 	},
+	reflect.TypeOf(&v1.Operation_OperationCopy{}): {
+		maxAge:  365 * 24 * time.Hour,
+		keepMin: 1,
+		keepMax: 12,
+	},
 }
 
 var defaultGcSettings = gcSettingsForType{
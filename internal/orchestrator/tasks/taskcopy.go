@@ -0,0 +1,200 @@
+package tasks
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"slices"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/config"
+	"github.com/garethgeorge/backrest/internal/oplog"
+	"github.com/garethgeorge/backrest/internal/protoutil"
+)
+
+// CopyTask copies the snapshots of a repo to the repo named by its copy policy with restic copy.
+type CopyTask struct {
+	BaseTask
+	force  bool
+	didRun bool
+}
+
+func NewCopyTask(repo *v1.Repo, planID string, force bool) Task {
+	return &CopyTask{
+		BaseTask: BaseTask{
+			TaskType:   "copy",
+			TaskName:   fmt.Sprintf("copy repo %q to %q", repo.Id, repo.GetCopyPolicy().GetToRepo()),
+			TaskRepo:   repo,
+			TaskPlanID: planID,
+		},
+		force: force,
+	}
+}
+
+func (t *CopyTask) Next(now time.Time, runner TaskRunner) (ScheduledTask, error) {
+	if t.force {
+		if t.didRun {
+			return NeverScheduledTask, nil
+		}
+		t.didRun = true
+		return ScheduledTask{
+			Task:  t,
+			RunAt: now,
+			Op: &v1.Operation{
+				Op: &v1.Operation_OperationCopy{},
+			},
+		}, nil
+	}
+
+	repo, err := runner.GetRepo(t.RepoID())
+	if err != nil {
+		return ScheduledTask{}, fmt.Errorf("get repo %v: %w", t.RepoID(), err)
+	}
+
+	if repo.CopyPolicy.GetSchedule() == nil || repo.CopyPolicy.GetToRepo() == "" {
+		return NeverScheduledTask, nil
+	}
+
+	var lastRan time.Time
+	var foundBackup bool
+	if err := runner.QueryOperations(oplog.Query{}.
+		SetInstanceID(runner.InstanceID()). // note: this means that copy tasks run by remote instances are ignored.
+		SetRepoGUID(repo.GetGuid()).
+		SetReversed(true), func(op *v1.Operation) error {
+		if op.Status == v1.OperationStatus_STATUS_PENDING || op.Status == v1.OperationStatus_STATUS_SYSTEM_CANCELLED {
+			return nil
+		}
+		if _, ok := op.Op.(*v1.Operation_OperationCopy); ok && op.UnixTimeEndMs != 0 {
+			lastRan = time.Unix(0, op.UnixTimeEndMs*int64(time.Millisecond))
+			return oplog.ErrStopIteration
+		}
+		if _, ok := op.Op.(*v1.Operation_OperationBackup); ok {
+			foundBackup = true
+		}
+		return nil
+	}); err != nil {
+		return NeverScheduledTask, fmt.Errorf("finding last copy run time: %w", err)
+	} else if !foundBackup {
+		lastRan = now
+	}
+
+	runAt, err := protoutil.ResolveSchedule(repo.CopyPolicy.GetSchedule(), lastRan, now)
+	if errors.Is(err, protoutil.ErrScheduleDisabled) {
+		return NeverScheduledTask, nil
+	} else if err != nil {
+		return NeverScheduledTask, fmt.Errorf("resolve schedule: %w", err)
+	}
+
+	return ScheduledTask{
+		Task:  t,
+		RunAt: runAt,
+		Op: &v1.Operation{
+			Op: &v1.Operation_OperationCopy{},
+		},
+	}, nil
+}
+
+func (t *CopyTask) Run(ctx context.Context, st ScheduledTask, runner TaskRunner) error {
+	op := st.Op
+
+	// the policy is read from the current config, the task's repo may predate a config change.
+	srcConfig, err := runner.GetRepo(t.RepoID())
+	if err != nil {
+		return fmt.Errorf("get repo %q: %w", t.RepoID(), err)
+	}
+	policy := srcConfig.GetCopyPolicy()
+	if policy.GetToRepo() == "" {
+		return fmt.Errorf("repo %q has no copy destination", t.RepoID())
+	}
+	destConfig, err := runner.GetRepo(policy.GetToRepo())
+	if err != nil {
+		return fmt.Errorf("get destination repo %q: %w", policy.GetToRepo(), err)
+	}
+
+	src, err := runner.GetRepoOrchestrator(t.RepoID())
+	if err != nil {
+		return fmt.Errorf("couldn't get repo %q: %w", t.RepoID(), err)
+	}
+	dest, err := runner.GetRepoOrchestrator(destConfig.Id)
+	if err != nil {
+		return fmt.Errorf("couldn't get repo %q: %w", destConfig.Id, err)
+	}
+
+	if err := runner.ExecuteHooks(ctx, []v1.Hook_Condition{
+		v1.Hook_CONDITION_COPY_START,
+	}, HookVars{CopyToRepo: destConfig}); err != nil {
+		return fmt.Errorf("copy start hook: %w", err)
+	}
+
+	if err := src.UnlockIfAutoEnabled(ctx); err != nil {
+		return fmt.Errorf("auto unlock repo %q: %w", t.RepoID(), err)
+	}
+	if err := dest.UnlockIfAutoEnabled(ctx); err != nil {
+		return fmt.Errorf("auto unlock repo %q: %w", destConfig.Id, err)
+	}
+
+	opCopy := &v1.Operation_OperationCopy{
+		OperationCopy: &v1.OperationCopy{
+			ToRepo: destConfig.Id,
+		},
+	}
+	op.Op = opCopy
+
+	liveID, writer, err := runner.LogrefWriter()
+	if err != nil {
+		return fmt.Errorf("create logref writer: %w", err)
+	}
+	defer writer.Close()
+	opCopy.OperationCopy.OutputLogref = liveID
+
+	if err := runner.UpdateOperation(op); err != nil {
+		return fmt.Errorf("update operation: %w", err)
+	}
+
+	err = src.CopyTo(ctx, dest, policy.GetPlans(), writer)
+	if err != nil {
+		runner.ExecuteHooks(ctx, []v1.Hook_Condition{
+			v1.Hook_CONDITION_COPY_ERROR,
+			v1.Hook_CONDITION_ANY_ERROR,
+		}, HookVars{
+			CopyToRepo: destConfig,
+			Error:      err.Error(),
+		})
+
+		return fmt.Errorf("copy: %w", err)
+	}
+
+	if err := writer.Close(); err != nil {
+		return fmt.Errorf("close logref writer: %w", err)
+	}
+
+	// index the copied snapshots under the destination repo, then apply the destination's retention to them.
+	at := time.Now()
+	if err := runner.ScheduleTask(NewOneoffIndexSnapshotsTask(destConfig, at), TaskPriorityIndexSnapshots); err != nil {
+		return fmt.Errorf("schedule index snapshots task for repo %q: %w", destConfig.Id, err)
+	}
+	for _, plan := range runner.Config().GetPlans() {
+		if !slices.Contains(config.PlanRepos(plan), t.RepoID()) {
+			continue
+		}
+		if len(policy.GetPlans()) != 0 && !slices.Contains(policy.GetPlans(), plan.Id) {
+			continue
+		}
+		retention := config.RetentionForRepo(plan, destConfig.Id)
+		if _, ok := retention.GetPolicy().(*v1.RetentionPolicy_PolicyKeepAll); retention == nil || ok {
+			continue
+		}
+		if err := runner.ScheduleTask(NewOneoffForgetTask(destConfig, plan.Id, op.FlowId, at), TaskPriorityForget); err != nil {
+			return fmt.Errorf("schedule forget task for plan %q in repo %q: %w", plan.Id, destConfig.Id, err)
+		}
+	}
+
+	if err := runner.ExecuteHooks(ctx, []v1.Hook_Condition{
+		v1.Hook_CONDITION_COPY_SUCCESS,
+	}, HookVars{CopyToRepo: destConfig}); err != nil {
+		return fmt.Errorf("execute copy success hooks: %w", err)
+	}
+
+	return nil
+}
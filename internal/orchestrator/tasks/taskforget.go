@@ -6,10 +6,12 @@ import (
 	"time"
 
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/config"
 	"github.com/garethgeorge/backrest/internal/oplog"
 	"github.com/garethgeorge/backrest/internal/orchestrator/repo"
 	"github.com/hashicorp/go-multierror"
 	"go.uber.org/zap"
+	"google.golang.org/protobuf/proto"
 )
 
 func NewOneoffForgetTask(repo *v1.Repo, planID string, flowID int64, at time.Time) Task {
@@ -66,6 +68,10 @@ func forgetHelper(ctx context.Context, st ScheduledTask, taskRunner TaskRunner)
 	if err != nil {
 		return fmt.Errorf("get plan %q: %w", t.PlanID(), err)
 	}
+	if retention := config.RetentionForRepo(plan, t.RepoID()); retention != plan.Retention {
+		plan = proto.Clone(plan).(*v1.Plan)
+		plan.Retention = retention
+	}
 
 	tags := []string{repo.TagForPlan(t.PlanID())}
 	if compat, err := useLegacyCompatMode(l, taskRunner, t.Repo().GetGuid(), t.PlanID()); err != nil {
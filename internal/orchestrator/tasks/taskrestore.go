@@ -47,6 +47,67 @@ func NewOneoffRestoreTask(repo *v1.Repo, planID string, flowID int64, at time.Ti
 	}
 }
 
+// NewOneoffDatabaseRestoreTask restores the database dump at path in the snapshot into database using the client of
+// the plan's database source.
+func NewOneoffDatabaseRestoreTask(repo *v1.Repo, planID string, flowID int64, at time.Time, snapshotID, path, database string) Task {
+	return &GenericOneoffTask{
+		OneoffTask: OneoffTask{
+			BaseTask: BaseTask{
+				TaskType:   "restore",
+				TaskName:   fmt.Sprintf("restore database %q from snapshot %q in repo %q", database, snapshotID, repo.Id),
+				TaskRepo:   repo,
+				TaskPlanID: planID,
+			},
+			FlowID: flowID,
+			RunAt:  at,
+			ProtoOp: &v1.Operation{
+				SnapshotId: snapshotID,
+				Op: &v1.Operation_OperationRestore{
+					OperationRestore: &v1.OperationRestore{
+						Path:           path,
+						TargetDatabase: database,
+					},
+				},
+			},
+		},
+		Do: func(ctx context.Context, st ScheduledTask, taskRunner TaskRunner) error {
+			if err := restoreDatabaseHelper(ctx, st, taskRunner, snapshotID, path, database); err != nil {
+				taskRunner.ExecuteHooks(ctx, []v1.Hook_Condition{
+					v1.Hook_CONDITION_ANY_ERROR,
+				}, HookVars{
+					Task:  st.Task.Name(),
+					Error: err.Error(),
+				})
+				return err
+			}
+			return nil
+		},
+	}
+}
+
+func restoreDatabaseHelper(ctx context.Context, st ScheduledTask, taskRunner TaskRunner, snapshotID, path, database string) error {
+	t := st.Task
+
+	if snapshotID == "" || path == "" || database == "" {
+		return errors.New("snapshotID, path, and database are required")
+	}
+
+	plan, err := taskRunner.GetPlan(t.PlanID())
+	if err != nil {
+		return fmt.Errorf("couldn't get plan %q: %w", t.PlanID(), err)
+	}
+	if plan.GetDatabaseSource() == nil {
+		return fmt.Errorf("plan %q does not back up databases", plan.Id)
+	}
+
+	repo, err := taskRunner.GetRepoOrchestrator(t.RepoID())
+	if err != nil {
+		return fmt.Errorf("couldn't get repo %q: %w", t.RepoID(), err)
+	}
+
+	return repo.RestoreDatabase(ctx, snapshotID, path, plan.GetDatabaseSource(), database)
+}
+
 func restoreHelper(ctx context.Context, st ScheduledTask, taskRunner TaskRunner, snapshotID, path, target string) error {
 	t := st.Task
 	op := st.Op
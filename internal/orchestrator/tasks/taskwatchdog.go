@@ -0,0 +1,141 @@
+package tasks
+
+import (
+	"context"
+	"fmt"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/oplog"
+	"go.uber.org/zap"
+)
+
+const (
+	watchdogInterval = 1 * time.Hour
+
+	// BackupHistoryLimit bounds the operations examined when looking for the last successful backup of a plan, a plan
+	// without a successful backup among them is treated as never having backed up successfully.
+	BackupHistoryLimit = 1000
+)
+
+// BackupHistory summarizes the backups of a plan for deciding whether the plan is stale.
+type BackupHistory struct {
+	LastSuccess  time.Time // end time of the last backup that succeeded or finished with warnings, zero if none.
+	FirstAttempt time.Time // start time of the oldest backup examined, zero if none.
+}
+
+// ObserveBackup adds a backup operation to the history, operations may be observed in any order.
+func (h *BackupHistory) ObserveBackup(op *v1.Operation) {
+	if op.GetOperationBackup() == nil || op.Status == v1.OperationStatus_STATUS_PENDING {
+		return
+	}
+	if start := time.UnixMilli(op.UnixTimeStartMs); h.FirstAttempt.IsZero() || start.Before(h.FirstAttempt) {
+		h.FirstAttempt = start
+	}
+	if op.Status == v1.OperationStatus_STATUS_SUCCESS || op.Status == v1.OperationStatus_STATUS_WARNING {
+		if end := time.UnixMilli(op.UnixTimeEndMs); end.After(h.LastSuccess) {
+			h.LastSuccess = end
+		}
+	}
+}
+
+// IsPlanStale returns true if the plan has a max snapshot age and no backup succeeded within it. A plan that never
+// backed up successfully is stale once the max age has passed since since, e.g. its oldest backup attempt.
+func IsPlanStale(plan *v1.Plan, history BackupHistory, since time.Time, now time.Time) bool {
+	maxAge := time.Duration(plan.GetMaxSnapshotAgeHours()) * time.Hour
+	if maxAge <= 0 {
+		return false
+	}
+	if !history.LastSuccess.IsZero() {
+		return now.Sub(history.LastSuccess) > maxAge
+	}
+	return !since.IsZero() && now.Sub(since) > maxAge
+}
+
+// PlanStalenessWatchdogTask periodically checks that a plan backed up successfully to a repo within its max snapshot
+// age, and runs the plan's CONDITION_PLAN_STALE hooks once each time the plan becomes stale in the repo. Whether the
+// hooks already ran for the current stale period is derived from the hook operations in the oplog so that it survives
+// restarts.
+type PlanStalenessWatchdogTask struct {
+	BaseTask
+	plan    *v1.Plan
+	started time.Time // time of the first check, the reference for plans that never backed up successfully.
+}
+
+var _ Task = &PlanStalenessWatchdogTask{}
+
+func NewPlanStalenessWatchdogTask(repo *v1.Repo, plan *v1.Plan) *PlanStalenessWatchdogTask {
+	return &PlanStalenessWatchdogTask{
+		BaseTask: BaseTask{
+			TaskType:   "staleness_watchdog",
+			TaskName:   fmt.Sprintf("staleness watchdog for plan %q in repo %q", plan.Id, repo.Id),
+			TaskRepo:   repo,
+			TaskPlanID: plan.Id,
+		},
+		plan: plan,
+	}
+}
+
+func (t *PlanStalenessWatchdogTask) Next(now time.Time, runner TaskRunner) (ScheduledTask, error) {
+	if t.plan.GetMaxSnapshotAgeHours() <= 0 {
+		return NeverScheduledTask, nil
+	}
+	if t.started.IsZero() {
+		t.started = now
+	}
+	return ScheduledTask{
+		Task:  t,
+		RunAt: now.Add(watchdogInterval),
+	}, nil
+}
+
+func (t *PlanStalenessWatchdogTask) Run(ctx context.Context, st ScheduledTask, runner TaskRunner) error {
+	var history BackupHistory
+	alerted := false // whether stale hooks ran since the last successful backup.
+	if err := runner.QueryOperations(oplog.Query{}.
+		SetInstanceID(runner.InstanceID()).
+		SetRepoGUID(t.Repo().GetGuid()).
+		SetPlanID(t.plan.Id).
+		SetReversed(true).
+		SetLimit(BackupHistoryLimit), func(op *v1.Operation) error {
+		if op.GetOperationRunHook().GetCondition() == v1.Hook_CONDITION_PLAN_STALE {
+			alerted = true
+		}
+		history.ObserveBackup(op)
+		if !history.LastSuccess.IsZero() {
+			return oplog.ErrStopIteration
+		}
+		return nil
+	}); err != nil {
+		return fmt.Errorf("query backups of plan %q in repo %q: %w", t.plan.Id, t.RepoID(), err)
+	}
+
+	since := t.started
+	if !history.FirstAttempt.IsZero() && history.FirstAttempt.Before(since) {
+		since = history.FirstAttempt
+	}
+
+	now := time.Now()
+	if !IsPlanStale(t.plan, history, since, now) || alerted {
+		return nil
+	}
+
+	var msg string
+	if history.LastSuccess.IsZero() {
+		msg = fmt.Sprintf("plan %q has no successful backup to repo %q, expected one every %dh", t.plan.Id, t.RepoID(), t.plan.GetMaxSnapshotAgeHours())
+	} else {
+		msg = fmt.Sprintf("plan %q last backed up successfully to repo %q %v ago at %v, expected one every %dh", t.plan.Id, t.RepoID(),
+			now.Sub(history.LastSuccess).Truncate(time.Minute), history.LastSuccess.Format(time.RFC3339), t.plan.GetMaxSnapshotAgeHours())
+	}
+	zap.L().Warn("plan is stale", zap.String("plan", t.plan.Id), zap.String("repo", t.RepoID()), zap.String("reason", msg))
+
+	if err := runner.ExecuteHooks(ctx, []v1.Hook_Condition{
+		v1.Hook_CONDITION_PLAN_STALE,
+	}, HookVars{
+		Task:  t.Name(),
+		Error: msg,
+	}); err != nil {
+		return fmt.Errorf("plan stale hooks: %w", err)
+	}
+	return nil
+}
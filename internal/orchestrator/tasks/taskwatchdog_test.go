@@ -0,0 +1,116 @@
+package tasks
+
+import (
+	"context"
+	"slices"
+	"testing"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/oplog"
+	"github.com/garethgeorge/backrest/internal/oplog/sqlitestore"
+)
+
+func TestIsPlanStale(t *testing.T) {
+	now := time.Unix(1000000, 0)
+	plan := &v1.Plan{Id: "plan1", MaxSnapshotAgeHours: 24}
+
+	tcs := []struct {
+		name    string
+		plan    *v1.Plan
+		history BackupHistory
+		since   time.Time
+		want    bool
+	}{
+		{name: "disabled", plan: &v1.Plan{Id: "plan1"}, history: BackupHistory{LastSuccess: now.Add(-48 * time.Hour)}},
+		{name: "recent success", plan: plan, history: BackupHistory{LastSuccess: now.Add(-time.Hour)}},
+		{name: "old success", plan: plan, history: BackupHistory{LastSuccess: now.Add(-25 * time.Hour)}, want: true},
+		{name: "never succeeded, new plan", plan: plan, since: now.Add(-time.Hour)},
+		{name: "never succeeded, old plan", plan: plan, since: now.Add(-25 * time.Hour), want: true},
+	}
+
+	for _, tc := range tcs {
+		t.Run(tc.name, func(t *testing.T) {
+			if got := IsPlanStale(tc.plan, tc.history, tc.since, now); got != tc.want {
+				t.Errorf("IsPlanStale() = %v, want %v", got, tc.want)
+			}
+		})
+	}
+}
+
+func TestPlanStalenessWatchdog(t *testing.T) {
+	repo := &v1.Repo{Id: "repo1", Guid: "repo1-guid"}
+	plan := &v1.Plan{Id: "plan1", Repo: "repo1", MaxSnapshotAgeHours: 24}
+	cfg := &v1.Config{Instance: "instance1", Repos: []*v1.Repo{repo}, Plans: []*v1.Plan{plan}}
+
+	opstore, err := sqlitestore.NewMemorySqliteStore()
+	if err != nil {
+		t.Fatalf("failed to create opstore: %v", err)
+	}
+	t.Cleanup(func() { opstore.Close() })
+	log, err := oplog.NewOpLog(opstore)
+	if err != nil {
+		t.Fatalf("failed to create oplog: %v", err)
+	}
+	runner := newTestTaskRunner(t, cfg, log)
+
+	addBackup := func(end time.Time) {
+		if err := runner.CreateOperation(&v1.Operation{
+			RepoId:          repo.Id,
+			RepoGuid:        repo.Guid,
+			PlanId:          plan.Id,
+			Status:          v1.OperationStatus_STATUS_SUCCESS,
+			UnixTimeStartMs: end.Add(-time.Minute).UnixMilli(),
+			UnixTimeEndMs:   end.UnixMilli(),
+			Op:              &v1.Operation_OperationBackup{OperationBackup: &v1.OperationBackup{}},
+		}); err != nil {
+			t.Fatalf("failed to add backup: %v", err)
+		}
+	}
+
+	addStaleHook := func() {
+		// the operation recorded by the hook task that runs for the stale condition.
+		if err := runner.CreateOperation(&v1.Operation{
+			RepoId:          repo.Id,
+			RepoGuid:        repo.Guid,
+			PlanId:          plan.Id,
+			Status:          v1.OperationStatus_STATUS_SUCCESS,
+			UnixTimeStartMs: time.Now().UnixMilli(),
+			UnixTimeEndMs:   time.Now().UnixMilli(),
+			Op: &v1.Operation_OperationRunHook{OperationRunHook: &v1.OperationRunHook{
+				Condition: v1.Hook_CONDITION_PLAN_STALE,
+			}},
+		}); err != nil {
+			t.Fatalf("failed to add hook operation: %v", err)
+		}
+	}
+	runWatchdog := func() {
+		// each run uses a new task, as after a restart.
+		task := NewPlanStalenessWatchdogTask(repo, plan)
+		st, err := task.Next(time.Now(), runner)
+		if err != nil {
+			t.Fatalf("Next() error = %v", err)
+		}
+		if err := task.Run(context.Background(), st, runner); err != nil {
+			t.Fatalf("Run() error = %v", err)
+		}
+	}
+
+	addBackup(time.Now().Add(-48 * time.Hour))
+	runWatchdog()
+	if want := []v1.Hook_Condition{v1.Hook_CONDITION_PLAN_STALE}; !slices.Equal(runner.hooks, want) {
+		t.Fatalf("hooks after a stale run = %v, want %v", runner.hooks, want)
+	}
+	addStaleHook()
+	runWatchdog()
+	if len(runner.hooks) != 1 {
+		t.Fatalf("hooks after a second stale run = %v, want hooks to run once per stale period", runner.hooks)
+	}
+
+	// a new backup ends the stale period.
+	addBackup(time.Now())
+	runWatchdog()
+	if len(runner.hooks) != 1 {
+		t.Errorf("hooks after a successful backup = %v, want no new hooks", runner.hooks)
+	}
+}
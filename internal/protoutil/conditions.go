@@ -6,6 +6,7 @@ import (
 
 var startConditionsMap = map[v1.Hook_Condition]bool{
 	v1.Hook_CONDITION_CHECK_START:    true,
+	v1.Hook_CONDITION_COPY_START:     true,
 	v1.Hook_CONDITION_PRUNE_START:    true,
 	v1.Hook_CONDITION_SNAPSHOT_START: true,
 }
@@ -13,6 +14,7 @@ var startConditionsMap = map[v1.Hook_Condition]bool{
 var errorConditionsMap = map[v1.Hook_Condition]bool{
 	v1.Hook_CONDITION_ANY_ERROR:      true,
 	v1.Hook_CONDITION_CHECK_ERROR:    true,
+	v1.Hook_CONDITION_COPY_ERROR:     true,
 	v1.Hook_CONDITION_PRUNE_ERROR:    true,
 	v1.Hook_CONDITION_SNAPSHOT_ERROR: true,
 	v1.Hook_CONDITION_UNKNOWN:        true,
@@ -24,6 +26,7 @@ var logConditionsMap = map[v1.Hook_Condition]bool{
 
 var successConditionsMap = map[v1.Hook_Condition]bool{
 	v1.Hook_CONDITION_CHECK_SUCCESS:    true,
+	v1.Hook_CONDITION_COPY_SUCCESS:     true,
 	v1.Hook_CONDITION_PRUNE_SUCCESS:    true,
 	v1.Hook_CONDITION_SNAPSHOT_SUCCESS: true,
 }
@@ -107,11 +107,19 @@ func RetentionPolicyFromProto(p *v1.RetentionPolicy) *restic.RetentionPolicy {
 		return nil
 	case *v1.RetentionPolicy_PolicyTimeBucketed:
 		return &restic.RetentionPolicy{
-			KeepDaily:   int(p.PolicyTimeBucketed.Daily),
-			KeepHourly:  int(p.PolicyTimeBucketed.Hourly),
-			KeepWeekly:  int(p.PolicyTimeBucketed.Weekly),
-			KeepMonthly: int(p.PolicyTimeBucketed.Monthly),
-			KeepYearly:  int(p.PolicyTimeBucketed.Yearly),
+			KeepLastN:          int(p.PolicyTimeBucketed.Last),
+			KeepDaily:          int(p.PolicyTimeBucketed.Daily),
+			KeepHourly:         int(p.PolicyTimeBucketed.Hourly),
+			KeepWeekly:         int(p.PolicyTimeBucketed.Weekly),
+			KeepMonthly:        int(p.PolicyTimeBucketed.Monthly),
+			KeepYearly:         int(p.PolicyTimeBucketed.Yearly),
+			KeepWithinDuration: p.PolicyTimeBucketed.Within,
+			KeepWithinHourly:   p.PolicyTimeBucketed.WithinHourly,
+			KeepWithinDaily:    p.PolicyTimeBucketed.WithinDaily,
+			KeepWithinWeekly:   p.PolicyTimeBucketed.WithinWeekly,
+			KeepWithinMonthly:  p.PolicyTimeBucketed.WithinMonthly,
+			KeepWithinYearly:   p.PolicyTimeBucketed.WithinYearly,
+			KeepTags:           p.PolicyTimeBucketed.KeepTags,
 		}
 	case *v1.RetentionPolicy_PolicyKeepLastN:
 		return &restic.RetentionPolicy{
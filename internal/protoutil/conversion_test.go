@@ -1,6 +1,7 @@
 package protoutil
 
 import (
+	"reflect"
 	"testing"
 
 	v1 "github.com/garethgeorge/backrest/gen/go/v1"
@@ -187,3 +188,29 @@ func TestRepoStatsToProto(t *testing.T) {
 		})
 	}
 }
+
+func TestRetentionPolicyFromProto(t *testing.T) {
+	// keep everything from the last 7 days, plus 12 monthly, plus anything tagged keep.
+	policy := &v1.RetentionPolicy{
+		Policy: &v1.RetentionPolicy_PolicyTimeBucketed{
+			PolicyTimeBucketed: &v1.RetentionPolicy_TimeBucketedCounts{
+				Monthly:  12,
+				Within:   "7d",
+				KeepTags: []string{"keep"},
+			},
+		},
+	}
+
+	want := &restic.RetentionPolicy{
+		KeepMonthly:        12,
+		KeepWithinDuration: "7d",
+		KeepTags:           []string{"keep"},
+	}
+	if got := RetentionPolicyFromProto(policy); !reflect.DeepEqual(got, want) {
+		t.Errorf("wanted: %+v, got: %+v", want, got)
+	}
+
+	if got := RetentionPolicyFromProto(&v1.RetentionPolicy{Policy: &v1.RetentionPolicy_PolicyKeepAll{PolicyKeepAll: true}}); got != nil {
+		t.Errorf("wanted nil for keep all, got: %+v", got)
+	}
+}
@@ -0,0 +1,78 @@
+package sqliteutil
+
+import (
+	"context"
+	"fmt"
+	"os"
+	"path/filepath"
+
+	"zombiezen.com/go/sqlite"
+	"zombiezen.com/go/sqlite/sqlitex"
+)
+
+// OpenPool opens a pool of connections to the WAL mode sqlite database at dbpath, creating the database and its
+// directory if they don't exist, and migrates its schema.
+//
+// migrations are the scripts that build up the schema in order. The number of applied scripts is tracked in the
+// database's user_version, so scripts may be appended but never edited or reordered once released.
+func OpenPool(dbpath string, poolSize int, migrations []string) (*sqlitex.Pool, error) {
+	if err := os.MkdirAll(filepath.Dir(dbpath), 0700); err != nil {
+		return nil, fmt.Errorf("create dir: %v", err)
+	}
+
+	dbpool, err := sqlitex.NewPool(dbpath, sqlitex.PoolOptions{
+		PoolSize: poolSize,
+		Flags:    sqlite.OpenReadWrite | sqlite.OpenCreate | sqlite.OpenWAL,
+	})
+	if err != nil {
+		return nil, fmt.Errorf("open sqlite pool: %v", err)
+	}
+
+	if err := migrate(dbpool, migrations); err != nil {
+		dbpool.Close()
+		return nil, err
+	}
+	return dbpool, nil
+}
+
+func migrate(dbpool *sqlitex.Pool, migrations []string) error {
+	conn, err := dbpool.Take(context.Background())
+	if err != nil {
+		return fmt.Errorf("take connection: %v", err)
+	}
+	defer dbpool.Put(conn)
+
+	if err := sqlitex.ExecuteTransient(conn, "PRAGMA journal_mode=WAL", nil); err != nil {
+		return fmt.Errorf("enable WAL: %v", err)
+	}
+
+	var version int
+	if err := sqlitex.ExecuteTransient(conn, "PRAGMA user_version", &sqlitex.ExecOptions{
+		ResultFunc: func(stmt *sqlite.Stmt) error {
+			version = stmt.ColumnInt(0)
+			return nil
+		},
+	}); err != nil {
+		return fmt.Errorf("get schema version: %v", err)
+	}
+	if version > len(migrations) {
+		return fmt.Errorf("database schema version %d is newer than the latest known version %d", version, len(migrations))
+	}
+
+	for i := version; i < len(migrations); i++ {
+		if err := func() (err error) {
+			endFunc, err := sqlitex.ImmediateTransaction(conn)
+			if err != nil {
+				return err
+			}
+			defer endFunc(&err)
+			if err := sqlitex.ExecuteScript(conn, migrations[i], nil); err != nil {
+				return err
+			}
+			return sqlitex.ExecuteTransient(conn, fmt.Sprintf("PRAGMA user_version = %d", i+1), nil)
+		}(); err != nil {
+			return fmt.Errorf("apply schema migration %d: %v", i+1, err)
+		}
+	}
+	return nil
+}
@@ -0,0 +1,63 @@
+package sqliteutil
+
+import (
+	"context"
+	"path/filepath"
+	"testing"
+
+	"zombiezen.com/go/sqlite"
+	"zombiezen.com/go/sqlite/sqlitex"
+)
+
+func TestOpenPoolMigrations(t *testing.T) {
+	dbpath := filepath.Join(t.TempDir(), "sub", "test.sqlite")
+	migrations := []string{
+		"CREATE TABLE items (id INTEGER PRIMARY KEY); INSERT INTO items (id) VALUES (1);",
+	}
+
+	dbpool, err := OpenPool(dbpath, 1, migrations)
+	if err != nil {
+		t.Fatalf("OpenPool() error = %v", err)
+	}
+	dbpool.Close()
+
+	// reopening applies only the new migration, the first would fail if it ran again.
+	migrations = append(migrations, "ALTER TABLE items ADD COLUMN name TEXT NOT NULL DEFAULT 'x';")
+	dbpool, err = OpenPool(dbpath, 1, migrations)
+	if err != nil {
+		t.Fatalf("OpenPool() with a new migration error = %v", err)
+	}
+	defer dbpool.Close()
+
+	conn, err := dbpool.Take(context.Background())
+	if err != nil {
+		t.Fatalf("take connection: %v", err)
+	}
+	defer dbpool.Put(conn)
+
+	var version int
+	var names []string
+	if err := sqlitex.ExecuteTransient(conn, "PRAGMA user_version", &sqlitex.ExecOptions{
+		ResultFunc: func(stmt *sqlite.Stmt) error {
+			version = stmt.ColumnInt(0)
+			return nil
+		},
+	}); err != nil {
+		t.Fatalf("get user_version: %v", err)
+	}
+	if err := sqlitex.ExecuteTransient(conn, "SELECT name FROM items", &sqlitex.ExecOptions{
+		ResultFunc: func(stmt *sqlite.Stmt) error {
+			names = append(names, stmt.ColumnText(0))
+			return nil
+		},
+	}); err != nil {
+		t.Fatalf("select items: %v", err)
+	}
+	if version != 2 || len(names) != 1 || names[0] != "x" {
+		t.Errorf("got schema version %d and names %v, want version 2 and names [x]", version, names)
+	}
+
+	if _, err := OpenPool(dbpath, 1, migrations[:1]); err == nil {
+		t.Errorf("OpenPool() with fewer migrations than applied succeeded, want an error")
+	}
+}
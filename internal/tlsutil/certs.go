@@ -0,0 +1,289 @@
+package tlsutil
+
+import (
+	"crypto/ecdsa"
+	"crypto/elliptic"
+	"crypto/rand"
+	"crypto/sha256"
+	"crypto/tls"
+	"crypto/x509"
+	"crypto/x509/pkix"
+	"encoding/hex"
+	"encoding/pem"
+	"errors"
+	"fmt"
+	"math/big"
+	"net"
+	"os"
+	"path/filepath"
+	"strings"
+	"sync"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/config"
+	"go.uber.org/zap"
+)
+
+const (
+	reloadCheckInterval = 10 * time.Second         // how often certificate files are checked for changes.
+	selfSignedValidity  = 5 * 365 * 24 * time.Hour // validity of generated self-signed certificates.
+	selfSignedRenewal   = 30 * 24 * time.Hour      // self-signed certificates are regenerated this long before they expire.
+
+	selfSignedCertFile = "selfsigned-cert.pem"
+	selfSignedKeyFile  = "selfsigned-key.pem"
+)
+
+// CertManager provides the TLS certificate of the web server. The certificate is loaded from the files set by the
+// environment or the config, or is a self-signed certificate generated in the data dir. Certificate files are reloaded
+// when they change so that renewed certificates are served without a restart.
+type CertManager struct {
+	config        config.ConfigStore
+	selfSignedDir string
+	envCertFile   string // overrides the config if set.
+	envKeyFile    string
+
+	mu        sync.Mutex
+	current   *loadedCert
+	checkedAt time.Time
+}
+
+type loadedCert struct {
+	cert       *tls.Certificate
+	certFile   string
+	keyFile    string
+	certMod    time.Time
+	keyMod     time.Time
+	selfSigned bool
+}
+
+func NewCertManager(config config.ConfigStore, selfSignedDir, envCertFile, envKeyFile string) *CertManager {
+	return &CertManager{
+		config:        config,
+		selfSignedDir: selfSignedDir,
+		envCertFile:   envCertFile,
+		envKeyFile:    envKeyFile,
+	}
+}
+
+// Enabled returns true if the web server should serve TLS.
+func (m *CertManager) Enabled() bool {
+	if m.envCertFile != "" || m.envKeyFile != "" {
+		return true
+	}
+	cfg, err := m.config.Get()
+	if err != nil {
+		return false
+	}
+	return cfg.GetTls().GetEnabled()
+}
+
+// TLSConfig returns a server TLS config that serves the current certificate.
+func (m *CertManager) TLSConfig() *tls.Config {
+	return &tls.Config{
+		MinVersion:     tls.VersionTLS12,
+		GetCertificate: m.GetCertificate,
+	}
+}
+
+// GetCertificate returns the current certificate, reloading it if its files or the config changed.
+func (m *CertManager) GetCertificate(*tls.ClientHelloInfo) (*tls.Certificate, error) {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+
+	if m.current != nil && time.Since(m.checkedAt) < reloadCheckInterval {
+		return m.current.cert, nil
+	}
+	m.checkedAt = time.Now()
+
+	loaded, err := m.load()
+	if err != nil {
+		if m.current != nil {
+			zap.S().Errorf("failed to reload TLS certificate, continuing to serve the previous certificate: %v", err)
+			return m.current.cert, nil
+		}
+		return nil, err
+	}
+	m.current = loaded
+	return loaded.cert, nil
+}
+
+// Info describes the certificate currently served.
+func (m *CertManager) Info() (*v1.TLSInfo, error) {
+	if !m.Enabled() {
+		return &v1.TLSInfo{}, nil
+	}
+	if _, err := m.GetCertificate(nil); err != nil {
+		return nil, err
+	}
+
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	leaf := m.current.cert.Leaf
+	return &v1.TLSInfo{
+		Enabled:           true,
+		SelfSigned:        m.current.selfSigned,
+		CertFile:          m.current.certFile,
+		Subject:           leaf.Subject.String(),
+		DnsNames:          leaf.DNSNames,
+		NotAfterMs:        leaf.NotAfter.UnixMilli(),
+		Sha256Fingerprint: Fingerprint(leaf.Raw),
+	}, nil
+}
+
+// load returns the current certificate if its files are unchanged, or loads the certificate that should be served.
+func (m *CertManager) load() (*loadedCert, error) {
+	certFile, keyFile, err := m.certFiles()
+	if err != nil {
+		return nil, err
+	}
+	selfSigned := certFile == ""
+	if selfSigned {
+		certFile = filepath.Join(m.selfSignedDir, selfSignedCertFile)
+		keyFile = filepath.Join(m.selfSignedDir, selfSignedKeyFile)
+		if err := ensureSelfSigned(certFile, keyFile); err != nil {
+			return nil, fmt.Errorf("self-signed certificate: %w", err)
+		}
+	}
+
+	certStat, err := os.Stat(certFile)
+	if err != nil {
+		return nil, fmt.Errorf("stat certificate: %w", err)
+	}
+	keyStat, err := os.Stat(keyFile)
+	if err != nil {
+		return nil, fmt.Errorf("stat key: %w", err)
+	}
+	if c := m.current; c != nil && c.certFile == certFile && c.keyFile == keyFile && c.certMod.Equal(certStat.ModTime()) && c.keyMod.Equal(keyStat.ModTime()) {
+		return c, nil
+	}
+
+	cert, err := tls.LoadX509KeyPair(certFile, keyFile)
+	if err != nil {
+		return nil, fmt.Errorf("load certificate: %w", err)
+	}
+	if cert.Leaf == nil {
+		if cert.Leaf, err = x509.ParseCertificate(cert.Certificate[0]); err != nil {
+			return nil, fmt.Errorf("parse certificate: %w", err)
+		}
+	}
+	zap.S().Infof("loaded TLS certificate %q (sha256 fingerprint %v, expires %v)", certFile, Fingerprint(cert.Leaf.Raw), cert.Leaf.NotAfter.Format(time.RFC3339))
+
+	return &loadedCert{
+		cert:       &cert,
+		certFile:   certFile,
+		keyFile:    keyFile,
+		certMod:    certStat.ModTime(),
+		keyMod:     keyStat.ModTime(),
+		selfSigned: selfSigned,
+	}, nil
+}
+
+// certFiles returns the configured certificate and key files, or empty strings if a self-signed certificate is used.
+func (m *CertManager) certFiles() (string, string, error) {
+	certFile, keyFile := m.envCertFile, m.envKeyFile
+	if certFile == "" && keyFile == "" {
+		cfg, err := m.config.Get()
+		if err != nil {
+			return "", "", fmt.Errorf("get config: %w", err)
+		}
+		certFile, keyFile = cfg.GetTls().GetCertFile(), cfg.GetTls().GetKeyFile()
+	}
+	if (certFile == "") != (keyFile == "") {
+		return "", "", errors.New("both a certificate and a key file must be set")
+	}
+	return certFile, keyFile, nil
+}
+
+// ensureSelfSigned generates a self-signed certificate unless a valid one already exists.
+func ensureSelfSigned(certFile, keyFile string) error {
+	if data, err := os.ReadFile(certFile); err == nil {
+		if block, _ := pem.Decode(data); block != nil {
+			if cert, err := x509.ParseCertificate(block.Bytes); err == nil && time.Until(cert.NotAfter) > selfSignedRenewal {
+				if _, err := os.Stat(keyFile); err == nil {
+					return nil
+				}
+			}
+		}
+	}
+
+	certPEM, keyPEM, err := generateSelfSigned(time.Now())
+	if err != nil {
+		return err
+	}
+	if err := os.MkdirAll(filepath.Dir(certFile), 0700); err != nil {
+		return fmt.Errorf("create dir: %w", err)
+	}
+	if err := os.WriteFile(keyFile, keyPEM, 0600); err != nil {
+		return fmt.Errorf("write key: %w", err)
+	}
+	if err := os.WriteFile(certFile, certPEM, 0644); err != nil {
+		return fmt.Errorf("write certificate: %w", err)
+	}
+	zap.S().Infof("generated self-signed TLS certificate %q", certFile)
+	return nil
+}
+
+// generateSelfSigned returns a PEM encoded certificate and key valid for localhost and the host's name and addresses.
+func generateSelfSigned(now time.Time) ([]byte, []byte, error) {
+	key, err := ecdsa.GenerateKey(elliptic.P256(), rand.Reader)
+	if err != nil {
+		return nil, nil, fmt.Errorf("generate key: %w", err)
+	}
+	serial, err := rand.Int(rand.Reader, new(big.Int).Lsh(big.NewInt(1), 128))
+	if err != nil {
+		return nil, nil, fmt.Errorf("generate serial: %w", err)
+	}
+
+	dnsNames := []string{"localhost"}
+	if hostname, err := os.Hostname(); err == nil && hostname != "" && hostname != "localhost" {
+		dnsNames = append(dnsNames, hostname)
+	}
+	ips := []net.IP{net.IPv4(127, 0, 0, 1), net.IPv6loopback}
+	if addrs, err := net.InterfaceAddrs(); err == nil {
+		for _, addr := range addrs {
+			if ipnet, ok := addr.(*net.IPNet); ok && !ipnet.IP.IsLoopback() && !ipnet.IP.IsLinkLocalUnicast() {
+				ips = append(ips, ipnet.IP)
+			}
+		}
+	}
+
+	template := &x509.Certificate{
+		SerialNumber:          serial,
+		Subject:               pkix.Name{CommonName: dnsNames[len(dnsNames)-1], Organization: []string{"Backrest self-signed"}},
+		NotBefore:             now.Add(-time.Hour),
+		NotAfter:              now.Add(selfSignedValidity),
+		KeyUsage:              x509.KeyUsageDigitalSignature, // a leaf certificate, trusting it must not let its key sign other certificates.
+		ExtKeyUsage:           []x509.ExtKeyUsage{x509.ExtKeyUsageServerAuth},
+		BasicConstraintsValid: true,
+		IsCA:                  false,
+		DNSNames:              dnsNames,
+		IPAddresses:           ips,
+	}
+	der, err := x509.CreateCertificate(rand.Reader, template, template, &key.PublicKey, key)
+	if err != nil {
+		return nil, nil, fmt.Errorf("create certificate: %w", err)
+	}
+	keyDER, err := x509.MarshalECPrivateKey(key)
+	if err != nil {
+		return nil, nil, fmt.Errorf("marshal key: %w", err)
+	}
+	return pem.EncodeToMemory(&pem.Block{Type: "CERTIFICATE", Bytes: der}),
+		pem.EncodeToMemory(&pem.Block{Type: "EC PRIVATE KEY", Bytes: keyDER}), nil
+}
+
+// Fingerprint returns the SHA-256 fingerprint of a DER encoded certificate as colon separated hex e.g. "AB:CD:...".
+func Fingerprint(der []byte) string {
+	sum := sha256.Sum256(der)
+	return NormalizeFingerprint(hex.EncodeToString(sum[:]))
+}
+
+// NormalizeFingerprint returns the fingerprint in the format of Fingerprint, accepting lower case and no separators.
+func NormalizeFingerprint(fingerprint string) string {
+	hexSum := strings.ToUpper(strings.NewReplacer(":", "", " ", "").Replace(fingerprint))
+	parts := make([]string, 0, len(hexSum)/2)
+	for i := 0; i+1 < len(hexSum); i += 2 {
+		parts = append(parts, hexSum[i:i+2])
+	}
+	return strings.Join(parts, ":")
+}
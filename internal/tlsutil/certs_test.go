@@ -0,0 +1,110 @@
+package tlsutil
+
+import (
+	"crypto/x509"
+	"encoding/pem"
+	"os"
+	"path/filepath"
+	"testing"
+	"time"
+
+	v1 "github.com/garethgeorge/backrest/gen/go/v1"
+	"github.com/garethgeorge/backrest/internal/config"
+)
+
+func TestSelfSignedCertificate(t *testing.T) {
+	dir := t.TempDir()
+	store := &config.MemoryStore{Config: &v1.Config{Tls: &v1.TLS{Enabled: true}}}
+
+	m := NewCertManager(store, dir, "", "")
+	if !m.Enabled() {
+		t.Fatalf("Enabled() = false, want true")
+	}
+	info, err := m.Info()
+	if err != nil {
+		t.Fatalf("Info() error = %v", err)
+	}
+	if !info.SelfSigned || info.Sha256Fingerprint == "" {
+		t.Fatalf("Info() = %v, want a self-signed certificate with a fingerprint", info)
+	}
+	certPEM, err := os.ReadFile(filepath.Join(dir, selfSignedCertFile))
+	if err != nil {
+		t.Fatalf("read certificate: %v", err)
+	}
+	block, _ := pem.Decode(certPEM)
+	if block == nil {
+		t.Fatalf("certificate file is not PEM encoded")
+	}
+	cert, err := x509.ParseCertificate(block.Bytes)
+	if err != nil {
+		t.Fatalf("parse certificate: %v", err)
+	}
+	if cert.IsCA || cert.KeyUsage&x509.KeyUsageCertSign != 0 {
+		t.Errorf("self-signed certificate may sign certificates, IsCA = %v, KeyUsage = %v", cert.IsCA, cert.KeyUsage)
+	}
+
+	// the certificate is persisted and reused after a restart.
+	info2, err := NewCertManager(store, dir, "", "").Info()
+	if err != nil {
+		t.Fatalf("Info() error = %v", err)
+	}
+	if info2.Sha256Fingerprint != info.Sha256Fingerprint {
+		t.Errorf("fingerprint after restart = %v, want %v", info2.Sha256Fingerprint, info.Sha256Fingerprint)
+	}
+}
+
+func TestCertificateReload(t *testing.T) {
+	dir := t.TempDir()
+	certFile := filepath.Join(dir, "cert.pem")
+	keyFile := filepath.Join(dir, "key.pem")
+	writeCert := func(mtime time.Time) {
+		certPEM, keyPEM, err := generateSelfSigned(time.Now())
+		if err != nil {
+			t.Fatalf("generateSelfSigned() error = %v", err)
+		}
+		for file, data := range map[string][]byte{certFile: certPEM, keyFile: keyPEM} {
+			if err := os.WriteFile(file, data, 0600); err != nil {
+				t.Fatalf("write %v: %v", file, err)
+			}
+			if err := os.Chtimes(file, mtime, mtime); err != nil {
+				t.Fatalf("chtimes %v: %v", file, err)
+			}
+		}
+	}
+
+	writeCert(time.Now().Add(-time.Hour))
+	m := NewCertManager(&config.MemoryStore{Config: &v1.Config{}}, dir, certFile, keyFile)
+	first, err := m.GetCertificate(nil)
+	if err != nil {
+		t.Fatalf("GetCertificate() error = %v", err)
+	}
+
+	writeCert(time.Now())
+	m.checkedAt = time.Time{} // skip the reload check interval.
+	second, err := m.GetCertificate(nil)
+	if err != nil {
+		t.Fatalf("GetCertificate() error = %v", err)
+	}
+	if Fingerprint(first.Leaf.Raw) == Fingerprint(second.Leaf.Raw) {
+		t.Errorf("GetCertificate() after the files changed returned the old certificate")
+	}
+
+	// a broken certificate file keeps the previous certificate in service.
+	if err := os.WriteFile(certFile, []byte("garbage"), 0600); err != nil {
+		t.Fatalf("write cert: %v", err)
+	}
+	m.checkedAt = time.Time{}
+	third, err := m.GetCertificate(nil)
+	if err != nil || third != second {
+		t.Errorf("GetCertificate() with a broken file = %v, %v, want the previous certificate", third, err)
+	}
+}
+
+func TestNormalizeFingerprint(t *testing.T) {
+	if got, want := NormalizeFingerprint("ab:cd ef01"), "AB:CD:EF:01"; got != want {
+		t.Errorf("NormalizeFingerprint() = %q, want %q", got, want)
+	}
+	if got := Fingerprint([]byte("cert")); NormalizeFingerprint(got) != got || len(got) != 32*3-1 {
+		t.Errorf("Fingerprint() = %q, want 32 colon separated upper case hex bytes", got)
+	}
+}
@@ -0,0 +1,116 @@
+// Package tracing exports OpenTelemetry trace spans for task execution, hooks and restic commands. Tracing is off
+// unless an OTLP endpoint or a trace file is configured, in which case the global tracer provider is replaced.
+package tracing
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"os"
+
+	"go.opentelemetry.io/otel"
+	"go.opentelemetry.io/otel/attribute"
+	"go.opentelemetry.io/otel/codes"
+	"go.opentelemetry.io/otel/exporters/otlp/otlptrace/otlptracehttp"
+	"go.opentelemetry.io/otel/exporters/stdout/stdouttrace"
+	"go.opentelemetry.io/otel/sdk/resource"
+	sdktrace "go.opentelemetry.io/otel/sdk/trace"
+	semconv "go.opentelemetry.io/otel/semconv/v1.26.0"
+	"go.opentelemetry.io/otel/trace"
+)
+
+const instrumentationName = "github.com/garethgeorge/backrest"
+
+// Options selects where spans are exported to, spans are exported to every configured destination.
+type Options struct {
+	// OTLPEndpoint enables export to an OTLP/HTTP collector. The exporter is configured by the standard
+	// OTEL_EXPORTER_OTLP_* environment variables so only its presence is checked here.
+	OTLPEndpoint bool
+	// File is a path that spans are appended to as JSON.
+	File string
+	// Version is reported as the service version.
+	Version string
+}
+
+// OptionsFromEnv enables OTLP export if OTEL_EXPORTER_OTLP_ENDPOINT or OTEL_EXPORTER_OTLP_TRACES_ENDPOINT is set.
+func OptionsFromEnv(traceFile string, version string) Options {
+	return Options{
+		OTLPEndpoint: os.Getenv("OTEL_EXPORTER_OTLP_ENDPOINT") != "" || os.Getenv("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT") != "",
+		File:         traceFile,
+		Version:      version,
+	}
+}
+
+// Enabled returns true if spans are exported anywhere.
+func (o Options) Enabled() bool {
+	return o.OTLPEndpoint || o.File != ""
+}
+
+// Init installs a tracer provider exporting spans as configured by opts. The returned function flushes pending spans
+// and must be called before exiting. If tracing is not enabled the default no-op provider is kept.
+func Init(ctx context.Context, opts Options) (func(context.Context) error, error) {
+	if !opts.Enabled() {
+		return func(context.Context) error { return nil }, nil
+	}
+
+	var closers []func(context.Context) error
+	providerOpts := []sdktrace.TracerProviderOption{
+		sdktrace.WithResource(resource.NewSchemaless(
+			semconv.ServiceName("backrest"),
+			semconv.ServiceVersion(opts.Version),
+		)),
+	}
+
+	if opts.OTLPEndpoint {
+		exporter, err := otlptracehttp.New(ctx)
+		if err != nil {
+			return nil, fmt.Errorf("create OTLP trace exporter: %w", err)
+		}
+		providerOpts = append(providerOpts, sdktrace.WithBatcher(exporter))
+	}
+
+	if opts.File != "" {
+		f, err := os.OpenFile(opts.File, os.O_CREATE|os.O_WRONLY|os.O_APPEND, 0600)
+		if err != nil {
+			return nil, fmt.Errorf("open trace file: %w", err)
+		}
+		exporter, err := stdouttrace.New(stdouttrace.WithWriter(f))
+		if err != nil {
+			f.Close()
+			return nil, fmt.Errorf("create file trace exporter: %w", err)
+		}
+		providerOpts = append(providerOpts, sdktrace.WithBatcher(exporter))
+		closers = append(closers, func(context.Context) error { return f.Close() })
+	}
+
+	provider := sdktrace.NewTracerProvider(providerOpts...)
+	otel.SetTracerProvider(provider)
+
+	return func(ctx context.Context) error {
+		// the provider flushes the exporters before the file they write to is closed.
+		err := provider.Shutdown(ctx)
+		for _, closer := range closers {
+			err = errors.Join(err, closer(ctx))
+		}
+		return err
+	}, nil
+}
+
+// Tracer returns the tracer used for all of backrest's spans, it is a no-op unless Init enabled tracing.
+func Tracer() trace.Tracer {
+	return otel.Tracer(instrumentationName)
+}
+
+// Start starts a span as a child of any span in ctx.
+func Start(ctx context.Context, name string, attrs ...attribute.KeyValue) (context.Context, trace.Span) {
+	return Tracer().Start(ctx, name, trace.WithAttributes(attrs...))
+}
+
+// End records err on the span, if any, and ends it.
+func End(span trace.Span, err error) {
+	if err != nil {
+		span.RecordError(err)
+		span.SetStatus(codes.Error, err.Error())
+	}
+	span.End()
+}
@@ -0,0 +1,51 @@
+package tracing
+
+import (
+	"context"
+	"errors"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func TestTraceFile(t *testing.T) {
+	file := filepath.Join(t.TempDir(), "trace.json")
+	shutdown, err := Init(context.Background(), Options{File: file, Version: "test"})
+	if err != nil {
+		t.Fatalf("Init() error = %v", err)
+	}
+
+	ctx, parent := Start(context.Background(), "task backup")
+	_, child := Start(ctx, "restic backup")
+	End(child, errors.New("exit code 1"))
+	End(parent, nil)
+
+	if err := shutdown(context.Background()); err != nil {
+		t.Fatalf("shutdown() error = %v", err)
+	}
+
+	data, err := os.ReadFile(file)
+	if err != nil {
+		t.Fatalf("read trace file: %v", err)
+	}
+	for _, want := range []string{`"Name":"task backup"`, `"Name":"restic backup"`, "exit code 1"} {
+		if !strings.Contains(string(data), want) {
+			t.Errorf("trace file does not contain %s:\n%s", want, data)
+		}
+	}
+}
+
+func TestDisabled(t *testing.T) {
+	opts := Options{}
+	if opts.Enabled() {
+		t.Fatalf("Enabled() = true for empty options")
+	}
+	shutdown, err := Init(context.Background(), opts)
+	if err != nil {
+		t.Fatalf("Init() error = %v", err)
+	}
+	if err := shutdown(context.Background()); err != nil {
+		t.Errorf("shutdown() error = %v", err)
+	}
+}
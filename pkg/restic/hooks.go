@@ -0,0 +1,60 @@
+package restic
+
+import (
+	"context"
+	"errors"
+	"os/exec"
+	"strings"
+)
+
+// CommandHook is called before each restic command is run with the restic subcommand e.g. "backup". It returns a
+// function that is called with the command's exit code, or -1 if the command didn't exit, and its error once it
+// finishes. Hooks let callers observe commands e.g. to trace them without this package depending on how.
+type CommandHook func(ctx context.Context, subcommand string) func(exitCode int, err error)
+
+// WithCommandHook adds a hook that observes every command run by the repo. It only takes effect when passed to NewRepo.
+func WithCommandHook(hook CommandHook) GenericOption {
+	return func(opts *GenericOpts) {
+		opts.commandHooks = append(opts.commandHooks, hook)
+	}
+}
+
+// runCmd runs a command built by commandWithContext and notifies the repo's command hooks.
+func (r *Repo) runCmd(ctx context.Context, cmd *exec.Cmd) error {
+	var opts GenericOpts
+	resolveOpts(&opts, r.opts)
+	if len(opts.commandHooks) == 0 {
+		return cmd.Run()
+	}
+
+	subcommand := r.subcommand(cmd)
+	done := make([]func(int, error), 0, len(opts.commandHooks))
+	for _, hook := range opts.commandHooks {
+		done = append(done, hook(ctx, subcommand))
+	}
+	err := cmd.Run()
+	exitCode := -1
+	var exitErr *exec.ExitError
+	if errors.As(err, &exitErr) {
+		exitCode = exitErr.ExitCode()
+	} else if err == nil {
+		exitCode = 0
+	}
+	for _, fn := range done {
+		fn(exitCode, err)
+	}
+	return err
+}
+
+// subcommand returns the restic subcommand run by cmd, skipping any prefix command e.g. nice or ionice.
+func (r *Repo) subcommand(cmd *exec.Cmd) string {
+	for i, arg := range cmd.Args {
+		if arg == r.cmd && i+1 < len(cmd.Args) {
+			if next := cmd.Args[i+1]; !strings.HasPrefix(next, "-") {
+				return next
+			}
+			break
+		}
+	}
+	return "command"
+}
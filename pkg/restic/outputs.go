@@ -225,9 +225,165 @@ func readLs(output io.Reader) (*Snapshot, []*LsEntry, error) {
 	return snapshot, entries, nil
 }
 
+// readLsEntries calls fn for each entry of restic ls --json output, the leading snapshot line is skipped.
+func readLsEntries(output io.Reader, fn func(entry *LsEntry) error) error {
+	scanner := bufio.NewScanner(output)
+	scanner.Buffer(make([]byte, 0, 64*1024), 1024*1024)
+
+	if !scanner.Scan() {
+		return scanner.Err()
+	}
+	for scanner.Scan() {
+		var entry LsEntry
+		if err := json.Unmarshal(scanner.Bytes(), &entry); err != nil {
+			return fmt.Errorf("failed to parse JSON: %w", err)
+		}
+		if err := fn(&entry); err != nil {
+			return err
+		}
+	}
+	return scanner.Err()
+}
+
+// DiffChange is a path that differs between two snapshots. Directory paths end with a slash.
+type DiffChange struct {
+	MessageType string `json:"message_type"` // "change"
+	Path        string `json:"path"`
+	Modifier    string `json:"modifier"` // + added, - removed, M content modified, T type changed, U metadata changed, ? bitrot.
+}
+
+type DiffStat struct {
+	Files     int   `json:"files"`
+	Dirs      int   `json:"dirs"`
+	Others    int   `json:"others"`
+	DataBlobs int   `json:"data_blobs"`
+	TreeBlobs int   `json:"tree_blobs"`
+	Bytes     int64 `json:"bytes"`
+}
+
+type DiffStatistics struct {
+	MessageType    string   `json:"message_type"` // "statistics"
+	SourceSnapshot string   `json:"source_snapshot"`
+	TargetSnapshot string   `json:"target_snapshot"`
+	ChangedFiles   int      `json:"changed_files"`
+	Added          DiffStat `json:"added"`
+	Removed        DiffStat `json:"removed"`
+}
+
+// readDiff calls fn for each change in restic diff --json output and returns the statistics that end the output.
+func readDiff(output io.Reader, fn func(change *DiffChange) error) (*DiffStatistics, error) {
+	scanner := bufio.NewScanner(output)
+	scanner.Buffer(make([]byte, 0, 64*1024), 1024*1024)
+
+	var stats *DiffStatistics
+	for scanner.Scan() {
+		line := scanner.Bytes()
+		var msg struct {
+			MessageType string `json:"message_type"`
+		}
+		if err := json.Unmarshal(line, &msg); err != nil {
+			return nil, fmt.Errorf("failed to parse JSON: %w", err)
+		}
+		switch msg.MessageType {
+		case "change":
+			var change DiffChange
+			if err := json.Unmarshal(line, &change); err != nil {
+				return nil, fmt.Errorf("failed to parse JSON: %w", err)
+			}
+			if err := fn(&change); err != nil {
+				return nil, err
+			}
+		case "statistics":
+			stats = &DiffStatistics{}
+			if err := json.Unmarshal(line, stats); err != nil {
+				return nil, fmt.Errorf("failed to parse JSON: %w", err)
+			}
+		}
+	}
+	if err := scanner.Err(); err != nil {
+		return nil, err
+	}
+	if stats == nil {
+		return nil, errors.New("no statistics in diff output")
+	}
+	return stats, nil
+}
+
+// readFind calls fn for each snapshot in restic find --json output. At most maxMatches matches are kept per snapshot,
+// Hits counts all of them. Snapshots are decoded one at a time as the output is read.
+func readFind(output io.Reader, maxMatches int, fn func(result *FindResult) error) error {
+	dec := json.NewDecoder(output)
+	if err := expectDelim(dec, '['); err != nil {
+		return err
+	}
+	for dec.More() {
+		if err := expectDelim(dec, '{'); err != nil {
+			return err
+		}
+		result := &FindResult{}
+		for dec.More() {
+			key, err := dec.Token()
+			if err != nil {
+				return fmt.Errorf("failed to parse JSON: %w", err)
+			}
+			switch key {
+			case "matches":
+				if err := expectDelim(dec, '['); err != nil {
+					return err
+				}
+				for dec.More() {
+					var entry LsEntry
+					if err := dec.Decode(&entry); err != nil {
+						return fmt.Errorf("failed to parse JSON: %w", err)
+					}
+					if len(result.Matches) < maxMatches {
+						result.Matches = append(result.Matches, &entry)
+					}
+				}
+				err = expectDelim(dec, ']')
+			case "hits":
+				err = dec.Decode(&result.Hits)
+			case "snapshot":
+				err = dec.Decode(&result.Snapshot)
+			default:
+				var value json.RawMessage
+				err = dec.Decode(&value)
+			}
+			if err != nil {
+				return fmt.Errorf("failed to parse JSON: %w", err)
+			}
+		}
+		if err := expectDelim(dec, '}'); err != nil {
+			return err
+		}
+		if err := fn(result); err != nil {
+			return err
+		}
+	}
+	return expectDelim(dec, ']')
+}
+
+func expectDelim(dec *json.Decoder, delim json.Delim) error {
+	tok, err := dec.Token()
+	if err != nil {
+		return fmt.Errorf("failed to parse JSON: %w", err)
+	}
+	if tok != delim {
+		return fmt.Errorf("failed to parse JSON: got %v, expected %v", tok, delim)
+	}
+	return nil
+}
+
 type ForgetResult struct {
-	Keep   []Snapshot `json:"keep"`
-	Remove []Snapshot `json:"remove"`
+	Keep    []Snapshot   `json:"keep"`
+	Remove  []Snapshot   `json:"remove"`
+	Reasons []KeepReason `json:"reasons"`
+}
+
+// KeepReason lists the rules of a retention policy that keep a snapshot e.g. "daily snapshot".
+type KeepReason struct {
+	Snapshot Snapshot `json:"snapshot"`
+	Matches  []string `json:"matches"`
 }
 
 func (r *ForgetResult) Validate() error {
@@ -60,9 +60,20 @@ func (r *Repo) commandWithContext(ctx context.Context, args []string, opts ...Ge
 	}
 
 	fullCmd = append(fullCmd, opt.extraArgs...)
+	fullCmd = append(fullCmd, opt.trailingArgs...)
 
 	cmd := exec.CommandContext(ctx, fullCmd[0], fullCmd[1:]...)
 	cmd.Env = append(cmd.Env, opt.extraEnv...)
+	for _, envFunc := range opt.envFuncs {
+		if cmd.Err != nil {
+			break // the command can't be started.
+		}
+		env, err := envFunc(ctx)
+		if err != nil {
+			cmd.Err = err // returned when the command is started.
+		}
+		cmd.Env = append(cmd.Env, env...)
+	}
 
 	logger := LoggerFromContext(ctx)
 	if logger != nil {
@@ -99,7 +110,7 @@ func (r *Repo) Exists(ctx context.Context, opts ...GenericOption) error {
 		output := bytes.NewBuffer(nil)
 		cmd := r.commandWithContext(ctx, []string{"cat", "config"}, opts...)
 		r.pipeCmdOutputToWriter(cmd, output)
-		if err := cmd.Run(); err != nil {
+		if err := r.runCmd(ctx, cmd); err != nil {
 			var exitErr *exec.ExitError
 			if errors.As(err, &exitErr) && exitErr.ExitCode() == 10 {
 				err = ErrRepoNotFound
@@ -125,7 +136,7 @@ func (r *Repo) init(ctx context.Context, opts ...GenericOption) error {
 		output := bytes.NewBuffer(nil)
 		r.pipeCmdOutputToWriter(cmd, output)
 
-		if err := cmd.Run(); err != nil {
+		if err := r.runCmd(ctx, cmd); err != nil {
 			if strings.Contains(output.String(), "config file already exists") || strings.Contains(output.String(), "already initialized") {
 				r.initialized = errAlreadyInitialized
 			} else {
@@ -165,6 +176,26 @@ func (r *Repo) Backup(ctx context.Context, paths []string, progressCallback func
 
 	args := []string{"backup", "--json"}
 	args = append(args, paths...)
+	return r.backup(ctx, args, progressCallback, opts...)
+}
+
+// BackupFromCommand backs up the stdout of a command as a single file named filename. The backup fails without creating
+// a snapshot if the command exits with a non-zero status.
+func (r *Repo) BackupFromCommand(ctx context.Context, command []string, filename string, progressCallback func(*BackupProgressEntry), opts ...GenericOption) (*BackupProgressEntry, error) {
+	if len(command) == 0 {
+		return nil, errors.New("command is required")
+	}
+	if filename == "" {
+		return nil, errors.New("filename is required")
+	}
+
+	args := []string{"backup", "--json", "--stdin-filename", filename, "--stdin-from-command"}
+	// the command must follow all restic flags so that they are not passed to it.
+	opts = append(slices.Clone(opts), withTrailingArgs(append([]string{"--"}, command...)...))
+	return r.backup(ctx, args, progressCallback, opts...)
+}
+
+func (r *Repo) backup(ctx context.Context, args []string, progressCallback func(*BackupProgressEntry), opts ...GenericOption) (*BackupProgressEntry, error) {
 	opts = append(slices.Clone(opts), WithEnv("RESTIC_PROGRESS_FPS=2"))
 
 	logger := LoggerFromContext(ctx)
@@ -195,7 +226,7 @@ func (r *Repo) Backup(ctx context.Context, paths []string, progressCallback func
 		}
 	}()
 
-	cmdErr := cmd.Run()
+	cmdErr := r.runCmd(ctx, cmd)
 	writer.Close()
 	wg.Wait()
 
@@ -243,7 +274,7 @@ func (r *Repo) Restore(ctx context.Context, snapshot string, callback func(*Rest
 		}
 	}()
 
-	cmdErr := cmd.Run()
+	cmdErr := r.runCmd(ctx, cmd)
 	writer.Close()
 	wg.Wait()
 	if cmdErr != nil || readErr != nil {
@@ -268,7 +299,7 @@ func (r *Repo) Snapshots(ctx context.Context, opts ...GenericOption) ([]*Snapsho
 	output := bytes.NewBuffer(nil)
 	r.pipeCmdOutputToWriter(cmd, output)
 
-	if err := cmd.Run(); err != nil {
+	if err := r.runCmd(ctx, cmd); err != nil {
 		return nil, newCmdError(ctx, cmd, newErrorWithOutput(err, output.String()))
 	}
 
@@ -292,7 +323,7 @@ func (r *Repo) Forget(ctx context.Context, policy *RetentionPolicy, opts ...Gene
 	cmd := r.commandWithContext(ctx, args, opts...)
 	output := bytes.NewBuffer(nil)
 	r.pipeCmdOutputToWriter(cmd, output)
-	if err := cmd.Run(); err != nil {
+	if err := r.runCmd(ctx, cmd); err != nil {
 		return nil, newCmdError(ctx, cmd, newErrorWithOutput(err, output.String()))
 	}
 
@@ -300,14 +331,21 @@ func (r *Repo) Forget(ctx context.Context, policy *RetentionPolicy, opts ...Gene
 	if err := json.Unmarshal(output.Bytes(), &result); err != nil {
 		return nil, newCmdError(ctx, cmd, newErrorWithOutput(fmt.Errorf("command output is not valid JSON: %w", err), output.String()))
 	}
-	if len(result) != 1 {
-		return nil, fmt.Errorf("expected 1 output from forget, got %v", len(result))
-	}
-	if err := result[0].Validate(); err != nil {
-		return nil, newCmdError(ctx, cmd, fmt.Errorf("invalid forget result: %w", err))
+	if len(result) == 0 {
+		return nil, fmt.Errorf("expected at least 1 output from forget, got %v", len(result))
 	}
 
-	return &result[0], nil
+	// restic applies the policy to each group separately e.g. with --group-by tags, the results are merged.
+	merged := &ForgetResult{}
+	for _, group := range result {
+		if err := group.Validate(); err != nil {
+			return nil, newCmdError(ctx, cmd, fmt.Errorf("invalid forget result: %w", err))
+		}
+		merged.Keep = append(merged.Keep, group.Keep...)
+		merged.Remove = append(merged.Remove, group.Remove...)
+		merged.Reasons = append(merged.Reasons, group.Reasons...)
+	}
+	return merged, nil
 }
 
 func (r *Repo) ForgetSnapshot(ctx context.Context, snapshotId string, opts ...GenericOption) error {
@@ -316,7 +354,7 @@ func (r *Repo) ForgetSnapshot(ctx context.Context, snapshotId string, opts ...Ge
 	output := bytes.NewBuffer(nil)
 	cmd := r.commandWithContext(ctx, args, opts...)
 	r.pipeCmdOutputToWriter(cmd, output)
-	if err := cmd.Run(); err != nil {
+	if err := r.runCmd(ctx, cmd); err != nil {
 		return newCmdError(ctx, cmd, newErrorWithOutput(err, output.String()))
 	}
 
@@ -329,7 +367,7 @@ func (r *Repo) Prune(ctx context.Context, pruneOutput io.Writer, opts ...Generic
 	if pruneOutput != nil {
 		r.pipeCmdOutputToWriter(cmd, pruneOutput)
 	}
-	if err := cmd.Run(); err != nil {
+	if err := r.runCmd(ctx, cmd); err != nil {
 		return newCmdError(ctx, cmd, err)
 	}
 	return nil
@@ -342,7 +380,27 @@ func (r *Repo) Check(ctx context.Context, checkOutput io.Writer, opts ...Generic
 	if checkOutput != nil {
 		r.pipeCmdOutputToWriter(cmd, checkOutput)
 	}
-	if err := cmd.Run(); err != nil {
+	if err := r.runCmd(ctx, cmd); err != nil {
+		return newCmdError(ctx, cmd, err)
+	}
+	return nil
+}
+
+// Copy copies snapshots from the repo at fromRepo to this repo, snapshots that already exist in this repo are skipped.
+// The source repo's password must be provided in the RESTIC_FROM_PASSWORD, RESTIC_FROM_PASSWORD_FILE or
+// RESTIC_FROM_PASSWORD_COMMAND environment variable. fromRepo may be empty if RESTIC_FROM_REPOSITORY or
+// RESTIC_FROM_REPOSITORY_FILE is set instead.
+func (r *Repo) Copy(ctx context.Context, fromRepo string, copyOutput io.Writer, opts ...GenericOption) error {
+	args := []string{"copy"}
+	if fromRepo != "" {
+		args = append(args, "--from-repo", fromRepo)
+	}
+	cmd := r.commandWithContext(ctx, args, opts...)
+	cmd.Stdin = bytes.NewBuffer(nil)
+	if copyOutput != nil {
+		r.pipeCmdOutputToWriter(cmd, copyOutput)
+	}
+	if err := r.runCmd(ctx, cmd); err != nil {
 		return newCmdError(ctx, cmd, err)
 	}
 	return nil
@@ -358,7 +416,7 @@ func (r *Repo) ListDirectory(ctx context.Context, snapshot string, path string,
 	output := bytes.NewBuffer(nil)
 	r.pipeCmdOutputToWriter(cmd, output)
 
-	if err := cmd.Run(); err != nil {
+	if err := r.runCmd(ctx, cmd); err != nil {
 		return nil, nil, newCmdError(ctx, cmd, newErrorWithOutput(err, output.String()))
 	}
 
@@ -370,11 +428,99 @@ func (r *Repo) ListDirectory(ctx context.Context, snapshot string, path string,
 	return snapshots, entries, nil
 }
 
+// Dump writes the contents of a file in a snapshot to w, directories are written as a tar archive.
+func (r *Repo) Dump(ctx context.Context, snapshot string, path string, w io.Writer, opts ...GenericOption) error {
+	cmd := r.commandWithContext(ctx, []string{"dump", snapshot, path}, opts...)
+	stderr := bytes.NewBuffer(nil)
+	if cmd.Stderr != nil {
+		cmd.Stderr = io.MultiWriter(cmd.Stderr, stderr)
+	} else {
+		cmd.Stderr = stderr
+	}
+	cmd.Stdout = w
+	if err := r.runCmd(ctx, cmd); err != nil {
+		return newCmdError(ctx, cmd, newErrorWithOutput(err, stderr.String()))
+	}
+	return nil
+}
+
+// ListFiles calls fn for every file and directory in a snapshot. If paths are given only those paths and the direct
+// children of directories among them are listed. Entries are streamed, fn may return an error to stop.
+func (r *Repo) ListFiles(ctx context.Context, snapshot string, paths []string, fn func(entry *LsEntry) error, opts ...GenericOption) error {
+	return r.runStreaming(ctx, append([]string{"ls", "--json", snapshot}, paths...), func(output io.Reader) error {
+		return readLsEntries(output, fn)
+	}, opts...)
+}
+
+// Diff calls fn for each path that changed from snapshot a to snapshot b and returns the diff's statistics. Changes
+// are streamed, fn may return an error to stop, the statistics aren't available then.
+func (r *Repo) Diff(ctx context.Context, a string, b string, fn func(change *DiffChange) error, opts ...GenericOption) (*DiffStatistics, error) {
+	var stats *DiffStatistics
+	err := r.runStreaming(ctx, []string{"diff", "--json", a, b}, func(output io.Reader) error {
+		var err error
+		stats, err = readDiff(output, fn)
+		return err
+	}, opts...)
+	if err != nil {
+		return nil, err
+	}
+	return stats, nil
+}
+
+// runStreaming runs a restic command and passes its output to read as it's written. If read returns an error the
+// command is stopped and the error is returned as is.
+func (r *Repo) runStreaming(ctx context.Context, args []string, read func(output io.Reader) error, opts ...GenericOption) error {
+	cmdCtx, cancel := context.WithCancel(ctx)
+	defer cancel()
+	cmd := r.commandWithContext(cmdCtx, args, opts...)
+	stderr := bytes.NewBuffer(nil)
+	if cmd.Stderr != nil {
+		cmd.Stderr = io.MultiWriter(cmd.Stderr, stderr)
+	} else {
+		cmd.Stderr = stderr
+	}
+	reader, writer := io.Pipe()
+	cmd.Stdout = writer
+
+	var readErr error
+	done := make(chan struct{})
+	go func() {
+		defer close(done)
+		if readErr = read(reader); readErr != nil {
+			cancel() // stop the command, the remaining output isn't read.
+			reader.CloseWithError(readErr)
+			return
+		}
+		io.Copy(io.Discard, reader) // don't block the command if read stopped before the end of the output.
+	}()
+
+	cmdErr := r.runCmd(cmdCtx, cmd)
+	writer.Close()
+	<-done
+
+	if readErr != nil {
+		return readErr
+	}
+	if cmdErr != nil {
+		return newCmdError(ctx, cmd, newErrorWithOutput(cmdErr, stderr.String()))
+	}
+	return nil
+}
+
+// Find calls fn with the files matching any of the glob patterns in each snapshot, snapshots without matches are
+// omitted. At most maxMatches matches are kept per snapshot. Results are streamed, fn may return an error to stop.
+// All snapshots are searched unless limited by opts e.g. WithFlags("--snapshot", id).
+func (r *Repo) Find(ctx context.Context, patterns []string, maxMatches int, fn func(result *FindResult) error, opts ...GenericOption) error {
+	return r.runStreaming(ctx, []string{"find", "--json"}, func(output io.Reader) error {
+		return readFind(output, maxMatches, fn)
+	}, append(opts, withTrailingArgs(append([]string{"--"}, patterns...)...))...)
+}
+
 func (r *Repo) Unlock(ctx context.Context, opts ...GenericOption) error {
 	output := bytes.NewBuffer(nil)
 	cmd := r.commandWithContext(ctx, []string{"unlock"}, opts...)
 	r.pipeCmdOutputToWriter(cmd, output)
-	if err := cmd.Run(); err != nil {
+	if err := r.runCmd(ctx, cmd); err != nil {
 		return newCmdError(ctx, cmd, newErrorWithOutput(err, output.String()))
 	}
 	return nil
@@ -385,7 +531,7 @@ func (r *Repo) Stats(ctx context.Context, opts ...GenericOption) (*RepoStats, er
 	output := bytes.NewBuffer(nil)
 	r.pipeCmdOutputToWriter(cmd, output)
 
-	if err := cmd.Run(); err != nil {
+	if err := r.runCmd(ctx, cmd); err != nil {
 		return nil, newCmdError(ctx, cmd, err)
 	}
 
@@ -404,7 +550,7 @@ func (r *Repo) AddTags(ctx context.Context, snapshotIDs []string, tags []string,
 	args = append(args, snapshotIDs...)
 
 	cmd := r.commandWithContext(ctx, args, opts...)
-	if err := cmd.Run(); err != nil {
+	if err := r.runCmd(ctx, cmd); err != nil {
 		return newCmdError(ctx, cmd, err)
 	}
 	return nil
@@ -412,20 +558,26 @@ func (r *Repo) AddTags(ctx context.Context, snapshotIDs []string, tags []string,
 
 func (r *Repo) GenericCommand(ctx context.Context, args []string, opts ...GenericOption) error {
 	cmd := r.commandWithContext(ctx, args, opts...)
-	if err := cmd.Run(); err != nil {
+	if err := r.runCmd(ctx, cmd); err != nil {
 		return err
 	}
 	return nil
 }
 
 type RetentionPolicy struct {
-	KeepLastN          int    // keep the last n snapshots.
-	KeepHourly         int    // keep the last n hourly snapshots.
-	KeepDaily          int    // keep the last n daily snapshots.
-	KeepWeekly         int    // keep the last n weekly snapshots.
-	KeepMonthly        int    // keep the last n monthly snapshots.
-	KeepYearly         int    // keep the last n yearly snapshots.
-	KeepWithinDuration string // keep snapshots within a duration e.g. 1y2m3d4h5m6s
+	KeepLastN          int      // keep the last n snapshots.
+	KeepHourly         int      // keep the last n hourly snapshots.
+	KeepDaily          int      // keep the last n daily snapshots.
+	KeepWeekly         int      // keep the last n weekly snapshots.
+	KeepMonthly        int      // keep the last n monthly snapshots.
+	KeepYearly         int      // keep the last n yearly snapshots.
+	KeepWithinDuration string   // keep snapshots within a duration e.g. 1y2m3d4h
+	KeepWithinHourly   string   // keep hourly snapshots within a duration.
+	KeepWithinDaily    string   // keep daily snapshots within a duration.
+	KeepWithinWeekly   string   // keep weekly snapshots within a duration.
+	KeepWithinMonthly  string   // keep monthly snapshots within a duration.
+	KeepWithinYearly   string   // keep yearly snapshots within a duration.
+	KeepTags           []string // keep snapshots with all of the comma separated tags of any entry.
 }
 
 func (r *RetentionPolicy) toForgetFlags() []string {
@@ -451,13 +603,34 @@ func (r *RetentionPolicy) toForgetFlags() []string {
 	if r.KeepWithinDuration != "" {
 		flags = append(flags, "--keep-within", r.KeepWithinDuration)
 	}
+	if r.KeepWithinHourly != "" {
+		flags = append(flags, "--keep-within-hourly", r.KeepWithinHourly)
+	}
+	if r.KeepWithinDaily != "" {
+		flags = append(flags, "--keep-within-daily", r.KeepWithinDaily)
+	}
+	if r.KeepWithinWeekly != "" {
+		flags = append(flags, "--keep-within-weekly", r.KeepWithinWeekly)
+	}
+	if r.KeepWithinMonthly != "" {
+		flags = append(flags, "--keep-within-monthly", r.KeepWithinMonthly)
+	}
+	if r.KeepWithinYearly != "" {
+		flags = append(flags, "--keep-within-yearly", r.KeepWithinYearly)
+	}
+	for _, tags := range r.KeepTags {
+		flags = append(flags, "--keep-tag", tags)
+	}
 	return flags
 }
 
 type GenericOpts struct {
-	extraArgs []string
-	extraEnv  []string
-	prefixCmd []string
+	extraArgs    []string
+	extraEnv     []string
+	envFuncs     []func(ctx context.Context) ([]string, error)
+	prefixCmd    []string
+	trailingArgs []string // args that must come after extraArgs e.g. the command run by --stdin-from-command.
+	commandHooks []CommandHook
 }
 
 func resolveOpts(opt *GenericOpts, opts []GenericOption) {
@@ -488,6 +661,14 @@ func WithEnv(env ...string) GenericOption {
 	}
 }
 
+// WithEnvFunc adds env vars that are computed each time a command is built e.g. secrets that must not be cached. The
+// vars are added after those of WithEnv, an error fails the command when it is started.
+func WithEnvFunc(f func(ctx context.Context) ([]string, error)) GenericOption {
+	return func(opts *GenericOpts) {
+		opts.envFuncs = append(opts.envFuncs, f)
+	}
+}
+
 var EnvToPropagate = []string{
 	// *nix systems
 	"PATH", "HOME", "XDG_CACHE_HOME", "XDG_CONFIG_HOME", "XDG_DATA_HOME",
@@ -511,6 +692,12 @@ func WithEnviron() GenericOption {
 	return WithEnv(os.Environ()...)
 }
 
+func withTrailingArgs(args ...string) GenericOption {
+	return func(opts *GenericOpts) {
+		opts.trailingArgs = append(opts.trailingArgs, args...)
+	}
+}
+
 func WithPrefixCommand(args ...string) GenericOption {
 	return func(opts *GenericOpts) {
 		opts.prefixCmd = append(opts.prefixCmd, args...)
@@ -192,6 +192,49 @@ func TestResticBackup(t *testing.T) {
 	}
 }
 
+func TestResticBackupFromCommand(t *testing.T) {
+	t.Parallel()
+	if runtime.GOOS == "windows" {
+		t.Skip("test is unix only")
+	}
+	repo := t.TempDir()
+
+	r := NewRepo(helpers.ResticBinary(t), repo, WithFlags("--no-cache"), WithEnv("RESTIC_PASSWORD=test"))
+	if err := r.Init(context.Background()); err != nil {
+		t.Fatalf("failed to init repo: %v", err)
+	}
+
+	// flags must be passed to restic rather than to the command.
+	summary, err := r.BackupFromCommand(context.Background(), []string{"sh", "-c", "echo hello"}, "dump.sql", func(event *BackupProgressEntry) {}, WithFlags("--tag", "dump"))
+	if err != nil {
+		t.Fatalf("backup from command failed: %v", err)
+	}
+	if summary.TotalFilesProcessed != 1 || summary.SnapshotId == "" {
+		t.Errorf("wanted 1 file in a new snapshot, got summary: %+v", summary)
+	}
+
+	snapshots, err := r.Snapshots(context.Background(), WithFlags("--tag", "dump"))
+	if err != nil {
+		t.Fatalf("failed to list snapshots: %v", err)
+	}
+	if len(snapshots) != 1 || !slices.Equal(snapshots[0].Paths, []string{"/dump.sql"}) {
+		t.Errorf("wanted one snapshot of /dump.sql, got: %+v", snapshots)
+	}
+
+	var dumped bytes.Buffer
+	if err := r.Dump(context.Background(), summary.SnapshotId, "/dump.sql", &dumped); err != nil {
+		t.Fatalf("failed to dump file: %v", err)
+	}
+	if dumped.String() != "hello\n" {
+		t.Errorf("wanted dumped file to contain the command output, got: %q", dumped.String())
+	}
+
+	// a failing command fails the backup.
+	if _, err := r.BackupFromCommand(context.Background(), []string{"sh", "-c", "echo partial; exit 2"}, "dump.sql", func(event *BackupProgressEntry) {}); err == nil {
+		t.Errorf("wanted error from failing command, got nil")
+	}
+}
+
 func TestResticPartialBackup(t *testing.T) {
 	t.Parallel()
 	repo := t.TempDir()
@@ -435,6 +478,57 @@ func TestResticForget(t *testing.T) {
 	}
 }
 
+func TestRetentionPolicyForgetFlags(t *testing.T) {
+	policy := &RetentionPolicy{
+		KeepLastN:        2,
+		KeepMonthly:      12,
+		KeepWithinDaily:  "7d",
+		KeepWithinYearly: "10y",
+		KeepTags:         []string{"keep", "a,b"},
+	}
+	want := []string{"--keep-last", "2", "--keep-monthly", "12", "--keep-within-daily", "7d", "--keep-within-yearly", "10y", "--keep-tag", "keep", "--keep-tag", "a,b"}
+	if got := policy.toForgetFlags(); !slices.Equal(got, want) {
+		t.Errorf("wanted flags %q, got: %q", want, got)
+	}
+}
+
+func TestResticForgetDryRun(t *testing.T) {
+	t.Parallel()
+
+	repo := t.TempDir()
+	r := NewRepo(helpers.ResticBinary(t), repo, WithFlags("--no-cache"), WithEnv("RESTIC_PASSWORD=test"))
+	if err := r.Init(context.Background()); err != nil {
+		t.Fatalf("failed to init repo: %v", err)
+	}
+
+	testData := helpers.CreateTestData(t)
+	for i := 0; i < 3; i++ {
+		if _, err := r.Backup(context.Background(), []string{testData}, nil); err != nil {
+			t.Fatalf("failed to backup and create new snapshot: %v", err)
+		}
+	}
+
+	res, err := r.Forget(context.Background(), &RetentionPolicy{KeepLastN: 1}, WithFlags("--dry-run", "--no-lock"))
+	if err != nil {
+		t.Fatalf("failed to forget snapshots: %v", err)
+	}
+	if len(res.Keep) != 1 || len(res.Remove) != 2 {
+		t.Errorf("wanted 1 snapshot kept and 2 removed, got: %d kept, %d removed", len(res.Keep), len(res.Remove))
+	}
+	if len(res.Reasons) != 1 || res.Reasons[0].Snapshot.Id != res.Keep[0].Id || !slices.Contains(res.Reasons[0].Matches, "last snapshot") {
+		t.Errorf("wanted the kept snapshot to be kept as the last snapshot, got reasons: %+v", res.Reasons)
+	}
+
+	// a dry run doesn't remove snapshots.
+	snapshots, err := r.Snapshots(context.Background())
+	if err != nil {
+		t.Fatalf("failed to list snapshots: %v", err)
+	}
+	if len(snapshots) != 3 {
+		t.Errorf("wanted 3 snapshots after a dry run, got: %d", len(snapshots))
+	}
+}
+
 func TestForgetSnapshotId(t *testing.T) {
 	t.Parallel()
 
@@ -580,6 +674,164 @@ func TestResticStats(t *testing.T) {
 	}
 }
 
+func TestResticDiff(t *testing.T) {
+	t.Parallel()
+	if runtime.GOOS == "windows" {
+		t.Skip("test is unix only")
+	}
+
+	repo := t.TempDir()
+	r := NewRepo(helpers.ResticBinary(t), repo, WithFlags("--no-cache"), WithEnv("RESTIC_PASSWORD=test"))
+	if err := r.Init(context.Background()); err != nil {
+		t.Fatalf("failed to init repo: %v", err)
+	}
+
+	testData := helpers.CreateTestData(t)
+	before, err := r.Backup(context.Background(), []string{testData}, nil)
+	if err != nil {
+		t.Fatalf("failed to backup and create new snapshot: %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(testData, "file 1"), []byte("changed data"), 0644); err != nil {
+		t.Fatalf("failed to modify file: %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(testData, "new"), []byte("new data"), 0644); err != nil {
+		t.Fatalf("failed to add file: %v", err)
+	}
+	if err := os.Remove(filepath.Join(testData, "file 2")); err != nil {
+		t.Fatalf("failed to remove file: %v", err)
+	}
+	after, err := r.Backup(context.Background(), []string{testData}, nil)
+	if err != nil {
+		t.Fatalf("failed to backup and create new snapshot: %v", err)
+	}
+
+	modifiers := make(map[string]string)
+	stats, err := r.Diff(context.Background(), before.SnapshotId, after.SnapshotId, func(change *DiffChange) error {
+		modifiers[filepath.Base(change.Path)] = change.Modifier
+		return nil
+	})
+	if err != nil {
+		t.Fatalf("failed to diff snapshots: %v", err)
+	}
+	if modifiers["file 1"] != "M" || modifiers["new"] != "+" || modifiers["file 2"] != "-" {
+		t.Errorf("wanted file 1 modified, new added and file 2 removed, got modifiers: %v", modifiers)
+	}
+	if stats.Added.Files == 0 || stats.Removed.Files == 0 || stats.Added.Bytes == 0 {
+		t.Errorf("wanted files and bytes added and files removed, got statistics: %+v", stats)
+	}
+
+	// an error returned by the callback stops the diff.
+	errStop := errors.New("stop")
+	if _, err := r.Diff(context.Background(), before.SnapshotId, after.SnapshotId, func(change *DiffChange) error { return errStop }); !errors.Is(err, errStop) {
+		t.Errorf("wanted the callback's error from diff, got: %v", err)
+	}
+
+	var sizes []int64
+	if err := r.ListFiles(context.Background(), after.SnapshotId, nil, func(entry *LsEntry) error {
+		if entry.Name == "new" {
+			sizes = append(sizes, entry.Size)
+		}
+		return nil
+	}); err != nil {
+		t.Fatalf("failed to list files: %v", err)
+	}
+	if !slices.Equal(sizes, []int64{int64(len("new data"))}) {
+		t.Errorf("wanted the new file listed with its size, got sizes: %v", sizes)
+	}
+
+	// only the given paths are listed.
+	var paths []string
+	if err := r.ListFiles(context.Background(), after.SnapshotId, []string{filepath.Join(testData, "new")}, func(entry *LsEntry) error {
+		paths = append(paths, entry.Path)
+		return nil
+	}); err != nil {
+		t.Fatalf("failed to list path: %v", err)
+	}
+	if !slices.Equal(paths, []string{filepath.ToSlash(filepath.Join(testData, "new"))}) {
+		t.Errorf("wanted only the new file listed, got paths: %v", paths)
+	}
+
+	// an error returned by the callback stops the listing.
+	if err := r.ListFiles(context.Background(), after.SnapshotId, nil, func(entry *LsEntry) error { return errStop }); !errors.Is(err, errStop) {
+		t.Errorf("wanted the callback's error, got: %v", err)
+	}
+}
+
+func TestResticFind(t *testing.T) {
+	t.Parallel()
+
+	repo := t.TempDir()
+	r := NewRepo(helpers.ResticBinary(t), repo, WithFlags("--no-cache"), WithEnv("RESTIC_PASSWORD=test"))
+	if err := r.Init(context.Background()); err != nil {
+		t.Fatalf("failed to init repo: %v", err)
+	}
+
+	testData := helpers.CreateTestData(t)
+	first, err := r.Backup(context.Background(), []string{testData}, nil)
+	if err != nil {
+		t.Fatalf("failed to backup and create new snapshot: %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(testData, "report.txt"), []byte("report"), 0644); err != nil {
+		t.Fatalf("failed to add file: %v", err)
+	}
+	second, err := r.Backup(context.Background(), []string{testData}, nil)
+	if err != nil {
+		t.Fatalf("failed to backup and create new snapshot: %v", err)
+	}
+
+	find := func(patterns []string, maxMatches int, opts ...GenericOption) ([]*FindResult, error) {
+		var results []*FindResult
+		err := r.Find(context.Background(), patterns, maxMatches, func(result *FindResult) error {
+			results = append(results, result)
+			return nil
+		}, opts...)
+		return results, err
+	}
+
+	results, err := find([]string{"report.*"}, 100)
+	if err != nil {
+		t.Fatalf("failed to find files: %v", err)
+	}
+	if len(results) != 1 || !strings.HasPrefix(results[0].Snapshot, second.SnapshotId) {
+		t.Fatalf("wanted matches only in snapshot %v, got: %+v", second.SnapshotId, results)
+	}
+	if len(results[0].Matches) != 1 || results[0].Matches[0].Name != "report.txt" || results[0].Matches[0].Size != int64(len("report")) {
+		t.Errorf("wanted report.txt to match, got: %+v", results[0].Matches)
+	}
+
+	// the search can be limited to some snapshots.
+	results, err = find([]string{"file 1"}, 100, WithFlags("--snapshot", first.SnapshotId))
+	if err != nil {
+		t.Fatalf("failed to find files: %v", err)
+	}
+	if len(results) != 1 || !strings.HasPrefix(results[0].Snapshot, first.SnapshotId) || len(results[0].Matches) != 1 {
+		t.Errorf("wanted one match in snapshot %v, got: %+v", first.SnapshotId, results)
+	}
+
+	results, err = find([]string{"does-not-exist"}, 100)
+	if err != nil {
+		t.Fatalf("failed to find files: %v", err)
+	}
+	if len(results) != 0 {
+		t.Errorf("wanted no matches, got: %+v", results)
+	}
+
+	// matches beyond the limit are counted but not kept.
+	results, err = find([]string{"file *"}, 1, WithFlags("--snapshot", first.SnapshotId))
+	if err != nil {
+		t.Fatalf("failed to find files: %v", err)
+	}
+	if len(results) != 1 || len(results[0].Matches) != 1 || results[0].Hits < 2 {
+		t.Errorf("wanted one of several matches kept, got: %+v", results)
+	}
+
+	// an error returned by the callback stops the search.
+	errStop := errors.New("stop")
+	if err := r.Find(context.Background(), []string{"file *"}, 100, func(result *FindResult) error { return errStop }); !errors.Is(err, errStop) {
+		t.Errorf("wanted the callback's error, got: %v", err)
+	}
+}
+
 func TestResticCheck(t *testing.T) {
 	t.Parallel()
 
@@ -608,6 +860,41 @@ func TestResticCheck(t *testing.T) {
 	}
 }
 
+func TestResticCopy(t *testing.T) {
+	t.Parallel()
+
+	src := NewRepo(helpers.ResticBinary(t), t.TempDir(), WithFlags("--no-cache"), WithEnv("RESTIC_PASSWORD=test"))
+	if err := src.Init(context.Background()); err != nil {
+		t.Fatalf("failed to init source repo: %v", err)
+	}
+	dst := NewRepo(helpers.ResticBinary(t), t.TempDir(), WithFlags("--no-cache"), WithEnv("RESTIC_PASSWORD=other"))
+	if err := dst.Init(context.Background()); err != nil {
+		t.Fatalf("failed to init destination repo: %v", err)
+	}
+
+	testData := helpers.CreateTestData(t)
+	for _, tag := range []string{"copy", "skip"} {
+		if _, err := src.Backup(context.Background(), []string{testData}, nil, WithFlags("--tag", tag)); err != nil {
+			t.Fatalf("failed to backup and create new snapshot: %v", err)
+		}
+	}
+
+	// only snapshots matching the tag filter are copied, copying twice doesn't duplicate snapshots.
+	for i := 0; i < 2; i++ {
+		if err := dst.Copy(context.Background(), src.uri, nil, WithEnv("RESTIC_FROM_PASSWORD=test"), WithFlags("--tag", "copy")); err != nil {
+			t.Fatalf("failed to copy snapshots: %v", err)
+		}
+	}
+
+	snapshots, err := dst.Snapshots(context.Background())
+	if err != nil {
+		t.Fatalf("failed to list snapshots: %v", err)
+	}
+	if len(snapshots) != 1 || !slices.Contains(snapshots[0].Tags, "copy") {
+		t.Errorf("wanted the one snapshot tagged copy in the destination, got: %+v", snapshots)
+	}
+}
+
 func toRepoPath(path string) string {
 	if runtime.GOOS != "windows" {
 		return path
@@ -5,12 +5,26 @@ import { Authentication } from "../gen/ts/v1/authentication_pb";
 import { Backrest } from "../gen/ts/v1/service_pb";
 
 const tokenKey = "backrest-ui-authToken";
+const refreshTokenKey = "backrest-ui-refreshToken";
 
-export const setAuthToken = (token: string) => {
+export const setAuthToken = (token: string, refreshToken: string = "") => {
   localStorage.setItem(tokenKey, token);
+  localStorage.setItem(refreshTokenKey, refreshToken);
 };
 
-const fetch = (
+// single sign-on logins redirect back to the UI with the issued tokens in the URL fragment.
+const ssoParams = new URLSearchParams(window.location.hash.substring(1));
+const ssoToken = ssoParams.get("sso_token");
+if (ssoToken) {
+  setAuthToken(ssoToken, ssoParams.get("sso_refresh_token") || "");
+  window.history.replaceState(
+    null,
+    "",
+    window.location.pathname + window.location.search + "#/",
+  );
+}
+
+const fetchWithToken = (
   input: RequestInfo | URL,
   init?: RequestInit,
 ): Promise<Response> => {
@@ -19,8 +33,47 @@ const fetch = (
   if (token && token !== "") {
     headers.set("Authorization", "Bearer " + token);
   }
-  init = { ...init, headers };
-  return window.fetch(input, init);
+  return window.fetch(input, { ...init, headers });
+};
+
+// access tokens are short lived, concurrent requests that find theirs expired share one refresh.
+let pendingRefresh: Promise<boolean> | null = null;
+
+const refreshAuthToken = (): Promise<boolean> => {
+  if (!pendingRefresh) {
+    pendingRefresh = (async () => {
+      const refreshToken = localStorage.getItem(refreshTokenKey);
+      if (!refreshToken) {
+        return false;
+      }
+      try {
+        const res = await authenticationService.refreshToken({ refreshToken });
+        setAuthToken(res.token, res.refreshToken);
+        return true;
+      } catch (e) {
+        setAuthToken("");
+        return false;
+      }
+    })().finally(() => {
+      pendingRefresh = null;
+    });
+  }
+  return pendingRefresh;
+};
+
+const fetch = async (
+  input: RequestInfo | URL,
+  init?: RequestInit,
+): Promise<Response> => {
+  const res = await fetchWithToken(input, init);
+  if (
+    res.status === 401 &&
+    !input.toString().includes("/v1.Authentication/") &&
+    (await refreshAuthToken())
+  ) {
+    return fetchWithToken(input, init);
+  }
+  return res;
 };
 
 const transport = createConnectTransport({
@@ -34,3 +87,16 @@ export const authenticationService = createClient(
   transport,
 );
 export const backrestService = createClient(Backrest, transport);
+
+// logout ends the session on the server so that its tokens can't be used again.
+export const logout = async () => {
+  const refreshToken = localStorage.getItem(refreshTokenKey);
+  if (refreshToken) {
+    try {
+      await authenticationService.logout({ refreshToken });
+    } catch (e) {
+      console.warn("failed to end session: ", e);
+    }
+  }
+  setAuthToken("");
+};
@@ -0,0 +1,164 @@
+import React, { useState } from "react";
+import {
+  Alert,
+  Button,
+  Checkbox,
+  Collapse,
+  DatePicker,
+  Empty,
+  Flex,
+  Input,
+  List,
+  Typography,
+} from "antd";
+import { FileOutlined, FolderOutlined } from "@ant-design/icons";
+import { FindFilesResponse } from "../../gen/ts/v1/service_pb";
+import { backrestService } from "../api";
+import { useShowModal } from "./ModalManager";
+import { RestoreModal } from "./SnapshotBrowser";
+import {
+  formatBytes,
+  formatTime,
+  normalizeSnapshotId,
+} from "../lib/formatting";
+
+// FindFilesView searches the snapshots of a repo, or of a plan if planId is set, for files matching a pattern.
+export const FindFilesView = ({
+  repoId,
+  planId,
+}: {
+  repoId: string;
+  planId?: string;
+}) => {
+  const showModal = useShowModal();
+  const [ignoreCase, setIgnoreCase] = useState(true);
+  const [range, setRange] = useState<[number, number]>([0, 0]);
+  const [result, setResult] = useState<FindFilesResponse | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const search = async (pattern: string) => {
+    if (!pattern) {
+      return;
+    }
+    setLoading(true);
+    setError(null);
+    try {
+      setResult(
+        await backrestService.findFiles({
+          repoId,
+          planId,
+          pattern,
+          ignoreCase,
+          afterUnixTimeMs: BigInt(range[0]),
+          beforeUnixTimeMs: BigInt(range[1]),
+        })
+      );
+    } catch (e: any) {
+      setResult(null);
+      setError("Failed to search snapshots: " + e.message);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <Flex vertical gap="small">
+      <Flex gap="small" align="center" wrap="wrap">
+        <Input.Search
+          style={{ maxWidth: "400px" }}
+          placeholder="File name or glob e.g. *.xlsx or docs/report.xlsx"
+          enterButton="Search"
+          loading={loading}
+          onSearch={search}
+        />
+        <DatePicker.RangePicker
+          showTime
+          allowEmpty={[true, true]}
+          placeholder={["Snapshots after", "Snapshots before"]}
+          onChange={(values) =>
+            setRange([
+              values?.[0]?.valueOf() || 0,
+              values?.[1]?.valueOf() || 0,
+            ])
+          }
+        />
+        <Checkbox
+          checked={ignoreCase}
+          onChange={(e) => setIgnoreCase(e.target.checked)}
+        >
+          Ignore case
+        </Checkbox>
+      </Flex>
+      {error ? <Alert type="error" message={error} /> : null}
+      {result && result.truncated ? (
+        <Alert
+          type="warning"
+          message="Too many files matched, only the matches in the newest snapshots are shown."
+        />
+      ) : null}
+      {result && result.snapshots.length === 0 ? (
+        <Empty description="No matching files found" />
+      ) : null}
+      {result && result.snapshots.length > 0 ? (
+        <Collapse
+          size="small"
+          defaultActiveKey={[result.snapshots[0].snapshot!.id]}
+          items={result.snapshots.map((s) => ({
+            key: s.snapshot!.id,
+            label: (
+              <>
+                Snapshot {normalizeSnapshotId(s.snapshot!.id)} at{" "}
+                {formatTime(Number(s.snapshot!.unixTimeMs))}{" "}
+                <Typography.Text type="secondary">
+                  ({s.matches.length} matches)
+                </Typography.Text>
+              </>
+            ),
+            children: (
+              <List
+                size="small"
+                dataSource={s.matches}
+                renderItem={(entry) => (
+                  <List.Item
+                    actions={[
+                      <Button
+                        key="restore"
+                        size="small"
+                        onClick={() =>
+                          showModal(
+                            <RestoreModal
+                              repoId={repoId}
+                              planId={planId}
+                              snapshotId={s.snapshot!.id}
+                              path={entry.path}
+                            />
+                          )
+                        }
+                      >
+                        Restore
+                      </Button>,
+                    ]}
+                  >
+                    {entry.type === "file" ? (
+                      <FileOutlined />
+                    ) : (
+                      <FolderOutlined />
+                    )}{" "}
+                    {entry.path}{" "}
+                    {entry.type === "file" ? (
+                      <Typography.Text type="secondary">
+                        ({formatBytes(Number(entry.size))}, modified{" "}
+                        {formatTime(new Date(entry.mtime))})
+                      </Typography.Text>
+                    ) : null}
+                  </List.Item>
+                )}
+              />
+            ),
+          }))}
+        />
+      ) : null}
+    </Flex>
+  );
+};
@@ -478,6 +478,17 @@ const HookConditionsTooltip = ({ children }: { children: React.ReactNode }) => {
             <li>CONDITION_CHECK_START - start of check operation</li>
             <li>CONDITION_CHECK_SUCCESS - end of successful check</li>
             <li>CONDITION_CHECK_ERROR - end of failed check</li>
+            <li>CONDITION_COPY_START - start of copy to another repo</li>
+            <li>CONDITION_COPY_SUCCESS - end of successful copy</li>
+            <li>CONDITION_COPY_ERROR - end of failed copy</li>
+            <li>
+              CONDITION_LOGIN_LOCKOUT - repeated failed logins locked out a user
+              or client address
+            </li>
+            <li>
+              CONDITION_PLAN_STALE - no backup of the plan succeeded within its
+              max snapshot age
+            </li>
           </ul>
           for more info see the{" "}
           <a
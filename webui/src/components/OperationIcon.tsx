@@ -1,6 +1,7 @@
 import React from "react";
 import { DisplayType, colorForStatus } from "../state/flowdisplayaggregator";
 import {
+  CloudSyncOutlined,
   CodeOutlined,
   DeleteOutlined,
   DownloadOutlined,
@@ -49,6 +50,9 @@ export const OperationIcon = ({
     case DisplayType.RUNCOMMAND:
       avatar = <CodeOutlined style={{ color: color }} />;
       break;
+    case DisplayType.COPY:
+      avatar = <CloudSyncOutlined style={{ color: color }} />;
+      break;
   }
 
   return avatar;
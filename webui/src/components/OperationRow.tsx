@@ -9,9 +9,11 @@ import {
   Button,
   Col,
   Collapse,
+  Form,
   List,
   Modal,
   Progress,
+  Radio,
   Row,
   Typography,
 } from "antd";
@@ -44,6 +46,9 @@ import { LogView } from "./LogView";
 import { ConfirmButton } from "./SpinButton";
 import { create } from "@bufbuild/protobuf";
 import { OperationListView } from "./OperationListView";
+import { URIAutocomplete } from "./URIAutocomplete";
+import { validateForm } from "../lib/formutil";
+import { pathSeparator } from "../state/buildcfg";
 
 export const OperationRow = ({
   operation,
@@ -263,6 +268,14 @@ export const OperationRow = ({
         <pre>{check.output}</pre>
       ),
     });
+  } else if (operation.op.case === "operationCopy") {
+    const copy = operation.op.value;
+    expandedBodyItems.push("copy");
+    bodyItems.push({
+      key: "copy",
+      label: `Copy Output (to ${copy.toRepo})`,
+      children: <LogView logref={copy.outputLogref} />,
+    });
   } else if (operation.op.case === "operationRunCommand") {
     const run = operation.op.value;
     if (run.outputSizeBytes < 64 * 1024) {
@@ -434,11 +447,92 @@ const SnapshotDetails = ({ snapshot }: { snapshot: ResticSnapshot }) => {
   );
 };
 
+// RestoreDownloadModal downloads the restored files, or a file or directory within them, as a tar.gz or zip archive.
+const RestoreDownloadModal = ({
+  operationId,
+  target,
+}: {
+  operationId: bigint;
+  target: string;
+}) => {
+  const showModal = useShowModal();
+  const alertApi = useAlertApi();
+  const [form] = Form.useForm<{ path: string; format: string }>();
+
+  const handleOk = async () => {
+    try {
+      const values = await validateForm(form);
+      const subPath = values.path
+        .slice(target.length)
+        .split(pathSeparator)
+        .filter((p) => p !== "")
+        .map(encodeURIComponent)
+        .join("/");
+      const resp = await backrestService.getDownloadURL({
+        value: operationId,
+      });
+      window.open(
+        resp.value + subPath + (values.format === "zip" ? "?format=zip" : ""),
+        "_blank"
+      );
+      showModal(null);
+    } catch (e: any) {
+      alertApi?.error("Failed to download restored files: " + e.message);
+    }
+  };
+
+  return (
+    <Modal
+      open={true}
+      title="Download restored files"
+      onCancel={() => showModal(null)}
+      onOk={handleOk}
+      okText="Download"
+    >
+      <Form
+        form={form}
+        layout="vertical"
+        initialValues={{ path: target, format: "tar.gz" }}
+      >
+        <Form.Item
+          label="Path"
+          name="path"
+          tooltip="A file or directory within the restore, files are downloaded as is."
+          rules={[
+            {
+              validator: async (_, value: string) => {
+                if (
+                  value !== target &&
+                  !value?.startsWith(
+                    target.endsWith(pathSeparator)
+                      ? target
+                      : target + pathSeparator
+                  )
+                ) {
+                  throw new Error("Path must be within " + target);
+                }
+              },
+            },
+          ]}
+        >
+          <URIAutocomplete defaultValue={target} />
+        </Form.Item>
+        <Form.Item label="Archive format" name="format">
+          <Radio.Group>
+            <Radio.Button value="tar.gz">.tar.gz</Radio.Button>
+            <Radio.Button value="zip">.zip</Radio.Button>
+          </Radio.Group>
+        </Form.Item>
+      </Form>
+    </Modal>
+  );
+};
+
 const RestoreOperationStatus = ({ operation }: { operation: Operation }) => {
   const restoreOp = operation.op.value as OperationRestore;
   const isDone = restoreOp.lastStatus?.messageType === "summary";
   const progress = restoreOp.lastStatus?.percentDone || 0;
-  const alertApi = useAlertApi();
+  const showModal = useShowModal();
   const lastStatus = restoreOp.lastStatus;
 
   return (
@@ -452,14 +546,12 @@ const RestoreOperationStatus = ({ operation }: { operation: Operation }) => {
           <Button
             type="link"
             onClick={() => {
-              backrestService
-                .getDownloadURL({ value: operation.id })
-                .then((resp) => {
-                  window.open(resp.value, "_blank");
-                })
-                .catch((e) => {
-                  alertApi?.error("Failed to fetch download URL: " + e.message);
-                });
+              showModal(
+                <RestoreDownloadModal
+                  operationId={operation.id}
+                  target={restoreOp.target}
+                />
+              );
             }}
           >
             Download File(s)
@@ -605,6 +697,26 @@ const ForgetOperationDetails = ({
       if (val.yearly) {
         policyDesc.push(`Keep yearly for ${val.yearly} years`);
       }
+      if (val.last) {
+        policyDesc.push(`Keep last ${val.last} snapshots`);
+      }
+      if (val.within) {
+        policyDesc.push(`Keep all within ${val.within}`);
+      }
+      for (const [period, within] of [
+        ["hourly", val.withinHourly],
+        ["daily", val.withinDaily],
+        ["weekly", val.withinWeekly],
+        ["monthly", val.withinMonthly],
+        ["yearly", val.withinYearly],
+      ]) {
+        if (within) {
+          policyDesc.push(`Keep ${period} within ${within}`);
+        }
+      }
+      for (const tags of val.keepTags) {
+        policyDesc.push(`Keep tagged ${tags}`);
+      }
     }
   }
 
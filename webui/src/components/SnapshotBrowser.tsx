@@ -1,7 +1,21 @@
 import React, { useEffect, useMemo, useState } from "react";
-import { Button, Dropdown, Form, Input, Modal, Space, Spin, Tree } from "antd";
+import {
+  Button,
+  Dropdown,
+  Form,
+  Input,
+  Modal,
+  Space,
+  Spin,
+  Table,
+  Tag,
+  Tree,
+} from "antd";
 import type { DataNode, EventDataNode } from "antd/es/tree";
 import {
+  GetFileHistoryResponse,
+  GetSnapshotDownloadURLRequest_ArchiveFormat,
+  GetFileHistoryResponse_Version,
   ListSnapshotFilesResponse,
   ListSnapshotFilesResponseSchema,
   LsEntry,
@@ -11,12 +25,17 @@ import {
 } from "../../gen/ts/v1/service_pb";
 import { useAlertApi } from "./Alerts";
 import {
+  DiffOutlined,
   DownloadOutlined,
   FileOutlined,
   FolderOutlined,
 } from "@ant-design/icons";
 import { useShowModal } from "./ModalManager";
-import { formatBytes, normalizeSnapshotId } from "../lib/formatting";
+import {
+  formatBytes,
+  formatTime,
+  normalizeSnapshotId,
+} from "../lib/formatting";
 import { URIAutocomplete } from "./URIAutocomplete";
 import { validateForm } from "../lib/formutil";
 import { backrestService } from "../api";
@@ -24,11 +43,18 @@ import { ConfirmButton } from "./SpinButton";
 import { StringValueSchema } from "../../gen/ts/types/value_pb";
 import { pathSeparator } from "../state/buildcfg";
 import { create, toJsonString } from "@bufbuild/protobuf";
+import { useConfig } from "./ConfigProvider";
+import { SnapshotDiffModal } from "./SnapshotDiffModal";
+import {
+  DatabaseSource,
+  DatabaseSource_Engine,
+} from "../../gen/ts/v1/config_pb";
 
 const SnapshotBrowserContext = React.createContext<{
   snapshotId: string;
   planId?: string;
   repoId: string;
+  databaseSource?: DatabaseSource; // set if the plan backs up database dumps that can be restored into a database.
   showModal: (modal: React.ReactNode) => void; // slight performance hack.
 } | null>(null);
 
@@ -83,7 +109,11 @@ export const SnapshotBrowser = ({
 }>) => {
   const alertApi = useAlertApi();
   const showModal = useShowModal();
+  const [config] = useConfig();
   const [treeData, setTreeData] = useState<DataNode[]>([]);
+  const databaseSource = config?.plans.find(
+    (p) => p.id === planId
+  )?.databaseSource;
 
   useEffect(() => {
     setTreeData(
@@ -146,8 +176,23 @@ export const SnapshotBrowser = ({
 
   return (
     <SnapshotBrowserContext.Provider
-      value={{ snapshotId, repoId, planId, showModal }}
+      value={{ snapshotId, repoId, planId, databaseSource, showModal }}
     >
+      <Button
+        icon={<DiffOutlined />}
+        style={{ marginBottom: "8px" }}
+        onClick={() =>
+          showModal(
+            <SnapshotDiffModal
+              repoId={repoId}
+              planId={planId}
+              snapshotId={snapshotId}
+            />
+          )
+        }
+      >
+        Compare with...
+      </Button>
       <Tree<DataNode> loadData={onLoadData} treeData={treeData} />
     </SnapshotBrowserContext.Provider>
   );
@@ -171,9 +216,25 @@ const respToNodes = (resp: ListSnapshotFilesResponse): DataNode[] => {
 
 const FileNode = ({ entry }: { entry: LsEntry }) => {
   const [dropdown, setDropdown] = useState<React.ReactNode>(null);
-  const { snapshotId, repoId, planId, showModal } = React.useContext(
-    SnapshotBrowserContext
-  )!;
+  const { snapshotId, repoId, planId, databaseSource, showModal } =
+    React.useContext(SnapshotBrowserContext)!;
+  const alertApi = useAlertApi();
+
+  const download = async (
+    archiveFormat: GetSnapshotDownloadURLRequest_ArchiveFormat
+  ) => {
+    try {
+      const resp = await backrestService.getSnapshotDownloadURL({
+        repoId,
+        snapshotId,
+        path: entry.path!,
+        archiveFormat,
+      });
+      window.open(resp.value, "_blank");
+    } catch (e: any) {
+      alertApi?.error("Failed to fetch download URL: " + e.message);
+    }
+  };
 
   const showDropdown = () => {
     setDropdown(
@@ -201,6 +262,33 @@ const FileNode = ({ entry }: { entry: LsEntry }) => {
                 );
               },
             },
+            ...(entry.type === "file"
+              ? [
+                  {
+                    key: "download",
+                    label: "Download",
+                    onClick: () =>
+                      download(
+                        GetSnapshotDownloadURLRequest_ArchiveFormat.TAR_GZ
+                      ),
+                  },
+                ]
+              : [
+                  {
+                    key: "download-tar",
+                    label: "Download as .tar.gz",
+                    onClick: () =>
+                      download(
+                        GetSnapshotDownloadURLRequest_ArchiveFormat.TAR_GZ
+                      ),
+                  },
+                  {
+                    key: "download-zip",
+                    label: "Download as .zip",
+                    onClick: () =>
+                      download(GetSnapshotDownloadURLRequest_ArchiveFormat.ZIP),
+                  },
+                ]),
             {
               key: "restore",
               label: "Restore to path",
@@ -215,6 +303,42 @@ const FileNode = ({ entry }: { entry: LsEntry }) => {
                 );
               },
             },
+            ...(planId && entry.type === "file"
+              ? [
+                  {
+                    key: "history",
+                    label: "Version history",
+                    onClick: () => {
+                      showModal(
+                        <FileHistoryModal
+                          path={entry.path!}
+                          repoId={repoId}
+                          planId={planId}
+                        />
+                      );
+                    },
+                  },
+                ]
+              : []),
+            ...(databaseSource && entry.type === "file"
+              ? [
+                  {
+                    key: "restore-database",
+                    label: "Restore to database",
+                    onClick: () => {
+                      showModal(
+                        <DatabaseRestoreModal
+                          path={entry.path!}
+                          repoId={repoId}
+                          planId={planId!}
+                          snapshotId={snapshotId}
+                          databaseSource={databaseSource}
+                        />
+                      );
+                    },
+                  },
+                ]
+              : []),
           ],
         }}
       >
@@ -236,7 +360,7 @@ const FileNode = ({ entry }: { entry: LsEntry }) => {
   );
 };
 
-const RestoreModal = ({
+export const RestoreModal = ({
   repoId,
   planId,
   snapshotId,
@@ -380,6 +504,215 @@ const RestoreModal = ({
   );
 };
 
+const DatabaseRestoreModal = ({
+  repoId,
+  planId,
+  snapshotId,
+  path,
+  databaseSource,
+}: {
+  repoId: string;
+  planId: string;
+  snapshotId: string;
+  path: string;
+  databaseSource: DatabaseSource;
+}) => {
+  const [form] = Form.useForm<{ targetDatabase: string }>();
+  const showModal = useShowModal();
+  const isSqlite = databaseSource.engine === DatabaseSource_Engine.SQLITE;
+
+  useEffect(() => {
+    // dumps are stored as <database>.sql or <database>.dump, SQLite databases are restored to a new file.
+    const name = path
+      .slice(path.lastIndexOf("/") + 1)
+      .replace(/\.[^.]*$/, "");
+    form.setFieldsValue({ targetDatabase: isSqlite ? "" : name });
+  }, [path]);
+
+  const handleOk = async () => {
+    try {
+      const values = await validateForm(form);
+      await backrestService.restore(
+        create(RestoreSnapshotRequestSchema, {
+          repoId,
+          planId,
+          snapshotId,
+          path,
+          targetDatabase: values.targetDatabase,
+        })
+      );
+    } catch (e: any) {
+      alert("Failed to restore database: " + e.message);
+    } finally {
+      showModal(null); // close.
+    }
+  };
+
+  return (
+    <Modal
+      open={true}
+      onCancel={() => showModal(null)}
+      title={
+        "Restore " +
+        path +
+        " from snapshot " +
+        normalizeSnapshotId(snapshotId) +
+        " to a database"
+      }
+      width="40vw"
+      footer={[
+        <Button key="back" onClick={() => showModal(null)}>
+          Cancel
+        </Button>,
+        <ConfirmButton
+          key="submit"
+          type="primary"
+          confirmTitle="Confirm Restore?"
+          onClickAsync={handleOk}
+        >
+          Restore
+        </ConfirmButton>,
+      ]}
+    >
+      <Form
+        autoComplete="off"
+        form={form}
+        labelCol={{ span: 6 }}
+        wrapperCol={{ span: 16 }}
+      >
+        <p>
+          The dump is piped into the database's client using the connection
+          settings of the plan.{" "}
+          {isSqlite
+            ? "The database is restored to a new file."
+            : "The target database must already exist, restoring into a database that has data may fail or mix the data."}
+        </p>
+        <Form.Item
+          label={isSqlite ? "Database file" : "Database"}
+          name="targetDatabase"
+          rules={[{ required: true, message: "Target database is required" }]}
+        >
+          {isSqlite ? (
+            <URIAutocomplete placeholder="/path/to/restored.db" />
+          ) : (
+            <Input />
+          )}
+        </Form.Item>
+      </Form>
+    </Modal>
+  );
+};
+
+// FileHistoryModal lists the versions of a file in the snapshots of a plan, older snapshots are loaded a page at a time.
+const FileHistoryModal = ({
+  repoId,
+  planId,
+  path,
+}: {
+  repoId: string;
+  planId: string;
+  path: string;
+}) => {
+  const showModal = useShowModal();
+  const [history, setHistory] = useState<GetFileHistoryResponse | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const loadPage = async (prev: GetFileHistoryResponse | null) => {
+    setLoading(true);
+    try {
+      const page = await backrestService.getFileHistory({
+        repoId,
+        planId,
+        path,
+        beforeSnapshotId: prev?.nextBeforeSnapshotId,
+      });
+      if (prev) {
+        page.versions = [...prev.versions, ...page.versions];
+      }
+      setHistory(page);
+    } catch (e: any) {
+      setError("Failed to load file history: " + e.message);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  useEffect(() => {
+    setHistory(null);
+    loadPage(null);
+  }, [repoId, planId, path]);
+
+  return (
+    <Modal
+      open={true}
+      title={"Version history of " + path}
+      width="60vw"
+      onCancel={() => showModal(null)}
+      footer={null}
+    >
+      {error ? <p>{error}</p> : null}
+      {!history && !error ? <Spin /> : null}
+      {history ? (
+        <Table<GetFileHistoryResponse_Version>
+          size="small"
+          rowKey={(v) => v.snapshot!.id}
+          dataSource={history.versions}
+          pagination={{ pageSize: 20, hideOnSinglePage: true }}
+          columns={[
+            {
+              title: "Snapshot",
+              render: (_, v) =>
+                formatTime(Number(v.snapshot!.unixTimeMs)) +
+                " (" +
+                normalizeSnapshotId(v.snapshot!.id) +
+                ")",
+            },
+            {
+              title: "Size",
+              render: (_, v) => formatBytes(Number(v.entry!.size)),
+            },
+            {
+              title: "Modified",
+              render: (_, v) => formatTime(new Date(v.entry!.mtime)),
+            },
+            {
+              title: "",
+              render: (_, v) =>
+                v.changed ? <Tag color="blue">Changed</Tag> : null,
+            },
+            {
+              title: "",
+              render: (_, v) => (
+                <Button
+                  size="small"
+                  onClick={() =>
+                    showModal(
+                      <RestoreModal
+                        repoId={repoId}
+                        planId={planId}
+                        snapshotId={v.snapshot!.id}
+                        path={path}
+                      />
+                    )
+                  }
+                >
+                  Restore
+                </Button>
+              ),
+            },
+          ]}
+        />
+      ) : null}
+      {history && history.nextBeforeSnapshotId ? (
+        <Button loading={loading} onClick={() => loadPage(history)}>
+          Load older versions
+        </Button>
+      ) : null}
+    </Modal>
+  );
+};
+
 const basename = (path: string) => {
   const idx = path.lastIndexOf(pathSeparator);
   if (idx === -1) {
@@ -0,0 +1,235 @@
+import React, { useEffect, useMemo, useState } from "react";
+import {
+  Alert,
+  Modal,
+  Select,
+  Space,
+  Spin,
+  Table,
+  Tag,
+  Typography,
+} from "antd";
+import {
+  DiffSnapshotsResponse,
+  DiffSnapshotsResponse_Change,
+  DiffSnapshotsResponse_ChangeType,
+} from "../../gen/ts/v1/service_pb";
+import { ResticSnapshot } from "../../gen/ts/v1/restic_pb";
+import { backrestService } from "../api";
+import { useShowModal } from "./ModalManager";
+import {
+  formatBytes,
+  formatTime,
+  normalizeSnapshotId,
+} from "../lib/formatting";
+
+const changeTags: {
+  [key in DiffSnapshotsResponse_ChangeType]: { label: string; color: string };
+} = {
+  [DiffSnapshotsResponse_ChangeType.UNKNOWN]: {
+    label: "Changed",
+    color: "default",
+  },
+  [DiffSnapshotsResponse_ChangeType.ADDED]: {
+    label: "Added",
+    color: "green",
+  },
+  [DiffSnapshotsResponse_ChangeType.REMOVED]: {
+    label: "Removed",
+    color: "red",
+  },
+  [DiffSnapshotsResponse_ChangeType.MODIFIED]: {
+    label: "Modified",
+    color: "blue",
+  },
+  [DiffSnapshotsResponse_ChangeType.FILE_TYPE]: {
+    label: "Type Changed",
+    color: "purple",
+  },
+  [DiffSnapshotsResponse_ChangeType.METADATA]: {
+    label: "Metadata",
+    color: "default",
+  },
+};
+
+// SnapshotDiffModal compares a snapshot with another snapshot of the same repo (and plan, if set).
+export const SnapshotDiffModal = ({
+  repoId,
+  planId,
+  snapshotId,
+}: {
+  repoId: string;
+  planId?: string;
+  snapshotId: string;
+}) => {
+  const showModal = useShowModal();
+  const [snapshots, setSnapshots] = useState<ResticSnapshot[] | null>(null);
+  const [fromSnapshotId, setFromSnapshotId] = useState<string | null>(null);
+  const [diff, setDiff] = useState<DiffSnapshotsResponse | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    (async () => {
+      try {
+        const resp = await backrestService.listSnapshots({ repoId, planId });
+        const sorted = [...resp.snapshots].sort(
+          (a, b) => Number(b.unixTimeMs) - Number(a.unixTimeMs)
+        );
+        setSnapshots(sorted);
+
+        // default to the snapshot taken before this one.
+        const idx = sorted.findIndex((s) => s.id === snapshotId);
+        if (idx !== -1 && idx + 1 < sorted.length) {
+          setFromSnapshotId(sorted[idx + 1].id);
+        }
+      } catch (e: any) {
+        setError("Failed to list snapshots: " + e.message);
+      }
+    })();
+  }, [repoId, planId, snapshotId]);
+
+  useEffect(() => {
+    if (!fromSnapshotId) {
+      return;
+    }
+    setLoading(true);
+    setDiff(null);
+    setError(null);
+    (async () => {
+      try {
+        setDiff(
+          await backrestService.diffSnapshots({
+            repoId,
+            planId,
+            fromSnapshotId,
+            toSnapshotId: snapshotId,
+          })
+        );
+      } catch (e: any) {
+        setError("Failed to diff snapshots: " + e.message);
+      } finally {
+        setLoading(false);
+      }
+    })();
+  }, [fromSnapshotId]);
+
+  const options = useMemo(
+    () =>
+      (snapshots || [])
+        .filter((s) => s.id !== snapshotId)
+        .map((s) => ({
+          label:
+            normalizeSnapshotId(s.id) +
+            " (" +
+            formatTime(Number(s.unixTimeMs)) +
+            ")",
+          value: s.id,
+        })),
+    [snapshots]
+  );
+
+  return (
+    <Modal
+      open={true}
+      title={"Compare snapshot " + normalizeSnapshotId(snapshotId)}
+      width="60vw"
+      onCancel={() => showModal(null)}
+      footer={null}
+    >
+      <Space direction="vertical" style={{ width: "100%" }}>
+        <Space>
+          Changes since
+          <Select
+            style={{ minWidth: "300px" }}
+            loading={snapshots === null}
+            placeholder="Select a snapshot"
+            options={options}
+            value={fromSnapshotId}
+            onChange={(v) => setFromSnapshotId(v)}
+          />
+        </Space>
+        {error ? <Alert type="error" message={error} /> : null}
+        {loading ? <Spin /> : null}
+        {diff ? <DiffView diff={diff} /> : null}
+      </Space>
+    </Modal>
+  );
+};
+
+const DiffView = ({ diff }: { diff: DiffSnapshotsResponse }) => {
+  return (
+    <>
+      {diff.truncated ? null : (
+        <Typography.Text>
+          {diff.filesAdded.toString()} files added (
+          {formatBytes(Number(diff.bytesAdded))}),{" "}
+          {diff.filesRemoved.toString()} files removed (
+          {formatBytes(Number(diff.bytesRemoved))})
+        </Typography.Text>
+      )}
+      {diff.truncated ? (
+        <Alert
+          type="warning"
+          message={
+            "Only the first " + diff.changes.length + " changes are shown."
+          }
+        />
+      ) : null}
+      <Table<DiffSnapshotsResponse_Change>
+        size="small"
+        rowKey="path"
+        dataSource={diff.changes}
+        pagination={{ pageSize: 50, hideOnSinglePage: true }}
+        columns={[
+          {
+            title: "Change",
+            dataIndex: "type",
+            width: 120,
+            render: (type: DiffSnapshotsResponse_ChangeType) => (
+              <Tag color={changeTags[type].color}>
+                {changeTags[type].label}
+              </Tag>
+            ),
+          },
+          {
+            title: "Path",
+            dataIndex: "path",
+          },
+          {
+            title: "Size",
+            width: 240,
+            render: (_, change) => <SizeChange change={change} />,
+          },
+        ]}
+      />
+    </>
+  );
+};
+
+const SizeChange = ({ change }: { change: DiffSnapshotsResponse_Change }) => {
+  if (change.path.endsWith("/")) {
+    return null;
+  }
+  const before = Number(change.sizeBefore);
+  const after = Number(change.sizeAfter);
+  switch (change.type) {
+    case DiffSnapshotsResponse_ChangeType.ADDED:
+      return <>{formatBytes(after)}</>;
+    case DiffSnapshotsResponse_ChangeType.REMOVED:
+      return <>{formatBytes(before)}</>;
+  }
+  if (before === after) {
+    return <>{formatBytes(after)}</>;
+  }
+  const delta = after - before;
+  return (
+    <>
+      {formatBytes(before)} → {formatBytes(after)}{" "}
+      <Typography.Text type={delta > 0 ? "warning" : "success"}>
+        ({delta > 0 ? "+" : "-"}
+        {formatBytes(Math.abs(delta))})
+      </Typography.Text>
+    </>
+  );
+};
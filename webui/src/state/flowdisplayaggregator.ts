@@ -12,6 +12,7 @@ export enum DisplayType {
   STATS,
   RUNHOOK,
   RUNCOMMAND,
+  COPY,
 }
 
 export interface FlowDisplayInfo {
@@ -159,6 +160,8 @@ export const getTypeForDisplay = (op: Operation) => {
       return DisplayType.RUNHOOK;
     case "operationRunCommand":
       return DisplayType.RUNCOMMAND;
+    case "operationCopy":
+      return DisplayType.COPY;
     default:
       return DisplayType.UNKNOWN;
   }
@@ -184,6 +187,8 @@ export const displayTypeToString = (type: DisplayType) => {
       return "Run Hook";
     case DisplayType.RUNCOMMAND:
       return "Run Command";
+    case DisplayType.COPY:
+      return "Copy";
     default:
       return "Unknown";
   }
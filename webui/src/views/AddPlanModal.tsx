@@ -12,6 +12,9 @@ import {
   Col,
   Collapse,
   Checkbox,
+  Card,
+  List,
+  Tag,
 } from "antd";
 import React, { useEffect, useState } from "react";
 import { useShowModal } from "../components/ModalManager";
@@ -39,6 +42,11 @@ import {
   ScheduleFormItem,
 } from "../components/ScheduleFormItem";
 import { clone, create, equals, fromJson, toJson } from "@bufbuild/protobuf";
+import {
+  PreviewRetentionRequestSchema,
+  type PreviewRetentionResponse_Snapshot,
+} from "../../gen/ts/v1/service_pb";
+import { formatTime, normalizeSnapshotId } from "../lib/formatting";
 
 const planDefaults = create(PlanSchema, {
   schedule: {
@@ -66,6 +74,15 @@ export const AddPlanModal = ({ template }: { template: Plan | null }) => {
   const alertsApi = useAlertApi()!;
   const [config, setConfig] = useConfig();
   const [form] = Form.useForm();
+  const commandSource = Form.useWatch("commandSource", {
+    form,
+    preserve: true,
+  });
+  const databaseSource = Form.useWatch("databaseSource", {
+    form,
+    preserve: true,
+  });
+  const hasPaths = !commandSource && !databaseSource;
   useEffect(() => {
     form.setFieldsValue(
       template
@@ -266,187 +283,194 @@ export const AddPlanModal = ({ template }: { template: Plan | null }) => {
             </Form.Item>
           </Tooltip>
 
-          {/* Plan.paths */}
-          <Form.Item label="Paths" required={true}>
-            <Form.List
-              name="paths"
-              rules={[]}
-              initialValue={template ? template.paths : []}
-            >
-              {(fields, { add, remove }, { errors }) => (
-                <>
-                  {fields.map((field, index) => (
-                    <Form.Item key={field.key}>
-                      <Form.Item
-                        {...field}
-                        validateTrigger={["onChange", "onBlur"]}
-                        initialValue={""}
-                        rules={[
-                          {
-                            required: true,
-                          },
-                        ]}
-                        noStyle
-                      >
-                        <URIAutocomplete
-                          style={{ width: "90%" }}
-                          onBlur={() => form.validateFields()}
-                        />
-                      </Form.Item>
-                      <MinusCircleOutlined
-                        className="dynamic-delete-button"
-                        onClick={() => remove(field.name)}
-                        style={{ paddingLeft: "5px" }}
-                      />
-                    </Form.Item>
-                  ))}
-                  <Form.Item>
-                    <Button
-                      type="dashed"
-                      onClick={() => add()}
-                      style={{ width: "90%" }}
-                      icon={<PlusOutlined />}
-                    >
-                      Add Path
-                    </Button>
-                    <Form.ErrorList errors={errors} />
-                  </Form.Item>
-                </>
-              )}
-            </Form.List>
-          </Form.Item>
+          {/* Plan.commandSource, Plan.databaseSource */}
+          <BackupSourceView />
 
-          {/* Plan.excludes */}
-          <Tooltip
-            title={
-              <>
-                Paths to exclude from your backups. See the{" "}
-                <a
-                  href="https://restic.readthedocs.io/en/latest/040_backup.html#excluding-files"
-                  target="_blank"
+          {hasPaths ? (
+            <>
+              {/* Plan.paths */}
+              <Form.Item label="Paths" required={true}>
+                <Form.List
+                  name="paths"
+                  rules={[]}
+                  initialValue={template ? template.paths : []}
                 >
-                  restic docs
-                </a>{" "}
-                for more info.
-              </>
-            }
-          >
-            <Form.Item label="Excludes" required={false}>
-              <Form.List
-                name="excludes"
-                rules={[]}
-                initialValue={template ? template.excludes : []}
-              >
-                {(fields, { add, remove }, { errors }) => (
-                  <>
-                    {fields.map((field, index) => (
-                      <Form.Item required={false} key={field.key}>
-                        <Form.Item
-                          {...field}
-                          validateTrigger={["onChange", "onBlur"]}
-                          initialValue={""}
-                          rules={[
-                            {
-                              required: true,
-                            },
-                          ]}
-                          noStyle
-                        >
-                          <URIAutocomplete
-                            style={{ width: "90%" }}
-                            onBlur={() => form.validateFields()}
-                            globAllowed={true}
+                  {(fields, { add, remove }, { errors }) => (
+                    <>
+                      {fields.map((field, index) => (
+                        <Form.Item key={field.key}>
+                          <Form.Item
+                            {...field}
+                            validateTrigger={["onChange", "onBlur"]}
+                            initialValue={""}
+                            rules={[
+                              {
+                                required: true,
+                              },
+                            ]}
+                            noStyle
+                          >
+                            <URIAutocomplete
+                              style={{ width: "90%" }}
+                              onBlur={() => form.validateFields()}
+                            />
+                          </Form.Item>
+                          <MinusCircleOutlined
+                            className="dynamic-delete-button"
+                            onClick={() => remove(field.name)}
+                            style={{ paddingLeft: "5px" }}
                           />
                         </Form.Item>
-                        <MinusCircleOutlined
-                          className="dynamic-delete-button"
-                          onClick={() => remove(field.name)}
-                          style={{ paddingLeft: "5px" }}
-                        />
+                      ))}
+                      <Form.Item>
+                        <Button
+                          type="dashed"
+                          onClick={() => add()}
+                          style={{ width: "90%" }}
+                          icon={<PlusOutlined />}
+                        >
+                          Add Path
+                        </Button>
+                        <Form.ErrorList errors={errors} />
                       </Form.Item>
-                    ))}
-                    <Form.Item>
-                      <Button
-                        type="dashed"
-                        onClick={() => add()}
-                        style={{ width: "90%" }}
-                        icon={<PlusOutlined />}
-                      >
-                        Add Exclusion Glob
-                      </Button>
-                      <Form.ErrorList errors={errors} />
-                    </Form.Item>
-                  </>
-                )}
-              </Form.List>
-            </Form.Item>
-          </Tooltip>
+                    </>
+                  )}
+                </Form.List>
+              </Form.Item>
 
-          {/* Plan.iexcludes */}
-          <Tooltip
-            title={
-              <>
-                Case insensitive paths to exclude from your backups. See the{" "}
-                <a
-                  href="https://restic.readthedocs.io/en/latest/040_backup.html#excluding-files"
-                  target="_blank"
-                >
-                  restic docs
-                </a>{" "}
-                for more info.
-              </>
-            }
-          >
-            <Form.Item label="Excludes (Case Insensitive)" required={false}>
-              <Form.List
-                name="iexcludes"
-                rules={[]}
-                initialValue={template ? template.iexcludes : []}
-              >
-                {(fields, { add, remove }, { errors }) => (
+              {/* Plan.excludes */}
+              <Tooltip
+                title={
                   <>
-                    {fields.map((field, index) => (
-                      <Form.Item required={false} key={field.key}>
-                        <Form.Item
-                          {...field}
-                          validateTrigger={["onChange", "onBlur"]}
-                          initialValue={""}
-                          rules={[
-                            {
-                              required: true,
-                            },
-                          ]}
-                          noStyle
-                        >
-                          <URIAutocomplete
+                    Paths to exclude from your backups. See the{" "}
+                    <a
+                      href="https://restic.readthedocs.io/en/latest/040_backup.html#excluding-files"
+                      target="_blank"
+                    >
+                      restic docs
+                    </a>{" "}
+                    for more info.
+                  </>
+                }
+              >
+                <Form.Item label="Excludes" required={false}>
+                  <Form.List
+                    name="excludes"
+                    rules={[]}
+                    initialValue={template ? template.excludes : []}
+                  >
+                    {(fields, { add, remove }, { errors }) => (
+                      <>
+                        {fields.map((field, index) => (
+                          <Form.Item required={false} key={field.key}>
+                            <Form.Item
+                              {...field}
+                              validateTrigger={["onChange", "onBlur"]}
+                              initialValue={""}
+                              rules={[
+                                {
+                                  required: true,
+                                },
+                              ]}
+                              noStyle
+                            >
+                              <URIAutocomplete
+                                style={{ width: "90%" }}
+                                onBlur={() => form.validateFields()}
+                                globAllowed={true}
+                              />
+                            </Form.Item>
+                            <MinusCircleOutlined
+                              className="dynamic-delete-button"
+                              onClick={() => remove(field.name)}
+                              style={{ paddingLeft: "5px" }}
+                            />
+                          </Form.Item>
+                        ))}
+                        <Form.Item>
+                          <Button
+                            type="dashed"
+                            onClick={() => add()}
                             style={{ width: "90%" }}
-                            onBlur={() => form.validateFields()}
-                            globAllowed={true}
-                          />
+                            icon={<PlusOutlined />}
+                          >
+                            Add Exclusion Glob
+                          </Button>
+                          <Form.ErrorList errors={errors} />
                         </Form.Item>
-                        <MinusCircleOutlined
-                          className="dynamic-delete-button"
-                          onClick={() => remove(field.name)}
-                          style={{ paddingLeft: "5px" }}
-                        />
-                      </Form.Item>
-                    ))}
-                    <Form.Item>
-                      <Button
-                        type="dashed"
-                        onClick={() => add()}
-                        style={{ width: "90%" }}
-                        icon={<PlusOutlined />}
-                      >
-                        Add Case Insensitive Exclusion Glob
-                      </Button>
-                      <Form.ErrorList errors={errors} />
-                    </Form.Item>
+                      </>
+                    )}
+                  </Form.List>
+                </Form.Item>
+              </Tooltip>
+
+              {/* Plan.iexcludes */}
+              <Tooltip
+                title={
+                  <>
+                    Case insensitive paths to exclude from your backups. See the{" "}
+                    <a
+                      href="https://restic.readthedocs.io/en/latest/040_backup.html#excluding-files"
+                      target="_blank"
+                    >
+                      restic docs
+                    </a>{" "}
+                    for more info.
                   </>
-                )}
-              </Form.List>
-            </Form.Item>
-          </Tooltip>
+                }
+              >
+                <Form.Item label="Excludes (Case Insensitive)" required={false}>
+                  <Form.List
+                    name="iexcludes"
+                    rules={[]}
+                    initialValue={template ? template.iexcludes : []}
+                  >
+                    {(fields, { add, remove }, { errors }) => (
+                      <>
+                        {fields.map((field, index) => (
+                          <Form.Item required={false} key={field.key}>
+                            <Form.Item
+                              {...field}
+                              validateTrigger={["onChange", "onBlur"]}
+                              initialValue={""}
+                              rules={[
+                                {
+                                  required: true,
+                                },
+                              ]}
+                              noStyle
+                            >
+                              <URIAutocomplete
+                                style={{ width: "90%" }}
+                                onBlur={() => form.validateFields()}
+                                globAllowed={true}
+                              />
+                            </Form.Item>
+                            <MinusCircleOutlined
+                              className="dynamic-delete-button"
+                              onClick={() => remove(field.name)}
+                              style={{ paddingLeft: "5px" }}
+                            />
+                          </Form.Item>
+                        ))}
+                        <Form.Item>
+                          <Button
+                            type="dashed"
+                            onClick={() => add()}
+                            style={{ width: "90%" }}
+                            icon={<PlusOutlined />}
+                          >
+                            Add Case Insensitive Exclusion Glob
+                          </Button>
+                          <Form.ErrorList errors={errors} />
+                        </Form.Item>
+                      </>
+                    )}
+                  </Form.List>
+                </Form.Item>
+              </Tooltip>
+            </>
+          ) : null}
 
           {/* Plan.cron */}
           <Form.Item label="Backup Schedule">
@@ -456,6 +480,22 @@ export const AddPlanModal = ({ template }: { template: Plan | null }) => {
             />
           </Form.Item>
 
+          {/* Plan.maxSnapshotAgeHours */}
+          <Form.Item
+            label={
+              <Tooltip title="Runs CONDITION_PLAN_STALE hooks and shows a warning on the dashboard if no backup succeeds within this many hours, e.g. because the schedule stopped running. 0 disables the check.">
+                Max Snapshot Age
+              </Tooltip>
+            }
+            name="maxSnapshotAgeHours"
+            rules={[{ type: "number", min: 0, message: "Must not be negative" }]}
+          >
+            <InputNumber addonAfter="hours" min={0} type="number" />
+          </Form.Item>
+
+          {/* Plan.fsSnapshot */}
+          {hasPaths ? <FilesystemSnapshotView /> : null}
+
           {/* Plan.backup_flags */}
           <Form.Item
             label={
@@ -509,7 +549,69 @@ export const AddPlanModal = ({ template }: { template: Plan | null }) => {
           </Form.Item>
 
           {/* Plan.retention */}
-          <RetentionPolicyView />
+          <RetentionPolicyView planId={template?.id} />
+
+          {/* Plan.destinations */}
+          <Form.Item
+            label={
+              <Tooltip title="Additional repos the plan backs up to on the same schedule, e.g. an offsite copy for 3-2-1 backups. Each repo is backed up independently and may override the retention policy.">
+                Additional Repositories
+              </Tooltip>
+            }
+          >
+            <Form.List name="destinations">
+              {(fields, { add, remove }) => (
+                <>
+                  {fields.map((field) => (
+                    <Card
+                      key={field.key}
+                      size="small"
+                      style={{ marginBottom: "8px" }}
+                      extra={
+                        <MinusCircleOutlined
+                          className="dynamic-delete-button"
+                          onClick={() => remove(field.name)}
+                        />
+                      }
+                    >
+                      <Form.Item
+                        name={[field.name, "repo"]}
+                        rules={[
+                          {
+                            required: true,
+                            message: "Please select repository",
+                          },
+                        ]}
+                      >
+                        <Select
+                          placeholder="Repository"
+                          options={repos.map((repo) => ({
+                            value: repo.id,
+                          }))}
+                        />
+                      </Form.Item>
+                      <RetentionPolicyView
+                        name={[field.name, "retention"]}
+                        path={["destinations", field.name, "retention"]}
+                        label="Retention"
+                        allowInherit={true}
+                        planId={template?.id}
+                        repoPath={["destinations", field.name, "repo"]}
+                      />
+                    </Card>
+                  ))}
+                  <Button
+                    type="dashed"
+                    onClick={() => add({ repo: "" })}
+                    style={{ width: "90%" }}
+                    icon={<PlusOutlined />}
+                  >
+                    Add Repository
+                  </Button>
+                </>
+              )}
+            </Form.List>
+          </Form.Item>
 
           {/* Plan.hooks */}
           <Form.Item
@@ -544,13 +646,35 @@ export const AddPlanModal = ({ template }: { template: Plan | null }) => {
   );
 };
 
-const RetentionPolicyView = () => {
+// RetentionPolicyView edits a retention policy. name is the path of the policy's fields relative to the enclosing
+// Form.List (if any) and path is its absolute path in the form. allowInherit adds an option to use the plan's policy.
+// If planId is set the policy can be previewed against the plan's snapshots in the repo at repoPath.
+const RetentionPolicyView = ({
+  name = ["retention"],
+  path = name,
+  label = "Retention Policy",
+  allowInherit = false,
+  planId,
+  repoPath = ["repo"],
+}: {
+  name?: (string | number)[];
+  path?: (string | number)[];
+  label?: string;
+  allowInherit?: boolean;
+  planId?: string;
+  repoPath?: (string | number)[];
+}) => {
   const form = Form.useFormInstance();
-  const retention = Form.useWatch("retention", { form, preserve: true }) as any;
+  const retention = Form.useWatch(path, { form, preserve: true }) as any;
+  const planRetention = Form.useWatch("retention", {
+    form,
+    preserve: true,
+  }) as any;
+  const repoId = Form.useWatch(repoPath, { form, preserve: true }) as string;
 
   const determineMode = () => {
     if (!retention) {
-      return "policyTimeBucketed";
+      return allowInherit ? "inherit" : "policyTimeBucketed";
     } else if (retention.policyKeepLastN) {
       return "policyKeepLastN";
     } else if (retention.policyKeepAll) {
@@ -572,7 +696,7 @@ const RetentionPolicyView = () => {
           forgets performed externally on the next backup.
         </p>
         <Form.Item
-          name={["retention", "policyKeepAll"]}
+          name={[...name, "policyKeepAll"]}
           valuePropName="checked"
           initialValue={true}
           hidden={true}
@@ -584,7 +708,7 @@ const RetentionPolicyView = () => {
   } else if (mode === "policyKeepLastN") {
     elem = (
       <Form.Item
-        name={["retention", "policyKeepLastN"]}
+        name={[...name, "policyKeepLastN"]}
         initialValue={0}
         validateTrigger={["onChange", "onBlur"]}
         rules={[
@@ -601,83 +725,181 @@ const RetentionPolicyView = () => {
       </Form.Item>
     );
   } else if (mode === "policyTimeBucketed") {
+    const withinInput = (field: string, label: string, tooltip: string) => (
+      <Form.Item
+        name={[...name, "policyTimeBucketed", field]}
+        initialValue=""
+        required={false}
+        rules={[
+          {
+            pattern: /^(\d+[ymdh])*$/,
+            message: "Expected a duration e.g. 1y2m7d12h",
+          },
+        ]}
+      >
+        <Input
+          addonBefore={
+            <Tooltip title={tooltip}>
+              <div style={{ width: "8em" }}>{label}</div>
+            </Tooltip>
+          }
+          placeholder="e.g. 7d"
+        />
+      </Form.Item>
+    );
     elem = (
-      <Row>
-        <Col span={11}>
-          <Form.Item
-            name={["retention", "policyTimeBucketed", "yearly"]}
-            validateTrigger={["onChange", "onBlur"]}
-            initialValue={0}
-            required={false}
-          >
-            <InputNumber
-              addonBefore={<div style={{ width: "5em" }}>Yearly</div>}
-              type="number"
-            />
-          </Form.Item>
-          <Form.Item
-            name={["retention", "policyTimeBucketed", "monthly"]}
-            initialValue={0}
-            validateTrigger={["onChange", "onBlur"]}
-            required={false}
-          >
-            <InputNumber
-              addonBefore={<div style={{ width: "5em" }}>Monthly</div>}
-              type="number"
-            />
-          </Form.Item>
-          <Form.Item
-            name={["retention", "policyTimeBucketed", "weekly"]}
-            initialValue={0}
-            validateTrigger={["onChange", "onBlur"]}
-            required={false}
-          >
-            <InputNumber
-              addonBefore={<div style={{ width: "5em" }}>Weekly</div>}
-              type="number"
-            />
-          </Form.Item>
-        </Col>
-        <Col span={11} offset={1}>
-          <Form.Item
-            name={["retention", "policyTimeBucketed", "daily"]}
-            validateTrigger={["onChange", "onBlur"]}
-            initialValue={0}
-            required={false}
-          >
-            <InputNumber
-              addonBefore={<div style={{ width: "5em" }}>Daily</div>}
-              type="number"
-            />
-          </Form.Item>
-          <Form.Item
-            name={["retention", "policyTimeBucketed", "hourly"]}
-            validateTrigger={["onChange", "onBlur"]}
-            initialValue={0}
-            required={false}
-          >
-            <InputNumber
-              addonBefore={<div style={{ width: "5em" }}>Hourly</div>}
-              type="number"
-            />
-          </Form.Item>
-        </Col>
-      </Row>
+      <>
+        <Row>
+          <Col span={11}>
+            <Form.Item
+              name={[...name, "policyTimeBucketed", "yearly"]}
+              validateTrigger={["onChange", "onBlur"]}
+              initialValue={0}
+              required={false}
+            >
+              <InputNumber
+                addonBefore={<div style={{ width: "5em" }}>Yearly</div>}
+                type="number"
+              />
+            </Form.Item>
+            <Form.Item
+              name={[...name, "policyTimeBucketed", "monthly"]}
+              initialValue={0}
+              validateTrigger={["onChange", "onBlur"]}
+              required={false}
+            >
+              <InputNumber
+                addonBefore={<div style={{ width: "5em" }}>Monthly</div>}
+                type="number"
+              />
+            </Form.Item>
+            <Form.Item
+              name={[...name, "policyTimeBucketed", "weekly"]}
+              initialValue={0}
+              validateTrigger={["onChange", "onBlur"]}
+              required={false}
+            >
+              <InputNumber
+                addonBefore={<div style={{ width: "5em" }}>Weekly</div>}
+                type="number"
+              />
+            </Form.Item>
+          </Col>
+          <Col span={11} offset={1}>
+            <Form.Item
+              name={[...name, "policyTimeBucketed", "daily"]}
+              validateTrigger={["onChange", "onBlur"]}
+              initialValue={0}
+              required={false}
+            >
+              <InputNumber
+                addonBefore={<div style={{ width: "5em" }}>Daily</div>}
+                type="number"
+              />
+            </Form.Item>
+            <Form.Item
+              name={[...name, "policyTimeBucketed", "hourly"]}
+              validateTrigger={["onChange", "onBlur"]}
+              initialValue={0}
+              required={false}
+            >
+              <InputNumber
+                addonBefore={<div style={{ width: "5em" }}>Hourly</div>}
+                type="number"
+              />
+            </Form.Item>
+            <Form.Item
+              name={[...name, "policyTimeBucketed", "last"]}
+              validateTrigger={["onChange", "onBlur"]}
+              initialValue={0}
+              required={false}
+            >
+              <InputNumber
+                addonBefore={
+                  <Tooltip title="The last N snapshots are kept in addition to the snapshots kept by the other rules.">
+                    <div style={{ width: "5em" }}>Last</div>
+                  </Tooltip>
+                }
+                type="number"
+              />
+            </Form.Item>
+          </Col>
+        </Row>
+        {withinInput(
+          "within",
+          "Keep Within",
+          "All snapshots taken within this duration of the newest snapshot are kept e.g. 7d keeps everything from the last 7 days."
+        )}
+        <Form.Item
+          name={[...name, "policyTimeBucketed", "keepTags"]}
+          initialValue={[]}
+          required={false}
+        >
+          <Select
+            mode="tags"
+            tokenSeparators={[" "]}
+            placeholder="Keep snapshots tagged e.g. keep"
+            suffixIcon={null}
+            notFoundContent={null}
+          />
+        </Form.Item>
+        <Collapse
+          size="small"
+          items={[
+            {
+              key: "within",
+              label: "Keep Within Per Period",
+              forceRender: true,
+              children: (
+                <>
+                  {withinInput(
+                    "withinHourly",
+                    "Hourly Within",
+                    "The newest snapshot of each hour within this duration is kept."
+                  )}
+                  {withinInput(
+                    "withinDaily",
+                    "Daily Within",
+                    "The newest snapshot of each day within this duration is kept."
+                  )}
+                  {withinInput(
+                    "withinWeekly",
+                    "Weekly Within",
+                    "The newest snapshot of each week within this duration is kept."
+                  )}
+                  {withinInput(
+                    "withinMonthly",
+                    "Monthly Within",
+                    "The newest snapshot of each month within this duration is kept."
+                  )}
+                  {withinInput(
+                    "withinYearly",
+                    "Yearly Within",
+                    "The newest snapshot of each year within this duration is kept."
+                  )}
+                </>
+              ),
+            },
+          ]}
+        />
+      </>
     );
   }
 
   return (
     <>
-      <Form.Item label="Retention Policy">
+      <Form.Item label={label}>
         <Row>
           <Radio.Group
             value={mode}
             onChange={(e) => {
               const selected = e.target.value;
-              if (selected === "policyKeepLastN") {
-                form.setFieldValue("retention", { policyKeepLastN: 30 });
+              if (selected === "inherit") {
+                form.setFieldValue(path, null);
+              } else if (selected === "policyKeepLastN") {
+                form.setFieldValue(path, { policyKeepLastN: 30 });
               } else if (selected === "policyTimeBucketed") {
-                form.setFieldValue("retention", {
+                form.setFieldValue(path, {
                   policyTimeBucketed: {
                     yearly: 0,
                     monthly: 3,
@@ -687,17 +909,24 @@ const RetentionPolicyView = () => {
                   },
                 });
               } else {
-                form.setFieldValue("retention", { policyKeepAll: true });
+                form.setFieldValue(path, { policyKeepAll: true });
               }
             }}
           >
+            {allowInherit ? (
+              <Radio.Button value={"inherit"}>
+                <Tooltip title="Use the plan's retention policy in this repo.">
+                  Same as Plan
+                </Tooltip>
+              </Radio.Button>
+            ) : null}
             <Radio.Button value={"policyKeepLastN"}>
               <Tooltip title="The last N snapshots will be kept by restic. Retention policy is applied to drop older snapshots after each backup run.">
                 By Count
               </Tooltip>
             </Radio.Button>
             <Radio.Button value={"policyTimeBucketed"}>
-              <Tooltip title="Snapshots older than the specified time period will be dropped by restic. Retention policy is applied to drop older snapshots after each backup run.">
+              <Tooltip title="Snapshots are kept per time period, within durations, or by tag. A snapshot kept by any rule is retained, the rest are dropped by restic after each backup run.">
                 By Time Period
               </Tooltip>
             </Radio.Button>
@@ -712,7 +941,368 @@ const RetentionPolicyView = () => {
         <Row>
           <Form.Item>{elem}</Form.Item>
         </Row>
+        {planId && repoId ? (
+          <RetentionPreview
+            planId={planId}
+            repoId={repoId}
+            retention={retention || (allowInherit ? planRetention : null)}
+          />
+        ) : null}
       </Form.Item>
     </>
   );
 };
+
+// RetentionPreview shows which of a plan's snapshots a retention policy would keep or remove, without removing any.
+const RetentionPreview = ({
+  planId,
+  repoId,
+  retention,
+}: {
+  planId: string;
+  repoId: string;
+  retention: any;
+}) => {
+  const alertsApi = useAlertApi()!;
+  const [preview, setPreview] = useState<
+    PreviewRetentionResponse_Snapshot[] | null
+  >(null);
+
+  // a preview of a previous policy or repo is misleading, clear it on change.
+  const key = JSON.stringify([repoId, retention]);
+  useEffect(() => {
+    setPreview(null);
+  }, [key]);
+
+  const handlePreview = async () => {
+    try {
+      const resp = await backrestService.previewRetention(
+        create(PreviewRetentionRequestSchema, {
+          planId,
+          repoId,
+          retention: fromJson(RetentionPolicySchema, retention),
+        })
+      );
+      setPreview(resp.snapshots);
+    } catch (e: any) {
+      alertsApi.error(formatErrorAlert(e, "Retention preview error: "), 10);
+    }
+  };
+
+  if (!retention) {
+    return null;
+  }
+
+  const kept = preview?.filter((s) => s.keep).length || 0;
+  return (
+    <>
+      <Tooltip title="Runs restic forget --dry-run with this policy against the plan's current snapshots in the repo. Nothing is deleted.">
+        <SpinButton onClickAsync={handlePreview}>Preview Retention</SpinButton>
+      </Tooltip>
+      {preview ? (
+        <List
+          size="small"
+          header={
+            <Typography.Text>
+              {kept} snapshots kept, {preview.length - kept} removed in repo{" "}
+              {repoId}
+            </Typography.Text>
+          }
+          style={{ maxHeight: "300px", overflowY: "auto", marginTop: "8px" }}
+          dataSource={preview}
+          renderItem={(s) => (
+            <List.Item>
+              <Tag color={s.keep ? "green" : "red"}>
+                {s.keep ? "keep" : "remove"}
+              </Tag>
+              {formatTime(Number(s.snapshot!.unixTimeMs))}{" "}
+              <Typography.Text code>
+                {normalizeSnapshotId(s.snapshot!.id)}
+              </Typography.Text>{" "}
+              <Typography.Text type="secondary">
+                {s.reasons.join(", ")}
+              </Typography.Text>
+            </List.Item>
+          )}
+        />
+      ) : null}
+    </>
+  );
+};
+
+const FilesystemSnapshotView = () => {
+  const form = Form.useFormInstance();
+  const fsSnapshot = Form.useWatch("fsSnapshot", {
+    form,
+    preserve: true,
+  }) as any;
+
+  let mode = "none";
+  if (fsSnapshot?.providerLvm) {
+    mode = "providerLvm";
+  } else if (fsSnapshot?.providerBtrfs) {
+    mode = "providerBtrfs";
+  } else if (fsSnapshot?.providerZfs) {
+    mode = "providerZfs";
+  }
+
+  let elem: React.ReactNode = null;
+  if (mode === "providerLvm") {
+    elem = (
+      <>
+        <Form.Item
+          name={["fsSnapshot", "providerLvm", "volumeGroup"]}
+          rules={[{ required: true, message: "Volume group is required" }]}
+        >
+          <Input addonBefore="Volume Group" placeholder="vg0" />
+        </Form.Item>
+        <Form.Item
+          name={["fsSnapshot", "providerLvm", "logicalVolume"]}
+          rules={[{ required: true, message: "Logical volume is required" }]}
+        >
+          <Input addonBefore="Logical Volume" placeholder="data" />
+        </Form.Item>
+        <Tooltip title="Size of a classic snapshot e.g. 5G. Leave empty for a thin volume.">
+          <Form.Item name={["fsSnapshot", "providerLvm", "size"]}>
+            <Input addonBefore="Size" placeholder="empty for thin volumes" />
+          </Form.Item>
+        </Tooltip>
+        <Tooltip title="Extra mount options, the snapshot is always mounted read-only. XFS requires nouuid.">
+          <Form.Item name={["fsSnapshot", "providerLvm", "mountOptions"]}>
+            <Input addonBefore="Mount Options" placeholder="e.g. nouuid" />
+          </Form.Item>
+        </Tooltip>
+      </>
+    );
+  } else if (mode === "providerBtrfs") {
+    elem = (
+      <Tooltip title="Directory on the same filesystem that the read-only snapshot is created in, defaults to the parent directory of the subvolume.">
+        <Form.Item name={["fsSnapshot", "providerBtrfs", "snapshotDir"]}>
+          <Input addonBefore="Snapshot Directory" placeholder="optional" />
+        </Form.Item>
+      </Tooltip>
+    );
+  } else if (mode === "providerZfs") {
+    elem = (
+      <Form.Item
+        name={["fsSnapshot", "providerZfs", "dataset"]}
+        rules={[{ required: true, message: "Dataset is required" }]}
+      >
+        <Input addonBefore="Dataset" placeholder="tank/data" />
+      </Form.Item>
+    );
+  }
+
+  return (
+    <Form.Item
+      label={
+        <Tooltip title="Back up from a read-only snapshot of the filesystem so that files changing during the backup (e.g. databases or VM images) are captured consistently. The snapshot is created before each backup and removed afterwards, snapshots record the paths within the snapshot.">
+          Filesystem Snapshot
+        </Tooltip>
+      }
+    >
+      <Radio.Group
+        value={mode}
+        onChange={(e) => {
+          const selected = e.target.value;
+          if (selected === "none") {
+            form.setFieldValue("fsSnapshot", null);
+          } else {
+            form.setFieldValue("fsSnapshot", {
+              sourcePath: fsSnapshot?.sourcePath || "",
+              [selected]: {},
+            });
+          }
+        }}
+      >
+        <Radio.Button value={"none"}>None</Radio.Button>
+        <Radio.Button value={"providerLvm"}>LVM</Radio.Button>
+        <Radio.Button value={"providerBtrfs"}>Btrfs</Radio.Button>
+        <Radio.Button value={"providerZfs"}>ZFS</Radio.Button>
+      </Radio.Group>
+      {mode !== "none" ? (
+        <>
+          <br />
+          <br />
+          <Tooltip title="Where the filesystem is mounted (the subvolume for Btrfs). All paths of the plan must be within it.">
+            <Form.Item
+              name={["fsSnapshot", "sourcePath"]}
+              rules={[{ required: true, message: "Source path is required" }]}
+            >
+              <Input addonBefore="Source Path" placeholder="/var/lib" />
+            </Form.Item>
+          </Tooltip>
+          {elem}
+        </>
+      ) : null}
+    </Form.Item>
+  );
+};
+
+const BackupSourceView = () => {
+  const form = Form.useFormInstance();
+  const commandSource = Form.useWatch("commandSource", {
+    form,
+    preserve: true,
+  }) as any;
+  const databaseSource = Form.useWatch("databaseSource", {
+    form,
+    preserve: true,
+  }) as any;
+
+  let mode = "paths";
+  if (commandSource) {
+    mode = "command";
+  } else if (databaseSource) {
+    mode = "database";
+  }
+
+  let elem: React.ReactNode = null;
+  if (mode === "command") {
+    elem = (
+      <>
+        <Tooltip title="Command and arguments, split like a shell would but not run in a shell. Use sh -c '...' for pipes or redirects.">
+          <Form.Item
+            name={["commandSource", "command"]}
+            rules={[{ required: true, message: "Command is required" }]}
+          >
+            <Input addonBefore="Command" placeholder="pg_dump -Fc mydb" />
+          </Form.Item>
+        </Tooltip>
+        <Tooltip title="Name of the file that holds the command's output in the snapshot.">
+          <Form.Item
+            name={["commandSource", "filename"]}
+            rules={[{ required: true, message: "Filename is required" }]}
+          >
+            <Input addonBefore="Filename" placeholder="mydb.dump" />
+          </Form.Item>
+        </Tooltip>
+      </>
+    );
+  } else if (mode === "database") {
+    elem = <DatabaseSourceView engine={databaseSource?.engine} />;
+  }
+
+  return (
+    <Form.Item
+      label={
+        <Tooltip title="Back up files and directories, the output of a command (e.g. a custom dump script) stored in the snapshot as a single file, or dumps of databases stored in a snapshot per database. The backup fails if a command exits with an error.">
+          Source
+        </Tooltip>
+      }
+    >
+      <Radio.Group
+        value={mode}
+        onChange={(e) => {
+          const selected = e.target.value;
+          form.setFieldsValue({
+            commandSource:
+              selected === "command" ? { command: "", filename: "" } : null,
+            databaseSource:
+              selected === "database"
+                ? { engine: "ENGINE_POSTGRES", databases: [] }
+                : null,
+          });
+          if (selected !== "paths") {
+            form.setFieldsValue({ paths: [], fsSnapshot: null });
+          }
+        }}
+      >
+        <Radio.Button value={"paths"}>Paths</Radio.Button>
+        <Radio.Button value={"command"}>Command Output</Radio.Button>
+        <Radio.Button value={"database"}>Database</Radio.Button>
+      </Radio.Group>
+      {elem ? (
+        <>
+          <br />
+          <br />
+          {elem}
+        </>
+      ) : null}
+    </Form.Item>
+  );
+};
+
+const DatabaseSourceView = ({ engine }: { engine?: string }) => {
+  const isSqlite = engine === "ENGINE_SQLITE";
+  return (
+    <>
+      <Form.Item
+        name={["databaseSource", "engine"]}
+        rules={[{ required: true, message: "Engine is required" }]}
+      >
+        <Select
+          options={[
+            { label: "PostgreSQL (pg_dump)", value: "ENGINE_POSTGRES" },
+            { label: "MySQL / MariaDB (mysqldump)", value: "ENGINE_MYSQL" },
+            { label: "SQLite (sqlite3)", value: "ENGINE_SQLITE" },
+          ]}
+        />
+      </Form.Item>
+      {!isSqlite ? (
+        <>
+          <Form.Item name={["databaseSource", "host"]}>
+            <Input addonBefore="Host" placeholder="default: local socket" />
+          </Form.Item>
+          <Form.Item name={["databaseSource", "port"]}>
+            <InputNumber
+              addonBefore="Port"
+              placeholder="default port"
+              min={0}
+              max={65535}
+            />
+          </Form.Item>
+          <Form.Item name={["databaseSource", "username"]}>
+            <Input addonBefore="Username" placeholder="optional" />
+          </Form.Item>
+          <Tooltip title="Passed to the client in the PGPASSWORD or MYSQL_PWD environment variable.">
+            <Form.Item name={["databaseSource", "password"]}>
+              <Input.Password addonBefore="Password" placeholder="optional" />
+            </Form.Item>
+          </Tooltip>
+        </>
+      ) : null}
+      {engine === "ENGINE_POSTGRES" ? (
+        <Tooltip title="The custom format is compressed and restored with pg_restore, plain SQL is restored with psql.">
+          <Form.Item name={["databaseSource", "format"]}>
+            <Select
+              placeholder="Dump format"
+              options={[
+                { label: "Plain SQL", value: "FORMAT_SQL" },
+                { label: "Custom archive", value: "FORMAT_POSTGRES_CUSTOM" },
+              ]}
+            />
+          </Form.Item>
+        </Tooltip>
+      ) : null}
+      <Tooltip
+        title={
+          isSqlite
+            ? "Absolute paths of the database files, each is backed up in its own snapshot tagged db:<file name>."
+            : "Names of the databases, each is backed up in its own snapshot tagged db:<name>."
+        }
+      >
+        <Form.Item
+          name={["databaseSource", "databases"]}
+          rules={[
+            { required: true, message: "At least one database is required" },
+          ]}
+        >
+          <Select
+            mode="tags"
+            placeholder={isSqlite ? "/var/lib/app/app.db" : "Databases"}
+            tokenSeparators={[","]}
+          />
+        </Form.Item>
+      </Tooltip>
+      <Tooltip title="Extra flags for the dump command.">
+        <Form.Item name={["databaseSource", "extraArgs"]}>
+          <Select
+            mode="tags"
+            placeholder="Extra dump flags e.g. --exclude-table=logs"
+          />
+        </Form.Item>
+      </Tooltip>
+    </>
+  );
+};
@@ -41,6 +41,7 @@ import { ConfirmButton, SpinButton } from "../components/SpinButton";
 import { useConfig } from "../components/ConfigProvider";
 import Cron from "react-js-cron";
 import {
+  ScheduleDefaultsDaily,
   ScheduleDefaultsInfrequent,
   ScheduleFormItem,
 } from "../components/ScheduleFormItem";
@@ -79,6 +80,7 @@ export const AddRepoModal = ({ template }: { template: Repo | null }) => {
   const alertsApi = useAlertApi()!;
   const [config, setConfig] = useConfig();
   const [form] = Form.useForm<JsonValue>();
+  const copyToRepo = Form.useWatch(["copyPolicy", "toRepo"], form);
   useEffect(() => {
     const initVal = template
       ? toJson(RepoSchema, template, {
@@ -121,6 +123,10 @@ export const AddRepoModal = ({ template }: { template: Repo | null }) => {
 
     try {
       let repoFormData = await validateForm(form);
+      if (!(repoFormData as any).copyPolicy?.toRepo) {
+        // copy is disabled unless a destination is selected.
+        (repoFormData as any).copyPolicy = null;
+      }
       const repo = fromJson(RepoSchema, repoFormData, {
         ignoreUnknownFields: false,
       });
@@ -348,6 +354,12 @@ export const AddRepoModal = ({ template }: { template: Repo | null }) => {
                     RESTIC_PASSWORD, RESTIC_PASSWORD_FILE, or
                     RESTIC_PASSWORD_COMMAND.
                   </li>
+                  <li>
+                    Secret references are resolved when the repo is used:
+                    secret:file:/run/secrets/name, secret:env:NAME, or
+                    secret:command:pass show name. References also work as env
+                    variable values.
+                  </li>
                   <li>
                     Click [Generate] to seed a random password from your
                     browser's crypto random API.
@@ -579,6 +591,57 @@ export const AddRepoModal = ({ template }: { template: Repo | null }) => {
             />
           </Form.Item>
 
+          {/* Repo.copyPolicy */}
+          <Form.Item
+            label={
+              <Tooltip
+                title={
+                  <span>
+                    Copies the snapshots created by this instance to another
+                    repository on a schedule with restic copy. Snapshots that
+                    already exist in the destination are skipped and the plans'
+                    retention policies are applied to the destination after
+                    each copy.
+                  </span>
+                }
+              >
+                Copy Policy
+              </Tooltip>
+            }
+          >
+            <Form.Item name={["copyPolicy", "toRepo"]} required={false}>
+              <Select
+                allowClear
+                placeholder="Disabled, select a repo to copy snapshots to"
+                options={config.repos
+                  .filter((r) => r.id !== template?.id)
+                  .map((r) => ({ label: r.id, value: r.id }))}
+              />
+            </Form.Item>
+            {copyToRepo ? (
+              <>
+                <Form.Item name={["copyPolicy", "plans"]} required={false}>
+                  <Select
+                    mode="multiple"
+                    allowClear
+                    placeholder="All plans"
+                    options={config.plans
+                      .filter(
+                        (p) =>
+                          p.repo === template?.id ||
+                          p.destinations.some((d) => d.repo === template?.id)
+                      )
+                      .map((p) => ({ label: p.id, value: p.id }))}
+                  />
+                </Form.Item>
+                <ScheduleFormItem
+                  name={["copyPolicy", "schedule"]}
+                  defaults={ScheduleDefaultsDaily}
+                />
+              </>
+            ) : null}
+          </Form.Item>
+
           {/* Repo.commandPrefix */}
           {!isWindows && (
             <Form.Item
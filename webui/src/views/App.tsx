@@ -1,5 +1,6 @@
 import React, { Suspense, useEffect, useState } from "react";
 import {
+  AuditOutlined,
   ScheduleOutlined,
   DatabaseOutlined,
   PlusOutlined,
@@ -24,7 +25,7 @@ import LogoSvg from "url:../../assets/logo.svg";
 import _ from "lodash";
 import { Code } from "@connectrpc/connect";
 import { LoginModal } from "./LoginModal";
-import { backrestService, setAuthToken } from "../api";
+import { backrestService, logout } from "../api";
 import { useConfig } from "../components/ConfigProvider";
 import { shouldShowSettings } from "../state/configutil";
 import { OpSelector, OpSelectorSchema } from "../../gen/ts/v1/service_pb";
@@ -60,6 +61,12 @@ const RepoView = React.lazy(() =>
   }))
 );
 
+const AuditLogView = React.lazy(() =>
+  import("./AuditLogView").then((m) => ({
+    default: m.AuditLogView,
+  }))
+);
+
 const RepoViewContainer = () => {
   const { repoId } = useParams();
   const [config, setConfig] = useConfig();
@@ -113,6 +120,7 @@ export const App: React.FC = () => {
   } = theme.useToken();
   const navigate = useNavigate();
   const [config, setConfig] = useConfig();
+  const showModal = useShowModal();
 
   const items = getSidenavItems(config);
 
@@ -166,8 +174,36 @@ export const App: React.FC = () => {
               color: "white",
               visibility: config?.auth?.disabled ? "hidden" : "visible",
             }}
-            onClick={() => {
-              setAuthToken("");
+            onClick={async () => {
+              const { TwoFactorModal } = await import("./TwoFactorModal");
+              showModal(<TwoFactorModal />);
+            }}
+          >
+            2FA
+          </Button>
+          <Button
+            type="text"
+            style={{
+              marginLeft: "10px",
+              color: "white",
+              visibility: config?.auth?.disabled ? "hidden" : "visible",
+            }}
+            onClick={async () => {
+              const { SessionsModal } = await import("./SessionsModal");
+              showModal(<SessionsModal />);
+            }}
+          >
+            Sessions
+          </Button>
+          <Button
+            type="text"
+            style={{
+              marginLeft: "10px",
+              color: "white",
+              visibility: config?.auth?.disabled ? "hidden" : "visible",
+            }}
+            onClick={async () => {
+              await logout();
               window.location.reload();
             }}
           >
@@ -206,6 +242,14 @@ export const App: React.FC = () => {
                   </MainContentAreaTemplate>
                 }
               />
+              <Route
+                path="/audit"
+                element={
+                  <MainContentAreaTemplate breadcrumbs={[{ title: "Audit Log" }]}>
+                    <AuditLogView />
+                  </MainContentAreaTemplate>
+                }
+              />
               <Route path="/plan/:planId" element={<PlanViewContainer />} />
               <Route path="/repo/:repoId" element={<RepoViewContainer />} />
               <Route
@@ -393,6 +437,14 @@ const getSidenavItems = (config: Config | null): MenuProps["items"] => {
       label: "Repositories",
       children: repos,
     },
+    {
+      key: "audit",
+      icon: React.createElement(AuditOutlined),
+      label: "Audit Log",
+      onClick: () => {
+        navigate("/audit");
+      },
+    },
     {
       key: "settings",
       icon: React.createElement(SettingOutlined),
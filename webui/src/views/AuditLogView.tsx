@@ -0,0 +1,89 @@
+import React, { useEffect, useState } from "react";
+import { Button, Input, Space, Table, Tag, Typography } from "antd";
+import { backrestService } from "../api";
+import { formatErrorAlert, useAlertApi } from "../components/Alerts";
+import { formatTime } from "../lib/formatting";
+import { AuditLogEntry } from "../../gen/ts/v1/audit_pb";
+
+export const AuditLogView = () => {
+  const alertApi = useAlertApi()!;
+  const [entries, setEntries] = useState<AuditLogEntry[] | null>(null);
+  const [user, setUser] = useState("");
+
+  const load = async () => {
+    try {
+      const res = await backrestService.getAuditLog({
+        lastN: BigInt(1000),
+        user: user,
+      });
+      setEntries(res.entries);
+    } catch (e: any) {
+      alertApi.error(formatErrorAlert(e, "Failed to load audit log: "), 10);
+    }
+  };
+
+  useEffect(() => {
+    load();
+  }, []);
+
+  return (
+    <>
+      <Space style={{ marginBottom: "16px" }}>
+        <Input
+          placeholder="Filter by user"
+          value={user}
+          onChange={(e) => setUser(e.target.value)}
+          onPressEnter={load}
+        />
+        <Button onClick={load}>Refresh</Button>
+      </Space>
+      <Table
+        size="small"
+        loading={entries === null}
+        rowKey={(e, idx) => "" + e.unixTimeMs + "-" + idx}
+        dataSource={entries || []}
+        pagination={{ pageSize: 50 }}
+        columns={[
+          {
+            title: "Time",
+            key: "time",
+            render: (_, e) => formatTime(Number(e.unixTimeMs)),
+          },
+          {
+            title: "User",
+            key: "user",
+            render: (_, e) => (
+              <>
+                {e.user || (
+                  <Typography.Text type="secondary">none</Typography.Text>
+                )}
+                {e.apiKey ? (
+                  <Tag style={{ marginLeft: "4px" }}>key: {e.apiKey}</Tag>
+                ) : null}
+              </>
+            ),
+          },
+          { title: "Source", dataIndex: "sourceIp", key: "sourceIp" },
+          {
+            title: "Call",
+            key: "rpc",
+            render: (_, e) => e.rpc.substring(e.rpc.lastIndexOf("/") + 1),
+          },
+          { title: "Summary", dataIndex: "summary", key: "summary" },
+          {
+            title: "Result",
+            key: "result",
+            render: (_, e) =>
+              e.result === "ok" ? (
+                <Tag color="green">ok</Tag>
+              ) : (
+                <Typography.Text type="danger" title={e.error}>
+                  {e.result}
+                </Typography.Text>
+              ),
+          },
+        ]}
+      />
+    </>
+  );
+};
@@ -1,8 +1,13 @@
-import { LockOutlined, UserOutlined } from "@ant-design/icons";
+import {
+  LockOutlined,
+  SafetyOutlined,
+  UserOutlined,
+} from "@ant-design/icons";
 import { Button, Col, Form, Input, Modal, Row } from "antd";
 import React, { useEffect, useState } from "react";
 import { authenticationService, setAuthToken } from "../api";
 import {
+  LoginOptions,
   LoginRequest,
   LoginRequestSchema,
 } from "../../gen/ts/v1/authentication_pb";
@@ -14,16 +19,30 @@ export const LoginModal = () => {
 
   const [form] = Form.useForm();
   const alertApi = useAlertApi()!;
+  const [loginOptions, setLoginOptions] = useState<LoginOptions | null>(null);
+  const [totpRequired, setTotpRequired] = useState(false);
+
+  useEffect(() => {
+    authenticationService
+      .getLoginOptions({})
+      .then(setLoginOptions)
+      .catch((e) => console.error("failed to fetch login options", e));
+  }, []);
 
   const onFinish = async (values: any) => {
     const loginReq = create(LoginRequestSchema, {
       username: values.username,
       password: values.password,
+      totpCode: values.totpCode || "",
     });
 
     try {
       const loginResponse = await authenticationService.login(loginReq);
-      setAuthToken(loginResponse.token);
+      if (loginResponse.totpRequired) {
+        setTotpRequired(true);
+        return;
+      }
+      setAuthToken(loginResponse.token, loginResponse.refreshToken);
       alertApi.success("Logged in", 5);
       setTimeout(() => {
         window.location.reload();
@@ -87,7 +106,42 @@ export const LoginModal = () => {
             </Button>
           </Col>
         </Row>
+        {totpRequired ? (
+          <Row justify="center" style={{ width: "100%", marginTop: "16px" }}>
+            <Col span={20}>
+              <Form.Item
+                name="totpCode"
+                rules={[
+                  {
+                    required: true,
+                    message: "Please input the code from your authenticator app",
+                  },
+                ]}
+                style={{ width: "100%", paddingRight: "10px" }}
+              >
+                <Input
+                  prefix={<SafetyOutlined className="site-form-item-icon" />}
+                  placeholder="One time code or recovery code"
+                  autoComplete="one-time-code"
+                  autoFocus
+                />
+              </Form.Item>
+            </Col>
+            <Col span={4} />
+          </Row>
+        ) : null}
       </Form>
+      {loginOptions?.oidcLoginUrl ? (
+        <Row justify="center" style={{ width: "100%", marginTop: "16px" }}>
+          <Button
+            onClick={() => {
+              window.location.href = loginOptions.oidcLoginUrl;
+            }}
+          >
+            Log in with {loginOptions.oidcDisplayName || "single sign-on"}
+          </Button>
+        </Row>
+      ) : null}
     </Modal>
   );
 };
@@ -16,6 +16,7 @@ import { create } from "@bufbuild/protobuf";
 import { useConfig } from "../components/ConfigProvider";
 import { OperationListView } from "../components/OperationListView";
 import { OperationTreeView } from "../components/OperationTreeView";
+import { FindFilesView } from "../components/FindFilesView";
 
 export const PlanView = ({ plan }: React.PropsWithChildren<{ plan: Plan }>) => {
   const [config, _] = useConfig();
@@ -150,6 +151,11 @@ export const PlanView = ({ plan }: React.PropsWithChildren<{ plan: Plan }>) => {
             ),
             destroyInactiveTabPane: true,
           },
+          {
+            key: "3",
+            label: "Find Files",
+            children: <FindFilesView repoId={repo.id} planId={plan.id} />,
+          },
         ]}
       />
     </>
@@ -3,6 +3,7 @@ import { Repo } from "../../gen/ts/v1/config_pb";
 import { Flex, Tabs, Tooltip, Typography, Button } from "antd";
 import { OperationListView } from "../components/OperationListView";
 import { OperationTreeView } from "../components/OperationTreeView";
+import { FindFilesView } from "../components/FindFilesView";
 import { MAX_OPERATION_HISTORY, STATS_OPERATION_HISTORY } from "../constants";
 import {
   DoRepoTaskRequest_Task,
@@ -92,6 +93,19 @@ export const RepoView = ({ repo }: React.PropsWithChildren<{ repo: Repo }>) => {
     }
   };
 
+  const handleCopyNow = async () => {
+    try {
+      await backrestService.doRepoTask(
+        create(DoRepoTaskRequestSchema, {
+          repoId: repo.id!,
+          task: DoRepoTaskRequest_Task.COPY,
+        })
+      );
+    } catch (e: any) {
+      alertsApi.error(formatErrorAlert(e, "Failed to copy: "));
+    }
+  };
+
   // Gracefully handle deletions by checking if the plan is still in the config.
   let repoInConfig = config?.repos?.find((r) => r.id === repo.id);
   if (!repoInConfig) {
@@ -157,6 +171,11 @@ export const RepoView = ({ repo }: React.PropsWithChildren<{ repo: Repo }>) => {
       ),
       destroyInactiveTabPane: true,
     },
+    {
+      key: "4",
+      label: "Find Files",
+      children: <FindFilesView repoId={repo.id} />,
+    },
   ];
   return (
     <>
@@ -205,6 +224,16 @@ export const RepoView = ({ repo }: React.PropsWithChildren<{ repo: Repo }>) => {
             Compute Stats
           </SpinButton>
         </Tooltip>
+
+        {repo.copyPolicy?.toRepo ? (
+          <Tooltip
+            title={`Copies new snapshots to repo ${repo.copyPolicy.toRepo} now rather than waiting for the copy schedule`}
+          >
+            <SpinButton type="default" onClickAsync={handleCopyNow}>
+              Copy Now
+            </SpinButton>
+          </Tooltip>
+        ) : null}
       </Flex>
       <Tabs defaultActiveKey={items[0].key} items={items} />
     </>
@@ -0,0 +1,99 @@
+import React, { useEffect, useState } from "react";
+import { Button, Modal, Table, Tag } from "antd";
+import { backrestService } from "../api";
+import { formatErrorAlert, useAlertApi } from "../components/Alerts";
+import { useShowModal } from "../components/ModalManager";
+import { formatTime } from "../lib/formatting";
+import { Session } from "../../gen/ts/v1/authentication_pb";
+
+export const SessionsModal = () => {
+  const showModal = useShowModal();
+  const alertApi = useAlertApi()!;
+  const [sessions, setSessions] = useState<Session[] | null>(null);
+
+  const load = async () => {
+    try {
+      const res = await backrestService.listSessions({});
+      setSessions(res.sessions);
+    } catch (e: any) {
+      alertApi.error(formatErrorAlert(e, "Failed to load sessions: "), 10);
+    }
+  };
+
+  useEffect(() => {
+    load();
+  }, []);
+
+  const revoke = async (session: Session) => {
+    try {
+      await backrestService.revokeSession({ value: session.id });
+      alertApi.success("Session revoked", 5);
+      if (session.current) {
+        window.location.reload();
+        return;
+      }
+      await load();
+    } catch (e: any) {
+      alertApi.error(formatErrorAlert(e, "Failed to revoke session: "), 10);
+    }
+  };
+
+  return (
+    <Modal
+      open={true}
+      onCancel={() => showModal(null)}
+      title="Sessions"
+      width="60vw"
+      footer={[
+        <Button key="done" onClick={() => showModal(null)}>
+          Done
+        </Button>,
+      ]}
+    >
+      <Table
+        size="small"
+        loading={sessions === null}
+        rowKey={(s) => s.id}
+        dataSource={sessions || []}
+        pagination={false}
+        columns={[
+          {
+            title: "User",
+            key: "user",
+            render: (_, s) => (
+              <>
+                {s.user}
+                {s.current ? (
+                  <Tag color="blue" style={{ marginLeft: "4px" }}>
+                    this session
+                  </Tag>
+                ) : null}
+              </>
+            ),
+          },
+          { title: "Client", dataIndex: "clientAddr", key: "clientAddr" },
+          { title: "Browser", dataIndex: "userAgent", key: "userAgent" },
+          {
+            title: "Created",
+            key: "created",
+            render: (_, s) => formatTime(Number(s.createdAtMs)),
+          },
+          {
+            title: "Last Seen",
+            key: "lastSeen",
+            render: (_, s) => formatTime(Number(s.lastSeenAtMs)),
+          },
+          {
+            title: "",
+            key: "actions",
+            render: (_, s) => (
+              <Button size="small" danger onClick={() => revoke(s)}>
+                Revoke
+              </Button>
+            ),
+          },
+        ]}
+      />
+    </Modal>
+  );
+};
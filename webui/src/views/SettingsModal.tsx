@@ -25,18 +25,30 @@ import { clone, fromJson, toJson, toJsonString } from "@bufbuild/protobuf";
 import {
   AuthSchema,
   ConfigSchema,
+  TLSSchema,
   UserSchema,
 } from "../../gen/ts/v1/config_pb";
+import { TLSInfo } from "../../gen/ts/v1/service_pb";
+import { formatTime } from "../lib/formatting";
 
 interface FormData {
   auth: {
     users: {
       name: string;
       passwordBcrypt: string;
+      oidcSubject?: string;
       needsBcrypt?: boolean;
+      role?: string;
+      allowedRepos?: string[];
+      allowedPlans?: string[];
     }[];
   };
   instance: string;
+  tls?: {
+    enabled: boolean;
+    certFile: string;
+    keyFile: string;
+  };
 }
 
 export const SettingsModal = () => {
@@ -44,6 +56,14 @@ export const SettingsModal = () => {
   const showModal = useShowModal();
   const alertsApi = useAlertApi()!;
   const [form] = Form.useForm<FormData>();
+  const [tlsInfo, setTlsInfo] = useState<TLSInfo | null>(null);
+
+  useEffect(() => {
+    backrestService
+      .getTLSInfo({})
+      .then(setTlsInfo)
+      .catch((e) => console.warn("failed to get TLS info: ", e));
+  }, []);
 
   if (!config) {
     return null;
@@ -71,7 +91,20 @@ export const SettingsModal = () => {
       newConfig.auth = fromJson(AuthSchema, formData.auth, {
         ignoreUnknownFields: false,
       });
+      // single sign-on is configured in the config file, keep it as is.
+      newConfig.auth.oidc = config.auth?.oidc;
+      // two factor authentication is managed by each user, keep it for existing users.
+      for (const user of newConfig.auth.users) {
+        user.totp = config.auth?.users.find((u) => u.name === user.name)?.totp;
+      }
+      // API keys are managed with their own RPCs, keep them unless their user was removed.
+      newConfig.auth.apiKeys = (config.auth?.apiKeys || []).filter((k) =>
+        newConfig.auth!.users.some((u) => u.name === k.user)
+      );
       newConfig.instance = formData.instance;
+      newConfig.tls = fromJson(TLSSchema, formData.tls || {}, {
+        ignoreUnknownFields: false,
+      });
 
       if (!newConfig.auth?.users && !newConfig.auth?.disabled) {
         throw new Error(
@@ -168,9 +201,15 @@ export const SettingsModal = () => {
             <Form.List
               name={["auth", "users"]}
               initialValue={
-                config.auth?.users?.map((u) =>
-                  toJson(UserSchema, u, { alwaysEmitImplicit: true })
-                ) || []
+                config.auth?.users?.map((u) => {
+                  const user = toJson(UserSchema, u, {
+                    alwaysEmitImplicit: true,
+                  }) as any;
+                  if (user.role === "ROLE_DEFAULT") {
+                    user.role = "ROLE_ADMIN"; // users predating roles are admins.
+                  }
+                  return user;
+                }) || []
               }
             >
               {(fields, { add, remove }) => (
@@ -194,29 +233,41 @@ export const SettingsModal = () => {
                           </Form.Item>
                         </Col>
                         <Col span={11}>
-                          <Form.Item
-                            name={[field.name, "passwordBcrypt"]}
-                            rules={[
-                              {
-                                required: true,
-                                message: "Password is required",
-                              },
-                            ]}
-                          >
-                            <Input.Password
-                              placeholder="Password"
-                              onFocus={() => {
-                                form.setFieldValue(
-                                  ["auth", "users", index, "needsBcrypt"],
-                                  true
-                                );
-                                form.setFieldValue(
-                                  ["auth", "users", index, "passwordBcrypt"],
-                                  ""
-                                );
-                              }}
-                            />
+                          <Form.Item name={[field.name, "oidcSubject"]} hidden>
+                            <Input />
                           </Form.Item>
+                          {form.getFieldValue([
+                            "auth",
+                            "users",
+                            index,
+                            "oidcSubject",
+                          ]) ? (
+                            <Input disabled value="Single sign-on user" />
+                          ) : (
+                            <Form.Item
+                              name={[field.name, "passwordBcrypt"]}
+                              rules={[
+                                {
+                                  required: true,
+                                  message: "Password is required",
+                                },
+                              ]}
+                            >
+                              <Input.Password
+                                placeholder="Password"
+                                onFocus={() => {
+                                  form.setFieldValue(
+                                    ["auth", "users", index, "needsBcrypt"],
+                                    true
+                                  );
+                                  form.setFieldValue(
+                                    ["auth", "users", index, "passwordBcrypt"],
+                                    ""
+                                  );
+                                }}
+                              />
+                            </Form.Item>
+                          )}
                         </Col>
                         <Col span={2}>
                           <MinusCircleOutlined
@@ -225,6 +276,48 @@ export const SettingsModal = () => {
                             }}
                           />
                         </Col>
+                        <Col span={6}>
+                          <Tooltip title="Admins may edit the config and run commands, operators may run backups and maintenance, restore only users may browse and restore snapshots, read only users may only view.">
+                            <Form.Item name={[field.name, "role"]}>
+                              <Select
+                                placeholder="Role"
+                                options={[
+                                  { label: "Admin", value: "ROLE_ADMIN" },
+                                  { label: "Operator", value: "ROLE_OPERATOR" },
+                                  {
+                                    label: "Restore only",
+                                    value: "ROLE_RESTORE_ONLY",
+                                  },
+                                  { label: "Read only", value: "ROLE_READ_ONLY" },
+                                ]}
+                              />
+                            </Form.Item>
+                          </Tooltip>
+                        </Col>
+                        <Col span={8}>
+                          <Form.Item name={[field.name, "allowedRepos"]}>
+                            <Select
+                              mode="multiple"
+                              placeholder="All repos"
+                              options={config.repos.map((r) => ({
+                                label: r.id,
+                                value: r.id,
+                              }))}
+                            />
+                          </Form.Item>
+                        </Col>
+                        <Col span={8}>
+                          <Form.Item name={[field.name, "allowedPlans"]}>
+                            <Select
+                              mode="multiple"
+                              placeholder="All plans"
+                              options={config.plans.map((p) => ({
+                                label: p.id,
+                                value: p.id,
+                              }))}
+                            />
+                          </Form.Item>
+                        </Col>
                       </Row>
                     );
                   })}
@@ -232,7 +325,7 @@ export const SettingsModal = () => {
                     <Button
                       type="dashed"
                       onClick={() => {
-                        add();
+                        add({ role: "ROLE_ADMIN" });
                       }}
                       block
                     >
@@ -244,6 +337,43 @@ export const SettingsModal = () => {
             </Form.List>
           </Form.Item>
 
+          <Tooltip title="Serve the UI and API over HTTPS. Without certificate files a self-signed certificate is generated in the data directory. Enabling or disabling HTTPS takes effect after a restart, changed certificate files are picked up automatically.">
+            <Form.Item
+              label="HTTPS"
+              name={["tls", "enabled"]}
+              valuePropName="checked"
+              initialValue={config.tls?.enabled || false}
+            >
+              <Checkbox />
+            </Form.Item>
+          </Tooltip>
+          <Form.Item
+            label="Certificate File"
+            name={["tls", "certFile"]}
+            initialValue={config.tls?.certFile || ""}
+          >
+            <Input placeholder="/etc/backrest/tls/cert.pem (optional)" />
+          </Form.Item>
+          <Form.Item
+            label="Key File"
+            name={["tls", "keyFile"]}
+            initialValue={config.tls?.keyFile || ""}
+          >
+            <Input placeholder="/etc/backrest/tls/key.pem (optional)" />
+          </Form.Item>
+          {tlsInfo?.enabled ? (
+            <Form.Item label="Certificate">
+              <Typography.Text>
+                {tlsInfo.selfSigned ? "Self-signed" : tlsInfo.subject}, expires{" "}
+                {formatTime(Number(tlsInfo.notAfterMs))}
+              </Typography.Text>
+              <br />
+              <Typography.Text type="secondary" copyable>
+                SHA-256 {tlsInfo.sha256Fingerprint}
+              </Typography.Text>
+            </Form.Item>
+          ) : null}
+
           <Form.Item shouldUpdate label="Preview">
             {() => (
               <Collapse
@@ -1,4 +1,5 @@
 import {
+  Alert,
   Button,
   Card,
   Col,
@@ -242,6 +243,36 @@ const SummaryPanel = ({
     }
   );
 
+  if (summary.destinations.length > 0) {
+    cardInfo.push({
+      key: 7,
+      label: "Repositories",
+      children: (
+        <>
+          {summary.destinations.map((d) => (
+            <Typography.Text
+              key={d.repoId}
+              type={destinationTextType(d.lastBackupStatus)}
+              style={{ marginRight: "5px" }}
+              title={
+                d.lastBackupMs
+                  ? `Last backup at ${formatTime(Number(d.lastBackupMs))}` +
+                    (d.lastSuccessfulBackupMs
+                      ? `, last success at ${formatTime(
+                          Number(d.lastSuccessfulBackupMs)
+                        )}`
+                      : ", never succeeded")
+                  : "No backups yet"
+              }
+            >
+              {d.repoId}
+            </Typography.Text>
+          ))}
+        </>
+      ),
+    });
+  }
+
   // check if mobile layout
   if (!isMobile()) {
     cardInfo.push(
@@ -265,8 +296,30 @@ const SummaryPanel = ({
     );
   }
 
+  const staleRepos = summary.destinations
+    .filter((d) => d.stale)
+    .map((d) => d.repoId);
+
   return (
     <Card title={summary.id} style={{ width: "100%" }}>
+      {summary.stale ? (
+        <Alert
+          type="warning"
+          showIcon
+          style={{ marginBottom: "16px" }}
+          message={
+            staleRepos.length > 0
+              ? `No backup to ${staleRepos.join(
+                  ", "
+                )} succeeded within the plan's max snapshot age.`
+              : summary.lastSuccessfulBackupMs
+              ? `No backup succeeded within the plan's max snapshot age, the last successful backup was at ${formatTime(
+                  Number(summary.lastSuccessfulBackupMs)
+                )}.`
+              : "No backup succeeded within the plan's max snapshot age."
+          }
+        />
+      ) : null}
       <Row gutter={16} key={1}>
         <Col span={10}>
           <Descriptions
@@ -293,3 +346,16 @@ const SummaryPanel = ({
     </Card>
   );
 };
+
+const destinationTextType = (status: OperationStatus) => {
+  switch (status) {
+    case OperationStatus.STATUS_SUCCESS:
+      return "success";
+    case OperationStatus.STATUS_WARNING:
+      return "warning";
+    case OperationStatus.STATUS_ERROR:
+      return "danger";
+    default:
+      return "secondary";
+  }
+};
@@ -0,0 +1,138 @@
+import React, { useState } from "react";
+import { Button, Input, Modal, QRCode, Space, Typography } from "antd";
+import { backrestService } from "../api";
+import { formatErrorAlert, useAlertApi } from "../components/Alerts";
+import { useShowModal } from "../components/ModalManager";
+import { TOTPEnrollment } from "../../gen/ts/v1/service_pb";
+
+export const TwoFactorModal = () => {
+  const showModal = useShowModal();
+  const alertApi = useAlertApi()!;
+  const [enrollment, setEnrollment] = useState<TOTPEnrollment | null>(null);
+  const [code, setCode] = useState("");
+  const [currentCredential, setCurrentCredential] = useState("");
+  const [recoveryCodes, setRecoveryCodes] = useState<string[] | null>(null);
+
+  const handleCancel = () => {
+    showModal(null);
+  };
+
+  const beginEnrollment = async () => {
+    try {
+      setEnrollment(await backrestService.beginTOTPEnrollment({}));
+    } catch (e: any) {
+      alertApi.error(formatErrorAlert(e, "Failed to start enrollment: "), 10);
+    }
+  };
+
+  const confirmEnrollment = async () => {
+    try {
+      const res = await backrestService.confirmTOTPEnrollment({
+        secret: enrollment!.secret,
+        code: code,
+        currentCredential: currentCredential,
+      });
+      setRecoveryCodes(res.codes);
+      alertApi.success("Two factor authentication enabled", 5);
+    } catch (e: any) {
+      alertApi.error(formatErrorAlert(e, "Failed to verify code: "), 10);
+    }
+  };
+
+  const disable = async () => {
+    try {
+      await backrestService.disableTOTP({ currentCredential });
+      alertApi.success("Two factor authentication disabled", 5);
+      showModal(null);
+    } catch (e: any) {
+      alertApi.error(formatErrorAlert(e, "Failed to disable: "), 10);
+    }
+  };
+
+  let content: React.ReactNode;
+  if (recoveryCodes) {
+    content = (
+      <>
+        <p>
+          Store these recovery codes somewhere safe. Each code can be used once
+          in place of a one time code if you lose access to your authenticator
+          app. They will not be shown again.
+        </p>
+        <Typography.Paragraph copyable={{ text: recoveryCodes.join("\n") }}>
+          <pre>{recoveryCodes.join("\n")}</pre>
+        </Typography.Paragraph>
+      </>
+    );
+  } else if (enrollment) {
+    content = (
+      <Space direction="vertical" align="center" style={{ width: "100%" }}>
+        <p>
+          Scan the QR code with your authenticator app, then enter the code it
+          shows to finish setup.
+        </p>
+        <QRCode value={enrollment.provisioningUri} />
+        <Typography.Text type="secondary" copyable>
+          {enrollment.secret}
+        </Typography.Text>
+        <Space.Compact>
+          <Input
+            placeholder="123456"
+            value={code}
+            onChange={(e) => setCode(e.target.value)}
+            onPressEnter={confirmEnrollment}
+            autoComplete="one-time-code"
+          />
+          <Button type="primary" onClick={confirmEnrollment}>
+            Verify
+          </Button>
+        </Space.Compact>
+      </Space>
+    );
+  } else {
+    content = (
+      <>
+        <p>
+          Two factor authentication requires a one time code from an
+          authenticator app in addition to your password when logging in.
+          Enabling it again replaces your current authenticator and recovery
+          codes.
+        </p>
+        <p>
+          If two factor authentication is already enabled, confirm the change
+          with your current password or a one time code.
+        </p>
+        <Input.Password
+          placeholder="Current password or one time code"
+          value={currentCredential}
+          onChange={(e) => setCurrentCredential(e.target.value)}
+          autoComplete="current-password"
+          style={{ marginBottom: "1em" }}
+        />
+        <Space>
+          <Button type="primary" onClick={beginEnrollment}>
+            Set up authenticator app
+          </Button>
+          <Button danger onClick={disable}>
+            Disable
+          </Button>
+        </Space>
+      </>
+    );
+  }
+
+  return (
+    <Modal
+      open={true}
+      onCancel={handleCancel}
+      title="Two Factor Authentication"
+      width="40vw"
+      footer={[
+        <Button key="done" onClick={handleCancel}>
+          {recoveryCodes ? "Done" : "Cancel"}
+        </Button>,
+      ]}
+    >
+      {content}
+    </Modal>
+  );
+};